@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS jobs_status_id_index;
+DROP TABLE IF EXISTS jobs;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS "jobs" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "domain" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'queued',
+    "error" TEXT,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "started_at" TIMESTAMPTZ,
+    "finished_at" TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS "jobs_status_id_index" ON "jobs" ("status", "id");
@@ -0,0 +1,4 @@
+DROP TRIGGER IF EXISTS refresh_problem_statements_updated_at_step1 ON problem_statements;
+DROP TRIGGER IF EXISTS refresh_problem_statements_updated_at_step2 ON problem_statements;
+DROP TRIGGER IF EXISTS refresh_problem_statements_updated_at_step3 ON problem_statements;
+DROP TABLE IF EXISTS problem_statements;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS "problem_statements" (
+    "problem_id" TEXT PRIMARY KEY REFERENCES "problems" ("problem_id") ON DELETE CASCADE,
+    "statement_ja" TEXT[] NOT NULL,
+    "statement_en" TEXT[] NOT NULL,
+    "html_ja" TEXT[] NOT NULL,
+    "html_en" TEXT[] NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER refresh_problem_statements_updated_at_step1 BEFORE
+UPDATE ON problem_statements FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step1();
+
+CREATE TRIGGER refresh_problem_statements_updated_at_step2 BEFORE
+UPDATE OF updated_at ON problem_statements FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step2();
+
+CREATE TRIGGER refresh_problem_statements_updated_at_step3 BEFORE
+UPDATE ON problem_statements FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step3();
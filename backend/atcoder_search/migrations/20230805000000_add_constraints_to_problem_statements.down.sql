@@ -0,0 +1,3 @@
+ALTER TABLE "problem_statements"
+    DROP COLUMN IF EXISTS "constraints_ja",
+    DROP COLUMN IF EXISTS "constraints_en";
@@ -0,0 +1,3 @@
+ALTER TABLE "problem_statements"
+    ADD COLUMN IF NOT EXISTS "constraints_ja" TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS "constraints_en" TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS "problem_statement_versions";
+
+ALTER TABLE "problem_statements"
+    DROP COLUMN IF EXISTS "version",
+    DROP COLUMN IF EXISTS "statement_updated_at";
@@ -0,0 +1,17 @@
+ALTER TABLE "problem_statements"
+    ADD COLUMN IF NOT EXISTS "version" INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS "statement_updated_at" TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS "problem_statement_versions" (
+    "problem_id" TEXT NOT NULL REFERENCES "problems" ("problem_id") ON DELETE CASCADE,
+    "version" INTEGER NOT NULL,
+    "statement_ja" TEXT[] NOT NULL,
+    "statement_en" TEXT[] NOT NULL,
+    "diff_summary" TEXT,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("problem_id", "version")
+);
+
+INSERT INTO "problem_statement_versions" ("problem_id", "version", "statement_ja", "statement_en")
+SELECT "problem_id", "version", "statement_ja", "statement_en" FROM "problem_statements"
+ON CONFLICT DO NOTHING;
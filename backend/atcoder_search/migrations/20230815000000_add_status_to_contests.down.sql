@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS contests_status_start_epoch_second_index;
+
+ALTER TABLE "contests" DROP COLUMN IF EXISTS "status";
@@ -0,0 +1,3 @@
+ALTER TABLE "contests" ADD COLUMN IF NOT EXISTS "status" TEXT NOT NULL DEFAULT 'finished';
+
+CREATE INDEX IF NOT EXISTS contests_status_start_epoch_second_index ON "contests" ("status", "start_epoch_second");
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS saved_searches_expires_at_index;
+
+DROP TABLE IF EXISTS "saved_searches";
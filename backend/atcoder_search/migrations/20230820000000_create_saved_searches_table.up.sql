@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS "saved_searches" (
+    "token" TEXT PRIMARY KEY,
+    "query" TEXT NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "expires_at" TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS saved_searches_expires_at_index ON "saved_searches" ("expires_at");
@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS refresh_submissions_updated_at_step1 ON submissions;
+DROP TRIGGER IF EXISTS refresh_submissions_updated_at_step2 ON submissions;
+DROP TRIGGER IF EXISTS refresh_submissions_updated_at_step3 ON submissions;
+
+DROP INDEX IF EXISTS submissions_user_id_problem_id_index;
+
+DROP TABLE IF EXISTS "submissions";
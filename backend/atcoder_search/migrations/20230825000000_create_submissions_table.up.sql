@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS "submissions" (
+    "id" BIGINT PRIMARY KEY,
+    "epoch_second" BIGINT NOT NULL,
+    "problem_id" TEXT NOT NULL,
+    "contest_id" TEXT NOT NULL,
+    "user_id" TEXT NOT NULL,
+    "language" TEXT NOT NULL,
+    "point" DOUBLE PRECISION NOT NULL,
+    "length" INTEGER NOT NULL,
+    "result" TEXT NOT NULL,
+    "execution_time" INTEGER,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS submissions_user_id_problem_id_index ON "submissions" ("user_id", "problem_id");
+
+CREATE TRIGGER refresh_submissions_updated_at_step1 BEFORE
+UPDATE ON submissions FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step1();
+
+CREATE TRIGGER refresh_submissions_updated_at_step2 BEFORE
+UPDATE OF updated_at ON submissions FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step2();
+
+CREATE TRIGGER refresh_submissions_updated_at_step3 BEFORE
+UPDATE ON submissions FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step3();
@@ -0,0 +1,3 @@
+ALTER TABLE "problem_statements"
+    DROP COLUMN IF EXISTS "is_interactive",
+    DROP COLUMN IF EXISTS "has_figures";
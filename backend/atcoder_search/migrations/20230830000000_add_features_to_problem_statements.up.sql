@@ -0,0 +1,3 @@
+ALTER TABLE "problem_statements"
+    ADD COLUMN IF NOT EXISTS "is_interactive" BOOLEAN,
+    ADD COLUMN IF NOT EXISTS "has_figures" BOOLEAN;
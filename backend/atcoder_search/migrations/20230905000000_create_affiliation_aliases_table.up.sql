@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS "affiliation_aliases" (
+    "alias" TEXT PRIMARY KEY,
+    "affiliation" TEXT NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER refresh_affiliation_aliases_updated_at_step1 BEFORE
+UPDATE ON affiliation_aliases FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step1();
+
+CREATE TRIGGER refresh_affiliation_aliases_updated_at_step2 BEFORE
+UPDATE OF updated_at ON affiliation_aliases FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step2();
+
+CREATE TRIGGER refresh_affiliation_aliases_updated_at_step3 BEFORE
+UPDATE ON affiliation_aliases FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step3();
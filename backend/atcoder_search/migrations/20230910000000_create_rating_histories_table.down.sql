@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS rating_histories_end_time_index;
+
+DROP TABLE IF EXISTS "rating_histories";
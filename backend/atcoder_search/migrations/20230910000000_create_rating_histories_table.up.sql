@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS "rating_histories" (
+    "user_name" TEXT NOT NULL REFERENCES "users" ("user_name") ON DELETE CASCADE,
+    "contest_id" TEXT NOT NULL,
+    "end_time" TIMESTAMPTZ NOT NULL,
+    "place" INTEGER NOT NULL,
+    "old_rating" INTEGER NOT NULL,
+    "new_rating" INTEGER NOT NULL,
+    "performance" INTEGER NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("user_name", "contest_id")
+);
+
+CREATE INDEX IF NOT EXISTS rating_histories_end_time_index ON "rating_histories" ("end_time");
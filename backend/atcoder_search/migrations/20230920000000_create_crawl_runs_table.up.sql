@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS "crawl_runs" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "domain" TEXT NOT NULL,
+    "status" TEXT NOT NULL DEFAULT 'running',
+    "started_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "finished_at" TIMESTAMPTZ,
+    "fetched" INTEGER NOT NULL DEFAULT 0,
+    "inserted" INTEGER NOT NULL DEFAULT 0,
+    "updated" INTEGER NOT NULL DEFAULT 0,
+    "errors" INTEGER NOT NULL DEFAULT 0,
+    "error" TEXT
+);
+
+CREATE INDEX IF NOT EXISTS "crawl_runs_domain_id_index" ON "crawl_runs" ("domain", "id");
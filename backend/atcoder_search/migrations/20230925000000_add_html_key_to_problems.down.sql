@@ -0,0 +1,3 @@
+UPDATE "problems" SET "html" = '' WHERE "html" IS NULL;
+ALTER TABLE "problems" ALTER COLUMN "html" SET NOT NULL;
+ALTER TABLE "problems" DROP COLUMN IF EXISTS "html_key";
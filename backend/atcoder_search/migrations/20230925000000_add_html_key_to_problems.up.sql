@@ -0,0 +1,2 @@
+ALTER TABLE "problems" ADD COLUMN "html_key" TEXT;
+ALTER TABLE "problems" ALTER COLUMN "html" DROP NOT NULL;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS "problem_html_objects" (
+    "html_key" TEXT PRIMARY KEY,
+    "problem_id" TEXT NOT NULL REFERENCES "problems" ("problem_id") ON DELETE CASCADE,
+    "size" BIGINT NOT NULL,
+    "stored_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS "problem_html_objects_problem_id_index" ON "problem_html_objects" ("problem_id", "stored_at");
+
+-- 既に外部のストレージに保存されているHTMLは大きさが分からないので0として記録する
+INSERT INTO "problem_html_objects" ("html_key", "problem_id", "size")
+SELECT "html_key", "problem_id", 0 FROM "problems" WHERE "html_key" IS NOT NULL
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS "category_relationships" (
+    "from_category" TEXT NOT NULL,
+    "to_category" TEXT NOT NULL,
+    "weight" DOUBLE PRECISION NOT NULL,
+    "created_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY ("from_category", "to_category")
+);
+
+CREATE TRIGGER refresh_category_relationships_updated_at_step1 BEFORE
+UPDATE ON category_relationships FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step1();
+
+CREATE TRIGGER refresh_category_relationships_updated_at_step2 BEFORE
+UPDATE OF updated_at ON category_relationships FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step2();
+
+CREATE TRIGGER refresh_category_relationships_updated_at_step3 BEFORE
+UPDATE ON category_relationships FOR EACH ROW EXECUTE PROCEDURE refresh_updated_at_step3();
@@ -0,0 +1,2 @@
+DROP MATERIALIZED VIEW IF EXISTS "problem_stats";
+DROP MATERIALIZED VIEW IF EXISTS "solved_counts";
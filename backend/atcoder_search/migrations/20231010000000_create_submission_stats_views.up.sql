@@ -0,0 +1,25 @@
+CREATE MATERIALIZED VIEW IF NOT EXISTS "solved_counts" AS
+SELECT
+    "problem_id",
+    COUNT(DISTINCT "user_id") AS "solved_count"
+FROM
+    "submissions"
+WHERE
+    "result" = 'AC'
+GROUP BY
+    "problem_id";
+
+CREATE UNIQUE INDEX IF NOT EXISTS "solved_counts_problem_id_index" ON "solved_counts" ("problem_id");
+
+CREATE MATERIALIZED VIEW IF NOT EXISTS "problem_stats" AS
+SELECT
+    "problem_id",
+    COUNT(*) AS "submission_count",
+    COUNT(DISTINCT "user_id") AS "submitter_count",
+    COUNT(*) FILTER (WHERE "result" = 'AC') AS "accepted_count"
+FROM
+    "submissions"
+GROUP BY
+    "problem_id";
+
+CREATE UNIQUE INDEX IF NOT EXISTS "problem_stats_problem_id_index" ON "problem_stats" ("problem_id");
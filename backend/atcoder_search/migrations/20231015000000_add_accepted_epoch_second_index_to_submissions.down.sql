@@ -0,0 +1 @@
+DROP INDEX IF EXISTS "submissions_accepted_epoch_second_index";
@@ -0,0 +1,3 @@
+CREATE INDEX IF NOT EXISTS "submissions_accepted_epoch_second_index" ON "submissions" ("epoch_second")
+WHERE
+    "result" = 'AC';
@@ -0,0 +1 @@
+ALTER TABLE "contests" DROP COLUMN IF EXISTS "category_rules_version";
@@ -0,0 +1 @@
+ALTER TABLE "contests" ADD COLUMN IF NOT EXISTS "category_rules_version" TEXT NOT NULL DEFAULT 'builtin';
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS "submissions_epoch_second_index";
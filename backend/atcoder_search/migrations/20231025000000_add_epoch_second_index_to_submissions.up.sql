@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS "submissions_epoch_second_index" ON "submissions" ("epoch_second");
@@ -0,0 +1,70 @@
+use crate::{cmd::database::DatabaseArgs, modules::users::affiliation::AffiliationAliasStore};
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+pub struct AffiliationArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(subcommand)]
+    command: AffiliationCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum AffiliationCommands {
+    /// Show all mappings from notational variants to normalized affiliations
+    List,
+    /// Map a notational variant of an affiliation to the normalized one, replacing the existing mapping
+    Add {
+        /// Notational variant, e.g. `Univ. of Tokyo`. Case, width, punctuation and spaces are ignored
+        alias: String,
+        /// Normalized affiliation, e.g. `東京大学`
+        affiliation: String,
+    },
+    /// Remove the mapping of a notational variant
+    Remove { alias: String },
+}
+
+pub async fn run(args: AffiliationArgs) -> Result<()> {
+    let store = AffiliationAliasStore::new(args.database.connect().await?);
+
+    match args.command {
+        AffiliationCommands::List => {
+            let aliases = store.list().await.with_context(|| {
+                let message = "failed to get the affiliation mappings";
+                tracing::error!(message);
+                message
+            })?;
+            for alias in aliases.iter() {
+                println!("{}\t{}", alias.alias, alias.affiliation);
+            }
+        }
+        AffiliationCommands::Add { alias, affiliation } => {
+            let alias = store.add(&alias, &affiliation).await.with_context(|| {
+                let message = format!("failed to map the affiliation `{}`", alias);
+                tracing::error!(message);
+                message
+            })?;
+            // インデックスに反映するにはユーザのドキュメントを生成し直す必要がある
+            tracing::info!(
+                "`{}` is mapped to `{}`. update the user index to apply it",
+                alias.alias,
+                alias.affiliation
+            );
+        }
+        AffiliationCommands::Remove { alias } => {
+            let removed = store.remove(&alias).await.with_context(|| {
+                let message = format!("failed to remove the mapping of `{}`", alias);
+                tracing::error!(message);
+                message
+            })?;
+            if removed {
+                tracing::info!("the mapping of `{}` has been removed", alias);
+            } else {
+                tracing::warn!("`{}` is not mapped", alias);
+            }
+        }
+    }
+
+    Ok(())
+}
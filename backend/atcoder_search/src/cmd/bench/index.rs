@@ -0,0 +1,318 @@
+use super::Xorshift;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use atcoder_search_libs::{
+    indexing::{Manifest, MANIFEST_FILE},
+    solr::core::{SolrCore, StandaloneSolrCore},
+    DocumentFormat, DocumentUploader, GenerateDocument, PostDocument, PostOptions, ReadRows,
+    ToDocument,
+};
+use clap::Args;
+use serde::Serialize;
+use std::{env, path::Path, pin::Pin};
+use tokio::time::Instant;
+use tokio_stream::Stream;
+
+// 合成ドキュメントの本文に使う単語
+const WORDS: [&str; 16] = [
+    "graph", "tree", "dp", "greedy", "binary", "search", "segment", "query", "string", "modulo",
+    "prime", "matrix", "bitset", "flow", "geometry", "sort",
+];
+
+#[derive(Debug, Args)]
+pub struct BenchIndexArgs {
+    /// Number of synthetic documents to generate
+    #[arg(long, default_value_t = 100000)]
+    documents: usize,
+    /// Approximate size of the text of each document in bytes
+    #[arg(long, default_value_t = 2048)]
+    size: usize,
+    /// Comma separated numbers of documents written into one file
+    #[arg(long, value_delimiter = ',', default_value = "1000")]
+    chunk_sizes: Vec<usize>,
+    /// Comma separated numbers of rows converted into documents concurrently
+    #[arg(long, value_delimiter = ',', default_value = "64")]
+    workers: Vec<usize>,
+    #[arg(long, default_value_t = DocumentFormat::Json)]
+    format: DocumentFormat,
+    /// Name of the Solr core to post the documents to. Only the generation is measured if omitted.
+    /// The core is truncated before each run, so never give a core which serves the search.
+    /// The documents have the fields `id` and `body_t`.
+    #[arg(long)]
+    core: Option<String>,
+}
+
+/// ベンチマーク用の合成データの行
+#[derive(Debug)]
+struct SyntheticRow {
+    index: usize,
+    size: usize,
+}
+
+/// ベンチマーク用の合成ドキュメント
+#[derive(Debug, Serialize)]
+struct SyntheticDocument {
+    id: String,
+    body_t: String,
+}
+
+impl ToDocument for SyntheticRow {
+    type Document = SyntheticDocument;
+
+    fn to_document(self) -> Result<Self::Document> {
+        Ok(SyntheticDocument {
+            id: format!("bench-{:08}", self.index),
+            body_t: synthetic_text(self.index as u64, self.size),
+        })
+    }
+}
+
+// 行番号をシードにして、単語を並べたおよそ`size`バイトの文章を作る関数
+fn synthetic_text(seed: u64, size: usize) -> String {
+    let mut rng = Xorshift::new(seed);
+    let mut text = String::with_capacity(size + 16);
+    while text.len() < size {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(rng.choose(&WORDS));
+    }
+    text
+}
+
+/// 合成ドキュメントを生成するジェネレータ
+struct SyntheticDocumentGenerator {
+    documents: usize,
+    size: usize,
+}
+
+#[async_trait]
+impl<'a> ReadRows<'a> for SyntheticDocumentGenerator {
+    type Row = SyntheticRow;
+
+    async fn read_rows(
+        &'a self,
+    ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Self::Row, sqlx::Error>> + Send + 'a>>>
+    {
+        let size = self.size;
+        Ok(Box::pin(tokio_stream::iter(
+            (0..self.documents).map(move |index| Ok(SyntheticRow { index, size })),
+        )))
+    }
+}
+
+#[async_trait]
+impl<'a> GenerateDocument<'a> for SyntheticDocumentGenerator {}
+
+/// 1つのパラメータの組み合わせでの計測結果
+#[derive(Debug, Clone, PartialEq)]
+struct BenchResult {
+    chunk_size: usize,
+    workers: usize,
+    files: usize,
+    /// 生成したファイルの合計サイズ(バイト)
+    bytes: u64,
+    /// 生成にかかった時間(秒)
+    generate: f64,
+    /// 投入にかかった時間(秒)。投入しなかった場合は`None`
+    post: Option<f64>,
+}
+
+pub async fn run(args: BenchIndexArgs) -> Result<()> {
+    let core = match &args.core {
+        Some(core_name) => {
+            let solr_host = env::var("SOLR_HOST").unwrap_or_else(|_| {
+                tracing::info!("SOLR_HOST environment variable is not set. Default value `http://localhost:8983` will be used.");
+                String::from("http://localhost:8983")
+            });
+            Some(StandaloneSolrCore::new(core_name, &solr_host)?)
+        }
+        None => None,
+    };
+
+    let generator = SyntheticDocumentGenerator {
+        documents: args.documents,
+        size: args.size,
+    };
+    let save_dir = env::temp_dir().join(format!(
+        "atcoder_search_bench_{}",
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ));
+
+    let mut results = Vec::new();
+    for &chunk_size in args.chunk_sizes.iter() {
+        for &workers in args.workers.iter() {
+            tokio::fs::create_dir_all(&save_dir).await?;
+            let result = bench(
+                &generator,
+                core.as_ref(),
+                &save_dir,
+                chunk_size,
+                workers,
+                args.format,
+            )
+            .await;
+            tokio::fs::remove_dir_all(&save_dir).await?;
+            results.push(result?);
+        }
+    }
+
+    if let Some(core) = &core {
+        core.truncate().await?;
+    }
+
+    println!(
+        "{} documents, {} bytes of text per document",
+        args.documents, args.size
+    );
+    println!(
+        "{:>10} {:>8} {:>6} {:>10} {:>10} {:>12} {:>10} {:>12} {:>10}",
+        "chunk",
+        "workers",
+        "files",
+        "size (MB)",
+        "gen (s)",
+        "gen (doc/s)",
+        "post (s)",
+        "post (doc/s)",
+        "post (MB/s)"
+    );
+    for result in results.iter() {
+        let megabytes = result.bytes as f64 / 1024.0 / 1024.0;
+        let (post, post_documents, post_megabytes) = match result.post {
+            Some(post) => (
+                format!("{:.2}", post),
+                format!("{:.0}", throughput(args.documents as f64, post)),
+                format!("{:.2}", throughput(megabytes, post)),
+            ),
+            None => (String::from("-"), String::from("-"), String::from("-")),
+        };
+        println!(
+            "{:>10} {:>8} {:>6} {:>10.2} {:>10.2} {:>12.0} {:>10} {:>12} {:>10}",
+            result.chunk_size,
+            result.workers,
+            result.files,
+            megabytes,
+            result.generate,
+            throughput(args.documents as f64, result.generate),
+            post,
+            post_documents,
+            post_megabytes
+        );
+    }
+
+    Ok(())
+}
+
+fn throughput(amount: f64, seconds: f64) -> f64 {
+    amount / seconds.max(f64::EPSILON)
+}
+
+/// 1つのパラメータの組み合わせでドキュメントを生成し、コアが与えられていれば投入して時間を計る関数
+async fn bench<C: SolrCore + Clone + Sync + Send + 'static>(
+    generator: &SyntheticDocumentGenerator,
+    core: Option<&C>,
+    save_dir: &Path,
+    chunk_size: usize,
+    workers: usize,
+    format: DocumentFormat,
+) -> Result<BenchResult> {
+    tracing::info!(
+        "Start the benchmark with chunk size {} and {} workers",
+        chunk_size,
+        workers
+    );
+
+    let start = Instant::now();
+    generator
+        .generate(save_dir, chunk_size, format, false, workers)
+        .await
+        .with_context(|| {
+            let message = "failed to generate the synthetic documents";
+            tracing::error!(message);
+            message
+        })?;
+    let generate = start.elapsed().as_secs_f64();
+
+    let manifest = Manifest::load(&save_dir.join(MANIFEST_FILE))
+        .await?
+        .unwrap_or_default();
+    let mut bytes = 0;
+    for chunk in manifest.chunks.iter() {
+        bytes += tokio::fs::metadata(save_dir.join(&chunk.file)).await?.len();
+    }
+
+    let post = match core {
+        Some(core) => {
+            core.truncate().await?;
+            let start = Instant::now();
+            DocumentUploader::new()
+                .post_documents(core.clone(), save_dir, &PostOptions::default())
+                .await?;
+            Some(start.elapsed().as_secs_f64())
+        }
+        None => None,
+    };
+
+    Ok(BenchResult {
+        chunk_size,
+        workers,
+        files: manifest.chunks.len(),
+        bytes,
+        generate,
+        post,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::{MockRequest, MockSolrCore};
+
+    #[test]
+    fn test_synthetic_text() {
+        let text = synthetic_text(1, 100);
+        assert!(text.len() >= 100 && text.len() < 120);
+        assert!(text.split(' ').all(|word| WORDS.contains(&word)));
+        assert_eq!(text, synthetic_text(1, 100));
+        assert_ne!(text, synthetic_text(2, 100));
+    }
+
+    #[tokio::test]
+    async fn test_bench() {
+        let save_dir = env::temp_dir().join("atcoder_search_test_bench_index");
+        tokio::fs::create_dir_all(&save_dir).await.unwrap();
+        let generator = SyntheticDocumentGenerator {
+            documents: 25,
+            size: 64,
+        };
+        let core = MockSolrCore::new("bench");
+
+        let result = bench(
+            &generator,
+            Some(&core),
+            &save_dir,
+            10,
+            4,
+            DocumentFormat::Json,
+        )
+        .await
+        .unwrap();
+        tokio::fs::remove_dir_all(&save_dir).await.unwrap();
+
+        assert_eq!(result.chunk_size, 10);
+        assert_eq!(result.workers, 4);
+        assert_eq!(result.files, 3);
+        assert!(result.bytes > 25 * 64);
+        assert!(result.post.is_some());
+        let requests = core.requests();
+        assert_eq!(requests[0], MockRequest::Truncate);
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| matches!(request, MockRequest::Post(_)))
+                .count(),
+            3
+        );
+    }
+}
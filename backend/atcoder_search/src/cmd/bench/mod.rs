@@ -0,0 +1,48 @@
+pub mod index;
+pub mod search;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    #[command(subcommand)]
+    command: BenchCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum BenchCommands {
+    /// Measure the throughput of generating and posting synthetic documents
+    Index(index::BenchIndexArgs),
+    /// Send a mix of search queries at a target rate and measure the latencies
+    Search(search::BenchSearchArgs),
+}
+
+pub async fn run(args: BenchArgs) -> Result<()> {
+    match args.command {
+        BenchCommands::Index(args) => index::run(args).await,
+        BenchCommands::Search(args) => search::run(args).await,
+    }
+}
+
+/// ベンチマークの入力を決定的に作るための疑似乱数生成器(xorshift64)
+#[derive(Debug, Clone)]
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// `items`の中から1つを選ぶ
+    fn choose<T: Copy>(&mut self, items: &[T]) -> T {
+        items[(self.next() % items.len() as u64) as usize]
+    }
+}
@@ -0,0 +1,408 @@
+use super::Xorshift;
+use crate::{
+    cmd::{replay::percentile, TargetDomain},
+    modules::handlers::{problem::ProblemSearchParameter, user::UserSearchParameter},
+};
+use anyhow::Result;
+use atcoder_search_libs::{
+    solr::core::{SolrCore, StandaloneSolrCore},
+    ToQueryParameter,
+};
+use clap::Args;
+use serde_json::Value;
+use std::{env, sync::Arc};
+use tokio::{
+    sync::Semaphore,
+    task::JoinSet,
+    time::{Duration, Instant, MissedTickBehavior},
+};
+
+// クエリのキーワードに使う語
+const KEYWORDS: [&str; 12] = [
+    "dp",
+    "graph",
+    "二分探索",
+    "最短経路",
+    "木",
+    "文字列",
+    "shortest path",
+    "segment tree",
+    "bit全探索",
+    "素数",
+    "greedy",
+    "幾何",
+];
+const PROBLEM_CATEGORIES: [&str; 4] = ["ABC", "ARC", "AGC", "ABC-Like"];
+const USER_COLORS: [&str; 5] = ["green", "cyan", "blue", "yellow", "orange"];
+
+#[derive(Debug, Args)]
+pub struct BenchSearchArgs {
+    domain: TargetDomain,
+    /// Base URL of the running API server, e.g. `http://localhost:8000`
+    #[arg(long, conflicts_with = "core")]
+    url: Option<String>,
+    /// Name of the Solr core to query directly instead of the API server
+    #[arg(long, required_unless_present = "url")]
+    core: Option<String>,
+    /// Number of requests sent per second
+    #[arg(long, default_value_t = 10)]
+    rps: u32,
+    /// Duration of the load test in seconds
+    #[arg(long, default_value_t = 30)]
+    duration: u64,
+    /// Maximum number of requests in flight. Requests beyond it are dropped and reported
+    #[arg(long, default_value_t = 64)]
+    concurrency: usize,
+    /// Relative frequency of queries with only a keyword
+    #[arg(long, default_value_t = 6)]
+    keyword_weight: u32,
+    /// Relative frequency of queries with a keyword and filters
+    #[arg(long, default_value_t = 3)]
+    filter_weight: u32,
+    /// Relative frequency of queries with a keyword and facets
+    #[arg(long, default_value_t = 1)]
+    facet_weight: u32,
+    /// Seed of the query mix, to replay the same load
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+}
+
+/// 負荷試験で送るクエリの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryKind {
+    Keyword,
+    Filter,
+    Facet,
+}
+
+/// クエリの種類の比率に従ってクエリ文字列を作る
+#[derive(Debug, Clone)]
+struct QueryMix {
+    domain: TargetDomain,
+    weights: Vec<(QueryKind, u32)>,
+    rng: Xorshift,
+}
+
+impl QueryMix {
+    fn new(domain: TargetDomain, weights: Vec<(QueryKind, u32)>, seed: u64) -> Result<Self> {
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            anyhow::bail!("at least one of the query weights must be positive");
+        }
+        if domain.unique_key().is_none() {
+            anyhow::bail!("bench search is not supported for {}", domain);
+        }
+        Ok(Self {
+            domain,
+            weights,
+            rng: Xorshift::new(seed),
+        })
+    }
+
+    fn kind(&mut self) -> QueryKind {
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut n = (self.rng.next() % total as u64) as u32;
+        for (kind, weight) in self.weights.iter() {
+            if n < *weight {
+                return *kind;
+            }
+            n -= weight;
+        }
+        unreachable!()
+    }
+
+    /// 検索APIに与えるクエリ文字列を作る
+    fn next_query(&mut self) -> String {
+        let kind = self.kind();
+        let keyword = percent_encoding::utf8_percent_encode(
+            self.rng.choose(&KEYWORDS),
+            percent_encoding::NON_ALPHANUMERIC,
+        );
+        match (&self.domain, kind) {
+            (_, QueryKind::Keyword) => format!("keyword={}", keyword),
+            (TargetDomain::Problems, QueryKind::Filter) => {
+                let from = self.rng.choose(&[0, 400, 800, 1200, 1600, 2000]);
+                format!(
+                    "keyword={}&filter.category={}&filter.difficulty.from={}&filter.difficulty.to={}",
+                    keyword,
+                    self.rng.choose(&PROBLEM_CATEGORIES),
+                    from,
+                    from + 800
+                )
+            }
+            (TargetDomain::Problems, QueryKind::Facet) => {
+                format!("keyword={}&facet=category,difficulty", keyword)
+            }
+            (_, QueryKind::Filter) => {
+                format!(
+                    "filter.color={}&sort=-rating",
+                    self.rng.choose(&USER_COLORS)
+                )
+            }
+            (_, QueryKind::Facet) => format!("keyword={}&facet=color,country", keyword),
+        }
+    }
+}
+
+/// 負荷試験の送り先
+enum Target<C> {
+    Server {
+        client: reqwest::Client,
+        url: String,
+    },
+    Solr(C),
+}
+
+impl<C: SolrCore + Sync> Target<C> {
+    /// クエリを送り、成功したかどうかを返す
+    async fn send(&self, domain: &TargetDomain, query: &str) -> Result<()> {
+        match self {
+            Target::Server { client, url } => {
+                let path = match domain {
+                    TargetDomain::Problems => "problem",
+                    _ => "user",
+                };
+                client
+                    .get(format!("{}/api/v1/search/{}?{}", url, path, query))
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            Target::Solr(core) => {
+                // APIと同じパラメータ構造体を経由してSolrのパラメータに変換する
+                let params = match domain {
+                    TargetDomain::Problems => {
+                        serde_structuredqs::from_str::<ProblemSearchParameter>(query)?.to_query()
+                    }
+                    _ => serde_structuredqs::from_str::<UserSearchParameter>(query)?.to_query(),
+                };
+                core.select::<Value, Value>(&params).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 負荷試験の結果
+#[derive(Debug, Clone, Default, PartialEq)]
+struct LoadReport {
+    /// 成功したリクエストにかかった時間(ミリ秒)
+    latencies: Vec<f64>,
+    errors: usize,
+    /// 同時に実行中のリクエストが多すぎて送らなかった数
+    dropped: usize,
+    /// 負荷試験にかかった時間(秒)
+    elapsed: f64,
+}
+
+impl LoadReport {
+    fn sent(&self) -> usize {
+        self.latencies.len() + self.errors
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.sent() == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.sent() as f64
+        }
+    }
+}
+
+pub async fn run(args: BenchSearchArgs) -> Result<()> {
+    let target = match (&args.url, &args.core) {
+        (Some(url), _) => Target::Server {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+        },
+        (None, Some(core_name)) => {
+            let solr_host = env::var("SOLR_HOST").unwrap_or_else(|_| {
+                tracing::info!("SOLR_HOST environment variable is not set. Default value `http://localhost:8983` will be used.");
+                String::from("http://localhost:8983")
+            });
+            Target::Solr(StandaloneSolrCore::new(core_name, &solr_host)?)
+        }
+        (None, None) => anyhow::bail!("either --url or --core must be given"),
+    };
+    let mix = QueryMix::new(
+        args.domain.clone(),
+        vec![
+            (QueryKind::Keyword, args.keyword_weight),
+            (QueryKind::Filter, args.filter_weight),
+            (QueryKind::Facet, args.facet_weight),
+        ],
+        args.seed,
+    )?;
+    if args.rps == 0 {
+        anyhow::bail!("--rps must be positive");
+    }
+
+    tracing::info!(
+        "Send {} requests per second for {} seconds",
+        args.rps,
+        args.duration
+    );
+    let report = load(
+        Arc::new(target),
+        mix,
+        args.rps,
+        Duration::from_secs(args.duration),
+        args.concurrency,
+    )
+    .await;
+
+    let mut latencies = report.latencies.clone();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    println!(
+        "sent: {}, errors: {} ({:.2}%), dropped: {}, throughput: {:.1} req/s",
+        report.sent(),
+        report.errors,
+        report.error_rate() * 100.0,
+        report.dropped,
+        report.sent() as f64 / report.elapsed.max(f64::EPSILON)
+    );
+    println!(
+        "{:>10} {:>10} {:>10} {:>10} {:>10}",
+        "p50 (ms)", "p90 (ms)", "p95 (ms)", "p99 (ms)", "max (ms)"
+    );
+    println!(
+        "{:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+        percentile(&latencies, 50.0),
+        percentile(&latencies, 90.0),
+        percentile(&latencies, 95.0),
+        percentile(&latencies, 99.0),
+        latencies.last().copied().unwrap_or(0.0)
+    );
+
+    Ok(())
+}
+
+/// `rps`の頻度でクエリを送り続け、かかった時間とエラー数を集計する関数
+///
+/// 応答を待たずに一定の間隔で送るので、送り先が遅くなっても負荷は下がらない。
+/// 実行中のリクエストが`concurrency`に達している間に送る予定だったリクエストは送らずに数える。
+async fn load<C: SolrCore + Sync + Send + 'static>(
+    target: Arc<Target<C>>,
+    mut mix: QueryMix,
+    rps: u32,
+    duration: Duration,
+    concurrency: usize,
+) -> LoadReport {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut interval = tokio::time::interval(Duration::from_secs(1) / rps);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Burst);
+
+    let mut report = LoadReport::default();
+    let mut tasks: JoinSet<Option<f64>> = JoinSet::new();
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        interval.tick().await;
+        let query = mix.next_query();
+        let permit = match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                report.dropped += 1;
+                continue;
+            }
+        };
+
+        let target = target.clone();
+        let domain = mix.domain.clone();
+        tasks.spawn(async move {
+            let start = Instant::now();
+            let result = target.send(&domain, &query).await;
+            drop(permit);
+            match result {
+                Ok(_) => Some(start.elapsed().as_secs_f64() * 1000.0),
+                Err(e) => {
+                    tracing::warn!("the query {} failed cause: {:?}", query, e);
+                    None
+                }
+            }
+        });
+    }
+
+    while let Some(task) = tasks.join_next().await {
+        match task {
+            Ok(Some(latency)) => report.latencies.push(latency),
+            _ => report.errors += 1,
+        }
+    }
+    report.elapsed = start.elapsed().as_secs_f64();
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::{MockRequest, MockSolrCore};
+
+    fn mix(domain: TargetDomain, weights: [u32; 3]) -> QueryMix {
+        QueryMix::new(
+            domain,
+            vec![
+                (QueryKind::Keyword, weights[0]),
+                (QueryKind::Filter, weights[1]),
+                (QueryKind::Facet, weights[2]),
+            ],
+            1,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_query_mix() {
+        let mut keyword_only = mix(TargetDomain::Problems, [1, 0, 0]);
+        assert!((0..100).all(|_| keyword_only.kind() == QueryKind::Keyword));
+
+        let mut problems = mix(TargetDomain::Problems, [6, 3, 1]);
+        let kinds: Vec<QueryKind> = (0..1000).map(|_| problems.kind()).collect();
+        let count = |kind| kinds.iter().filter(|k| **k == kind).count();
+        assert!(count(QueryKind::Keyword) > count(QueryKind::Filter));
+        assert!(count(QueryKind::Filter) > count(QueryKind::Facet));
+        assert!(count(QueryKind::Facet) > 0);
+
+        assert!(QueryMix::new(TargetDomain::Problems, vec![(QueryKind::Keyword, 0)], 1).is_err());
+        assert!(QueryMix::new(TargetDomain::Recommend, vec![(QueryKind::Keyword, 1)], 1).is_err());
+    }
+
+    #[test]
+    fn test_queries_are_valid_parameters() {
+        let mut problems = mix(TargetDomain::Problems, [1, 1, 1]);
+        for _ in 0..100 {
+            let query = problems.next_query();
+            serde_structuredqs::from_str::<ProblemSearchParameter>(&query).unwrap();
+        }
+        let mut users = mix(TargetDomain::Users, [1, 1, 1]);
+        for _ in 0..100 {
+            let query = users.next_query();
+            serde_structuredqs::from_str::<UserSearchParameter>(&query).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let core = MockSolrCore::new("problems");
+        let target = Arc::new(Target::Solr(core.clone()));
+
+        let report = load(
+            target,
+            mix(TargetDomain::Problems, [6, 3, 1]),
+            100,
+            Duration::from_millis(200),
+            8,
+        )
+        .await;
+
+        assert_eq!(report.errors, 0);
+        assert_eq!(report.dropped, 0);
+        assert!(report.sent() >= 10);
+        assert_eq!(
+            core.requests()
+                .iter()
+                .filter(|request| matches!(request, MockRequest::Select(_)))
+                .count(),
+            report.sent()
+        );
+    }
+}
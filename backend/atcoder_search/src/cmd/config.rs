@@ -0,0 +1,441 @@
+use crate::cmd::TargetDomain;
+use anyhow::{Context, Result};
+use atcoder_search_libs::solr::{
+    backend::{BackendKind, SearchBackend},
+    core::{SolrCoreConfig, StandaloneSolrCore},
+};
+use clap::Args;
+use serde::Deserialize;
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+// Solrのデフォルトの接続先
+const DEFAULT_SOLR_URL: &str = "http://localhost:8983";
+
+// OpenSearchのデフォルトの接続先
+const DEFAULT_OPENSEARCH_URL: &str = "http://localhost:9200";
+
+/// 検索バックエンドの設定ファイルに関するオプション
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    /// TOML file mapping each domain to its core name, URL and credentials. Environment variables take precedence over it
+    #[arg(long = "config", env = "ATCODER_SEARCH_CONFIG")]
+    config_file: Option<PathBuf>,
+}
+
+impl ConfigArgs {
+    /// 設定ファイルを読み込み、環境変数の値で上書きした設定を返す
+    pub fn load(&self) -> Result<SearchConfig> {
+        let config = match &self.config_file {
+            Some(path) => SearchConfig::load(path)?,
+            None => SearchConfig::default(),
+        };
+        config.with_env_overrides(|key| env::var(key).ok())
+    }
+}
+
+/// ドメインごとのコアの接続先の設定
+#[derive(Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoreConfig {
+    pub core: Option<String>,
+    /// `update`コマンドで投入してから本番のコアと入れ替えるコア
+    pub staging_core: Option<String>,
+    /// 省略した場合はトップレベルの`url`
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 接続先の証明書を検証するPEM形式のCA証明書のパス
+    pub ca_cert: Option<PathBuf>,
+    /// `true`の場合、接続先の証明書を検証しない
+    pub insecure: Option<bool>,
+}
+
+// パスワードをログに出さないようにする
+impl fmt::Debug for CoreConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CoreConfig")
+            .field("core", &self.core)
+            .field("staging_core", &self.staging_core)
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "********"))
+            .field("ca_cert", &self.ca_cert)
+            .field("insecure", &self.insecure)
+            .finish()
+    }
+}
+
+/// 検索バックエンドの設定ファイル
+///
+/// ```toml
+/// backend = "solr"
+/// url = "http://localhost:8983"
+///
+/// [problems]
+/// core = "problems"
+/// staging_core = "problems_staging"
+///
+/// [users]
+/// core = "users"
+/// staging_core = "users_staging"
+/// url = "https://solr-users.example.com"
+/// username = "solr"
+/// password = "SolrRocks"
+/// ca_cert = "/etc/ssl/certs/solr-ca.pem"
+///
+/// [recommend]
+/// core = "recommends"
+/// ```
+///
+/// 環境変数が設定されていればファイルの値より優先する。
+///
+/// - `SEARCH_BACKEND`: `backend`
+/// - `SOLR_HOST`(OpenSearchの場合は`OPENSEARCH_URL`): `url`
+/// - `<DOMAIN>_CORE_NAME`・`<DOMAIN>_STAGING_CORE_NAME`・`<DOMAIN>_CORE_URL`・`<DOMAIN>_CORE_USERNAME`・`<DOMAIN>_CORE_PASSWORD`・
+///   `<DOMAIN>_CORE_CA_CERT`・`<DOMAIN>_CORE_INSECURE`: ドメインごとの値。`<DOMAIN>`は`PROBLEMS`・`USERS`・`RECOMMENDS`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchConfig {
+    pub backend: Option<String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub problems: CoreConfig,
+    #[serde(default)]
+    pub users: CoreConfig,
+    #[serde(default)]
+    pub recommend: CoreConfig,
+}
+
+impl SearchConfig {
+    /// 設定ファイルを読み込む関数
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| {
+            let message = format!("failed to read the config {}", path.display());
+            tracing::error!(message);
+            message
+        })?;
+        Self::parse(&content)
+    }
+
+    /// TOML形式の設定をパースする関数
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: SearchConfig = toml::from_str(content).with_context(|| {
+            let message = "invalid config";
+            tracing::error!(message);
+            message
+        })?;
+        config.kind()?;
+
+        Ok(config)
+    }
+
+    /// `var`で取得した環境変数の値で上書きした設定を返す関数
+    pub fn with_env_overrides(mut self, var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        if let Some(backend) = var("SEARCH_BACKEND") {
+            self.backend = Some(backend);
+        }
+        let url_key = match self.kind()? {
+            BackendKind::Solr => "SOLR_HOST",
+            BackendKind::OpenSearch => "OPENSEARCH_URL",
+        };
+        if let Some(url) = var(url_key) {
+            self.url = Some(url);
+        }
+
+        for domain in [
+            TargetDomain::Problems,
+            TargetDomain::Users,
+            TargetDomain::Recommend,
+        ] {
+            let prefix = domain.env_prefix();
+            let core = self.domain_mut(&domain);
+            for (suffix, value) in [
+                ("CORE_NAME", &mut core.core),
+                ("STAGING_CORE_NAME", &mut core.staging_core),
+                ("CORE_URL", &mut core.url),
+                ("CORE_USERNAME", &mut core.username),
+                ("CORE_PASSWORD", &mut core.password),
+            ] {
+                if let Some(overridden) = var(&format!("{}_{}", prefix, suffix)) {
+                    *value = Some(overridden);
+                }
+            }
+            if let Some(ca_cert) = var(&format!("{}_CORE_CA_CERT", prefix)) {
+                core.ca_cert = Some(PathBuf::from(ca_cert));
+            }
+            let insecure_key = format!("{}_CORE_INSECURE", prefix);
+            if let Some(insecure) = var(&insecure_key) {
+                core.insecure = Some(insecure.parse().with_context(|| {
+                    let message = format!("{} must be `true` or `false`", insecure_key);
+                    tracing::error!(message);
+                    message
+                })?);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// 検索バックエンドの種類
+    pub fn kind(&self) -> Result<BackendKind> {
+        match &self.backend {
+            Some(backend) => Ok(backend.parse()?),
+            None => Ok(BackendKind::default()),
+        }
+    }
+
+    /// ドメインのコアの設定
+    pub fn domain(&self, domain: &TargetDomain) -> &CoreConfig {
+        match domain {
+            TargetDomain::Problems => &self.problems,
+            TargetDomain::Users => &self.users,
+            TargetDomain::Recommend => &self.recommend,
+        }
+    }
+
+    fn domain_mut(&mut self, domain: &TargetDomain) -> &mut CoreConfig {
+        match domain {
+            TargetDomain::Problems => &mut self.problems,
+            TargetDomain::Users => &mut self.users,
+            TargetDomain::Recommend => &mut self.recommend,
+        }
+    }
+
+    /// `domain`の本番のコアの接続先を返す関数
+    ///
+    /// レコメンド用のコアは名前が設定されていなければ`recommends`とする。
+    pub fn core(&self, domain: &TargetDomain) -> Result<CoreTarget> {
+        let name = match (&self.domain(domain).core, domain) {
+            (Some(name), _) => name.clone(),
+            (None, TargetDomain::Recommend) => {
+                tracing::warn!("The name of the recommend core is not set. Default value `recommends` will be used.");
+                String::from("recommends")
+            }
+            (None, _) => {
+                let message = format!(
+                    "`{}.core` in the config or {}_CORE_NAME must be set",
+                    domain,
+                    domain.env_prefix()
+                );
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        };
+        self.target(domain, name)
+    }
+
+    /// `domain`のステージング用のコアの接続先を返す関数
+    pub fn staging_core(&self, domain: &TargetDomain) -> Result<CoreTarget> {
+        let Some(name) = self.domain(domain).staging_core.clone() else {
+            let message = format!(
+                "`{}.staging_core` in the config or {}_STAGING_CORE_NAME must be set",
+                domain,
+                domain.env_prefix()
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        };
+        self.target(domain, name)
+    }
+
+    /// `domain`と同じ接続先にある`name`のコアを返す関数
+    pub fn named_core(&self, domain: &TargetDomain, name: &str) -> Result<CoreTarget> {
+        self.target(domain, name.to_string())
+    }
+
+    fn target(&self, domain: &TargetDomain, name: String) -> Result<CoreTarget> {
+        let kind = self.kind()?;
+        let config = self.domain(domain);
+        let url = match config.url.as_ref().or(self.url.as_ref()) {
+            Some(url) => url.clone(),
+            None => {
+                let url = match kind {
+                    BackendKind::Solr => DEFAULT_SOLR_URL,
+                    BackendKind::OpenSearch => DEFAULT_OPENSEARCH_URL,
+                };
+                tracing::info!(
+                    "The URL of {} is not set. Default value `{}` will be used.",
+                    kind,
+                    url
+                );
+                url.to_string()
+            }
+        };
+
+        Ok(CoreTarget {
+            kind,
+            name,
+            url,
+            config: SolrCoreConfig {
+                username: config.username.clone(),
+                password: config.password.clone(),
+                ca_cert: config.ca_cert.clone(),
+                insecure: config.insecure.unwrap_or_default(),
+            },
+        })
+    }
+}
+
+/// 接続先が決まったコア
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreTarget {
+    pub kind: BackendKind,
+    pub name: String,
+    pub url: String,
+    /// 認証とTLSの設定
+    pub config: SolrCoreConfig,
+}
+
+impl CoreTarget {
+    /// コアのクライアントを作成する関数
+    ///
+    /// `unique_key`はOpenSearchでドキュメントのIDに使うフィールド。
+    pub fn backend(&self, unique_key: &str) -> Result<SearchBackend> {
+        SearchBackend::with_config(self.kind, &self.name, &self.url, unique_key, &self.config)
+            .with_context(|| {
+                let message = format!("couldn't create {} client for {}", self.kind, self.name);
+                tracing::error!(message);
+                message
+            })
+    }
+
+    /// Solrのコアのクライアントを作成する関数
+    ///
+    /// Solrにしかない機能を使うコマンドのためのもので、バックエンドがOpenSearchの場合はエラーにする。
+    pub fn solr_core(&self) -> Result<StandaloneSolrCore> {
+        if self.kind != BackendKind::Solr {
+            let message = format!(
+                "{} is an index of {}, but this command requires Solr",
+                self.name, self.kind
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        }
+        StandaloneSolrCore::with_config(&self.name, &self.url, &self.config).with_context(|| {
+            let message = format!("couldn't create Solr client for {}", self.name);
+            tracing::error!(message);
+            message
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_config() {
+        let config = SearchConfig::parse(
+            r#"
+            url = "http://solr:8983"
+
+            [problems]
+            core = "problems"
+            staging_core = "problems_staging"
+
+            [users]
+            core = "users"
+            url = "https://solr-users:8983"
+            username = "solr"
+            password = "SolrRocks"
+            ca_cert = "/etc/ssl/certs/solr-ca.pem"
+            "#,
+        )
+        .unwrap();
+
+        let problems = config.core(&TargetDomain::Problems).unwrap();
+        assert_eq!(problems.kind, BackendKind::Solr);
+        assert_eq!(problems.name, "problems");
+        assert_eq!(problems.url, "http://solr:8983");
+        assert_eq!(problems.config, SolrCoreConfig::default());
+        assert_eq!(
+            config.staging_core(&TargetDomain::Problems).unwrap().name,
+            "problems_staging"
+        );
+
+        let users = config.core(&TargetDomain::Users).unwrap();
+        assert_eq!(users.url, "https://solr-users:8983");
+        assert_eq!(users.config.username.as_deref(), Some("solr"));
+        assert_eq!(
+            users.config.ca_cert,
+            Some(PathBuf::from("/etc/ssl/certs/solr-ca.pem"))
+        );
+        assert!(!format!("{:?}", users).contains("SolrRocks"));
+        assert!(!format!("{:?}", config).contains("SolrRocks"));
+        assert!(config.staging_core(&TargetDomain::Users).is_err());
+
+        assert_eq!(
+            config.core(&TargetDomain::Recommend).unwrap().name,
+            "recommends"
+        );
+        assert_eq!(
+            config
+                .named_core(&TargetDomain::Problems, "problems_experimental")
+                .unwrap()
+                .url,
+            "http://solr:8983"
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_config() {
+        assert!(SearchConfig::parse(r#"backend = "elasticsearch""#).is_err());
+        assert!(SearchConfig::parse(
+            r#"
+            [problems]
+            name = "problems"
+            "#
+        )
+        .is_err());
+        assert!(SearchConfig::default()
+            .core(&TargetDomain::Problems)
+            .is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let config = SearchConfig::parse(
+            r#"
+            url = "http://solr:8983"
+
+            [problems]
+            core = "problems"
+            "#,
+        )
+        .unwrap();
+        let vars = HashMap::from([
+            ("SOLR_HOST", "http://localhost:8983"),
+            ("PROBLEMS_CORE_NAME", "problems_v2"),
+            ("USERS_CORE_NAME", "users"),
+            ("USERS_CORE_PASSWORD", "SolrRocks"),
+            ("USERS_CORE_INSECURE", "true"),
+            ("OPENSEARCH_URL", "http://localhost:9200"),
+        ]);
+        let config = config
+            .with_env_overrides(|key| vars.get(key).map(|value| value.to_string()))
+            .unwrap();
+
+        let problems = config.core(&TargetDomain::Problems).unwrap();
+        assert_eq!(problems.name, "problems_v2");
+        assert_eq!(problems.url, "http://localhost:8983");
+        assert_eq!(config.users.password.as_deref(), Some("SolrRocks"));
+        assert!(config.core(&TargetDomain::Users).unwrap().config.insecure);
+        assert!(SearchConfig::default()
+            .with_env_overrides(|key| (key == "USERS_CORE_INSECURE").then(|| String::from("yes")))
+            .is_err());
+
+        let vars = HashMap::from([
+            ("SEARCH_BACKEND", "opensearch"),
+            ("SOLR_HOST", "http://localhost:8983"),
+        ]);
+        let config = SearchConfig::default()
+            .with_env_overrides(|key| vars.get(key).map(|value| value.to_string()))
+            .unwrap();
+        assert_eq!(config.kind().unwrap(), BackendKind::OpenSearch);
+        assert_eq!(config.url, None);
+    }
+}
@@ -1,60 +1,173 @@
 use crate::{
-    cmd::TargetDomain,
+    cmd::{database::DatabaseArgs, TargetDomain},
     modules::{
+        crawl_runs::{CrawlRunStore, CrawlStats},
         migration::MIGRATOR,
-        problems::crawler::{ContestCrawler, ProblemCrawler},
-        users::crawler::UserCrawler,
+        problems::{
+            crawler::{ContestCrawler, DifficultyCrawler, ProblemCrawler},
+            html_storage::HtmlStorage,
+        },
+        users::{
+            crawler::UserCrawler, rating_history::RatingHistoryCrawler,
+            submissions::SubmissionCrawler,
+        },
     },
+    types::contest::CategoryRules,
 };
-use anyhow::{Context, Result};
+use anyhow::Result;
 use clap::Args;
 use sqlx::{postgres::Postgres, Pool};
-use std::env;
 use tokio::time::Duration;
 
 #[derive(Debug, Args)]
 pub struct CrawlArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
     domain: TargetDomain,
     #[arg(long)]
     all: bool,
+    /// Crawl the rating histories of users whose join count has changed instead of the ranking pages (only for `users`)
+    #[arg(long)]
+    rating_history: bool,
+    /// Crawl the submissions from AtCoder Problems since the latest stored one instead of the ranking pages (only for `users`). With `--all`, crawl from the first submission
+    #[arg(long, conflicts_with = "rating_history")]
+    submissions: bool,
+    /// Maximum number of users to crawl the rating histories of
+    #[arg(long, requires = "rating_history")]
+    limit: Option<i64>,
+    /// Only fetch the lists from AtCoder Problems and AtCoder and print how many items would be crawled, without fetching the problem pages or writing to the database
+    #[arg(long)]
+    dry_run: bool,
 }
 
 pub async fn run(args: CrawlArgs) -> Result<()> {
-    let database_url: String = env::var("DATABASE_URL").with_context(|| {
-        let message = "DATABASE_URL must be configured.";
-        tracing::error!(message);
-        message
-    })?;
-
-    let pool: Pool<Postgres> = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .with_context(|| {
-            let message = "Failed to create database connection pool.";
-            tracing::error!(message);
-            message
-        })?;
+    if args.dry_run {
+        // データベースへは書き込まないので、マイグレーションも実行しない
+        let pool: Pool<Postgres> = args.database.connect_read_only().await?;
+        return dry_run(&pool, &args).await;
+    }
+
+    let pool: Pool<Postgres> = args.database.connect().await?;
 
     MIGRATOR.run(&pool).await?;
 
-    match args.domain {
+    if args.rating_history {
+        return match args.domain {
+            TargetDomain::Users => {
+                let crawler = RatingHistoryCrawler::new(&pool);
+                CrawlRunStore::new(pool.clone())
+                    .record(
+                        "rating_histories",
+                        crawler.crawl(args.limit, Duration::from_millis(1000)),
+                    )
+                    .await?;
+                Ok(())
+            }
+            _ => anyhow::bail!("--rating-history is only supported for users"),
+        };
+    }
+
+    if args.submissions {
+        return match args.domain {
+            TargetDomain::Users => {
+                let crawler = SubmissionCrawler::new(&pool);
+                CrawlRunStore::new(pool.clone())
+                    .record(
+                        "submissions",
+                        crawler.crawl(args.all, Duration::from_millis(1000)),
+                    )
+                    .await?;
+                Ok(())
+            }
+            _ => anyhow::bail!("--submissions is only supported for users"),
+        };
+    }
+
+    crawl(&pool, &args.domain, args.all).await
+}
+
+/// ドメインのデータをAtCoderから収集してデータベースへ保存する関数
+///
+/// 取得・保存した件数は`crawl_runs`テーブルに記録する。
+pub async fn crawl(pool: &Pool<Postgres>, domain: &TargetDomain, all: bool) -> Result<()> {
+    if let TargetDomain::Recommend = domain {
+        anyhow::bail!("crawl is not supported for {}", domain);
+    }
+
+    CrawlRunStore::new(pool.clone())
+        .record(&domain.to_string(), crawl_domain(pool, domain, all))
+        .await?;
+    Ok(())
+}
+
+async fn crawl_domain(
+    pool: &Pool<Postgres>,
+    domain: &TargetDomain,
+    all: bool,
+) -> Result<CrawlStats> {
+    let mut stats = CrawlStats::default();
+    match domain {
         TargetDomain::Problems => {
-            let crawler = ContestCrawler::new(&pool);
-            crawler.run().await?;
+            let crawler = ContestCrawler::new(pool).with_category_rules(CategoryRules::from_env()?);
+            stats += crawler.run().await?;
+
+            let crawler = ProblemCrawler::new(pool).with_html_storage(HtmlStorage::from_env()?);
+            stats += crawler.run(all, Duration::from_millis(1000)).await?;
 
-            let crawler = ProblemCrawler::new(&pool);
-            crawler.run(args.all, Duration::from_millis(1000)).await?;
-            Ok(())
+            // 新しく保存した問題にも難易度が付くよう、問題の収集の後に取り込む
+            let crawler = DifficultyCrawler::new(pool);
+            stats += crawler.run().await?;
         }
         TargetDomain::Users => {
-            let crawler = UserCrawler::new(&pool);
-            crawler.crawl().await?;
+            let crawler = UserCrawler::new(pool);
+            stats += crawler.crawl().await?;
+        }
+        TargetDomain::Recommend => anyhow::bail!("crawl is not supported for {}", domain),
+    }
+
+    Ok(stats)
+}
+
+/// クロールで取得・更新される件数を表示する関数
+///
+/// 一覧の取得と差分の検出だけを行い、問題ページの取得やデータベースへの書き込みは行わない。
+async fn dry_run(pool: &Pool<Postgres>, args: &CrawlArgs) -> Result<()> {
+    match args.domain {
+        TargetDomain::Problems => {
+            let (new, updated) = ContestCrawler::new(pool).plan().await?;
+            println!("contests: {} new, {} updated", new, updated);
 
-            Ok(())
+            let targets = ProblemCrawler::new(pool).targets(args.all).await?;
+            println!("problems: {} to fetch", targets.len());
+
+            let (summary, changed) = DifficultyCrawler::new(pool).plan().await?;
+            println!("problem models: {}", summary);
+            println!("difficulties: {} updated", changed);
+        }
+        TargetDomain::Users if args.rating_history => {
+            let targets = RatingHistoryCrawler::new(pool).targets(args.limit).await?;
+            println!("rating histories: {} users to fetch", targets.len());
         }
-        _ => {
-            todo!();
+        TargetDomain::Users if args.submissions => {
+            let from_second = if args.all {
+                0
+            } else {
+                SubmissionCrawler::new(pool).latest_epoch_second().await?
+            };
+            println!(
+                "submissions: fetched from epoch second {} until the latest one",
+                from_second
+            );
         }
+        TargetDomain::Users => {
+            let stored = UserCrawler::new(pool).stored_users().await?;
+            println!(
+                "users: ranking pages are fetched until an empty page, {} stored users would be updated",
+                stored
+            );
+        }
+        TargetDomain::Recommend => anyhow::bail!("crawl is not supported for {}", args.domain),
     }
+
+    Ok(())
 }
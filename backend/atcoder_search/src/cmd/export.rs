@@ -0,0 +1,46 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::dump::{DumpTable, TableDumper},
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// Directory to write the gzip compressed NDJSON dumps into
+    #[arg(long)]
+    dir: PathBuf,
+    /// Tables to export. All the crawled tables are exported when omitted
+    #[arg(long, value_enum, value_delimiter = ',')]
+    tables: Vec<DumpTable>,
+}
+
+pub async fn run(args: ExportArgs) -> Result<()> {
+    // 書き出すだけなので読み込み専用の方に接続する
+    let pool = args.database.connect_read_only().await?;
+    std::fs::create_dir_all(&args.dir).with_context(|| {
+        let message = format!("failed to create the directory {}", args.dir.display());
+        tracing::error!(message);
+        message
+    })?;
+
+    let dumper = TableDumper::new(&pool);
+    for table in DumpTable::resolve(&args.tables) {
+        let count = dumper.export(table, &args.dir).await.with_context(|| {
+            let message = format!("failed to export the table {}", table);
+            tracing::error!(message);
+            message
+        })?;
+        tracing::info!(
+            "{} rows of {} have been exported to {}",
+            count,
+            table,
+            table.path(&args.dir).display()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::problems::{html_storage::HtmlStorage, statement::StatementStore},
+};
+use anyhow::{Context, Result};
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct ExtractArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// Re-extract the statements of all problems, including ones already saved
+    #[arg(long)]
+    all: bool,
+}
+
+pub async fn run(args: ExtractArgs) -> Result<()> {
+    // 抽出した問題文はデータベースに書き込むので、読み込み専用ではない方に接続する
+    let store = StatementStore::new(args.database.connect().await?)
+        .with_html_storage(HtmlStorage::from_env()?);
+
+    let count = store.refresh(args.all).await.with_context(|| {
+        let message = "failed to save the problem statements";
+        tracing::error!(message);
+        message
+    })?;
+    tracing::info!("{} problem statements have been saved", count);
+
+    Ok(())
+}
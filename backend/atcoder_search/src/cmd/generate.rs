@@ -1,10 +1,16 @@
 use crate::{
-    cmd::TargetDomain,
+    cmd::{database::DatabaseArgs, TargetDomain},
     modules::{
-        problems::generator::ProblemDocumentGenerator, users::generator::UserDocumentGenerator,
+        problems::{
+            generator::ProblemDocumentGenerator, html_storage::HtmlStorage,
+            statement::StatementStore,
+        },
+        recommend::generator::RecommendDocumentGenerator,
+        users::generator::UserDocumentGenerator,
     },
 };
 use anyhow::{Context, Result};
+use atcoder_search_libs::{DocumentFormat, ExpandMode};
 use clap::Args;
 use sqlx::{postgres::Postgres, Pool};
 use std::{
@@ -15,27 +21,27 @@ use std::{
 
 #[derive(Debug, Args)]
 pub struct GenerateArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
     domain: TargetDomain,
     #[arg(long)]
     save_dir: Option<OsString>,
+    /// Format of the document files. `ndjson` writes one document per line
+    #[arg(long, default_value_t = DocumentFormat::Json)]
+    format: DocumentFormat,
+    /// Write the document files compressed with gzip (`.json.gz`)
+    #[arg(long)]
+    compress: bool,
+    /// Maximum number of rows converted into documents concurrently
+    #[arg(long, default_value_t = 64)]
+    workers: usize,
+    /// How to output the suffixed fields. `copyfield` leaves copying them to the copyField directives of Solr (apply the schema with the same mode)
+    #[arg(long, default_value_t = ExpandMode::Inline)]
+    expand_mode: ExpandMode,
 }
 
 pub async fn run(args: GenerateArgs) -> Result<()> {
-    let database_url: String = env::var("DATABASE_URL").with_context(|| {
-        let message = "DATABASE_URL must be configured.";
-        tracing::error!(message);
-        message
-    })?;
-
-    let pool: Pool<Postgres> = sqlx::postgres::PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .with_context(|| {
-            let message = "Failed to create database connection pool.";
-            tracing::error!(message);
-            message
-        })?;
+    let pool: Pool<Postgres> = args.database.connect_read_only().await?;
 
     let save_dir: PathBuf = match args.save_dir {
         Some(path) => PathBuf::from(path),
@@ -79,15 +85,37 @@ pub async fn run(args: GenerateArgs) -> Result<()> {
 
     match args.domain {
         TargetDomain::Problems => {
-            let generator = ProblemDocumentGenerator::new(&pool, &save_dir);
-            generator.run().await
+            let html_storage = HtmlStorage::from_env()?;
+
+            // 問題文を先に抽出して保存しておき、ドキュメントの生成ではそれを再利用する。
+            // 読み込み専用のデータベースには書き込めないので書き込み用に接続する。
+            let store = StatementStore::new(args.database.connect().await?)
+                .with_html_storage(html_storage.clone());
+            let count = store.refresh(false).await.with_context(|| {
+                let message = "failed to save the problem statements";
+                tracing::error!(message);
+                message
+            })?;
+            tracing::info!("{} problem statements have been saved", count);
+
+            let generator = ProblemDocumentGenerator::new(&pool, &save_dir)
+                .with_html_storage(html_storage)
+                .with_expand_mode(args.expand_mode);
+            generator
+                .run(args.format, args.compress, args.workers)
+                .await
         }
         TargetDomain::Users => {
             let generator = UserDocumentGenerator::new(&pool, &save_dir);
-            generator.run().await
+            generator
+                .run(args.format, args.compress, args.workers)
+                .await
         }
         TargetDomain::Recommend => {
-            todo!();
+            let generator = RecommendDocumentGenerator::new(&pool, &save_dir);
+            generator
+                .run(args.format, args.compress, args.workers)
+                .await
         }
     }
 }
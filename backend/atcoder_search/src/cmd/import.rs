@@ -0,0 +1,51 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::{
+        dump::{DumpTable, TableDumper},
+        migration::MIGRATOR,
+    },
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// Directory containing the dumps written by the `export` command
+    #[arg(long)]
+    dir: PathBuf,
+    /// Tables to import. All the tables whose dump exists in the directory are imported when omitted
+    #[arg(long, value_enum, value_delimiter = ',')]
+    tables: Vec<DumpTable>,
+}
+
+pub async fn run(args: ImportArgs) -> Result<()> {
+    let pool = args.database.connect().await?;
+
+    MIGRATOR.run(&pool).await?;
+
+    let dumper = TableDumper::new(&pool);
+    for table in DumpTable::resolve(&args.tables) {
+        let path = table.path(&args.dir);
+        // テーブルを明示しなかった場合は、ダンプファイルの無いテーブルを読み飛ばす
+        if args.tables.is_empty() && !path.exists() {
+            tracing::info!(
+                "{} is skipped because {} does not exist",
+                table,
+                path.display()
+            );
+            continue;
+        }
+
+        let count = dumper.import(table, &args.dir).await.with_context(|| {
+            let message = format!("failed to import the table {}", table);
+            tracing::error!(message);
+            message
+        })?;
+        tracing::info!("{} rows of {} have been imported", count, table);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use crate::{cmd::database::DatabaseArgs, modules::migration::MIGRATOR};
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use sqlx::{
+    migrate::{Migrate, Migration},
+    postgres::Postgres,
+    Pool,
+};
+use std::collections::HashSet;
+
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(subcommand)]
+    command: MigrateCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum MigrateCommands {
+    /// Apply all pending migrations
+    Up {
+        /// Print the pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Revert the applied migrations newer than the target version
+    Down {
+        /// Version to revert to. Only the latest migration is reverted when omitted
+        #[arg(long)]
+        target: Option<i64>,
+        /// Print the migrations to be reverted without reverting them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show all migrations and whether they have been applied
+    Status,
+}
+
+pub async fn run(args: MigrateArgs) -> Result<()> {
+    let pool: Pool<Postgres> = args.database.connect().await?;
+
+    let applied = applied_versions(&pool).await?;
+    let migrations = MIGRATOR.iter().collect::<Vec<&Migration>>();
+
+    match args.command {
+        MigrateCommands::Up { dry_run } => {
+            let pending = pending_migrations(&migrations, &applied);
+            if pending.is_empty() {
+                println!("no pending migrations");
+                return Ok(());
+            }
+            for migration in pending.iter() {
+                println!("pending: {}/{}", migration.version, migration.description);
+            }
+            if dry_run {
+                return Ok(());
+            }
+
+            MIGRATOR.run(&pool).await?;
+            tracing::info!("{} migrations applied", pending.len());
+        }
+        MigrateCommands::Down { target, dry_run } => {
+            let target = target.unwrap_or_else(|| previous_version(&applied));
+            let reverted = reverted_migrations(&migrations, &applied, target);
+            if reverted.is_empty() {
+                println!("no migrations to revert");
+                return Ok(());
+            }
+            for migration in reverted.iter() {
+                println!("revert: {}/{}", migration.version, migration.description);
+            }
+            if dry_run {
+                return Ok(());
+            }
+
+            MIGRATOR.undo(&pool, target).await?;
+            tracing::info!("{} migrations reverted", reverted.len());
+        }
+        MigrateCommands::Status => {
+            for migration in migrations
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+            {
+                let status = if applied.contains(&migration.version) {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!(
+                    "{}/{} ({})",
+                    migration.version, migration.description, status
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 適用済みのマイグレーションのバージョンの一覧を取得する関数
+///
+/// マイグレーション管理用のテーブルがまだ無ければ作らずに空の一覧を返す。
+async fn applied_versions(pool: &Pool<Postgres>) -> Result<HashSet<i64>> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(HashSet::new());
+    }
+
+    let mut conn = pool.acquire().await?;
+    let applied = conn.list_applied_migrations().await?;
+    Ok(applied
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
+
+fn pending_migrations<'a>(
+    migrations: &[&'a Migration],
+    applied: &HashSet<i64>,
+) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .filter(|migration| !applied.contains(&migration.version))
+        .copied()
+        .collect()
+}
+
+/// `target`より新しい適用済みのマイグレーションを、取り消す順に返す関数
+fn reverted_migrations<'a>(
+    migrations: &[&'a Migration],
+    applied: &HashSet<i64>,
+    target: i64,
+) -> Vec<&'a Migration> {
+    migrations
+        .iter()
+        .rev()
+        .filter(|migration| migration.migration_type.is_down_migration())
+        .filter(|migration| applied.contains(&migration.version))
+        .filter(|migration| migration.version > target)
+        .copied()
+        .collect()
+}
+
+/// 最新のマイグレーションだけを取り消すときの目標のバージョン
+fn previous_version(applied: &HashSet<i64>) -> i64 {
+    let mut versions = applied.iter().copied().collect::<Vec<i64>>();
+    versions.sort();
+    versions.iter().rev().nth(1).copied().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pending_migrations() {
+        let migrations = MIGRATOR.iter().collect::<Vec<&Migration>>();
+
+        let pending = pending_migrations(&migrations, &HashSet::new());
+        assert_eq!(
+            pending
+                .iter()
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>(),
+            vec![
+                20230514033116,
+                20230619091439,
+                20230701000000,
+                20230801000000,
+                20230805000000,
+                20230810000000,
+                20230815000000,
+                20230820000000,
+                20230825000000,
+                20230830000000,
+                20230905000000,
+                20230910000000,
+                20230915000000,
+                20230920000000,
+                20230925000000,
+                20230930000000,
+                20231005000000,
+                20231010000000,
+                20231015000000,
+                20231020000000,
+                20231025000000
+            ]
+        );
+
+        let pending = pending_migrations(
+            &migrations,
+            &HashSet::from([20230514033116, 20230619091439]),
+        );
+        assert_eq!(pending.len(), 19);
+        assert_eq!(pending[0].version, 20230701000000);
+    }
+
+    #[test]
+    fn test_reverted_migrations() {
+        let migrations = MIGRATOR.iter().collect::<Vec<&Migration>>();
+        let applied = HashSet::from([20230514033116, 20230619091439, 20230701000000]);
+
+        let target = previous_version(&applied);
+        assert_eq!(target, 20230619091439);
+        let reverted = reverted_migrations(&migrations, &applied, target);
+        assert_eq!(reverted.len(), 1);
+        assert_eq!(reverted[0].version, 20230701000000);
+        assert!(reverted[0].migration_type.is_down_migration());
+
+        let reverted = reverted_migrations(&migrations, &applied, 0);
+        assert_eq!(
+            reverted
+                .iter()
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>(),
+            vec![20230701000000, 20230619091439, 20230514033116]
+        );
+        assert_eq!(previous_version(&HashSet::new()), 0);
+    }
+}
@@ -1,19 +1,56 @@
+pub mod affiliation;
+pub mod bench;
+pub mod config;
 pub mod crawl;
+pub mod database;
+pub mod export;
+pub mod extract;
 pub mod generate;
+pub mod import;
+pub mod migrate;
 pub mod post;
+pub mod recategorize;
+pub mod reconcile;
+pub mod refresh_stats;
+pub mod replay;
+pub mod schema;
+pub mod seed;
 pub mod server;
 pub mod update;
+pub mod vacuum;
 
 use clap::ValueEnum;
+use serde::Deserialize;
 use std::fmt;
 
-#[derive(Debug, ValueEnum, Clone)]
+#[derive(Debug, ValueEnum, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TargetDomain {
     Problems,
     Users,
     Recommend,
 }
 
+impl TargetDomain {
+    /// ドメインに対応するコアのユニークキー
+    pub fn unique_key(&self) -> Option<&'static str> {
+        match self {
+            TargetDomain::Problems => Some("problem_id"),
+            TargetDomain::Users => Some("user_name"),
+            TargetDomain::Recommend => None,
+        }
+    }
+
+    /// ドメインの設定を上書きする環境変数の接頭辞
+    pub fn env_prefix(&self) -> &'static str {
+        match self {
+            TargetDomain::Problems => "PROBLEMS",
+            TargetDomain::Users => "USERS",
+            TargetDomain::Recommend => "RECOMMENDS",
+        }
+    }
+}
+
 impl fmt::Display for TargetDomain {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -1,24 +1,44 @@
-use crate::cmd::TargetDomain;
+use crate::cmd::{config::ConfigArgs, TargetDomain};
 use anyhow::{Context, Result};
-use atcoder_search_libs::solr::core::{SolrCore, StandaloneSolrCore};
-use atcoder_search_libs::{DocumentUploader, PostDocument};
+use atcoder_search_libs::solr::core::{CommitParams, SolrCore, UpdateParams};
+use atcoder_search_libs::{DocumentUploader, PostDocument, PostOptions};
 use clap::Args;
 use std::{env, ffi::OsString, path::PathBuf};
 
 #[derive(Debug, Args)]
 pub struct PostArgs {
     domain: TargetDomain,
+    #[command(flatten)]
+    search: ConfigArgs,
     #[arg(long)]
     save_dir: Option<OsString>,
     #[arg(short, long)]
     optimize: bool,
+    /// Let Solr commit the documents within the milliseconds instead of committing at the end
+    #[arg(long)]
+    commit_within: Option<u64>,
+    /// Overwrite the documents which have the same unique key. Defaults to Solr's behavior (true)
+    #[arg(long)]
+    overwrite: Option<bool>,
+    /// Wait for a new searcher to be opened on commit. Defaults to Solr's behavior (true)
+    #[arg(long)]
+    wait_searcher: Option<bool>,
+    /// Validate the document files and print the summary without posting them
+    #[arg(long)]
+    dry_run: bool,
+    /// Field which every document must have. Defaults to the unique key of the domain
+    #[arg(long)]
+    unique_key: Option<String>,
+    /// Post only the document files changed since the last upload, without truncating the core
+    #[arg(long)]
+    skip_unchanged: bool,
 }
 
 pub async fn run(args: PostArgs) -> Result<()> {
     let save_dir: PathBuf = match args.save_dir {
         Some(save_dir) => PathBuf::from(save_dir),
         None => match env::var("DOCUMENT_SAVE_DIRECTORY") {
-            Ok(path) => PathBuf::from(path).join(&args.domain.to_string()),
+            Ok(path) => PathBuf::from(path).join(args.domain.to_string()),
             Err(e) => {
                 let message = format!("couldn't determine document save directory {:?}", e);
                 tracing::error!(message);
@@ -26,32 +46,65 @@ pub async fn run(args: PostArgs) -> Result<()> {
             }
         },
     };
-    let solr_host = env::var("SOLR_HOST").unwrap_or_else(|_| {
-                tracing::info!("SOLR_HOST environment variable is not set. Default value `http://localhost:8983` will be used.");
-                String::from("http://localhost:8983")
-            });
-
-    let core_name_key = format!("{}_CORE_NAME", args.domain.to_string().to_uppercase());
-    let core_name = match env::var(&core_name_key) {
-        Ok(core_name) => core_name,
-        Err(_) => {
-            let message = format!("{} must be set", core_name_key);
+    let unique_key = match args.unique_key.as_deref().or(args.domain.unique_key()) {
+        Some(unique_key) => unique_key.to_string(),
+        None => {
+            let message = format!("--unique-key must be specified for {}", args.domain);
             tracing::error!(message);
             anyhow::bail!(message)
         }
     };
 
-    let core = StandaloneSolrCore::new(&core_name, &solr_host).with_context(|| {
-        let message = "Failed to create Solr core client";
-        tracing::error!(message);
-        message
-    })?;
-
-    core.truncate().await?;
+    // 不正なファイルで投入が途中で失敗しないよう、truncateする前に全てのファイルを検証しておく
     let uploader = DocumentUploader::new();
-    uploader
-        .post_documents(core, &save_dir, args.optimize)
-        .await?;
+    let summary = uploader
+        .validate_documents(&save_dir, &unique_key)
+        .await
+        .with_context(|| {
+            let message = format!("Invalid document files in {}", save_dir.display());
+            tracing::error!(message);
+            message
+        })?;
+    println!("{}", summary);
+
+    if args.dry_run {
+        if args.skip_unchanged {
+            let changed = uploader.changed_document_files(&save_dir).await?;
+            println!("{} files changed since the last upload", changed.len());
+            for file in changed {
+                println!("{}", file.display());
+            }
+        }
+        tracing::info!("dry run: skip posting the documents");
+        return Ok(());
+    }
+
+    let core = args
+        .search
+        .load()?
+        .core(&args.domain)?
+        .backend(&unique_key)?;
+
+    let options = PostOptions {
+        optimize: args.optimize,
+        update: UpdateParams {
+            commit_within: args.commit_within,
+            overwrite: args.overwrite,
+        },
+        commit: CommitParams {
+            wait_searcher: args.wait_searcher,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    if args.skip_unchanged {
+        uploader
+            .post_changed_documents(core, &save_dir, &options)
+            .await?;
+    } else {
+        core.truncate().await?;
+        uploader.post_documents(core, &save_dir, &options).await?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,61 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::{migration::MIGRATOR, problems::recategorize::ContestRecategorizer},
+    types::contest::CategoryRules,
+};
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::PathBuf;
+
+// 保存されているコンテストのカテゴリを、収集し直さずに識別し直すコマンドの引数
+#[derive(Debug, Args)]
+pub struct RecategorizeArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// TOML file of the category rules applied before the built-in classification. Only the built-in classification is used when omitted
+    #[arg(long, env = "CATEGORY_RULES_FILE")]
+    rules: Option<PathBuf>,
+    /// Print the contests whose category would change without updating the database
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub async fn run(args: RecategorizeArgs) -> Result<()> {
+    let rules = match &args.rules {
+        Some(path) => CategoryRules::load(path)?,
+        None => CategoryRules::default(),
+    };
+
+    if args.dry_run {
+        let pool = args.database.connect_read_only().await?;
+        let changes = ContestRecategorizer::new(pool).plan(&rules).await?;
+        for change in changes.iter() {
+            println!("{}", change);
+        }
+        println!("{} contests would be recategorized", changes.len());
+        return Ok(());
+    }
+
+    let pool = args.database.connect().await?;
+    MIGRATOR.run(&pool).await?;
+
+    let changes = ContestRecategorizer::new(pool)
+        .run(&rules)
+        .await
+        .with_context(|| {
+            let message = "failed to recategorize the contests";
+            tracing::error!(message);
+            message
+        })?;
+    for change in changes.iter() {
+        tracing::info!("{}", change);
+    }
+    // 問題のカテゴリに反映するにはドキュメントを生成し直す必要がある
+    tracing::info!(
+        "{} contests have been recategorized with the rules {}. generate the problem documents to apply them",
+        changes.len(),
+        rules.version
+    );
+
+    Ok(())
+}
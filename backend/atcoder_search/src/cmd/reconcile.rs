@@ -0,0 +1,219 @@
+use crate::cmd::{config::ConfigArgs, database::DatabaseArgs, TargetDomain};
+use anyhow::Result;
+use atcoder_search_libs::solr::core::{CommitParams, SolrCore, UpdateParams};
+use clap::Args;
+use serde_json::{json, Map, Value};
+use sqlx::{postgres::Postgres, Pool};
+use std::collections::HashSet;
+
+/// 一度のリクエストで取得・削除するドキュメントの数
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Args)]
+pub struct ReconcileArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(flatten)]
+    search: ConfigArgs,
+    domain: TargetDomain,
+    /// Report the drift without deleting the orphaned documents
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// データベースとインデックスの差分
+#[derive(Debug, PartialEq, Eq)]
+struct Drift {
+    database: usize,
+    index: usize,
+    /// インデックスにだけ存在するドキュメントのID
+    orphaned: Vec<String>,
+    /// データベースにだけ存在する行のID
+    missing: Vec<String>,
+}
+
+impl Drift {
+    fn new(database: &HashSet<String>, index: &HashSet<String>) -> Self {
+        let mut orphaned = index.difference(database).cloned().collect::<Vec<_>>();
+        let mut missing = database.difference(index).cloned().collect::<Vec<_>>();
+        orphaned.sort();
+        missing.sort();
+
+        Self {
+            database: database.len(),
+            index: index.len(),
+            orphaned,
+            missing,
+        }
+    }
+}
+
+pub async fn run(args: ReconcileArgs) -> Result<()> {
+    let domain = args.domain.to_string();
+    let (unique_key, query) = match args.domain {
+        TargetDomain::Problems => (
+            "problem_id",
+            "SELECT problems.problem_id FROM problems JOIN contests ON problems.contest_id = contests.contest_id",
+        ),
+        TargetDomain::Users => ("user_name", r#"SELECT user_name FROM "users""#),
+        TargetDomain::Recommend => anyhow::bail!("reconcile is not supported for {}", domain),
+    };
+
+    let pool: Pool<Postgres> = args.database.connect_read_only().await?;
+    let target = args.search.load()?.core(&args.domain)?;
+    let core_name = target.name.clone();
+    let core = target.solr_core()?;
+
+    let database: HashSet<String> = sqlx::query_scalar(query)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .collect();
+    let index = indexed_ids(&core, unique_key).await?;
+
+    let drift = Drift::new(&database, &index);
+    println!(
+        "database: {}, index: {}, orphaned: {}, missing: {}",
+        drift.database,
+        drift.index,
+        drift.orphaned.len(),
+        drift.missing.len()
+    );
+    for id in drift.orphaned.iter() {
+        println!("orphaned: {}", id);
+    }
+    for id in drift.missing.iter() {
+        println!("missing: {}", id);
+    }
+
+    if args.dry_run || drift.orphaned.is_empty() {
+        return Ok(());
+    }
+    delete_documents(&core, &drift.orphaned).await?;
+    tracing::info!(
+        "{} orphaned documents deleted from {}",
+        drift.orphaned.len(),
+        core_name
+    );
+
+    Ok(())
+}
+
+/// カーソルでページングしながらインデックスに存在する全てのドキュメントのIDを取得する関数
+async fn indexed_ids<C: SolrCore + Sync>(core: &C, unique_key: &str) -> Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+    let mut cursor = String::from("*");
+    loop {
+        let params = [
+            ("q", String::from("*:*")),
+            ("fl", String::from(unique_key)),
+            ("sort", format!("{} asc", unique_key)),
+            ("rows", BATCH_SIZE.to_string()),
+            ("cursorMark", cursor.clone()),
+        ];
+        let res = core.select::<Map<String, Value>, Value>(&params).await?;
+        ids.extend(res.response.docs.iter().filter_map(|doc| {
+            doc.get(unique_key)
+                .and_then(Value::as_str)
+                .map(String::from)
+        }));
+
+        match res.next_cursor_mark {
+            Some(next) if next != cursor => cursor = next,
+            _ => break,
+        }
+    }
+
+    Ok(ids)
+}
+
+/// IDを指定してドキュメントを削除し、コミットする関数
+async fn delete_documents<C: SolrCore + Sync>(core: &C, ids: &[String]) -> Result<()> {
+    for chunk in ids.chunks(BATCH_SIZE) {
+        let body = serde_json::to_vec(&json!({ "delete": chunk }))?;
+        if let Err(e) = core.post(body, &UpdateParams::default()).await {
+            core.rollback().await?;
+            return Err(anyhow::anyhow!(e));
+        }
+    }
+    core.commit(&CommitParams::default()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::{MockRequest, MockSolrCore};
+
+    fn ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_drift() {
+        let drift = Drift::new(
+            &ids(&["abc001_a", "abc001_b", "abc002_a"]),
+            &ids(&["abc001_a", "abc001_b", "abc001_c", "arc001_a"]),
+        );
+        assert_eq!(
+            drift,
+            Drift {
+                database: 3,
+                index: 4,
+                orphaned: vec![String::from("abc001_c"), String::from("arc001_a")],
+                missing: vec![String::from("abc002_a")],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indexed_ids() {
+        let core = MockSolrCore::new("problems");
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 0},
+            "response": {"numFound": 3, "start": 0, "numFoundExact": true, "docs": [{"problem_id": "abc001_a"}, {"problem_id": "abc001_b"}]},
+            "nextCursorMark": "AoE1"
+        }));
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 0},
+            "response": {"numFound": 3, "start": 0, "numFoundExact": true, "docs": [{"problem_id": "abc001_c"}]},
+            "nextCursorMark": "AoE2"
+        }));
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 0},
+            "response": {"numFound": 3, "start": 0, "numFoundExact": true, "docs": []},
+            "nextCursorMark": "AoE2"
+        }));
+
+        let indexed = indexed_ids(&core, "problem_id").await.unwrap();
+        assert_eq!(indexed, ids(&["abc001_a", "abc001_b", "abc001_c"]));
+
+        let cursors = core
+            .selects()
+            .into_iter()
+            .map(|params| {
+                params
+                    .into_iter()
+                    .find(|(key, _)| key == "cursorMark")
+                    .map(|(_, value)| value)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(cursors, vec!["*", "AoE1", "AoE2"]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_documents() {
+        let core = MockSolrCore::new("problems");
+        let orphaned = vec![String::from("abc001_c"), String::from("arc001_a")];
+
+        delete_documents(&core, &orphaned).await.unwrap();
+        assert_eq!(
+            core.requests(),
+            vec![
+                MockRequest::Post(Some(br#"{"delete":["abc001_c","arc001_a"]}"#.to_vec())),
+                MockRequest::Commit,
+            ]
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::{migration::MIGRATOR, stats_views::StatsViews},
+};
+use anyhow::{Context, Result};
+use clap::Args;
+
+// 提出の集計結果のマテリアライズドビューを更新するコマンドの引数
+//
+// 提出を収集した後、問題のドキュメントを生成する前に実行する。
+#[derive(Debug, Args)]
+pub struct RefreshStatsArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+}
+
+pub async fn run(args: RefreshStatsArgs) -> Result<()> {
+    let pool = args.database.connect().await?;
+    MIGRATOR.run(&pool).await?;
+
+    StatsViews::new(pool).refresh().await.with_context(|| {
+        let message = "failed to refresh the submission stats";
+        tracing::error!(message);
+        message
+    })?;
+
+    Ok(())
+}
@@ -0,0 +1,459 @@
+use crate::{
+    cmd::TargetDomain,
+    modules::handlers::{problem::ProblemSearchParameter, user::UserSearchParameter},
+};
+use anyhow::{Context, Result};
+use atcoder_search_libs::{
+    solr::core::{SolrCore, StandaloneSolrCore},
+    ToQueryParameter,
+};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashSet, env, path::PathBuf};
+use tokio::time::Instant;
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+    domain: TargetDomain,
+    /// JSONL file of logged queries. Each line is an object like `{"query": "keyword=dp&sort=-score"}`
+    #[arg(long)]
+    input: PathBuf,
+    /// Name of the Solr core that serves the current ranking
+    #[arg(long)]
+    baseline: String,
+    /// Name of the Solr core to compare with the baseline
+    #[arg(long)]
+    target: String,
+    /// Number of top results to compare
+    #[arg(long, default_value_t = 10)]
+    top_k: u32,
+    /// Fail if the mean overlap of the top results falls below this ratio
+    #[arg(long)]
+    min_overlap: Option<f64>,
+    /// Write the comparison of each query to this file as JSON
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// ログに記録された検索クエリ
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct LoggedQuery {
+    /// 検索APIに与えられたクエリ文字列
+    query: String,
+}
+
+/// 1つのコアでクエリを実行した結果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct QueryRun {
+    ids: Vec<String>,
+    /// リクエストにかかった時間(ミリ秒)
+    elapsed: f64,
+}
+
+/// 1つのクエリのベースラインとターゲットの比較
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct QueryComparison {
+    query: String,
+    baseline: QueryRun,
+    target: QueryRun,
+    /// ベースラインの上位K件のうちターゲットの上位K件にも含まれる割合
+    overlap: f64,
+    /// 上位K件が順序まで一致しているかどうか
+    identical: bool,
+}
+
+impl QueryComparison {
+    fn new(query: String, baseline: QueryRun, target: QueryRun) -> Self {
+        let target_ids: HashSet<&String> = target.ids.iter().collect();
+        let common = baseline
+            .ids
+            .iter()
+            .filter(|id| target_ids.contains(id))
+            .count();
+        let overlap = if baseline.ids.is_empty() {
+            if target.ids.is_empty() {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            common as f64 / baseline.ids.len() as f64
+        };
+        let identical = baseline.ids == target.ids;
+
+        Self {
+            query,
+            baseline,
+            target,
+            overlap,
+            identical,
+        }
+    }
+}
+
+/// リクエストにかかった時間の分布
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Latency {
+    p50: f64,
+    p95: f64,
+    max: f64,
+}
+
+impl Latency {
+    fn new(elapsed: &[f64]) -> Self {
+        let mut elapsed = elapsed.to_vec();
+        elapsed.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            p50: percentile(&elapsed, 50.0),
+            p95: percentile(&elapsed, 95.0),
+            max: elapsed.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+// ソート済みの値の`p`パーセンタイルを最近傍法で求める関数
+pub(crate) fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// リプレイ結果の集計
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ReplayReport {
+    queries: usize,
+    mean_overlap: f64,
+    identical: usize,
+    baseline_latency: Latency,
+    target_latency: Latency,
+    comparisons: Vec<QueryComparison>,
+}
+
+impl ReplayReport {
+    fn new(comparisons: Vec<QueryComparison>) -> Self {
+        let queries = comparisons.len();
+        let mean_overlap = if queries == 0 {
+            1.0
+        } else {
+            comparisons.iter().map(|c| c.overlap).sum::<f64>() / queries as f64
+        };
+        let elapsed =
+            |f: fn(&QueryComparison) -> f64| comparisons.iter().map(f).collect::<Vec<_>>();
+
+        Self {
+            queries,
+            mean_overlap,
+            identical: comparisons.iter().filter(|c| c.identical).count(),
+            baseline_latency: Latency::new(&elapsed(|c| c.baseline.elapsed)),
+            target_latency: Latency::new(&elapsed(|c| c.target.elapsed)),
+            comparisons,
+        }
+    }
+}
+
+pub async fn run(args: ReplayArgs) -> Result<()> {
+    let unique_key = match args.domain.unique_key() {
+        Some(unique_key) => unique_key,
+        None => anyhow::bail!("replay is not supported for {}", args.domain),
+    };
+    let queries = read_queries(&args.input).await?;
+    tracing::info!(
+        "{} queries loaded from {}",
+        queries.len(),
+        args.input.display()
+    );
+
+    let solr_host = env::var("SOLR_HOST").unwrap_or_else(|_| {
+        tracing::info!("SOLR_HOST environment variable is not set. Default value `http://localhost:8983` will be used.");
+        String::from("http://localhost:8983")
+    });
+    let baseline = StandaloneSolrCore::new(&args.baseline, &solr_host)?;
+    let target = StandaloneSolrCore::new(&args.target, &solr_host)?;
+
+    let report = replay(
+        &baseline,
+        &target,
+        &queries,
+        &args.domain,
+        unique_key,
+        args.top_k,
+    )
+    .await?;
+
+    println!(
+        "queries: {}, mean overlap@{}: {:.3}, identical: {}",
+        report.queries, args.top_k, report.mean_overlap, report.identical
+    );
+    println!(
+        "{:<10} {:>10} {:>10} {:>10}",
+        "latency", "p50 (ms)", "p95 (ms)", "max (ms)"
+    );
+    for (name, latency) in [
+        (&args.baseline, &report.baseline_latency),
+        (&args.target, &report.target_latency),
+    ] {
+        println!(
+            "{:<10} {:>10.1} {:>10.1} {:>10.1}",
+            name, latency.p50, latency.p95, latency.max
+        );
+    }
+    for comparison in report.comparisons.iter().filter(|c| c.overlap < 1.0) {
+        println!("overlap {:.3}: {}", comparison.overlap, comparison.query);
+    }
+
+    if let Some(output) = &args.output {
+        tokio::fs::write(output, serde_json::to_vec_pretty(&report)?).await?;
+        tracing::info!("The report is written to {}", output.display());
+    }
+
+    if let Some(min_overlap) = args.min_overlap {
+        if report.mean_overlap < min_overlap {
+            let message = format!(
+                "mean overlap {:.3} is below the threshold {}",
+                report.mean_overlap, min_overlap
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        }
+    }
+
+    Ok(())
+}
+
+/// JSONL形式のクエリログを読み込む関数
+async fn read_queries(path: &PathBuf) -> Result<Vec<LoggedQuery>> {
+    let content = tokio::fs::read_to_string(path).await.with_context(|| {
+        let message = format!("failed to read the query log {}", path.display());
+        tracing::error!(message);
+        message
+    })?;
+    parse_queries(&content)
+}
+
+// 空行を除いた各行をクエリとしてパースする関数
+fn parse_queries(content: &str) -> Result<Vec<LoggedQuery>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| {
+                let message = format!("invalid query log at line {}", i + 1);
+                tracing::error!(message);
+                message
+            })
+        })
+        .collect()
+}
+
+/// クエリ文字列を、上位K件のIDだけを取得するSolrのパラメータに変換する関数
+fn to_solr_params(
+    domain: &TargetDomain,
+    query: &str,
+    unique_key: &str,
+    top_k: u32,
+) -> Result<Vec<(String, String)>> {
+    let fields = Some(vec![String::from(unique_key)]);
+    let params = match domain {
+        TargetDomain::Problems => {
+            let params: ProblemSearchParameter = serde_structuredqs::from_str(query)?;
+            ProblemSearchParameter {
+                limit: Some(top_k),
+                page: None,
+                facet: None,
+                stats: None,
+                fields,
+                debug: None,
+                count_only: None,
+                ..params
+            }
+            .to_query()
+        }
+        TargetDomain::Users => {
+            let params: UserSearchParameter = serde_structuredqs::from_str(query)?;
+            UserSearchParameter {
+                limit: Some(top_k),
+                page: None,
+                facet: None,
+                stats: None,
+                fields,
+                debug: None,
+                count_only: None,
+                ..params
+            }
+            .to_query()
+        }
+        TargetDomain::Recommend => anyhow::bail!("replay is not supported for {}", domain),
+    };
+
+    Ok(params)
+}
+
+/// 全てのクエリをベースラインとターゲットのコアで実行して比較する関数
+async fn replay<C: SolrCore + Sync>(
+    baseline: &C,
+    target: &C,
+    queries: &[LoggedQuery],
+    domain: &TargetDomain,
+    unique_key: &str,
+    top_k: u32,
+) -> Result<ReplayReport> {
+    let mut comparisons = Vec::with_capacity(queries.len());
+    for query in queries.iter() {
+        let params = match to_solr_params(domain, &query.query, unique_key, top_k) {
+            Ok(params) => params,
+            Err(e) => {
+                tracing::warn!("skip the query {} cause: {:?}", query.query, e);
+                continue;
+            }
+        };
+
+        let baseline_run = run_query(baseline, &params, unique_key).await?;
+        let target_run = run_query(target, &params, unique_key).await?;
+        comparisons.push(QueryComparison::new(
+            query.query.clone(),
+            baseline_run,
+            target_run,
+        ));
+    }
+
+    Ok(ReplayReport::new(comparisons))
+}
+
+/// クエリを実行して、ヒットしたドキュメントのIDと時間を返す関数
+async fn run_query<C: SolrCore + Sync>(
+    core: &C,
+    params: &[(String, String)],
+    unique_key: &str,
+) -> Result<QueryRun> {
+    let start = Instant::now();
+    let res = core.select::<Map<String, Value>, Value>(params).await?;
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+    let ids = res
+        .response
+        .docs
+        .iter()
+        .filter_map(|doc| {
+            doc.get(unique_key)
+                .and_then(Value::as_str)
+                .map(String::from)
+        })
+        .collect();
+
+    Ok(QueryRun { ids, elapsed })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::MockSolrCore;
+    use serde_json::json;
+
+    fn run(ids: &[&str], elapsed: f64) -> QueryRun {
+        QueryRun {
+            ids: ids.iter().map(|id| id.to_string()).collect(),
+            elapsed,
+        }
+    }
+
+    fn push_ids(core: &MockSolrCore, ids: &[&str]) {
+        let docs: Vec<Value> = ids.iter().map(|id| json!({ "problem_id": id })).collect();
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 0},
+            "response": {"numFound": ids.len(), "start": 0, "numFoundExact": true, "docs": docs}
+        }));
+    }
+
+    #[test]
+    fn test_query_comparison() {
+        let comparison = QueryComparison::new(
+            String::from("keyword=dp"),
+            run(&["abc001_a", "abc001_b", "abc001_c", "abc001_d"], 10.0),
+            run(&["abc001_b", "abc001_a", "abc001_e", "abc001_d"], 12.0),
+        );
+        assert_eq!(comparison.overlap, 0.75);
+        assert!(!comparison.identical);
+
+        let comparison =
+            QueryComparison::new(String::from("keyword=xyz"), run(&[], 1.0), run(&[], 1.0));
+        assert_eq!(comparison.overlap, 1.0);
+        assert!(comparison.identical);
+    }
+
+    #[test]
+    fn test_latency() {
+        let elapsed: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(
+            Latency::new(&elapsed),
+            Latency {
+                p50: 10.0,
+                p95: 19.0,
+                max: 20.0
+            }
+        );
+        assert_eq!(Latency::new(&[]).max, 0.0);
+    }
+
+    #[test]
+    fn test_parse_queries() {
+        let queries = parse_queries(
+            "{\"query\": \"keyword=dp\"}\n\n{\"query\": \"keyword=graph&sort=-score\"}\n",
+        )
+        .unwrap();
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[1].query, "keyword=graph&sort=-score");
+        assert!(parse_queries("{\"query\": \"keyword=dp\"}\nkeyword=dp").is_err());
+    }
+
+    #[test]
+    fn test_to_solr_params() {
+        let params = to_solr_params(
+            &TargetDomain::Problems,
+            "keyword=dp&page=3&limit=50&facet=category",
+            "problem_id",
+            5,
+        )
+        .unwrap();
+        assert!(params.contains(&(String::from("rows"), String::from("5"))));
+        assert!(params.contains(&(String::from("start"), String::from("0"))));
+        assert!(params.contains(&(String::from("fl"), String::from("problem_id"))));
+        assert!(!params.iter().any(|(key, _)| key == "json.facet"));
+    }
+
+    #[tokio::test]
+    async fn test_replay() {
+        let baseline = MockSolrCore::new("problems");
+        let target = MockSolrCore::new("problems_experimental");
+        push_ids(&baseline, &["abc001_a", "abc001_b"]);
+        push_ids(&target, &["abc001_a", "abc001_c"]);
+        push_ids(&baseline, &["abc002_a"]);
+        push_ids(&target, &["abc002_a"]);
+        let queries = parse_queries(
+            "{\"query\": \"keyword=dp\"}\n{\"query\": \"sort=invalid&limit=x\"}\n{\"query\": \"keyword=graph\"}",
+        )
+        .unwrap();
+
+        let report = replay(
+            &baseline,
+            &target,
+            &queries,
+            &TargetDomain::Problems,
+            "problem_id",
+            10,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.queries, 2);
+        assert_eq!(report.mean_overlap, 0.75);
+        assert_eq!(report.identical, 1);
+        assert_eq!(
+            report.comparisons[0].target.ids,
+            vec!["abc001_a", "abc001_c"]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{
+    cmd::{config::ConfigArgs, TargetDomain},
+    modules::{problems::generator::ProblemIndex, users::generator::UserIndex},
+};
+use anyhow::{Context, Result};
+use atcoder_search_libs::{
+    schema::{apply_schema, SolrSchema},
+    ExpandMode,
+};
+use clap::{Args, Subcommand};
+use serde_json::Value;
+
+#[derive(Debug, Args)]
+pub struct SchemaArgs {
+    #[command(subcommand)]
+    command: SchemaCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SchemaCommands {
+    /// Print the Schema API request body generated from the document struct
+    Show {
+        domain: TargetDomain,
+        /// Add the copyField directives needed by documents generated with this expand mode
+        #[arg(long, default_value_t = ExpandMode::Inline)]
+        expand_mode: ExpandMode,
+    },
+    /// Add or replace the fields of the Solr core to match the document struct
+    Apply {
+        domain: TargetDomain,
+        #[command(flatten)]
+        search: ConfigArgs,
+        /// Add the copyField directives needed by documents generated with this expand mode
+        #[arg(long, default_value_t = ExpandMode::Inline)]
+        expand_mode: ExpandMode,
+    },
+}
+
+pub async fn run(args: SchemaArgs) -> Result<()> {
+    match args.command {
+        SchemaCommands::Show {
+            domain,
+            expand_mode,
+        } => {
+            let schema = match domain {
+                TargetDomain::Problems => ProblemIndex::schema_with(expand_mode),
+                TargetDomain::Users => UserIndex::schema_with(expand_mode),
+                TargetDomain::Recommend => {
+                    anyhow::bail!("schema generation is not supported for {}", domain)
+                }
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        SchemaCommands::Apply {
+            domain,
+            search,
+            expand_mode,
+        } => {
+            let target = search.load()?.core(&domain)?;
+            let core_name = target.name.clone();
+            let core = target.solr_core()?;
+
+            let commands: Value = match domain {
+                TargetDomain::Problems => apply_schema::<ProblemIndex, _>(&core, expand_mode).await,
+                TargetDomain::Users => apply_schema::<UserIndex, _>(&core, expand_mode).await,
+                TargetDomain::Recommend => {
+                    anyhow::bail!("schema generation is not supported for {}", domain)
+                }
+            }
+            .with_context(|| {
+                let message = format!("Failed to apply schema to {}", core_name);
+                tracing::error!(message);
+                message
+            })?;
+            tracing::debug!("applied schema commands: {}", commands);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::problems::category_relationship::{
+        parse_category_relationships, CategoryRelationshipStore, BUNDLED_CATEGORY_RELATIONSHIPS,
+    },
+};
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct SeedArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(subcommand)]
+    command: SeedCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SeedCommands {
+    /// Load the weights of the relationships between problem categories used by the recommendation
+    CategoryRelations {
+        /// CSV file of `from_category,to_category,weight` rows. The weights bundled with the crate are loaded when omitted
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Delete the relationships which are not in the CSV
+        #[arg(long)]
+        replace: bool,
+    },
+}
+
+pub async fn run(args: SeedArgs) -> Result<()> {
+    match args.command {
+        SeedCommands::CategoryRelations { file, replace } => {
+            let csv = match &file {
+                Some(path) => std::fs::read_to_string(path).with_context(|| {
+                    let message = format!("failed to read {}", path.display());
+                    tracing::error!(message);
+                    message
+                })?,
+                None => String::from(BUNDLED_CATEGORY_RELATIONSHIPS),
+            };
+            let relationships = parse_category_relationships(&csv).with_context(|| {
+                let message = "failed to parse the category relationships";
+                tracing::error!(message);
+                message
+            })?;
+
+            let store = CategoryRelationshipStore::new(args.database.connect().await?);
+            let saved = store.save(&relationships, replace).await.with_context(|| {
+                let message = "failed to save the category relationships";
+                tracing::error!(message);
+                message
+            })?;
+            // レコメンドに反映するにはドキュメントを生成し直す必要がある
+            tracing::info!(
+                "{} category relationships have been saved. generate the recommend documents to apply them",
+                saved
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -1,13 +1,300 @@
-use anyhow::Result;
+use crate::{
+    cmd::{
+        config::{ConfigArgs, SearchConfig},
+        database::DatabaseArgs,
+        TargetDomain,
+    },
+    modules::{
+        handlers::ServerConfig,
+        problems::{generator::ProblemDocumentGenerator, html_storage::HtmlStorage},
+        users::generator::UserDocumentGenerator,
+        warmup::{Warmup, WarmupConfig},
+    },
+};
+use anyhow::{Context, Result};
+use atcoder_search_libs::{
+    solr::{
+        backend::{BackendKind, SearchBackend},
+        core::{CommitParams, SolrCore},
+    },
+    DocumentFormat, DocumentUploader, ExpandMode, PostDocument, PostOptions, ProgressReporter,
+};
 use clap::Args;
+use sqlx::{postgres::Postgres, Pool};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 #[derive(Debug, Args)]
 pub struct UpdateIndexArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    #[command(flatten)]
+    search: ConfigArgs,
     #[arg(long)]
-    all: bool,
+    domain: TargetDomain,
+    #[arg(short, long, conflicts_with = "soft")]
+    optimize: bool,
+    /// Make the documents searchable with a soft commit, leaving the hard commit to Solr's autoCommit
+    #[arg(long)]
+    soft: bool,
+    /// Keep the generated document files instead of deleting them after the update
+    #[arg(long)]
+    keep_artifacts: bool,
+    /// Allowed relative difference between the number of indexed documents and the database rows
+    #[arg(long, default_value_t = 0.01)]
+    tolerance: f64,
+    #[arg(long, default_value_t = 64)]
+    workers: usize,
+    /// How to output the suffixed fields. `copyfield` leaves copying them to the copyField directives of Solr (apply the schema with the same mode)
+    #[arg(long, default_value_t = ExpandMode::Inline)]
+    expand_mode: ExpandMode,
+    /// TOML file listing the search queries run against the core after it is swapped in
+    #[arg(long, env = "SEARCH_WARMUP")]
+    warmup: Option<PathBuf>,
+}
+
+/// インデックスを更新するときのオプション
+#[derive(Debug, Clone)]
+pub struct UpdateOptions {
+    pub optimize: bool,
+    pub soft: bool,
+    pub keep_artifacts: bool,
+    pub tolerance: f64,
+    pub workers: usize,
+    /// サフィックス付きフィールドの出力方法
+    pub expand_mode: ExpandMode,
+    /// 入れ替えた後の本番のコアで実行するウォームアップ
+    pub warmup: Option<Arc<Warmup>>,
+}
+
+impl Default for UpdateOptions {
+    fn default() -> Self {
+        Self {
+            optimize: false,
+            soft: false,
+            keep_artifacts: false,
+            tolerance: 0.01,
+            workers: 64,
+            expand_mode: ExpandMode::Inline,
+            warmup: None,
+        }
+    }
 }
 
 pub async fn run(args: UpdateIndexArgs) -> Result<()> {
-    println!("update index with {:?}", args);
+    let pool: Pool<Postgres> = args.database.connect_read_only().await?;
+    let options = UpdateOptions {
+        optimize: args.optimize,
+        soft: args.soft,
+        keep_artifacts: args.keep_artifacts,
+        tolerance: args.tolerance,
+        workers: args.workers,
+        expand_mode: args.expand_mode,
+        warmup: match &args.warmup {
+            Some(path) => Some(Arc::new(Warmup::new(
+                &WarmupConfig::load(path)?,
+                &ServerConfig::default(),
+            )?)),
+            None => None,
+        },
+    };
+
+    let search = args.search.load()?;
+    update_index(&pool, &search, &args.domain, &options, None).await
+}
+
+/// ドキュメントを生成してステージング用のコアへ投入し、検証してから本番のコアと入れ替える関数
+///
+/// `progress`が与えられればドキュメントの生成・投入の進捗を通知する。
+pub async fn update_index(
+    pool: &Pool<Postgres>,
+    search: &SearchConfig,
+    domain: &TargetDomain,
+    options: &UpdateOptions,
+    progress: Option<ProgressReporter>,
+) -> Result<()> {
+    let unique_key = match domain.unique_key() {
+        Some(unique_key) => unique_key,
+        None => anyhow::bail!("update is not supported for {}", domain),
+    };
+    let domain_name = domain.to_string();
+
+    let core = search.core(domain)?;
+    let core_name = core.name.clone();
+    let staging = search.staging_core(domain)?.backend(unique_key)?;
+    // 生成と投入を終えてからスワップで失敗しないよう、先に確認しておく
+    if staging.kind() != BackendKind::Solr {
+        anyhow::bail!(
+            "update swaps the staging core and is not supported by {}",
+            staging.kind()
+        );
+    }
+
+    let save_dir = env::temp_dir().join(format!(
+        "atcoder_search_{}_{}",
+        domain_name,
+        chrono::Local::now().format("%Y%m%d%H%M%S")
+    ));
+    tokio::fs::create_dir_all(&save_dir).await?;
+    tracing::info!("Documents will be save at {}", save_dir.display());
+
+    let result = update(
+        domain, options, progress, pool, &staging, &core_name, &save_dir, unique_key,
+    )
+    .await;
+
+    if options.keep_artifacts {
+        tracing::info!("The document files are kept at {}", save_dir.display());
+    } else if let Err(e) = tokio::fs::remove_dir_all(&save_dir).await {
+        tracing::warn!(
+            "failed to delete the directory {} cause {:?}",
+            save_dir.display(),
+            e
+        );
+    }
+
+    // 入れ替えた直後の本番のコアはキャッシュが空なので、利用者より先に代表的なクエリを実行しておく
+    if let (Ok(_), Some(warmup)) = (&result, &options.warmup) {
+        warmup.run(domain, &core.backend(unique_key)?).await;
+    }
+
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn update(
+    domain: &TargetDomain,
+    options: &UpdateOptions,
+    progress: Option<ProgressReporter>,
+    pool: &Pool<Postgres>,
+    staging: &SearchBackend,
+    core_name: &str,
+    save_dir: &Path,
+    unique_key: &str,
+) -> Result<()> {
+    // 1. 一時ディレクトリにドキュメントを生成する
+    let expected: i64 = match domain {
+        TargetDomain::Problems => {
+            ProblemDocumentGenerator::new(pool, save_dir)
+                .with_progress(progress.clone())
+                .with_html_storage(HtmlStorage::from_env()?)
+                .with_expand_mode(options.expand_mode)
+                .run(DocumentFormat::Json, false, options.workers)
+                .await?;
+            sqlx::query_scalar(
+                "SELECT COUNT(*) FROM problems JOIN contests ON problems.contest_id = contests.contest_id",
+            )
+            .fetch_one(pool)
+            .await?
+        }
+        TargetDomain::Users => {
+            UserDocumentGenerator::new(pool, save_dir)
+                .with_progress(progress.clone())
+                .run(DocumentFormat::Json, false, options.workers)
+                .await?;
+            sqlx::query_scalar(r#"SELECT COUNT(*) FROM "users""#)
+                .fetch_one(pool)
+                .await?
+        }
+        TargetDomain::Recommend => anyhow::bail!("update is not supported for recommend"),
+    };
+
+    // 2. ステージング用のコアへ投入する
+    let uploader = DocumentUploader::new();
+    let summary = uploader
+        .validate_documents(save_dir, unique_key)
+        .await
+        .with_context(|| {
+            let message = format!("Invalid document files in {}", save_dir.display());
+            tracing::error!(message);
+            message
+        })?;
+    tracing::info!(
+        "{} documents in {} files will be posted",
+        summary.total_documents(),
+        summary.files.len()
+    );
+
+    staging.truncate().await?;
+    uploader
+        .post_documents(
+            staging.clone(),
+            save_dir,
+            &PostOptions {
+                optimize: options.optimize,
+                commit: CommitParams {
+                    soft_commit: options.soft,
+                    ..Default::default()
+                },
+                progress,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    // 3. ドキュメント数を検証してから本番のコアと入れ替える
+    verify_and_swap(staging, core_name, expected, options.tolerance).await
+}
+
+/// ステージング用のコアのドキュメント数がデータベースの行数と許容誤差の範囲で一致すれば、本番のコアと入れ替える関数
+async fn verify_and_swap<C: SolrCore + Sync>(
+    staging: &C,
+    core_name: &str,
+    expected: i64,
+    tolerance: f64,
+) -> Result<()> {
+    let indexed = staging.status().await?.index.num_docs as i64;
+    let difference = (indexed - expected).abs() as f64;
+    if difference > expected as f64 * tolerance {
+        let message = format!(
+            "the number of indexed documents {} differs from the number of rows {} beyond the tolerance {}",
+            indexed, expected, tolerance
+        );
+        tracing::error!(message);
+        anyhow::bail!(message)
+    }
+
+    staging.swap(core_name).await?;
+    tracing::info!(
+        "{} documents have been indexed and swapped into {}",
+        indexed,
+        core_name
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::{MockRequest, MockSolrCore};
+
+    #[tokio::test]
+    async fn test_verify_and_swap() {
+        let staging = MockSolrCore::new("problems_staging");
+        staging.set_num_docs(995);
+
+        verify_and_swap(&staging, "problems", 1000, 0.01)
+            .await
+            .unwrap();
+        assert_eq!(
+            staging.requests(),
+            vec![
+                MockRequest::Status,
+                MockRequest::Swap(String::from("problems"))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_and_swap_beyond_tolerance() {
+        let staging = MockSolrCore::new("problems_staging");
+        staging.set_num_docs(900);
+
+        let result = verify_and_swap(&staging, "problems", 1000, 0.01).await;
+        assert!(result.is_err());
+        assert_eq!(staging.requests(), vec![MockRequest::Status]);
+    }
+}
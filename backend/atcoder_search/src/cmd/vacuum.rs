@@ -0,0 +1,46 @@
+use crate::{
+    cmd::database::DatabaseArgs,
+    modules::{
+        migration::MIGRATOR,
+        problems::{html_storage::HtmlStorage, vacuum::Vacuum},
+    },
+};
+use anyhow::{Context, Result};
+use chrono::Duration as ChronoDuration;
+use clap::Args;
+
+#[derive(Debug, Args)]
+pub struct VacuumArgs {
+    #[command(flatten)]
+    database: DatabaseArgs,
+    /// Days to keep statement versions and problem HTML after they have been superseded by a newer one
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(i64).range(0..))]
+    retention_days: i64,
+    /// Only print how many old versions would be deleted, without deleting them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub async fn run(args: VacuumArgs) -> Result<()> {
+    let retention = ChronoDuration::days(args.retention_days);
+
+    if args.dry_run {
+        let pool = args.database.connect_read_only().await?;
+        let report = Vacuum::new(&pool, retention).plan().await?;
+        println!("vacuum: {} would be deleted", report);
+        return Ok(());
+    }
+
+    let pool = args.database.connect().await?;
+    MIGRATOR.run(&pool).await?;
+
+    let vacuum = Vacuum::new(&pool, retention).with_html_storage(HtmlStorage::from_env()?);
+    let report = vacuum.run().await.with_context(|| {
+        let message = "failed to delete the old versions";
+        tracing::error!(message);
+        message
+    })?;
+    tracing::info!("vacuum finished: {} reclaimed", report);
+
+    Ok(())
+}
@@ -2,70 +2,185 @@ mod cmd;
 mod modules;
 mod types;
 
-use crate::cmd::{
-    crawl::{self, CrawlArgs},
-    generate::{self, GenerateArgs},
-    post::{self, PostArgs},
-    server::{self, ServerArgs},
-    update::{self, UpdateIndexArgs},
+use crate::{
+    cmd::{
+        affiliation::{self, AffiliationArgs},
+        bench::{self, BenchArgs},
+        crawl::{self, CrawlArgs},
+        export::{self, ExportArgs},
+        extract::{self, ExtractArgs},
+        generate::{self, GenerateArgs},
+        import::{self, ImportArgs},
+        migrate::{self, MigrateArgs},
+        post::{self, PostArgs},
+        recategorize::{self, RecategorizeArgs},
+        reconcile::{self, ReconcileArgs},
+        refresh_stats::{self, RefreshStatsArgs},
+        replay::{self, ReplayArgs},
+        schema::{self, SchemaArgs},
+        seed::{self, SeedArgs},
+        server::{self, ServerArgs},
+        update::{self, UpdateIndexArgs},
+        vacuum::{self, VacuumArgs},
+    },
+    modules::access_log::ACCESS_LOG_TARGET,
 };
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use dotenvy::dotenv;
-use std::{env, str::FromStr};
+use std::{env, io, path::PathBuf, str::FromStr};
 use tokio::runtime::Builder;
+use tracing::Metadata;
+use tracing_appender::{
+    non_blocking::{NonBlockingBuilder, WorkerGuard},
+    rolling,
+};
 use tracing_subscriber::{
-    filter::{EnvFilter, LevelFilter},
+    filter::{filter_fn, EnvFilter, FilterExt, LevelFilter},
     fmt::{self, time::OffsetTime},
+    prelude::*,
+    Layer,
 };
 
+// ログファイル名の接頭辞。日付が付いて`atcoder_search.log.2023-09-01`のようになる
+const LOG_FILE_PREFIX: &str = "atcoder_search.log";
+
 #[derive(Debug, Parser)]
 #[command(name = "atcoder_search")]
 #[command(about = "AtCoder Search")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// ログの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+/// ログの出力に関するオプション
+#[derive(Debug, Args)]
+struct LogArgs {
+    /// Format of the log output. `json` writes one JSON object per line
+    #[arg(long, global = true, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Write the logs to files in this directory rotated daily instead of the standard output
+    #[arg(long, global = true, env = "LOG_DIRECTORY")]
+    log_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
+    Affiliation(AffiliationArgs),
+    Bench(BenchArgs),
     Crawl(CrawlArgs),
+    Export(ExportArgs),
+    Extract(ExtractArgs),
     Generate(GenerateArgs),
+    Import(ImportArgs),
+    Migrate(MigrateArgs),
     Post(PostArgs),
+    Recategorize(RecategorizeArgs),
+    Reconcile(ReconcileArgs),
+    RefreshStats(RefreshStatsArgs),
+    Replay(ReplayArgs),
+    Schema(SchemaArgs),
+    Seed(SeedArgs),
     Server(ServerArgs),
     Update(UpdateIndexArgs),
+    Vacuum(VacuumArgs),
 }
 
 fn main() {
     dotenv().ok();
 
-    let log_level = env::var("RUST_LOG").unwrap_or(String::from("info"));
-    let filter = EnvFilter::builder()
-        .with_default_directive(
-            LevelFilter::from_str(&log_level)
-                .expect("couldn't parse specified log level")
-                .into(),
-        )
-        .from_env_lossy();
-    let format = fmt::format()
-        .with_level(true)
-        .with_target(true)
-        .with_ansi(false)
-        .with_thread_ids(true)
-        .with_timer(OffsetTime::local_rfc_3339().unwrap());
-    let subscriber = tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .event_format(format)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("failed to set tracing subscriber");
+    let cli = Cli::parse();
+    // ガードを破棄するとバッファに残ったログが書き出されなくなるので、終了まで保持する
+    let _guard = init_tracing(&cli.log);
 
     let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
 
-    match Cli::parse().command {
+    match cli.command {
+        Commands::Affiliation(args) => runtime.block_on(affiliation::run(args)),
+        Commands::Bench(args) => runtime.block_on(bench::run(args)),
         Commands::Crawl(args) => runtime.block_on(crawl::run(args)),
+        Commands::Export(args) => runtime.block_on(export::run(args)),
+        Commands::Extract(args) => runtime.block_on(extract::run(args)),
         Commands::Generate(args) => runtime.block_on(generate::run(args)),
+        Commands::Import(args) => runtime.block_on(import::run(args)),
+        Commands::Migrate(args) => runtime.block_on(migrate::run(args)),
         Commands::Post(args) => runtime.block_on(post::run(args)),
+        Commands::Recategorize(args) => runtime.block_on(recategorize::run(args)),
+        Commands::Reconcile(args) => runtime.block_on(reconcile::run(args)),
+        Commands::RefreshStats(args) => runtime.block_on(refresh_stats::run(args)),
+        Commands::Replay(args) => runtime.block_on(replay::run(args)),
+        Commands::Schema(args) => runtime.block_on(schema::run(args)),
+        Commands::Seed(args) => runtime.block_on(seed::run(args)),
         Commands::Server(args) => runtime.block_on(server::run(args)),
         Commands::Update(args) => runtime.block_on(update::run(args)),
+        Commands::Vacuum(args) => runtime.block_on(vacuum::run(args)),
     }
     .expect("command failed");
 }
+
+/// ログの出力先と形式を設定する関数
+///
+/// アクセスログは集計しやすいように、出力形式にかかわらずJSON形式で出力する。
+fn init_tracing(args: &LogArgs) -> WorkerGuard {
+    let log_level = env::var("RUST_LOG").unwrap_or(String::from("info"));
+    let filter = || {
+        EnvFilter::builder()
+            .with_default_directive(
+                LevelFilter::from_str(&log_level)
+                    .expect("couldn't parse specified log level")
+                    .into(),
+            )
+            .from_env_lossy()
+    };
+    let is_access_log = |metadata: &Metadata| metadata.target() == ACCESS_LOG_TARGET;
+
+    // ローカルのオフセットはスレッドが1つの間しか取得できないので、書き込み用のスレッドを起動する前に取得する
+    let timer = OffsetTime::local_rfc_3339().unwrap();
+
+    // 出力が詰まってもログを捨てないようにする
+    let builder = NonBlockingBuilder::default().lossy(false);
+    let (writer, guard) = match &args.log_dir {
+        Some(dir) => builder.finish(rolling::daily(dir, LOG_FILE_PREFIX)),
+        None => builder.finish(io::stdout()),
+    };
+
+    let layer = match args.log_format {
+        LogFormat::Text => fmt::layer()
+            .with_level(true)
+            .with_target(true)
+            .with_ansi(false)
+            .with_thread_ids(true)
+            .with_timer(timer.clone())
+            .with_writer(writer.clone())
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_level(true)
+            .with_target(true)
+            .with_thread_ids(true)
+            .with_timer(timer.clone())
+            .with_writer(writer.clone())
+            .boxed(),
+    };
+    let access_log = fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(false)
+        .with_timer(timer)
+        .with_writer(writer);
+
+    let subscriber = tracing_subscriber::registry()
+        .with(layer.with_filter(filter().and(filter_fn(is_access_log).not())))
+        .with(access_log.with_filter(filter().and(filter_fn(is_access_log))));
+    tracing::subscriber::set_global_default(subscriber).expect("failed to set tracing subscriber");
+
+    guard
+}
@@ -0,0 +1,143 @@
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+/// アクセスログを出力するtracingのターゲット
+///
+/// 検索条件を記録する`querylog`とは別に、JSON形式で出力する。
+pub const ACCESS_LOG_TARGET: &str = "accesslog";
+
+// 伏せ字にするパラメータ
+const REDACTED_PARAMS: [&str; 2] = ["keyword", "q"];
+const REDACTED: &str = "***";
+
+/// アクセスログの設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessLogConfig {
+    /// クエリ文字列を記録するリクエストの割合(0.0〜1.0)
+    pub sample_rate: f64,
+    /// `true`の場合、クエリ文字列の検索キーワードを伏せ字にする
+    pub redact_keyword: bool,
+}
+
+/// リクエストごとにメソッド・パス・ステータスコード・レイテンシを記録するミドルウェアの状態
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    config: AccessLogConfig,
+    requests: Arc<AtomicU64>,
+}
+
+impl AccessLog {
+    pub fn new(config: AccessLogConfig) -> Self {
+        Self {
+            config,
+            requests: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// このリクエストのクエリ文字列を記録するかどうか
+    ///
+    /// 乱数ではなくリクエストの通し番号で決めるので、記録される割合はちょうど`sample_rate`になる。
+    fn sampled(&self) -> bool {
+        let rate = self.config.sample_rate.clamp(0.0, 1.0);
+        let n = self.requests.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * rate).floor() > (n * rate).floor()
+    }
+
+    /// 記録するクエリ文字列
+    fn query(&self, query: Option<&str>) -> Option<String> {
+        let query = query.filter(|query| !query.is_empty())?;
+        if !self.sampled() {
+            return None;
+        }
+        if self.config.redact_keyword {
+            Some(redact_keyword(query))
+        } else {
+            Some(query.to_string())
+        }
+    }
+}
+
+/// クエリ文字列の検索キーワードの値を伏せ字にする関数
+fn redact_keyword(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            let key = pair.split_once('=').map(|(key, _)| key).unwrap_or(pair);
+            if REDACTED_PARAMS.contains(&key) {
+                format!("{}={}", key, REDACTED)
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// アクセスログを出力するミドルウェア
+pub async fn record<B>(
+    State(log): State<AccessLog>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = log.query(request.uri().query());
+
+    let response = next.run(request).await;
+
+    tracing::info!(
+        target: ACCESS_LOG_TARGET,
+        method = %method,
+        path = %path,
+        status = response.status().as_u16(),
+        latency_ms = start.elapsed().as_millis() as u64,
+        query = query.as_deref(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sampling() {
+        let log = AccessLog::new(AccessLogConfig {
+            sample_rate: 0.25,
+            redact_keyword: false,
+        });
+        let sampled = (0..100).filter(|_| log.sampled()).count();
+        assert_eq!(sampled, 25);
+
+        let log = AccessLog::new(AccessLogConfig {
+            sample_rate: 0.0,
+            redact_keyword: false,
+        });
+        assert!((0..100).all(|_| log.query(Some("keyword=dp")).is_none()));
+
+        let log = AccessLog::new(AccessLogConfig {
+            sample_rate: 1.0,
+            redact_keyword: false,
+        });
+        assert_eq!(log.query(Some("keyword=dp")).as_deref(), Some("keyword=dp"));
+        assert_eq!(log.query(Some("")), None);
+        assert_eq!(log.query(None), None);
+    }
+
+    #[test]
+    fn test_redact_keyword() {
+        assert_eq!(
+            redact_keyword("keyword=%E6%9C%A8&limit=20&filter.category=ABC"),
+            "keyword=***&limit=20&filter.category=ABC"
+        );
+        assert_eq!(redact_keyword("q=tourist&keyword"), "q=***&keyword=***");
+        assert_eq!(redact_keyword("keywords=dp"), "keywords=dp");
+    }
+}
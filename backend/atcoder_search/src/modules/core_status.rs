@@ -0,0 +1,127 @@
+use atcoder_search_libs::solr::core::SolrCore;
+use chrono::{DateTime, Utc};
+use std::{
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+// この回数の確認間隔が過ぎても更新されていない状態は古いとみなす
+const STALE_INTERVALS: u32 = 3;
+
+/// 1つのコアの状態の確認結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreHealth {
+    /// コアの名前とドキュメント数
+    Available { name: String, num_docs: u64 },
+    /// コアの状態を取得できなかった
+    Unavailable,
+}
+
+impl CoreHealth {
+    async fn check<C: SolrCore>(label: &str, core: &C) -> Self {
+        match core.status().await {
+            Ok(status) => CoreHealth::Available {
+                name: status.name,
+                num_docs: status.index.num_docs,
+            },
+            Err(e) => {
+                tracing::error!("status check of {} failed cause: {:?}", label, e);
+                CoreHealth::Unavailable
+            }
+        }
+    }
+}
+
+/// ある時点での問題・ユーザ・レコメンドのコアの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreStatusSnapshot {
+    pub problem_core: CoreHealth,
+    pub user_core: CoreHealth,
+    pub recommend_core: CoreHealth,
+    pub checked_at: DateTime<Utc>,
+    // 経過時間の計算に使う、時計の変更に影響されない確認時刻
+    refreshed_at: Instant,
+}
+
+impl CoreStatusSnapshot {
+    /// 確認してからの経過時間
+    pub fn age(&self) -> Duration {
+        self.refreshed_at.elapsed()
+    }
+
+    /// 確認間隔が`interval`のとき、この状態が更新されずに古くなっているかどうか
+    pub fn is_stale(&self, interval: Duration) -> bool {
+        self.age() > interval * STALE_INTERVALS
+    }
+}
+
+/// コアの状態をバックグラウンドで定期的に確認し、最新の結果を保持する構造体
+///
+/// readinessのようにコアの状態を参照するたびに`admin/cores`を呼ばないようにする。
+#[derive(Debug, Default)]
+pub struct CoreStatusCache {
+    snapshot: RwLock<Option<CoreStatusSnapshot>>,
+}
+
+impl CoreStatusCache {
+    /// 保持している最新の状態。まだ一度も確認していなければ`None`
+    pub fn snapshot(&self) -> Option<CoreStatusSnapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+
+    /// コアの状態を確認して保持している状態を置き換えるメソッド
+    pub async fn refresh<C: SolrCore>(
+        &self,
+        problem_core: &C,
+        user_core: &C,
+        recommend_core: &C,
+    ) -> CoreStatusSnapshot {
+        let snapshot = CoreStatusSnapshot {
+            problem_core: CoreHealth::check("problem_core", problem_core).await,
+            user_core: CoreHealth::check("user_core", user_core).await,
+            recommend_core: CoreHealth::check("recommend_core", recommend_core).await,
+            checked_at: Utc::now(),
+            refreshed_at: Instant::now(),
+        };
+        *self.snapshot.write().unwrap() = Some(snapshot.clone());
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::MockSolrCore;
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let problem_core = MockSolrCore::new("problems");
+        let user_core = MockSolrCore::new("users");
+        let recommend_core = MockSolrCore::new("recommends");
+        problem_core.set_num_docs(10);
+
+        let cache = CoreStatusCache::default();
+        assert_eq!(cache.snapshot(), None);
+
+        let snapshot = cache
+            .refresh(&problem_core, &user_core, &recommend_core)
+            .await;
+        assert_eq!(
+            snapshot.problem_core,
+            CoreHealth::Available {
+                name: String::from("problems"),
+                num_docs: 10
+            }
+        );
+        assert_eq!(cache.snapshot(), Some(snapshot.clone()));
+        assert!(!snapshot.is_stale(Duration::from_secs(10)));
+
+        // 次に確認するまでは保持している状態を返す
+        user_core.set_available(false);
+        assert_eq!(cache.snapshot(), Some(snapshot));
+        let snapshot = cache
+            .refresh(&problem_core, &user_core, &recommend_core)
+            .await;
+        assert_eq!(snapshot.user_core, CoreHealth::Unavailable);
+    }
+}
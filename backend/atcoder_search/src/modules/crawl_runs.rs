@@ -0,0 +1,210 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::{fmt, ops::AddAssign};
+
+/// クロールの実行ID
+pub type CrawlRunId = i64;
+
+/// 1回のクロールで取得・保存した件数
+///
+/// - fetched: AtCoderやAtCoder Problemsから取得した件数
+/// - inserted: 新しく保存した件数
+/// - updated: 既に保存されていて更新した件数
+/// - errors: 取得や解析に失敗して読み飛ばした件数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CrawlStats {
+    pub fetched: i32,
+    pub inserted: i32,
+    pub updated: i32,
+    pub errors: i32,
+}
+
+impl AddAssign for CrawlStats {
+    fn add_assign(&mut self, other: Self) {
+        self.fetched += other.fetched;
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.errors += other.errors;
+    }
+}
+
+impl fmt::Display for CrawlStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} fetched, {} inserted, {} updated, {} errors",
+            self.fetched, self.inserted, self.updated, self.errors
+        )
+    }
+}
+
+/// `crawl_runs`テーブルの行
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct CrawlRun {
+    pub id: CrawlRunId,
+    pub domain: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub fetched: i32,
+    pub inserted: i32,
+    pub updated: i32,
+    pub errors: i32,
+    pub error: Option<String>,
+}
+
+/// クロールの実行結果を記録する`crawl_runs`テーブルへのアクセスを提供する構造体
+#[derive(Debug, Clone)]
+pub struct CrawlRunStore {
+    pool: Pool<Postgres>,
+}
+
+impl CrawlRunStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// `domain`のクロールを開始したことを記録し、実行IDを返すメソッド
+    pub async fn start(&self, domain: &str) -> Result<CrawlRunId> {
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO "crawl_runs" ("domain", "status")
+            VALUES ($1, 'running')
+            RETURNING "id";
+            "#,
+        )
+        .bind(domain)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// クロールの結果を記録するメソッド
+    pub async fn finish(&self, id: CrawlRunId, result: &Result<CrawlStats>) -> Result<()> {
+        let (status, stats, error) = match result {
+            Ok(stats) => ("succeeded", *stats, None),
+            Err(e) => ("failed", CrawlStats::default(), Some(e.to_string())),
+        };
+        sqlx::query(
+            r#"
+            UPDATE "crawl_runs"
+            SET
+                "status" = $1,
+                "finished_at" = CURRENT_TIMESTAMP,
+                "fetched" = $2,
+                "inserted" = $3,
+                "updated" = $4,
+                "errors" = $5,
+                "error" = $6
+            WHERE
+                "id" = $7;
+            "#,
+        )
+        .bind(status)
+        .bind(stats.fetched)
+        .bind(stats.inserted)
+        .bind(stats.updated)
+        .bind(stats.errors)
+        .bind(error)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 新しい順に`limit`件のクロールの実行結果を取得するメソッド
+    pub async fn latest(&self, limit: i64) -> Result<Vec<CrawlRun>> {
+        let runs = sqlx::query_as(
+            r#"
+            SELECT
+                "id",
+                "domain",
+                "status",
+                "started_at",
+                "finished_at",
+                "fetched",
+                "inserted",
+                "updated",
+                "errors",
+                "error"
+            FROM
+                "crawl_runs"
+            ORDER BY
+                "id" DESC
+            LIMIT $1;
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    /// `domain`のクロールを実行し、その結果を記録するメソッド
+    ///
+    /// 記録に失敗してもクロールの結果は変えず、警告を出すだけにする。
+    pub async fn record<F>(&self, domain: &str, crawl: F) -> Result<CrawlStats>
+    where
+        F: std::future::Future<Output = Result<CrawlStats>>,
+    {
+        let id = match self.start(domain).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("failed to record the start of the crawl cause: {:?}", e);
+                None
+            }
+        };
+
+        let result = crawl.await;
+        match &result {
+            Ok(stats) => tracing::info!("crawl of {} finished: {}", domain, stats),
+            Err(e) => tracing::error!("crawl of {} failed cause: {:?}", domain, e),
+        }
+
+        if let Some(id) = id {
+            if let Err(e) = self.finish(id, &result).await {
+                tracing::warn!("failed to record the result of the crawl cause: {:?}", e);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_stats() {
+        let mut stats = CrawlStats {
+            fetched: 10,
+            inserted: 2,
+            updated: 8,
+            errors: 0,
+        };
+        stats += CrawlStats {
+            fetched: 5,
+            inserted: 5,
+            updated: 0,
+            errors: 1,
+        };
+        assert_eq!(
+            stats,
+            CrawlStats {
+                fetched: 15,
+                inserted: 7,
+                updated: 8,
+                errors: 1,
+            }
+        );
+        assert_eq!(
+            stats.to_string(),
+            "15 fetched, 7 inserted, 8 updated, 1 errors"
+        );
+    }
+}
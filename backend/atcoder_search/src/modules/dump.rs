@@ -0,0 +1,209 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sqlx::{postgres::Postgres, Pool};
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+use tokio_stream::StreamExt;
+
+/// 一度のINSERT文で取り込む行の数
+const BATCH_SIZE: usize = 1000;
+
+/// ダンプの対象にする、AtCoderから収集したデータのテーブル
+///
+/// 外部キーで参照されるテーブルが先に取り込まれるよう、参照される側から順に並べている。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DumpTable {
+    Contests,
+    Problems,
+    ProblemModels,
+    Users,
+    RatingHistories,
+    Submissions,
+}
+
+impl DumpTable {
+    /// 全てのテーブルを取り込める順に並べた配列
+    pub const ALL: [DumpTable; 6] = [
+        DumpTable::Contests,
+        DumpTable::Problems,
+        DumpTable::ProblemModels,
+        DumpTable::Users,
+        DumpTable::RatingHistories,
+        DumpTable::Submissions,
+    ];
+
+    /// テーブル名
+    pub fn name(&self) -> &'static str {
+        match self {
+            DumpTable::Contests => "contests",
+            DumpTable::Problems => "problems",
+            DumpTable::ProblemModels => "problem_models",
+            DumpTable::Users => "users",
+            DumpTable::RatingHistories => "rating_histories",
+            DumpTable::Submissions => "submissions",
+        }
+    }
+
+    /// 主キーのカラム。出力する行の順序と、取り込む際の重複の判定に使う
+    fn primary_key(&self) -> &'static str {
+        match self {
+            DumpTable::Contests => r#""contest_id""#,
+            DumpTable::Problems | DumpTable::ProblemModels => r#""problem_id""#,
+            DumpTable::Users => r#""user_name""#,
+            DumpTable::RatingHistories => r#""user_name", "contest_id""#,
+            DumpTable::Submissions => r#""id""#,
+        }
+    }
+
+    /// `dir`の中のダンプファイルのパス
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.ndjson.gz", self.name()))
+    }
+
+    /// 指定されたテーブルを取り込める順に並べ替える関数。空の場合は全てのテーブルを返す
+    pub fn resolve(tables: &[DumpTable]) -> Vec<DumpTable> {
+        DumpTable::ALL
+            .into_iter()
+            .filter(|table| tables.is_empty() || tables.contains(table))
+            .collect()
+    }
+}
+
+impl fmt::Display for DumpTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// テーブルの行を1行1つのJSONオブジェクトとしてgzip圧縮したファイルへ書き出し、また取り込む構造体
+pub struct TableDumper<'a> {
+    pool: &'a Pool<Postgres>,
+}
+
+impl<'a> TableDumper<'a> {
+    pub fn new(pool: &'a Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// `table`の全ての行を`dir`へ書き出し、書き出した行数を返すメソッド
+    pub async fn export(&self, table: DumpTable, dir: &Path) -> Result<usize> {
+        let path = table.path(dir);
+        let file =
+            File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+        let query = format!(
+            r#"SELECT row_to_json("t")::text FROM "{}" AS "t" ORDER BY {}"#,
+            table.name(),
+            table.primary_key()
+        );
+        let mut rows = sqlx::query_scalar::<_, String>(&query).fetch(self.pool);
+
+        let mut count = 0;
+        while let Some(row) = rows.try_next().await? {
+            writeln!(encoder, "{}", row)?;
+            count += 1;
+        }
+        encoder.finish()?.flush()?;
+
+        Ok(count)
+    }
+
+    /// `dir`にある`table`のダンプファイルを取り込み、取り込んだ行数を返すメソッド
+    ///
+    /// 主キーが既に存在する行は上書きせずに読み飛ばす。1テーブルを1つのトランザクションで取り込む。
+    pub async fn import(&self, table: DumpTable, dir: &Path) -> Result<u64> {
+        let path = table.path(dir);
+        let file =
+            File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+        let lines = read_lines(file);
+
+        let query = format!(
+            r#"
+            INSERT INTO "{table}"
+            SELECT * FROM json_populate_recordset(NULL::"{table}", $1::json)
+            ON CONFLICT ({key}) DO NOTHING;
+            "#,
+            table = table.name(),
+            key = table.primary_key()
+        );
+
+        let mut tx = self.pool.begin().await?;
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            batch.push(line);
+            if batch.len() >= BATCH_SIZE {
+                count += insert_batch(&mut tx, &query, &batch).await?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            count += insert_batch(&mut tx, &query, &batch).await?;
+        }
+        tx.commit().await?;
+
+        Ok(count)
+    }
+}
+
+// 1行1つのJSONオブジェクトをJSON配列にまとめて取り込む
+async fn insert_batch(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    query: &str,
+    batch: &[String],
+) -> Result<u64> {
+    let result = sqlx::query(query)
+        .bind(format!("[{}]", batch.join(",")))
+        .execute(tx)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// gzip圧縮されたダンプファイルを1行ずつ読み出すイテレータを返す関数
+fn read_lines<R: Read>(reader: R) -> impl Iterator<Item = std::io::Result<String>> {
+    BufReader::new(GzDecoder::new(reader)).lines()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tables() {
+        assert_eq!(DumpTable::resolve(&[]), DumpTable::ALL.to_vec());
+        assert_eq!(
+            DumpTable::resolve(&[DumpTable::Submissions, DumpTable::Contests]),
+            vec![DumpTable::Contests, DumpTable::Submissions]
+        );
+        assert_eq!(
+            DumpTable::ProblemModels.path(Path::new("/tmp/dump")),
+            PathBuf::from("/tmp/dump/problem_models.ndjson.gz")
+        );
+    }
+
+    #[test]
+    fn test_read_lines() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        writeln!(encoder, r#"{{"contest_id":"abc300"}}"#).unwrap();
+        writeln!(encoder, r#"{{"contest_id":"abc301"}}"#).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let lines = read_lines(compressed.as_slice())
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            lines,
+            vec![r#"{"contest_id":"abc300"}"#, r#"{"contest_id":"abc301"}"#]
+        );
+    }
+}
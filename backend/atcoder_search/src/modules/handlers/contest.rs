@@ -0,0 +1,169 @@
+use crate::{
+    modules::{
+        handlers::problem::validate_category_filtering, problems::calendar::ContestCalendar,
+    },
+    types::{
+        request::{comma_separated_values, split_negation, ValidatedQueryParameters},
+        tables::Contest,
+    },
+};
+use atcoder_search_libs::ApiError;
+use axum::{Extension, Json};
+use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+// 予定されているコンテストを取得する期間のデフォルトの日数
+const DEFAULT_CALENDAR_DAYS: u32 = 14;
+
+// 予定されているコンテストを取得する期間の日数の上限
+const MAX_CALENDAR_DAYS: u32 = 90;
+
+/// 予定されているコンテストの取得のパラメータ
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams, PartialEq, Eq, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct UpcomingContestParameter {
+    /// Number of days from now to look ahead. Defaults to 14, up to 90
+    #[validate(range(min = 1, max = 90))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    /// Comma separated contest categories to filter. Categories prefixed with `-` are excluded
+    #[validate(custom = "validate_category_filtering")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub category: Option<Vec<String>>,
+}
+
+impl UpcomingContestParameter {
+    // `now`から指定された日数後までの期間をUnix Epoch Timeで返す
+    fn window(&self, now: DateTime<Utc>) -> (i64, i64) {
+        let days = self
+            .days
+            .unwrap_or(DEFAULT_CALENDAR_DAYS)
+            .min(MAX_CALENDAR_DAYS);
+        let to = now + Duration::days(days as i64);
+        (now.timestamp(), to.timestamp())
+    }
+}
+
+/// 予定されているコンテスト
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct UpcomingContest {
+    pub contest_id: String,
+    pub title: String,
+    pub contest_url: String,
+    pub start_at: DateTime<FixedOffset>,
+    pub end_at: DateTime<FixedOffset>,
+    pub duration: i64,
+    pub rate_change: String,
+    pub category: String,
+}
+
+impl From<Contest> for UpcomingContest {
+    fn from(contest: Contest) -> Self {
+        let start_at = Utc
+            .timestamp_opt(contest.start_epoch_second, 0)
+            .unwrap()
+            .fixed_offset();
+        Self {
+            contest_url: format!("https://atcoder.jp/contests/{}", contest.contest_id),
+            end_at: start_at + Duration::seconds(contest.duration_second),
+            start_at,
+            contest_id: contest.contest_id,
+            title: contest.title,
+            duration: contest.duration_second,
+            rate_change: contest.rate_change,
+            category: contest.category,
+        }
+    }
+}
+
+/// 予定されているコンテストの一覧
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpcomingContestsResponse {
+    /// Beginning of the period
+    pub from: DateTime<FixedOffset>,
+    /// End of the period
+    pub to: DateTime<FixedOffset>,
+    pub items: Vec<UpcomingContest>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/contests/upcoming",
+    tag = "contest",
+    params(UpcomingContestParameter),
+    responses(
+        (status = 200, description = "Upcoming contests ordered by the start time", body = UpcomingContestsResponse),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+    )
+)]
+pub async fn upcoming_contests(
+    Extension(calendar): Extension<Arc<ContestCalendar>>,
+    ValidatedQueryParameters(params): ValidatedQueryParameters<UpcomingContestParameter>,
+) -> Result<Json<UpcomingContestsResponse>, ApiError> {
+    let (from, to) = params.window(Utc::now());
+    let (includes, excludes) = split_negation(params.category.as_deref().unwrap_or_default());
+
+    let contests = calendar
+        .upcoming(from, to, &includes, &excludes)
+        .await
+        .map_err(|e| {
+            tracing::error!("failed to get upcoming contests cause: {:?}", e);
+            ApiError::internal_error("failed to get upcoming contests")
+        })?;
+
+    Ok(Json(UpcomingContestsResponse {
+        from: Utc.timestamp_opt(from, 0).unwrap().fixed_offset(),
+        to: Utc.timestamp_opt(to, 0).unwrap().fixed_offset(),
+        items: contests.into_iter().map(UpcomingContest::from).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_window() {
+        let now = Utc.timestamp_opt(1691236800, 0).unwrap();
+        let params = UpcomingContestParameter::default();
+        assert_eq!(params.window(now), (1691236800, 1691236800 + 14 * 86400));
+
+        let params: UpcomingContestParameter =
+            serde_structuredqs::from_str("days=7&category=ABC,-AHC").unwrap();
+        assert!(params.validate().is_ok());
+        assert_eq!(params.window(now), (1691236800, 1691236800 + 7 * 86400));
+        assert_eq!(
+            params.category,
+            Some(vec![String::from("ABC"), String::from("-AHC")])
+        );
+
+        let params: UpcomingContestParameter = serde_structuredqs::from_str("days=91").unwrap();
+        assert!(params.validate().is_err());
+        let params: UpcomingContestParameter =
+            serde_structuredqs::from_str("category=Unknown").unwrap();
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn test_upcoming_contest() {
+        let contest = UpcomingContest::from(Contest {
+            contest_id: String::from("abc313"),
+            start_epoch_second: 1691236800,
+            duration_second: 6000,
+            title: String::from("AtCoder Beginner Contest 313"),
+            rate_change: String::from("~ 1999"),
+            category: String::from("ABC"),
+            status: String::from("upcoming"),
+        });
+        assert_eq!(contest.contest_url, "https://atcoder.jp/contests/abc313");
+        assert_eq!(contest.start_at.to_rfc3339(), "2023-08-05T12:00:00+00:00");
+        assert_eq!(contest.end_at.to_rfc3339(), "2023-08-05T13:40:00+00:00");
+    }
+}
@@ -0,0 +1,359 @@
+use crate::modules::{
+    handlers::{
+        contest::{UpcomingContest, UpcomingContestsResponse},
+        problem::{
+            InstantSearchResponse, ProblemFacetCounts, ProblemResponse, SavedSearchResponse,
+        },
+        recommend::{RecommendResponse, RecommendedProblem},
+        stats::{
+            ColorCount, DifficultyBucket, ProblemDifficultyStats, TrendingProblemEntry,
+            TrendingProblemsResponse, UnratedCount, UserColorStats,
+        },
+        user::{UserFacetCounts, UserResponse},
+        DependencyState, DependencyStatus, ReadinessResponse,
+    },
+    users::submissions::SolveStatus,
+};
+use atcoder_search_libs::{
+    api::{
+        v2::{Facet, FacetCount, Page, SearchMeta},
+        FieldFacetCount, FieldFacetEntry, PercentileValue, RangeFacetCount, RangeFacetEntry,
+        StatsFacetCount,
+    },
+    query_expansion::QueryExpansion,
+};
+use axum::{response::Html, Json};
+use utoipa::{
+    openapi::{
+        ArrayBuilder, Components, KnownFormat, ObjectBuilder, Ref, Schema, SchemaFormat, SchemaType,
+    },
+    Modify, OpenApi,
+};
+
+/// 検索APIのOpenAPI定義
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "AtCoder Search API"),
+    paths(
+        crate::modules::handlers::problem::search_problem,
+        crate::modules::handlers::problem::search_problem_v2,
+        crate::modules::handlers::problem::instant_search_problem,
+        crate::modules::handlers::problem::save_problem_search,
+        crate::modules::handlers::problem::search_saved_problem,
+        crate::modules::handlers::user::search_user,
+        crate::modules::handlers::statement::problem_statement,
+        crate::modules::handlers::contest::upcoming_contests,
+        crate::modules::handlers::stats::user_color_stats,
+        crate::modules::handlers::stats::problem_difficulty_stats,
+        crate::modules::handlers::stats::trending_problems,
+        crate::modules::handlers::recommend::recommend_problem,
+        crate::modules::handlers::liveness,
+        crate::modules::handlers::readiness,
+    ),
+    components(schemas(
+        ProblemResponse,
+        ProblemFacetCounts,
+        InstantSearchResponse,
+        SavedSearchResponse,
+        SolveStatus,
+        UserResponse,
+        UserFacetCounts,
+        FieldFacetCount,
+        FieldFacetEntry,
+        RangeFacetCount,
+        RangeFacetEntry,
+        StatsFacetCount,
+        PercentileValue,
+        QueryExpansion,
+        Page,
+        SearchMeta,
+        Facet,
+        FacetCount,
+        UpcomingContest,
+        UpcomingContestsResponse,
+        ColorCount,
+        UserColorStats,
+        DifficultyBucket,
+        UnratedCount,
+        ProblemDifficultyStats,
+        TrendingProblemEntry,
+        TrendingProblemsResponse,
+        RecommendedProblem,
+        RecommendResponse,
+        ReadinessResponse,
+        DependencyStatus,
+        DependencyState,
+    )),
+    modifiers(&SearchResultSchemas),
+    tags(
+        (name = "search", description = "Full text search of problems and users"),
+        (name = "problem", description = "Statements of problems extracted at generate time"),
+        (name = "contest", description = "Schedule of upcoming contests"),
+        (name = "stats", description = "Aggregations over the whole index for charts"),
+        (name = "recommend", description = "Problems related to a problem"),
+        (name = "health", description = "Health check of the API server, Solr and PostgreSQL"),
+    )
+)]
+pub struct ApiDoc;
+
+/// ジェネリックな`SearchResultResponse`の具体的なスキーマをドメインごとに登録するModifier
+///
+/// utoipaのaliasesではネストしたジェネリック型(`SearchResultStats<F>`)を解決できないため、手で組み立てる。
+/// `ApiError`もutoipaに依存しないライブラリクレートで定義されているので、ここでスキーマを登録する。
+struct SearchResultSchemas;
+
+impl Modify for SearchResultSchemas {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Components::new);
+        for (name, item, facet) in [
+            (
+                "ProblemSearchResult",
+                "ProblemResponse",
+                "ProblemFacetCounts",
+            ),
+            ("UserSearchResult", "UserResponse", "UserFacetCounts"),
+        ] {
+            components
+                .schemas
+                .insert(String::from(name), search_result_schema(item, facet).into());
+        }
+        components.schemas.insert(
+            String::from("ProblemSearchResultV2"),
+            search_result_v2_schema("ProblemResponse").into(),
+        );
+        components.schemas.insert(
+            String::from("ErrorResponse"),
+            error_response_schema().into(),
+        );
+    }
+}
+
+// `SearchResultResponse<D, F>`のスキーマを生成する関数
+fn search_result_schema(item: &str, facet: &str) -> Schema {
+    let integer = || {
+        ObjectBuilder::new()
+            .schema_type(SchemaType::Integer)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int32)))
+            .minimum(Some(0.0))
+    };
+
+    let stats = ObjectBuilder::new()
+        .property(
+            "time",
+            integer().description(Some("Elapsed time in milliseconds")),
+        )
+        .property(
+            "total",
+            integer().description(Some("Number of hit documents")),
+        )
+        .property("index", integer().description(Some("Current page number")))
+        .property("pages", integer().description(Some("Number of pages")))
+        .property(
+            "count",
+            integer().description(Some("Number of items in this page")),
+        )
+        .property(
+            "params",
+            ObjectBuilder::new().description(Some("Accepted request parameters")),
+        )
+        .property("facet", Ref::from_schema_name(facet))
+        .property(
+            "language",
+            ObjectBuilder::new()
+                .schema_type(SchemaType::String)
+                .enum_values(Some(["ja", "en", "mixed"]))
+                .nullable(true)
+                .description(Some("Language detected from the keyword")),
+        )
+        .property(
+            "expansions",
+            ArrayBuilder::new()
+                .items(Ref::from_schema_name("QueryExpansion"))
+                .description(Some("Synonym expansions applied to the keyword")),
+        )
+        .property(
+            "shortcut",
+            ObjectBuilder::new()
+                .schema_type(SchemaType::Boolean)
+                .description(Some(
+                    "Whether the keyword was taken as a problem such as `abc300 d` to narrow down the results",
+                )),
+        )
+        .property(
+            "explain",
+            ObjectBuilder::new().description(Some(
+                "Score explanations keyed by document id, only with `debug=true`",
+            )),
+        )
+        .required("time")
+        .required("total")
+        .required("index")
+        .required("pages")
+        .required("count")
+        .required("params")
+        .required("expansions")
+        .required("shortcut");
+
+    ObjectBuilder::new()
+        .property("stats", stats)
+        .property(
+            "items",
+            ArrayBuilder::new().items(Ref::from_schema_name(item)),
+        )
+        .required("stats")
+        .required("items")
+        .into()
+}
+
+// `v2::SearchResultResponse<D>`のスキーマを生成する関数
+fn search_result_v2_schema(item: &str) -> Schema {
+    ObjectBuilder::new()
+        .property(
+            "items",
+            ArrayBuilder::new().items(Ref::from_schema_name(item)),
+        )
+        .property("page", Ref::from_schema_name("Page"))
+        .property(
+            "facets",
+            ArrayBuilder::new().items(Ref::from_schema_name("Facet")),
+        )
+        .property("meta", Ref::from_schema_name("SearchMeta"))
+        .required("items")
+        .required("page")
+        .required("facets")
+        .required("meta")
+        .into()
+}
+
+// `ApiError`をシリアライズしたエラーレスポンスのスキーマを生成する関数
+fn error_response_schema() -> Schema {
+    let string = || ObjectBuilder::new().schema_type(SchemaType::String);
+
+    let detail = ObjectBuilder::new()
+        .property(
+            "field",
+            string().description(Some("Dot separated path of the invalid parameter")),
+        )
+        .property(
+            "constraint",
+            string().description(Some("Name of the violated constraint")),
+        )
+        .property(
+            "value",
+            ObjectBuilder::new().description(Some("Rejected value")),
+        )
+        .property("message", string())
+        .property(
+            "params",
+            ObjectBuilder::new().description(Some("Parameters of the constraint")),
+        )
+        .required("field")
+        .required("constraint");
+
+    let error = ObjectBuilder::new()
+        .property(
+            "code",
+            string().enum_values(Some([
+                "validation_error",
+                "solr_unavailable",
+                "rate_limited",
+                "not_found",
+                "forbidden",
+                "internal_error",
+            ])),
+        )
+        .property("message", string())
+        .property("details", ArrayBuilder::new().items(detail))
+        .required("code")
+        .required("message");
+
+    ObjectBuilder::new()
+        .property("error", error)
+        .required("error")
+        .into()
+}
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// Swagger UIの本体はCDNから読み込み、`/api/v1/openapi.json`を表示する
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <title>AtCoder Search API</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+  </head>
+  <body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+    <script>
+      window.onload = () => {
+        window.ui = SwaggerUIBundle({ url: "/api/v1/openapi.json", dom_id: "#swagger-ui" });
+      };
+    </script>
+  </body>
+</html>
+"##;
+
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::modules::handlers::{problem, user};
+    use serde_json::{json, Value};
+
+    fn parameter<'a>(doc: &'a Value, path: &str, name: &str) -> &'a Value {
+        doc["paths"][path]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|parameter| parameter["name"] == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_allowed_values_follow_validators() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        assert_eq!(
+            parameter(&doc, "/api/v1/search/problem", "sort")["schema"]["items"]["enum"],
+            json!(problem::SORT_OPTIONS)
+        );
+        assert_eq!(
+            parameter(&doc, "/api/v1/search/problem", "filter.category")["schema"]["items"]["enum"],
+            json!(problem::CATEGORY_OPTIONS)
+        );
+        assert_eq!(
+            parameter(&doc, "/api/v1/search/user", "facet")["schema"]["items"]["enum"],
+            json!(user::FACET_FIELDS)
+        );
+        assert_eq!(
+            parameter(&doc, "/api/v1/search/user", "filter.color")["explode"],
+            json!(false)
+        );
+    }
+
+    #[test]
+    fn test_search_result_schemas() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let schemas = &doc["components"]["schemas"];
+
+        for name in [
+            "ProblemSearchResult",
+            "UserSearchResult",
+            "ProblemSearchResultV2",
+            "ErrorResponse",
+        ] {
+            assert!(schemas[name].is_object(), "{} is not registered", name);
+        }
+        assert_eq!(
+            schemas["UserSearchResult"]["properties"]["stats"]["properties"]["facet"]["$ref"],
+            json!("#/components/schemas/UserFacetCounts")
+        );
+    }
+}
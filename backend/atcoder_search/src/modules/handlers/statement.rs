@@ -0,0 +1,262 @@
+use crate::{
+    modules::problems::statement::{ProblemStatement, StatementStore},
+    types::request::ValidatedQueryParameters,
+};
+use atcoder_search_libs::ApiError;
+use axum::{
+    extract::Path,
+    http::header::CONTENT_TYPE,
+    response::{Html, IntoResponse, Response},
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, fmt, sync::Arc};
+use utoipa::IntoParams;
+use validator::{Validate, ValidationError};
+
+// 問題文を返す形式
+const FORMAT_OPTIONS: [&str; 2] = ["text", "html"];
+
+// 問題文の言語
+const LANGUAGE_OPTIONS: [&str; 2] = ["ja", "en"];
+
+/// 問題文を返す形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatementFormat {
+    Text,
+    Html,
+}
+
+/// 問題文の言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatementLanguage {
+    Ja,
+    En,
+}
+
+impl fmt::Display for StatementLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatementLanguage::Ja => write!(f, "ja"),
+            StatementLanguage::En => write!(f, "en"),
+        }
+    }
+}
+
+/// 問題文取得のパラメータ
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams, PartialEq, Eq, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct StatementParameter {
+    /// `text` returns the plain text, `html` returns a printable HTML page. Defaults to `text`
+    #[validate(custom = "validate_format")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Language of the statement, `ja` or `en`. Defaults to `ja`
+    #[validate(custom = "validate_language")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl StatementParameter {
+    fn format(&self) -> StatementFormat {
+        match self.format.as_deref() {
+            Some("html") => StatementFormat::Html,
+            _ => StatementFormat::Text,
+        }
+    }
+
+    fn language(&self) -> StatementLanguage {
+        match self.lang.as_deref() {
+            Some("en") => StatementLanguage::En,
+            _ => StatementLanguage::Ja,
+        }
+    }
+}
+
+// 問題文を返す形式の指定をバリデーションする関数
+fn validate_format(value: &str) -> Result<(), ValidationError> {
+    if FORMAT_OPTIONS.contains(&value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("invalid format");
+    error.add_param(Cow::from("allowed"), &FORMAT_OPTIONS);
+    Err(error)
+}
+
+// 問題文の言語の指定をバリデーションする関数
+fn validate_language(value: &str) -> Result<(), ValidationError> {
+    if LANGUAGE_OPTIONS.contains(&value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("invalid language");
+    error.add_param(Cow::from("allowed"), &LANGUAGE_OPTIONS);
+    Err(error)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/problem/{id}/statement",
+    tag = "problem",
+    params(
+        ("id" = String, Path, description = "ID of the problem, e.g. `abc001_a`"),
+        StatementParameter,
+    ),
+    responses(
+        (status = 200, description = "Statement of the problem as plain text or a printable HTML page", content_type = "text/plain", body = String),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 404, description = "The statement is not available", body = ErrorResponse),
+    )
+)]
+pub async fn problem_statement(
+    Extension(store): Extension<Arc<StatementStore>>,
+    Path(problem_id): Path<String>,
+    ValidatedQueryParameters(params): ValidatedQueryParameters<StatementParameter>,
+) -> Result<Response, ApiError> {
+    let statement = match store.get(&problem_id).await {
+        Ok(Some(statement)) => statement,
+        Ok(None) => {
+            return Err(ApiError::not_found(format!(
+                "statement of {} is not found",
+                problem_id
+            )))
+        }
+        Err(e) => {
+            tracing::error!(
+                "failed to get the statement of {} cause: {:?}",
+                problem_id,
+                e
+            );
+            return Err(ApiError::internal_error("failed to get the statement"));
+        }
+    };
+
+    let lang = params.language();
+    match params.format() {
+        StatementFormat::Text => {
+            let text =
+                render_text(&statement, lang).ok_or_else(|| not_available(&problem_id, lang))?;
+            Ok(([(CONTENT_TYPE, "text/plain; charset=utf-8")], text).into_response())
+        }
+        StatementFormat::Html => {
+            let html =
+                render_html(&statement, lang).ok_or_else(|| not_available(&problem_id, lang))?;
+            Ok(Html(html).into_response())
+        }
+    }
+}
+
+fn not_available(problem_id: &str, lang: StatementLanguage) -> ApiError {
+    ApiError::not_found(format!(
+        "{} statement of {} is not available",
+        lang, problem_id
+    ))
+}
+
+// 問題文の各セクションを空行で区切ったテキストにする関数。その言語の問題文がなければ`None`を返す。
+fn render_text(statement: &ProblemStatement, lang: StatementLanguage) -> Option<String> {
+    let sections = match lang {
+        StatementLanguage::Ja => &statement.statement_ja,
+        StatementLanguage::En => &statement.statement_en,
+    };
+    if sections.is_empty() {
+        return None;
+    }
+    Some(sections.join("\n\n"))
+}
+
+// 問題文を印刷用のHTMLページにする関数。その言語の問題文がなければ`None`を返す。
+//
+// セクションは保存時にサニタイズ済みなので、ここではタイトルだけエスケープする。
+fn render_html(statement: &ProblemStatement, lang: StatementLanguage) -> Option<String> {
+    let sections = match lang {
+        StatementLanguage::Ja => &statement.html_ja,
+        StatementLanguage::En => &statement.html_en,
+    };
+    if sections.is_empty() {
+        return None;
+    }
+
+    let title = escape_html(&statement.title);
+    Some(format!(
+        r#"<!DOCTYPE html>
+<html lang="{lang}">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>@media print {{ section {{ break-inside: avoid; }} }}</style>
+</head>
+<body>
+<h1>{title}</h1>
+{sections}
+</body>
+</html>
+"#,
+        lang = lang,
+        title = title,
+        sections = sections.join("\n"),
+    ))
+}
+
+// HTMLの特殊文字をエスケープする関数
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn statement() -> ProblemStatement {
+        ProblemStatement {
+            problem_id: String::from("abc001_a"),
+            title: String::from("A. <積雪深差>"),
+            statement_ja: vec![
+                String::from("整数 N が与えられます。"),
+                String::from("出力せよ。"),
+            ],
+            statement_en: Vec::new(),
+            constraints_ja: Vec::new(),
+            constraints_en: Vec::new(),
+            html_ja: vec![String::from("<section><h3>問題文</h3></section>")],
+            html_en: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_text() {
+        assert_eq!(
+            render_text(&statement(), StatementLanguage::Ja),
+            Some(String::from("整数 N が与えられます。\n\n出力せよ。"))
+        );
+        assert_eq!(render_text(&statement(), StatementLanguage::En), None);
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html(&statement(), StatementLanguage::Ja).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"ja\">"));
+        assert!(html.contains("<h1>A. &lt;積雪深差&gt;</h1>"));
+        assert!(html.contains("<section><h3>問題文</h3></section>"));
+        assert_eq!(render_html(&statement(), StatementLanguage::En), None);
+    }
+
+    #[test]
+    fn test_statement_parameter() {
+        let params: StatementParameter =
+            serde_structuredqs::from_str("format=html&lang=en").unwrap();
+        assert!(params.validate().is_ok());
+        assert_eq!(params.format(), StatementFormat::Html);
+        assert_eq!(params.language(), StatementLanguage::En);
+
+        let params = StatementParameter::default();
+        assert_eq!(params.format(), StatementFormat::Text);
+        assert_eq!(params.language(), StatementLanguage::Ja);
+
+        let params: StatementParameter = serde_structuredqs::from_str("format=pdf").unwrap();
+        assert!(params.validate().is_err());
+    }
+}
@@ -0,0 +1,880 @@
+use crate::{
+    modules::{
+        handlers::{
+            problem::{validate_category_filtering, DIFFICULTY_FACET_RANGE},
+            AppState, ServerConfig,
+        },
+        problems::trending::{TrendingProblem, TrendingProblems},
+    },
+    types::request::{
+        canonical_values, comma_separated_values, term_filter_queries, Canonicalize, FacetRange,
+        RangeFacetParameter, ValidatedQueryParameters,
+    },
+};
+use atcoder_search_libs::{
+    api::FieldFacetEntry,
+    color::Color,
+    solr::{core::SolrCore, model::*, query::EDisMaxQueryBuilder},
+    ApiError, ToQueryParameter,
+};
+use axum::{
+    extract::State,
+    http::header::{HeaderName, CACHE_CONTROL, ETAG},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+// 直近に解かれている問題の集計結果をキャッシュする秒数
+//
+// 提出の集計はインデックスの集計より重く、数分単位で結果が変わる必要もないので1時間保持する。
+const TRENDING_CACHE_TTL: u64 = 3600;
+
+// 直近に解かれている問題を集計する期間のデフォルトの日数
+const DEFAULT_TRENDING_DAYS: u32 = 7;
+
+// 直近に解かれている問題を返す件数のデフォルト値
+const DEFAULT_TRENDING_LIMIT: u32 = 20;
+
+/// 集計結果を一定時間保持するキャッシュ
+///
+/// 集計はインデックス全体を対象にするので、インデックスが更新されるまではほとんど結果が変わらない。
+pub struct ResponseCache<T> {
+    entries: Mutex<HashMap<String, (Instant, T)>>,
+}
+
+impl<T: Clone> ResponseCache<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// `key`の結果が保存されてから`ttl`以内であれば返す
+    pub fn get(&self, key: &str, ttl: Duration) -> Option<T> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|(saved_at, _)| saved_at.elapsed() < ttl)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// `key`の結果を保存する。期限切れの結果はこのときに取り除く。
+    pub fn insert(&self, key: &str, value: T, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, (saved_at, _)| saved_at.elapsed() < ttl);
+        entries.insert(key.to_string(), (Instant::now(), value));
+    }
+}
+
+impl<T: Clone> Default for ResponseCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 集計APIのキャッシュ
+#[derive(Default)]
+pub struct StatsCache {
+    user_colors: ResponseCache<UserColorStats>,
+    problem_difficulty: ResponseCache<ProblemDifficultyStats>,
+    trending_problems: ResponseCache<TrendingProblemsResponse>,
+}
+
+// 集計APIのレスポンスに付けるCache-ControlヘッダとETagヘッダ
+//
+// ETagは正規化したパラメータのキーと集計結果から計算するので、同じ意味のリクエストには同じ値を返す。
+fn cache_headers<T: Serialize>(ttl: Duration, key: &str, stats: &T) -> [(HeaderName, String); 2] {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update(serde_json::to_vec(stats).unwrap_or_default());
+    let etag = format!("W/\"{}\"", hex::encode(&hasher.finalize()[..16]));
+
+    [
+        (CACHE_CONTROL, format!("public, max-age={}", ttl.as_secs())),
+        (ETAG, etag),
+    ]
+}
+
+/// ユーザの色の分布の集計のパラメータ
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams, PartialEq, Eq, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct UserColorStatsParameter {
+    /// Country code to count only the users in the country, e.g. `JP`
+    #[validate(length(min = 2, max = 3))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl Canonicalize for UserColorStatsParameter {
+    fn canonicalize(&self, _: &ServerConfig) -> Self {
+        Self {
+            country: self
+                .country
+                .as_ref()
+                .map(|country| country.trim().to_string())
+                .filter(|country| !country.is_empty()),
+        }
+    }
+}
+
+impl ToQueryParameter for UserColorStatsParameter {
+    fn to_query(&self) -> Vec<(String, String)> {
+        let fq = self
+            .country
+            .as_ref()
+            .map(|country| term_filter_queries("country", std::slice::from_ref(country)))
+            .unwrap_or_default();
+        let facet = json!({
+            "color": {
+                "type": "terms",
+                "field": "color",
+                "limit": -1,
+                "mincount": 0
+            },
+            "highest_color": {
+                "type": "terms",
+                "field": "highest_color",
+                "limit": -1,
+                "mincount": 0
+            }
+        });
+
+        EDisMaxQueryBuilder::new()
+            .q_alt("*:*")
+            .fq(&fq)
+            .rows(0)
+            .facet(facet.to_string())
+            .build()
+    }
+}
+
+// Solrから返される色のファセットカウント
+#[derive(Debug, Deserialize)]
+struct SolrUserColorFacets {
+    color: Option<SolrTermFacetCount>,
+    highest_color: Option<SolrTermFacetCount>,
+}
+
+/// 色ごとのユーザ数
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ColorCount {
+    pub color: String,
+    pub count: u32,
+}
+
+/// ユーザの色の分布
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct UserColorStats {
+    /// Number of users counted
+    pub total: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Number of users per color of the current rating, in ascending order of rating
+    pub color: Vec<ColorCount>,
+    /// Number of users per color of the highest rating, in ascending order of rating
+    pub highest_color: Vec<ColorCount>,
+}
+
+// ファセットカウントを、ユーザのいない色も含めてレーティングの低い色から順に並べる関数
+fn color_counts(facet: Option<SolrTermFacetCount>) -> Vec<ColorCount> {
+    let counts: HashMap<String, u32> = facet
+        .map(|facet| {
+            facet
+                .buckets
+                .into_iter()
+                .map(|bucket| (bucket.val, bucket.count))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Color::ALL
+        .iter()
+        .map(|color| ColorCount {
+            color: color.to_string(),
+            count: counts.get(color.as_str()).copied().unwrap_or(0),
+        })
+        .collect()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats/users/colors",
+    tag = "stats",
+    params(UserColorStatsParameter),
+    responses(
+        (status = 200, description = "Distribution of users per color", body = UserColorStats),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 503, description = "Failed to request to Solr", body = ErrorResponse),
+    )
+)]
+pub async fn user_color_stats<C>(
+    State(state): State<AppState<C>>,
+    ValidatedQueryParameters(params): ValidatedQueryParameters<UserColorStatsParameter>,
+) -> Result<([(HeaderName, String); 2], Json<UserColorStats>), ApiError>
+where
+    C: SolrCore + Send + Sync + 'static,
+{
+    let ttl = state.config.stats_cache_ttl;
+    let key = params.canonical_key(&state.config);
+    if let Some(stats) = state.stats.user_colors.get(&key, ttl) {
+        return Ok((cache_headers(ttl, &key, &stats), Json(stats)));
+    }
+
+    let response: SolrSelectResponse<Value, SolrUserColorFacets> =
+        match state.user_core.select(&params.to_query()).await {
+            Ok(res) => res,
+            Err(e) => {
+                tracing::error!("request failed cause: {:?}", e);
+                return Err(ApiError::solr_unavailable("failed to count users"));
+            }
+        };
+
+    let (color, highest_color) = match response.facets {
+        Some(facets) => (facets.color, facets.highest_color),
+        None => (None, None),
+    };
+    let stats = UserColorStats {
+        total: response.response.num_found,
+        country: params.country,
+        color: color_counts(color),
+        highest_color: color_counts(highest_color),
+    };
+    state.stats.user_colors.insert(&key, stats.clone(), ttl);
+
+    Ok((cache_headers(ttl, &key, &stats), Json(stats)))
+}
+
+/// 問題の難易度の分布の集計のパラメータ
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams, PartialEq, Eq, Clone)]
+#[into_params(parameter_in = Query)]
+#[validate(schema(function = "validate_difficulty_range"))]
+pub struct ProblemDifficultyStatsParameter {
+    /// Lower bound of the first bucket. Defaults to 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<i32>,
+    /// Upper bound of the last bucket. Defaults to 4000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<i32>,
+    /// Width of each bucket. Defaults to 400
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<i32>,
+    /// Comma separated contest categories to count. Categories prefixed with `-` are excluded
+    #[validate(custom = "validate_category_filtering")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub category: Option<Vec<String>>,
+}
+
+impl ProblemDifficultyStatsParameter {
+    // 指定されなかった値をデフォルト値で補った区間
+    fn range(&self) -> FacetRange {
+        RangeFacetParameter::resolve(
+            Some(&RangeFacetParameter {
+                start: self.start,
+                end: self.end,
+                gap: self.gap,
+            }),
+            DIFFICULTY_FACET_RANGE,
+        )
+    }
+}
+
+impl Canonicalize for ProblemDifficultyStatsParameter {
+    fn canonicalize(&self, _: &ServerConfig) -> Self {
+        let range = self.range();
+        Self {
+            start: Some(range.start),
+            end: Some(range.end),
+            gap: Some(range.gap),
+            category: canonical_values(&self.category),
+        }
+    }
+}
+
+// 難易度の区間指定をバリデーションする関数
+fn validate_difficulty_range(
+    params: &ProblemDifficultyStatsParameter,
+) -> Result<(), ValidationError> {
+    params.range().check()
+}
+
+impl ToQueryParameter for ProblemDifficultyStatsParameter {
+    fn to_query(&self) -> Vec<(String, String)> {
+        let fq = self
+            .category
+            .as_ref()
+            .map(|category| term_filter_queries("category", category))
+            .unwrap_or_default();
+        let range = self.range();
+        let category = json!({
+            "type": "terms",
+            "field": "category",
+            "limit": -1,
+            "mincount": 1
+        });
+        let facet = json!({
+            "difficulty": {
+                "type": "range",
+                "field": "difficulty",
+                "start": range.start,
+                "end": range.end,
+                "gap": range.gap,
+                "other": "all",
+                "facet": {
+                    "category": category
+                }
+            },
+            "unrated": {
+                "type": "query",
+                "q": "-difficulty:[* TO *]",
+                "facet": {
+                    "category": category
+                }
+            },
+            "category": category
+        });
+
+        EDisMaxQueryBuilder::new()
+            .q_alt("*:*")
+            .fq(&fq)
+            .rows(0)
+            .facet(facet.to_string())
+            .build()
+    }
+}
+
+// 難易度の区間ごとに集計するカテゴリのファセットカウント
+#[derive(Debug, Deserialize)]
+struct SolrCategoryFacets {
+    category: Option<SolrTermFacetCount>,
+}
+
+// Solrから返される難易度の分布のファセットカウント
+#[derive(Debug, Deserialize)]
+struct SolrDifficultyFacets {
+    difficulty: Option<SolrNestedRangeFacetCount<i32, SolrCategoryFacets>>,
+    unrated: Option<SolrNestedQueryFacetCount<SolrCategoryFacets>>,
+    category: Option<SolrTermFacetCount>,
+}
+
+/// 難易度の区間`[begin, end)`ごとの問題数
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq, Eq)]
+pub struct DifficultyBucket {
+    pub begin: i32,
+    pub end: i32,
+    pub count: u32,
+    /// Number of problems in the bucket per contest category
+    pub categories: Vec<FieldFacetEntry>,
+}
+
+/// 難易度が付いていない問題の数
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq, Eq)]
+pub struct UnratedCount {
+    pub count: u32,
+    /// Number of unrated problems per contest category
+    pub categories: Vec<FieldFacetEntry>,
+}
+
+/// 問題の難易度の分布
+#[derive(Debug, Clone, Serialize, ToSchema, PartialEq, Eq)]
+pub struct ProblemDifficultyStats {
+    /// Number of problems counted
+    pub total: u32,
+    /// Number of problems per difficulty bucket, in ascending order of difficulty
+    pub buckets: Vec<DifficultyBucket>,
+    /// Number of problems whose difficulty is lower than `start`
+    pub before: u32,
+    /// Number of problems whose difficulty is higher than or equal to `end`
+    pub after: u32,
+    pub unrated: UnratedCount,
+    /// Number of problems per contest category
+    pub categories: Vec<FieldFacetEntry>,
+}
+
+// カテゴリのファセットカウントを件数のリストにする関数
+fn category_counts(facet: Option<SolrTermFacetCount>) -> Vec<FieldFacetEntry> {
+    facet
+        .map(|facet| {
+            facet
+                .buckets
+                .into_iter()
+                .map(|bucket| FieldFacetEntry {
+                    label: bucket.val,
+                    count: bucket.count,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl ProblemDifficultyStats {
+    // Solrのレスポンスには区間の上端が含まれないので、区間の幅`gap`から計算する
+    fn from_solr(total: u32, facets: Option<SolrDifficultyFacets>, gap: i32) -> Self {
+        let (difficulty, unrated, category) = match facets {
+            Some(facets) => (facets.difficulty, facets.unrated, facets.category),
+            None => (None, None, None),
+        };
+
+        let (buckets, before, after) = match difficulty {
+            Some(difficulty) => (
+                difficulty
+                    .buckets
+                    .into_iter()
+                    .map(|bucket| DifficultyBucket {
+                        begin: bucket.val,
+                        end: bucket.val + gap,
+                        count: bucket.count,
+                        categories: category_counts(bucket.facets.category),
+                    })
+                    .collect(),
+                difficulty.before.map(|info| info.count).unwrap_or(0),
+                difficulty.after.map(|info| info.count).unwrap_or(0),
+            ),
+            None => (Vec::new(), 0, 0),
+        };
+        let unrated = match unrated {
+            Some(unrated) => UnratedCount {
+                count: unrated.count,
+                categories: category_counts(unrated.facets.category),
+            },
+            None => UnratedCount {
+                count: 0,
+                categories: Vec::new(),
+            },
+        };
+
+        Self {
+            total,
+            buckets,
+            before,
+            after,
+            unrated,
+            categories: category_counts(category),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats/problems/difficulty",
+    tag = "stats",
+    params(ProblemDifficultyStatsParameter),
+    responses(
+        (status = 200, description = "Distribution of problems per difficulty and category", body = ProblemDifficultyStats),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 503, description = "Failed to request to Solr", body = ErrorResponse),
+    )
+)]
+pub async fn problem_difficulty_stats<C>(
+    State(state): State<AppState<C>>,
+    ValidatedQueryParameters(params): ValidatedQueryParameters<ProblemDifficultyStatsParameter>,
+) -> Result<([(HeaderName, String); 2], Json<ProblemDifficultyStats>), ApiError>
+where
+    C: SolrCore + Send + Sync + 'static,
+{
+    let ttl = state.config.stats_cache_ttl;
+    let key = params.canonical_key(&state.config);
+    if let Some(stats) = state.stats.problem_difficulty.get(&key, ttl) {
+        return Ok((cache_headers(ttl, &key, &stats), Json(stats)));
+    }
+
+    let response: SolrSelectResponse<Value, SolrDifficultyFacets> =
+        match state.problem_core.select(&params.to_query()).await {
+            Ok(res) => res,
+            Err(e) => {
+                tracing::error!("request failed cause: {:?}", e);
+                return Err(ApiError::solr_unavailable("failed to count problems"));
+            }
+        };
+
+    let stats = ProblemDifficultyStats::from_solr(
+        response.response.num_found,
+        response.facets,
+        params.range().gap,
+    );
+    state
+        .stats
+        .problem_difficulty
+        .insert(&key, stats.clone(), ttl);
+
+    Ok((cache_headers(ttl, &key, &stats), Json(stats)))
+}
+
+/// 直近に解かれている問題の取得のパラメータ
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams, PartialEq, Eq, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct TrendingProblemsParameter {
+    /// Number of days to count the solves in. Defaults to 7, up to 30
+    #[validate(range(min = 1, max = 30))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<u32>,
+    /// Number of problems to return. Defaults to 20, up to 100
+    #[validate(range(min = 1, max = 100))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+impl Canonicalize for TrendingProblemsParameter {
+    fn canonicalize(&self, _: &ServerConfig) -> Self {
+        Self {
+            days: Some(self.days.unwrap_or(DEFAULT_TRENDING_DAYS)),
+            limit: Some(self.limit.unwrap_or(DEFAULT_TRENDING_LIMIT)),
+        }
+    }
+}
+
+/// 直近に解かれている問題
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct TrendingProblemEntry {
+    pub problem_id: String,
+    pub contest_id: String,
+    pub problem_title: String,
+    /// Number of users who solved the problem in the period
+    pub solved_count: i64,
+    /// Number of users who solved the problem in the preceding period of the same length
+    pub previous_solved_count: i64,
+    /// Difference of `solved_count` from `previous_solved_count`
+    pub velocity_delta: i64,
+}
+
+impl From<TrendingProblem> for TrendingProblemEntry {
+    fn from(problem: TrendingProblem) -> Self {
+        Self {
+            velocity_delta: problem.recent_solved_count - problem.previous_solved_count,
+            problem_id: problem.problem_id,
+            contest_id: problem.contest_id,
+            problem_title: problem.problem_title,
+            solved_count: problem.recent_solved_count,
+            previous_solved_count: problem.previous_solved_count,
+        }
+    }
+}
+
+/// 直近に解かれている問題の一覧
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct TrendingProblemsResponse {
+    /// Number of days the solves are counted in
+    pub days: u32,
+    /// Time the solves are counted at
+    pub computed_at: DateTime<Utc>,
+    /// Problems in descending order of `solved_count`
+    pub items: Vec<TrendingProblemEntry>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/problems/trending",
+    tag = "stats",
+    params(TrendingProblemsParameter),
+    responses(
+        (status = 200, description = "Problems solved by the most users recently", body = TrendingProblemsResponse),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 500, description = "Failed to count the solves", body = ErrorResponse),
+    )
+)]
+pub async fn trending_problems<C>(
+    State(state): State<AppState<C>>,
+    ValidatedQueryParameters(params): ValidatedQueryParameters<TrendingProblemsParameter>,
+) -> Result<([(HeaderName, String); 2], Json<TrendingProblemsResponse>), ApiError> {
+    let ttl = Duration::from_secs(TRENDING_CACHE_TTL);
+    let key = params.canonical_key(&state.config);
+    if let Some(trending) = state.stats.trending_problems.get(&key, ttl) {
+        return Ok((cache_headers(ttl, &key, &trending), Json(trending)));
+    }
+    let Some(pool) = state.database.clone() else {
+        return Err(ApiError::internal_error("database is not configured"));
+    };
+
+    let days = params.days.unwrap_or(DEFAULT_TRENDING_DAYS);
+    let limit = params.limit.unwrap_or(DEFAULT_TRENDING_LIMIT);
+    let now = Utc::now();
+    let problems = TrendingProblems::new(pool)
+        .top(now.timestamp(), days as i64 * 24 * 60 * 60, limit as i64)
+        .await
+        .map_err(|e| {
+            tracing::error!("failed to count the solves cause: {:?}", e);
+            ApiError::internal_error("failed to count the solves")
+        })?;
+    let trending = TrendingProblemsResponse {
+        days,
+        computed_at: now,
+        items: problems
+            .into_iter()
+            .map(TrendingProblemEntry::from)
+            .collect(),
+    };
+    state
+        .stats
+        .trending_problems
+        .insert(&key, trending.clone(), ttl);
+
+    Ok((cache_headers(ttl, &key, &trending), Json(trending)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::MockSolrCore;
+
+    #[test]
+    fn test_response_cache() {
+        let cache = ResponseCache::new();
+        cache.insert("JP", 1, Duration::from_secs(60));
+        assert_eq!(cache.get("JP", Duration::from_secs(60)), Some(1));
+        assert_eq!(cache.get("JP", Duration::ZERO), None);
+        assert_eq!(cache.get("US", Duration::from_secs(60)), None);
+    }
+
+    #[test]
+    fn test_color_stats_query() {
+        let params: UserColorStatsParameter = serde_structuredqs::from_str("country=JP").unwrap();
+        assert!(params.validate().is_ok());
+
+        let query = params.to_query();
+        assert!(query.contains(&(String::from("rows"), String::from("0"))));
+        assert!(query.contains(&(
+            String::from("fq"),
+            String::from(r#"{!tag=country}country:("JP")"#)
+        )));
+
+        let params: UserColorStatsParameter =
+            serde_structuredqs::from_str("country=Japan").unwrap();
+        assert!(params.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_user_color_stats() {
+        let core = MockSolrCore::new("users");
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 1},
+            "response": {"numFound": 30, "start": 0, "numFoundExact": true, "docs": []},
+            "facets": {
+                "count": 30,
+                "color": {"buckets": [{"val": "gray", "count": 20}, {"val": "red", "count": 10}]},
+                "highest_color": {"buckets": [{"val": "brown", "count": 15}, {"val": "gray", "count": 15}]}
+            }
+        }));
+        let state = AppState::new(
+            MockSolrCore::new("problems"),
+            core.clone(),
+            MockSolrCore::new("recommends"),
+        );
+
+        let mut etags = Vec::new();
+        for _ in 0..2 {
+            let (headers, Json(stats)) = user_color_stats(
+                State(state.clone()),
+                ValidatedQueryParameters(UserColorStatsParameter::default()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(headers[0].1, "public, max-age=300");
+            assert!(headers[1].1.starts_with("W/\""));
+            etags.push(headers[1].1.clone());
+            assert_eq!(stats.total, 30);
+            assert_eq!(stats.color.len(), 10);
+            assert_eq!(
+                stats.color[0],
+                ColorCount {
+                    color: String::from("gray"),
+                    count: 20
+                }
+            );
+            assert_eq!(stats.color[7].count, 10);
+            assert_eq!(stats.color[1].count, 0);
+            assert_eq!(stats.highest_color[1].count, 15);
+        }
+
+        // 2回目はキャッシュから返すのでSolrには1回しかリクエストしない
+        assert_eq!(core.selects().len(), 1);
+        assert_eq!(etags[0], etags[1]);
+    }
+
+    #[test]
+    fn test_difficulty_stats_cache_key() {
+        let config = ServerConfig::default();
+        let params: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("gap=200&category=ARC,ABC").unwrap();
+        let equivalent: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("start=0&end=4000&gap=200&category=ABC,ARC,ABC").unwrap();
+        assert_eq!(
+            params.canonical_key(&config),
+            equivalent.canonical_key(&config)
+        );
+
+        let other: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("gap=400&category=ARC,ABC").unwrap();
+        assert_ne!(params.canonical_key(&config), other.canonical_key(&config));
+    }
+
+    #[test]
+    fn test_difficulty_stats_query() {
+        let params: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("gap=200&category=ABC,-ARC").unwrap();
+        assert!(params.validate().is_ok());
+        assert_eq!(
+            params.range(),
+            FacetRange {
+                start: 0,
+                end: 4000,
+                gap: 200
+            }
+        );
+
+        let query = params.to_query();
+        assert!(query.contains(&(String::from("rows"), String::from("0"))));
+        assert!(query.contains(&(
+            String::from("fq"),
+            String::from(r#"{!tag=category}category:("ABC")"#)
+        )));
+        assert!(query.contains(&(
+            String::from("fq"),
+            String::from(r#"{!tag=category}-category:("ARC")"#)
+        )));
+        let facet: Value = query
+            .iter()
+            .find(|(key, _)| key == "json.facet")
+            .map(|(_, value)| serde_json::from_str(value).unwrap())
+            .unwrap();
+        assert_eq!(facet["difficulty"]["gap"], 200);
+        assert_eq!(
+            facet["difficulty"]["facet"]["category"]["field"],
+            "category"
+        );
+        assert_eq!(facet["unrated"]["q"], "-difficulty:[* TO *]");
+
+        let params: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("start=2000&end=1000").unwrap();
+        assert!(params.validate().is_err());
+        let params: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("category=XYZ").unwrap();
+        assert!(params.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_problem_difficulty_stats() {
+        let core = MockSolrCore::new("problems");
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 1},
+            "response": {"numFound": 10, "start": 0, "numFoundExact": true, "docs": []},
+            "facets": {
+                "count": 10,
+                "difficulty": {
+                    "buckets": [
+                        {"val": 0, "count": 5, "category": {"buckets": [{"val": "ABC", "count": 4}, {"val": "ARC", "count": 1}]}},
+                        {"val": 2000, "count": 0}
+                    ],
+                    "before": {"count": 1, "category": {"buckets": [{"val": "ABC", "count": 1}]}},
+                    "after": {"count": 2, "category": {"buckets": [{"val": "AGC", "count": 2}]}},
+                    "between": {"count": 5}
+                },
+                "unrated": {"count": 2, "category": {"buckets": [{"val": "Other Contests", "count": 2}]}},
+                "category": {"buckets": [{"val": "ABC", "count": 5}, {"val": "AGC", "count": 2}]}
+            }
+        }));
+        let state = AppState::new(
+            core.clone(),
+            MockSolrCore::new("users"),
+            MockSolrCore::new("recommends"),
+        );
+        let params: ProblemDifficultyStatsParameter =
+            serde_structuredqs::from_str("gap=2000").unwrap();
+
+        for _ in 0..2 {
+            let (headers, Json(stats)) = problem_difficulty_stats(
+                State(state.clone()),
+                ValidatedQueryParameters(params.clone()),
+            )
+            .await
+            .unwrap();
+            assert_eq!(headers[0].1, "public, max-age=300");
+            assert_eq!(stats.total, 10);
+            assert_eq!(stats.buckets.len(), 2);
+            assert_eq!(stats.buckets[0].begin, 0);
+            assert_eq!(stats.buckets[0].end, 2000);
+            assert_eq!(
+                stats.buckets[0].categories[1],
+                FieldFacetEntry {
+                    label: String::from("ARC"),
+                    count: 1
+                }
+            );
+            assert!(stats.buckets[1].categories.is_empty());
+            assert_eq!(stats.before, 1);
+            assert_eq!(stats.after, 2);
+            assert_eq!(stats.unrated.count, 2);
+            assert_eq!(stats.categories.len(), 2);
+        }
+
+        // 2回目はキャッシュから返すのでSolrには1回しかリクエストしない
+        assert_eq!(core.selects().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_trending_problems() {
+        let params: TrendingProblemsParameter =
+            serde_structuredqs::from_str("days=31&limit=10").unwrap();
+        assert!(params.validate().is_err());
+        let params: TrendingProblemsParameter = serde_structuredqs::from_str("limit=0").unwrap();
+        assert!(params.validate().is_err());
+
+        let config = ServerConfig::default();
+        let params: TrendingProblemsParameter = serde_structuredqs::from_str("limit=20").unwrap();
+        assert_eq!(
+            params.canonical_key(&config),
+            TrendingProblemsParameter::default().canonical_key(&config)
+        );
+
+        let state = AppState::new(
+            MockSolrCore::new("problems"),
+            MockSolrCore::new("users"),
+            MockSolrCore::new("recommends"),
+        );
+        assert!(trending_problems(
+            State(state.clone()),
+            ValidatedQueryParameters(params.clone())
+        )
+        .await
+        .is_err());
+
+        // キャッシュされている間はデータベースに問い合わせない
+        let trending = TrendingProblemsResponse {
+            days: 7,
+            computed_at: Utc::now(),
+            items: vec![TrendingProblemEntry::from(TrendingProblem {
+                problem_id: String::from("abc300_a"),
+                contest_id: String::from("abc300"),
+                problem_title: String::from("A. N-choice question"),
+                recent_solved_count: 30,
+                previous_solved_count: 45,
+            })],
+        };
+        assert_eq!(trending.items[0].velocity_delta, -15);
+        state.stats.trending_problems.insert(
+            &params.canonical_key(&config),
+            trending.clone(),
+            Duration::from_secs(TRENDING_CACHE_TTL),
+        );
+        let (headers, Json(response)) = trending_problems(
+            State(state),
+            ValidatedQueryParameters(TrendingProblemsParameter::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(headers[0].1, "public, max-age=3600");
+        assert_eq!(response, trending);
+    }
+}
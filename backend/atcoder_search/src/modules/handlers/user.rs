@@ -0,0 +1,963 @@
+use crate::{
+    modules::handlers::{AdminAccess, AppState, ProfileState, ServerConfig, DEFAULT_ROWS},
+    types::request::{
+        canonical_keyword, canonical_values, comma_separated_values, common_search_parameters,
+        json_facet, query_parameter, range_facet_parameters, range_query_parameters,
+        select_field_list, stats_facet, term_facet, term_facet_limit, to_sort_expression,
+        validate_limit, validate_response_fields, validate_sort_keys, Canonicalize, FacetRange,
+        PaginatedParameter, RangeFacetParameter, RangeFilterParameter,
+        ValidatedSearchQueryParameters, STATS_PERCENTILES,
+    },
+};
+use async_graphql::{InputObject, SimpleObject};
+use atcoder_search_libs::{
+    api::{
+        FieldFacetCount, FilterExpr, RangeFacetCount, SearchResultResponse, SearchResultStats,
+        StatsFacetCount,
+    },
+    color::{Color, COLOR_NAMES},
+    country::Country,
+    kana::to_reading,
+    solr::{
+        core::SolrCore,
+        model::*,
+        query::{parse_keyword, EDisMaxQueryBuilder, Operator},
+    },
+    ApiError, FieldList, ToQueryParameter,
+};
+use axum::{extract::State, Json};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_with::skip_serializing_none;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+};
+use tokio::time::Instant;
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterIn},
+        SchemaType,
+    },
+    IntoParams, ToSchema,
+};
+use validator::{Validate, ValidationError};
+
+// ソート順に指定できるフィールド
+pub const SORT_OPTIONS: [&str; 15] = [
+    "rating",
+    "-rating",
+    "highest_rating",
+    "-highest_rating",
+    "birth_year",
+    "-birth_year",
+    "join_count",
+    "-join_count",
+    "wins",
+    "-wins",
+    "rating_delta_3m",
+    "-rating_delta_3m",
+    "max_streak",
+    "-max_streak",
+    "-score",
+];
+
+// 絞り込みに指定できる色
+pub const COLOR_OPTIONS: [&str; 10] = COLOR_NAMES;
+
+// キーワード中で`<field>:<value>`の形で検索対象を絞り込めるフィールド
+pub const KEYWORD_FIELDS: [&str; 3] = ["color", "country", "affiliation"];
+
+// ファセットカウントに指定できるフィールド
+pub const FACET_FIELDS: [&str; 7] = [
+    "color",
+    "highest_color",
+    "affiliation",
+    "affiliation_normalized",
+    "country",
+    "rating",
+    "birth_year",
+];
+
+// 語のファセットのバケット数の上限をデフォルトから変えるフィールド
+//
+// 国・地域はデフォルトの上限より種類が多いのですべて返せるようにする。
+const TERM_FACET_LIMITS: [(&str, u32); 1] = [("country", 300)];
+
+// レーティングの範囲ファセットのデフォルトの区間
+const RATING_FACET_RANGE: FacetRange = FacetRange {
+    start: 0,
+    end: 4000,
+    gap: 400,
+};
+
+// 生まれ年の範囲ファセットのデフォルトの区間
+const BIRTH_YEAR_FACET_RANGE: FacetRange = FacetRange {
+    start: 1970,
+    end: 2020,
+    gap: 5,
+};
+
+// 統計量の集計に指定できるフィールド
+pub const STATS_FIELDS: [&str; 5] = [
+    "rating",
+    "highest_rating",
+    "birth_year",
+    "join_count",
+    "wins",
+];
+
+// ソート順に指定できるフィールドの集合
+static VALID_SORT_OPTIONS: Lazy<HashSet<&str>> = Lazy::new(|| HashSet::from(SORT_OPTIONS));
+
+// ファセットカウントに指定できるフィールドの集合
+static VALID_FACET_FIELDS: Lazy<HashSet<&str>> = Lazy::new(|| HashSet::from(FACET_FIELDS));
+
+// 統計量の集計に指定できるフィールドの集合
+static VALID_STATS_FIELDS: Lazy<HashSet<&str>> = Lazy::new(|| HashSet::from(STATS_FIELDS));
+
+// ソート順指定パラメータの値をバリデーションする関数
+fn validate_sort_fields(values: &[String]) -> Result<(), ValidationError> {
+    validate_sort_keys(values, &VALID_SORT_OPTIONS, &SORT_OPTIONS)
+}
+
+// 色の絞り込みパラメータの値をバリデーションする関数
+fn validate_color_filtering(values: &[String]) -> Result<(), ValidationError> {
+    let invalid_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.strip_prefix('-').unwrap_or(value))
+        .filter(|value| value.parse::<Color>().is_err())
+        .collect();
+
+    if invalid_values.is_empty() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("invalid color field");
+        error.add_param(Cow::from("invalid_values"), &invalid_values);
+        error.add_param(Cow::from("allowed"), &COLOR_OPTIONS);
+        Err(error)
+    }
+}
+
+// ファセットカウント指定パラメータの値をバリデーションする関数
+fn validate_facet_fields(values: &[String]) -> Result<(), ValidationError> {
+    let invalid_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.as_str())
+        .filter(|value| !VALID_FACET_FIELDS.contains(value))
+        .collect();
+
+    if invalid_values.is_empty() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("invalid facet field");
+        error.add_param(Cow::from("invalid_values"), &invalid_values);
+        error.add_param(Cow::from("allowed"), &FACET_FIELDS);
+        Err(error)
+    }
+}
+
+// レーティングの範囲ファセットの区間指定をバリデーションする関数
+fn validate_rating_facet(value: &RangeFacetParameter) -> Result<(), ValidationError> {
+    RangeFacetParameter::resolve(Some(value), RATING_FACET_RANGE).check()
+}
+
+// 生まれ年の範囲ファセットの区間指定をバリデーションする関数
+fn validate_birth_year_facet(value: &RangeFacetParameter) -> Result<(), ValidationError> {
+    RangeFacetParameter::resolve(Some(value), BIRTH_YEAR_FACET_RANGE).check()
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
+pub struct UserSearchParameter {
+    #[validate(length(max = 200))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    #[validate(custom(function = "validate_limit", arg = "&'v_a ServerConfig"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[validate(range(min = 1))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    #[validate]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<FilterParameter>,
+    #[validate(custom = "validate_sort_fields")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub sort: Option<Vec<String>>,
+    #[validate(custom = "validate_facet_fields")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub facet: Option<Vec<String>>,
+    #[validate(custom = "validate_stats_fields")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub stats: Option<Vec<String>>,
+    #[validate]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range_facet: Option<RangeFacetParameters>,
+    #[validate(custom = "validate_fields")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    pub fields: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+}
+
+// レスポンスのフィールド選択パラメータの値をバリデーションする関数
+fn validate_fields(values: &[String]) -> Result<(), ValidationError> {
+    validate_response_fields(values, UserResponse::field_list())
+}
+
+// 統計量の集計を指定するパラメータの値をバリデーションする関数
+fn validate_stats_fields(values: &[String]) -> Result<(), ValidationError> {
+    let invalid_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.as_str())
+        .filter(|value| !VALID_STATS_FIELDS.contains(value))
+        .collect();
+
+    if invalid_values.is_empty() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("invalid stats field");
+        error.add_param(Cow::from("invalid_values"), &invalid_values);
+        error.add_param(Cow::from("allowed"), &STATS_FIELDS);
+        Err(error)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
+pub struct RangeFacetParameters {
+    #[validate(custom = "validate_rating_facet")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<RangeFacetParameter>,
+    #[validate(custom = "validate_birth_year_facet")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    birth_year: Option<RangeFacetParameter>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, InputObject, PartialEq, Eq, Clone)]
+#[graphql(name = "UserFilter", rename_fields = "snake_case")]
+pub struct FilterParameter {
+    #[validate(custom = "validate_color_filtering")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    color: Option<Vec<String>>,
+    #[validate(custom = "validate_color_filtering")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    highest_color: Option<Vec<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    affiliation: Option<Vec<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    affiliation_normalized: Option<Vec<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "comma_separated_values"
+    )]
+    country: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<RangeFilterParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    birth_year: Option<RangeFilterParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    join_count: Option<RangeFilterParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating_delta_3m: Option<RangeFilterParameter>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_streak: Option<RangeFilterParameter>,
+}
+
+impl PaginatedParameter for UserSearchParameter {
+    fn limit_mut(&mut self) -> &mut Option<u32> {
+        &mut self.limit
+    }
+}
+
+impl IntoParams for UserSearchParameter {
+    fn into_params(_: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        let mut params = common_search_parameters(
+            &SORT_OPTIONS,
+            &FACET_FIELDS,
+            &STATS_FIELDS,
+            UserResponse::field_list(),
+        );
+        params.push(query_parameter(
+            "filter.color",
+            "Comma separated colors of current rating to filter. Colors prefixed with `-` are excluded",
+            SchemaType::String,
+            Some(&COLOR_OPTIONS),
+            true,
+        ));
+        params.push(query_parameter(
+            "filter.highest_color",
+            "Comma separated colors of highest rating to filter. Colors prefixed with `-` are excluded",
+            SchemaType::String,
+            Some(&COLOR_OPTIONS),
+            true,
+        ));
+        params.push(query_parameter(
+            "filter.affiliation",
+            "Comma separated affiliations to filter. Affiliations prefixed with `-` are excluded",
+            SchemaType::String,
+            None,
+            true,
+        ));
+        params.push(query_parameter(
+            "filter.affiliation_normalized",
+            "Comma separated normalized affiliations to filter, which merge the notational variants such as `Univ. of Tokyo` and `東京大学`. Affiliations prefixed with `-` are excluded",
+            SchemaType::String,
+            None,
+            true,
+        ));
+        params.push(query_parameter(
+            "filter.country",
+            "Comma separated country codes or English or Japanese country names to filter, e.g. `JP`, `Japan` or `日本`. Countries prefixed with `-` are excluded",
+            SchemaType::String,
+            None,
+            true,
+        ));
+        params.extend(range_query_parameters(
+            "filter.rating",
+            "Rating range to filter",
+        ));
+        params.extend(range_query_parameters(
+            "filter.birth_year",
+            "Birth year range to filter",
+        ));
+        params.extend(range_query_parameters(
+            "filter.join_count",
+            "Join count range to filter",
+        ));
+        params.extend(range_query_parameters(
+            "filter.rating_delta_3m",
+            "Range of the rating change over the last 90 days to filter. Users who have not joined any contest before the last 90 days are excluded",
+        ));
+        params.extend(range_query_parameters(
+            "filter.max_streak",
+            "Range of the longest run of consecutive rating increases to filter",
+        ));
+        params.extend(range_facet_parameters("rating", RATING_FACET_RANGE));
+        params.extend(range_facet_parameters("birth_year", BIRTH_YEAR_FACET_RANGE));
+        params
+    }
+}
+
+impl UserSearchParameter {
+    // レーティングの範囲ファセットの区間を返す関数
+    fn rating_facet_range(&self) -> FacetRange {
+        RangeFacetParameter::resolve(
+            self.range_facet
+                .as_ref()
+                .and_then(|range_facet| range_facet.rating.as_ref()),
+            RATING_FACET_RANGE,
+        )
+    }
+
+    // 生まれ年の範囲ファセットの区間を返す関数
+    fn birth_year_facet_range(&self) -> FacetRange {
+        RangeFacetParameter::resolve(
+            self.range_facet
+                .as_ref()
+                .and_then(|range_facet| range_facet.birth_year.as_ref()),
+            BIRTH_YEAR_FACET_RANGE,
+        )
+    }
+}
+
+// キーワードの各語に、ローマ字やカナから生成した読みを候補として加えたクエリを作る関数
+//
+// `hamayan`と`はまやん`のどちらで検索しても、`user_name`か`user_name_reading`のいずれかに一致するようにする。
+fn reading_keyword_query(keyword: &str) -> String {
+    parse_keyword(keyword, &KEYWORD_FIELDS)
+        .into_iter()
+        .map(|term| {
+            let reading = match term.field {
+                Some(_) => None,
+                None => to_reading(&term.value),
+            };
+            match reading {
+                Some(reading) if reading != term.value => term.with_alternatives(&[reading]),
+                _ => term.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl ToQueryParameter for UserSearchParameter {
+    fn to_query(&self) -> Vec<(String, String)> {
+        let rows = self.limit.unwrap_or(DEFAULT_ROWS);
+        let page = self.page.unwrap_or(1);
+        let start = (page - 1) * rows;
+        // 件数とファセットだけを返す場合はドキュメントの取得と並べ替えを省く
+        let count_only = self.count_only.unwrap_or(false);
+        let keyword = self
+            .keyword
+            .as_ref()
+            .map(|keyword| reading_keyword_query(keyword))
+            .unwrap_or(String::from(""));
+        let sort = to_sort_expression(&self.sort, "user_name");
+        let fq = self
+            .filter
+            .as_ref()
+            .map(|filter| filter.to_query())
+            .unwrap_or(vec![]);
+
+        let mut facet_params: BTreeMap<String, Value> = BTreeMap::new();
+        for field in self.facet.iter().flatten() {
+            match field.as_str() {
+                "color"
+                | "highest_color"
+                | "affiliation"
+                | "affiliation_normalized"
+                | "country" => {
+                    facet_params.insert(
+                        field.to_string(),
+                        term_facet(field, term_facet_limit(&TERM_FACET_LIMITS, field)),
+                    );
+                }
+                "rating" => {
+                    let range = self.rating_facet_range();
+                    facet_params.insert(
+                        field.to_string(),
+                        json!({
+                            "type": "range",
+                            "field": "rating",
+                            "start": range.start,
+                            "end": range.end,
+                            "gap": range.gap,
+                            "other": "all",
+                            "domain": {
+                                "excludeTags": ["rating"]
+                            }
+                        }),
+                    );
+                }
+                "birth_year" => {
+                    let range = self.birth_year_facet_range();
+                    facet_params.insert(
+                        field.to_string(),
+                        json!({
+                            "type": "range",
+                            "field": "birth_year",
+                            "start": range.start,
+                            "end": range.end,
+                            "gap": range.gap,
+                            "other": "all",
+                            "domain": {
+                                "excludeTags": ["birth_year"]
+                            }
+                        }),
+                    );
+                }
+                _ => {}
+            };
+        }
+        for field in self.stats.iter().flatten() {
+            facet_params.insert(format!("{}_stats", field), stats_facet(field));
+        }
+        let facet = json_facet(facet_params);
+
+        let builder = EDisMaxQueryBuilder::new()
+            .facet(facet)
+            .fl(select_field_list(&self.fields, UserResponse::field_list()))
+            .fq(&fq)
+            .op(Operator::AND)
+            .q(keyword)
+            .q_alt("*:*")
+            .qf("user_name user_name_reading")
+            .rows(if count_only { 0 } else { rows })
+            .sort(if count_only { String::new() } else { sort })
+            .sow(true)
+            .start(if count_only { 0 } else { start });
+
+        if self.debug.unwrap_or(false) {
+            builder.debug().build()
+        } else {
+            builder.build()
+        }
+    }
+}
+
+impl Canonicalize for UserSearchParameter {
+    fn canonicalize(&self, config: &ServerConfig) -> Self {
+        Self {
+            keyword: canonical_keyword(&self.keyword),
+            limit: Some(self.limit.unwrap_or(config.default_rows)),
+            page: Some(self.page.unwrap_or(1)),
+            filter: self.filter.as_ref().map(FilterParameter::canonicalize),
+            facet: canonical_values(&self.facet),
+            stats: canonical_values(&self.stats),
+            fields: canonical_values(&self.fields),
+            count_only: self.count_only.filter(|&count_only| count_only),
+            debug: self.debug.filter(|&debug| debug),
+            ..self.clone()
+        }
+    }
+}
+
+impl FilterParameter {
+    // 絞り込みの値の並びをそろえる関数
+    fn canonicalize(&self) -> Self {
+        Self {
+            color: canonical_values(&self.color),
+            highest_color: canonical_values(&self.highest_color),
+            affiliation: canonical_values(&self.affiliation),
+            affiliation_normalized: canonical_values(&self.affiliation_normalized),
+            country: canonical_values(&self.country),
+            ..self.clone()
+        }
+    }
+
+    pub fn to_query(&self) -> Vec<String> {
+        // 国は国コードのほかに英語や日本語の国名でも指定できる
+        let country = self.country.as_ref().map(|values| {
+            values
+                .iter()
+                .map(|value| {
+                    let (prefix, value) = match value.strip_prefix('-') {
+                        Some(value) => ("-", value),
+                        None => ("", value.as_str()),
+                    };
+                    match Country::resolve(value) {
+                        Some(country) => format!("{}{}", prefix, country.code),
+                        None => format!("{}{}", prefix, value),
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        // ファセットのexcludeTagsで除外できるように、全ての条件にフィールド名のタグを付ける
+        let mut filters: Vec<(&str, FilterExpr)> = vec![];
+        for (field, values) in [
+            ("color", &self.color),
+            ("highest_color", &self.highest_color),
+            ("affiliation", &self.affiliation),
+            ("affiliation_normalized", &self.affiliation_normalized),
+            ("country", &country),
+        ] {
+            if let Some(values) = values {
+                filters.extend(
+                    FilterExpr::term_set(field, values)
+                        .into_iter()
+                        .map(|filter| (field, filter)),
+                );
+            }
+        }
+        for (field, range) in [
+            ("rating", &self.rating),
+            ("birth_year", &self.birth_year),
+            ("join_count", &self.join_count),
+            ("rating_delta_3m", &self.rating_delta_3m),
+            ("max_streak", &self.max_streak),
+        ] {
+            if let Some(filter) = range.as_ref().and_then(|range| range.to_filter(field)) {
+                filters.push((field, filter));
+            }
+        }
+
+        filters
+            .iter()
+            .map(|(field, filter)| filter.to_fq(Some(field)))
+            .collect()
+    }
+}
+
+// `fields`パラメータで選択されなかったフィールドはSolrから返されないため、全てのフィールドを省略可能にしている
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, FieldList, ToSchema, SimpleObject)]
+#[graphql(name = "User", rename_fields = "snake_case")]
+pub struct UserResponse {
+    pub user_name: Option<String>,
+    pub rating: Option<i32>,
+    pub color: Option<String>,
+    pub highest_rating: Option<i32>,
+    pub highest_color: Option<String>,
+    pub affiliation: Option<String>,
+    pub affiliation_normalized: Option<String>,
+    pub birth_year: Option<i32>,
+    pub country: Option<String>,
+    /// English name of `country`
+    #[field_list(skip)]
+    #[graphql(skip)]
+    #[serde(default)]
+    pub country_name: Option<String>,
+    /// Japanese name of `country`
+    #[field_list(skip)]
+    #[graphql(skip)]
+    #[serde(default)]
+    pub country_name_ja: Option<String>,
+    pub crown: Option<String>,
+    pub join_count: Option<i32>,
+    pub rank: Option<i32>,
+    pub wins: Option<i32>,
+    /// Rating change over the last 90 days
+    pub rating_delta_3m: Option<i32>,
+    /// Longest run of consecutive rating increases
+    pub max_streak: Option<i32>,
+}
+
+impl UserResponse {
+    /// 国コードに対応する国名を付け加えるメソッド
+    fn fill_country_names(&mut self) {
+        if let Some(country) = self.country.as_deref().and_then(Country::from_code) {
+            self.country_name = Some(country.name.to_string());
+            self.country_name_ja = Some(country.name_ja.to_string());
+        }
+    }
+}
+
+// Solrから返されるファセットカウント
+#[derive(Debug, Deserialize)]
+struct SolrUserFacetCounts {
+    count: u32,
+    color: Option<SolrTermFacetCount>,
+    highest_color: Option<SolrTermFacetCount>,
+    affiliation: Option<SolrTermFacetCount>,
+    affiliation_normalized: Option<SolrTermFacetCount>,
+    country: Option<SolrTermFacetCount>,
+    rating: Option<SolrRangeFacetCount<i32>>,
+    birth_year: Option<SolrRangeFacetCount<i32>>,
+    rating_stats: Option<SolrStatsFacetCount>,
+    highest_rating_stats: Option<SolrStatsFacetCount>,
+    birth_year_stats: Option<SolrStatsFacetCount>,
+    join_count_stats: Option<SolrStatsFacetCount>,
+    wins_stats: Option<SolrStatsFacetCount>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserFacetCounts {
+    count: u32,
+    color: Option<FieldFacetCount>,
+    highest_color: Option<FieldFacetCount>,
+    affiliation: Option<FieldFacetCount>,
+    affiliation_normalized: Option<FieldFacetCount>,
+    country: Option<FieldFacetCount>,
+    rating: Option<RangeFacetCount>,
+    birth_year: Option<RangeFacetCount>,
+    rating_stats: Option<StatsFacetCount>,
+    highest_rating_stats: Option<StatsFacetCount>,
+    birth_year_stats: Option<StatsFacetCount>,
+    join_count_stats: Option<StatsFacetCount>,
+    wins_stats: Option<StatsFacetCount>,
+}
+
+impl UserFacetCounts {
+    fn from_solr(facets: SolrUserFacetCounts, params: &UserSearchParameter) -> Self {
+        let rating_gap = params.rating_facet_range().gap;
+        let birth_year_gap = params.birth_year_facet_range().gap;
+        let stats = |facet: Option<SolrStatsFacetCount>| {
+            facet.map(|facet| StatsFacetCount::from_solr(facet, &STATS_PERCENTILES))
+        };
+
+        Self {
+            count: facets.count,
+            color: facets.color.map(color_facet),
+            highest_color: facets.highest_color.map(color_facet),
+            affiliation: facets.affiliation.map(FieldFacetCount::from),
+            affiliation_normalized: facets.affiliation_normalized.map(FieldFacetCount::from),
+            country: facets.country.map(FieldFacetCount::from),
+            rating: facets
+                .rating
+                .map(|facet| RangeFacetCount::from_solr(facet, rating_gap)),
+            birth_year: facets
+                .birth_year
+                .map(|facet| RangeFacetCount::from_solr(facet, birth_year_gap)),
+            rating_stats: stats(facets.rating_stats),
+            highest_rating_stats: stats(facets.highest_rating_stats),
+            birth_year_stats: stats(facets.birth_year_stats),
+            join_count_stats: stats(facets.join_count_stats),
+            wins_stats: stats(facets.wins_stats),
+        }
+    }
+}
+
+// 色のファセットカウントを件数順ではなくレーティングの低い色から順に並べる関数。色でない値は末尾に置く。
+fn color_facet(facet: SolrTermFacetCount) -> FieldFacetCount {
+    let mut facet = FieldFacetCount::from(facet);
+    facet.counts.sort_by_key(|entry| {
+        entry
+            .label
+            .parse::<Color>()
+            .map_or(usize::MAX, |color| color as usize)
+    });
+    facet
+}
+
+type SearchResponse = Result<Json<SearchResultResponse<UserResponse, UserFacetCounts>>, ApiError>;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search/user",
+    tag = "search",
+    params(UserSearchParameter),
+    responses(
+        (status = 200, description = "Search result of users", body = UserSearchResult),
+        (status = 400, description = "Invalid query parameters", body = ErrorResponse),
+        (status = 503, description = "Failed to request to Solr", body = ErrorResponse),
+    )
+)]
+pub async fn search_user<C>(
+    State(state): State<AppState<C>>,
+    admin: AdminAccess,
+    ValidatedSearchQueryParameters(params): ValidatedSearchQueryParameters<UserSearchParameter>,
+) -> SearchResponse
+where
+    C: SolrCore + Send + Sync + 'static,
+{
+    if params.debug.unwrap_or(false) {
+        admin.require("debug")?;
+    }
+    let start_process = Instant::now();
+
+    let mut response: SolrSelectResponse<UserResponse, SolrUserFacetCounts> =
+        match state.user_core.select(&params.to_query()).await {
+            Ok(res) => res,
+            Err(e) => {
+                tracing::error!("request failed cause: {:?}", e);
+                return Err(ApiError::solr_unavailable("failed to search documents"));
+            }
+        };
+    for doc in response.response.docs.iter_mut() {
+        doc.fill_country_names();
+    }
+
+    let time: u32 = Instant::now().duration_since(start_process).as_millis() as u32;
+    let total: u32 = response.response.num_found;
+    let count: u32 = response.response.docs.len() as u32;
+    let rows: u32 = params.limit.unwrap_or(state.config.default_rows);
+    let index: u32 = (response.response.start / rows) + 1;
+    let pages: u32 = total.div_ceil(rows);
+
+    tracing::info!(
+        target: "querylog",
+        "domain=user elapsed_time={} hits={} params={}",
+        time, total, params.canonical_form(&state.config)
+    );
+
+    let stats = SearchResultStats {
+        time,
+        total,
+        index,
+        count,
+        pages,
+        params: serde_json::json!(params),
+        facet: response
+            .facets
+            .map(|facets| UserFacetCounts::from_solr(facets, &params)),
+        language: None,
+        expansions: Vec::new(),
+        shortcut: false,
+        explain: response.debug.and_then(|debug| debug.explain),
+    };
+
+    Ok(Json(SearchResultResponse {
+        stats,
+        items: response.response.docs,
+    }))
+}
+
+/// `/api/v1/:profile/search/user`で指定されたプロファイルのコアから検索する
+pub async fn search_user_in_profile<C>(
+    ProfileState(state): ProfileState<C>,
+    admin: AdminAccess,
+    params: ValidatedSearchQueryParameters<UserSearchParameter>,
+) -> SearchResponse
+where
+    C: SolrCore + Send + Sync + 'static,
+{
+    search_user(State(state), admin, params).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::MockSolrCore;
+
+    #[test]
+    fn test_reading_keyword_query() {
+        assert_eq!(
+            reading_keyword_query("hamayan tourist -ハマヤン color:red"),
+            "(hamayan OR はまやん) tourist -(ハマヤン OR はまやん) color:red"
+        );
+        assert_eq!(reading_keyword_query("はまやん"), "はまやん");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let query = "keyword=tourist&facet=color,rating&filter.color=red,silver&filter.rating.from=2800&sort=-rating";
+        let params: UserSearchParameter = serde_structuredqs::from_str(query).unwrap();
+
+        let expected = UserSearchParameter {
+            keyword: Some(String::from("tourist")),
+            limit: None,
+            page: None,
+            filter: Some(FilterParameter {
+                color: Some(vec![String::from("red"), String::from("silver")]),
+                highest_color: None,
+                affiliation: None,
+                affiliation_normalized: None,
+                country: None,
+                rating: Some(RangeFilterParameter {
+                    from: Some(2800),
+                    to: None,
+                }),
+                birth_year: None,
+                join_count: None,
+                rating_delta_3m: None,
+                max_streak: None,
+            }),
+            sort: Some(vec![String::from("-rating")]),
+            facet: Some(vec![String::from("color"), String::from("rating")]),
+            stats: None,
+            range_facet: None,
+            fields: None,
+            count_only: None,
+            debug: None,
+        };
+
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn test_filter_query() {
+        let query =
+            "filter.color=red,-gray&filter.affiliation_normalized=東京大学&filter.country=JP,-韓国,united%20states,XX&filter.rating.from=2800&filter.rating.to=3200&filter.rating_delta_3m.from=100";
+        let params: UserSearchParameter = serde_structuredqs::from_str(query).unwrap();
+
+        assert_eq!(
+            params.filter.unwrap().to_query(),
+            vec![
+                String::from(r#"{!tag=color}color:("red")"#),
+                String::from(r#"{!tag=color}-color:("gray")"#),
+                String::from(r#"{!tag=affiliation_normalized}affiliation_normalized:("東京大学")"#),
+                String::from(r#"{!tag=country}country:("JP" OR "US" OR "XX")"#),
+                String::from(r#"{!tag=country}-country:("KR")"#),
+                String::from("{!tag=rating}rating:[2800 TO 3200}"),
+                String::from("{!tag=rating_delta_3m}rating_delta_3m:[100 TO *}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_color_facet() {
+        let facet: SolrTermFacetCount = serde_json::from_value(json!({
+            "buckets": [
+                {"val": "gray", "count": 100},
+                {"val": "red", "count": 10},
+                {"val": "unknown", "count": 5},
+                {"val": "brown", "count": 50}
+            ]
+        }))
+        .unwrap();
+        let labels = color_facet(facet)
+            .counts
+            .into_iter()
+            .map(|entry| entry.label)
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["gray", "brown", "red", "unknown"]);
+    }
+
+    #[test]
+    fn test_term_facet_limit() {
+        let params: UserSearchParameter =
+            serde_structuredqs::from_str("facet=affiliation,country").unwrap();
+        let facet = params
+            .to_query()
+            .into_iter()
+            .find(|(key, _)| key == "json.facet")
+            .map(|(_, value)| serde_json::from_str::<Value>(&value).unwrap())
+            .unwrap();
+        assert_eq!(facet["affiliation"]["limit"], json!(200));
+        assert_eq!(facet["country"]["limit"], json!(300));
+    }
+
+    #[tokio::test]
+    async fn test_search_user() {
+        let core = MockSolrCore::new("users");
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 1},
+            "response": {
+                "numFound": 1,
+                "start": 0,
+                "numFoundExact": true,
+                "docs": [{
+                    "user_name": "tourist",
+                    "rating": 3800,
+                    "color": "red",
+                    "highest_rating": 4229,
+                    "highest_color": "red",
+                    "affiliation": "ITMO University",
+                    "birth_year": 1994,
+                    "country": "BY",
+                    "crown": "crown_champion",
+                    "join_count": 50,
+                    "rank": 1,
+                    "wins": 20
+                }]
+            }
+        }));
+        let state = AppState::new(
+            MockSolrCore::new("problems"),
+            core.clone(),
+            MockSolrCore::new("recommends"),
+        );
+        let params: UserSearchParameter =
+            serde_structuredqs::from_str("keyword=tourist&sort=-rating,wins").unwrap();
+
+        let Json(response) = search_user(
+            State(state),
+            AdminAccess(false),
+            ValidatedSearchQueryParameters(params),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.stats.total, 1);
+        assert_eq!(response.stats.pages, 1);
+        assert_eq!(response.items[0].user_name.as_deref(), Some("tourist"));
+        assert_eq!(response.items[0].country_name.as_deref(), Some("Belarus"));
+        assert_eq!(
+            response.items[0].country_name_ja.as_deref(),
+            Some("ベラルーシ")
+        );
+
+        let selects = core.selects();
+        assert_eq!(selects.len(), 1);
+        assert!(selects[0].contains(&(
+            String::from("sort"),
+            String::from("rating desc,wins asc,user_name asc")
+        )));
+    }
+}
@@ -0,0 +1,495 @@
+use crate::{
+    cmd::{
+        config::SearchConfig,
+        crawl::crawl,
+        update::{update_index, UpdateOptions},
+        TargetDomain,
+    },
+    modules::warmup::Warmup,
+};
+use anyhow::Result;
+use atcoder_search_libs::{IndexingProgress, ProgressReporter};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::{
+        broadcast::{self, Receiver, Sender},
+        watch, Notify,
+    },
+    time::Duration,
+};
+
+/// ジョブのID
+pub type JobId = i64;
+
+// 購読者が受け取りきれずに溜められるイベントの数
+const EVENT_CAPACITY: usize = 256;
+// ワーカーが通知を受け取れなかった場合にキューを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// ジョブの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// `jobs`テーブルの行
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct Job {
+    pub id: JobId,
+    pub domain: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// インデックス更新ジョブのキュー
+///
+/// ジョブは`jobs`テーブルに保存し、ワーカーが古いものから順に取り出して実行する。
+#[derive(Debug)]
+pub struct JobQueue {
+    pool: Pool<Postgres>,
+    notify: Notify,
+    search: SearchConfig,
+    warmup: Option<Arc<Warmup>>,
+}
+
+impl JobQueue {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            notify: Notify::new(),
+            search: SearchConfig::default(),
+            warmup: None,
+        }
+    }
+
+    /// ジョブで更新するコアの設定を`search`にする
+    pub fn with_search_config(self, search: SearchConfig) -> Self {
+        Self { search, ..self }
+    }
+
+    /// インデックスを入れ替えた後に`warmup`を実行する
+    pub fn with_warmup(self, warmup: Option<Arc<Warmup>>) -> Self {
+        Self { warmup, ..self }
+    }
+
+    /// ジョブを登録してワーカーに通知する
+    pub async fn enqueue(&self, domain: &TargetDomain) -> Result<JobId> {
+        let id: JobId = sqlx::query_scalar(
+            r#"
+            INSERT INTO "jobs" ("domain", "status")
+            VALUES ($1, $2)
+            RETURNING "id"
+            "#,
+        )
+        .bind(domain.to_string())
+        .bind(JobStatus::Queued.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.notify.notify_one();
+        Ok(id)
+    }
+
+    /// ジョブを取得する。存在しなければ`None`を返す。
+    pub async fn get(&self, id: JobId) -> Result<Option<Job>> {
+        let job: Option<Job> = sqlx::query_as(
+            r#"
+            SELECT
+                "id",
+                "domain",
+                "status",
+                "error",
+                "created_at",
+                "started_at",
+                "finished_at"
+            FROM
+                "jobs"
+            WHERE
+                "id" = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    // 最も古い待機中のジョブを実行中にして取り出す関数
+    async fn claim(&self) -> Result<Option<Job>> {
+        let job: Option<Job> = sqlx::query_as(
+            r#"
+            UPDATE "jobs"
+            SET
+                "status" = $1,
+                "started_at" = CURRENT_TIMESTAMP
+            WHERE
+                "id" = (
+                    SELECT "id" FROM "jobs"
+                    WHERE "status" = $2
+                    ORDER BY "id"
+                    LIMIT 1
+                    FOR UPDATE SKIP LOCKED
+                )
+            RETURNING
+                "id",
+                "domain",
+                "status",
+                "error",
+                "created_at",
+                "started_at",
+                "finished_at"
+            "#,
+        )
+        .bind(JobStatus::Running.as_str())
+        .bind(JobStatus::Queued.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    // ジョブの結果を記録する関数
+    async fn complete(&self, id: JobId, result: &Result<()>) -> Result<()> {
+        let (status, error) = match result {
+            Ok(_) => (JobStatus::Succeeded, None),
+            Err(e) => (JobStatus::Failed, Some(e.to_string())),
+        };
+        sqlx::query(
+            r#"
+            UPDATE "jobs"
+            SET
+                "status" = $1,
+                "error" = $2,
+                "finished_at" = CURRENT_TIMESTAMP
+            WHERE
+                "id" = $3
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(error)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// キューからジョブを取り出して1件ずつ実行し続ける関数
+    ///
+    /// ジョブの進捗は`runner`を通じて配信する。
+    /// `shutdown`に`true`が送られると実行中のジョブを中断して失敗として記録し、新しいジョブを取り出さずに終了する。
+    pub async fn run_worker(
+        self: Arc<Self>,
+        runner: Arc<JobRunner>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        while !*shutdown.borrow() {
+            match self.claim().await {
+                Ok(Some(job)) => self.execute(job, &runner, &mut shutdown).await,
+                Ok(None) => {
+                    tokio::select! {
+                        _ = tokio::time::timeout(POLL_INTERVAL, self.notify.notified()) => {},
+                        _ = shutdown_requested(&mut shutdown) => {},
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("failed to claim a job cause: {:?}", e);
+                    tokio::select! {
+                        _ = tokio::time::sleep(POLL_INTERVAL) => {},
+                        _ = shutdown_requested(&mut shutdown) => {},
+                    }
+                }
+            }
+        }
+        tracing::info!("job worker stopped");
+    }
+
+    // ジョブを実行して結果を記録する関数
+    async fn execute(
+        &self,
+        job: Job,
+        runner: &Arc<JobRunner>,
+        shutdown: &mut watch::Receiver<bool>,
+    ) {
+        tracing::info!("job {} for {} started", job.id, job.domain);
+        let handle = runner.register(job.id);
+        handle.send(JobEvent::Started);
+
+        // 入れ替え前に中断すれば本番のコアはそのまま残る
+        let result = match TargetDomain::from_str(&job.domain, true) {
+            Ok(domain) => tokio::select! {
+                result = self.reindex(&domain, handle.reporter()) => result,
+                _ = shutdown_requested(shutdown) => Err(anyhow::anyhow!("aborted by the server shutdown")),
+            },
+            Err(e) => Err(anyhow::anyhow!(e)),
+        };
+        match &result {
+            Ok(_) => tracing::info!("job {} succeeded", job.id),
+            Err(e) => tracing::error!("job {} failed cause: {:?}", job.id, e),
+        }
+
+        if let Err(e) = self.complete(job.id, &result).await {
+            tracing::error!(
+                "failed to record the result of job {} cause: {:?}",
+                job.id,
+                e
+            );
+        }
+        handle.finish(&result);
+    }
+
+    // データを収集してからインデックスを更新する関数
+    async fn reindex(&self, domain: &TargetDomain, progress: ProgressReporter) -> Result<()> {
+        crawl(&self.pool, domain, false).await?;
+        update_index(
+            &self.pool,
+            &self.search,
+            domain,
+            &UpdateOptions {
+                warmup: self.warmup.clone(),
+                ..Default::default()
+            },
+            Some(progress),
+        )
+        .await
+    }
+}
+
+/// `shutdown`に`true`が送られるまで待つ関数
+///
+/// 送信側が破棄された場合は停止の指示が来ないので、ずっと待ち続ける。
+async fn shutdown_requested(shutdown: &mut watch::Receiver<bool>) {
+    while !*shutdown.borrow() {
+        if shutdown.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// ジョブの進捗イベント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum JobEvent {
+    Queued,
+    Started,
+    Progress(IndexingProgress),
+    Failed { message: String },
+    Completed,
+}
+
+impl JobEvent {
+    /// SSEのイベント名
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobEvent::Queued => "queued",
+            JobEvent::Started => "started",
+            JobEvent::Progress(_) => "progress",
+            JobEvent::Failed { .. } => "failed",
+            JobEvent::Completed => "completed",
+        }
+    }
+
+    /// 実行中でないジョブの状態を表すイベントを返す
+    ///
+    /// 実行中のジョブでもチャネルがまだ登録されていなければ待機中として扱う。
+    pub fn from_job(job: &Job) -> Self {
+        if job.status == JobStatus::Succeeded.as_str() {
+            JobEvent::Completed
+        } else if job.status == JobStatus::Failed.as_str() {
+            JobEvent::Failed {
+                message: job.error.clone().unwrap_or_default(),
+            }
+        } else {
+            JobEvent::Queued
+        }
+    }
+
+    /// ジョブが終了したことを表すイベントかどうか
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobEvent::Failed { .. } | JobEvent::Completed)
+    }
+}
+
+/// 実行中のジョブの進捗イベントを購読者へ配信する
+///
+/// ジョブごとにブロードキャストチャネルを持ち、ジョブが終了したらチャネルを閉じる。
+#[derive(Debug, Default)]
+pub struct JobRunner {
+    channels: Mutex<HashMap<JobId, Sender<JobEvent>>>,
+}
+
+impl JobRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ジョブのチャネルを作り、進捗を通知するハンドルを返す
+    pub fn register(self: &Arc<Self>, id: JobId) -> JobHandle {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        self.channels.lock().unwrap().insert(id, sender.clone());
+        JobHandle {
+            id,
+            sender,
+            runner: self.clone(),
+        }
+    }
+
+    /// 実行中のジョブのイベントを購読する。ジョブが実行中でなければ`None`を返す。
+    pub fn subscribe(&self, id: JobId) -> Option<Receiver<JobEvent>> {
+        self.channels
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|sender| sender.subscribe())
+    }
+}
+
+/// 実行中のジョブから進捗イベントを送るハンドル
+pub struct JobHandle {
+    id: JobId,
+    sender: Sender<JobEvent>,
+    runner: Arc<JobRunner>,
+}
+
+impl JobHandle {
+    /// イベントを送る。購読者がいなければ捨てる。
+    pub fn send(&self, event: JobEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// ドキュメントの生成・投入の進捗をこのジョブのイベントとして送るコールバックを返す
+    pub fn reporter(&self) -> ProgressReporter {
+        let sender = self.sender.clone();
+        ProgressReporter::new(move |progress| {
+            let _ = sender.send(JobEvent::Progress(progress));
+        })
+    }
+
+    /// ジョブの結果を送り、チャネルを閉じる
+    pub fn finish(self, result: &Result<()>) {
+        match result {
+            Ok(_) => self.send(JobEvent::Completed),
+            Err(e) => self.send(JobEvent::Failed {
+                message: e.to_string(),
+            }),
+        }
+        self.runner.channels.lock().unwrap().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_job_status() {
+        assert_eq!(JobStatus::Queued.as_str(), "queued");
+        assert_eq!(JobStatus::Running.as_str(), "running");
+        assert_eq!(JobStatus::Succeeded.as_str(), "succeeded");
+        assert_eq!(JobStatus::Failed.as_str(), "failed");
+    }
+
+    #[tokio::test]
+    async fn test_job_events() {
+        let runner = Arc::new(JobRunner::new());
+        assert!(runner.subscribe(1).is_none());
+
+        let handle = runner.register(1);
+        let mut receiver = runner.subscribe(1).unwrap();
+        handle.send(JobEvent::Started);
+        handle
+            .reporter()
+            .report(IndexingProgress::RowsProcessed { rows: 10000 });
+        handle.finish(&Ok(()));
+
+        assert_eq!(receiver.recv().await.unwrap(), JobEvent::Started);
+        assert_eq!(
+            receiver.recv().await.unwrap(),
+            JobEvent::Progress(IndexingProgress::RowsProcessed { rows: 10000 })
+        );
+        assert_eq!(receiver.recv().await.unwrap(), JobEvent::Completed);
+        assert!(receiver.recv().await.is_err());
+        assert!(runner.subscribe(1).is_none());
+    }
+
+    #[test]
+    fn test_job_event_from_job() {
+        let job = |status: JobStatus, error: Option<&str>| Job {
+            id: 1,
+            domain: String::from("problems"),
+            status: String::from(status.as_str()),
+            error: error.map(String::from),
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+        };
+
+        assert_eq!(
+            JobEvent::from_job(&job(JobStatus::Queued, None)),
+            JobEvent::Queued
+        );
+        assert_eq!(
+            JobEvent::from_job(&job(JobStatus::Running, None)),
+            JobEvent::Queued
+        );
+        assert_eq!(
+            JobEvent::from_job(&job(JobStatus::Succeeded, None)),
+            JobEvent::Completed
+        );
+        assert_eq!(
+            JobEvent::from_job(&job(JobStatus::Failed, Some("timed out"))),
+            JobEvent::Failed {
+                message: String::from("timed out")
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_requested() {
+        let (sender, mut receiver) = watch::channel(false);
+        let waiting = tokio::spawn(async move { shutdown_requested(&mut receiver).await });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        sender.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 送信側が破棄されても停止の指示とはみなさない
+        let (sender, mut receiver) = watch::channel(false);
+        drop(sender);
+        assert!(tokio::time::timeout(
+            Duration::from_millis(100),
+            shutdown_requested(&mut receiver)
+        )
+        .await
+        .is_err());
+    }
+}
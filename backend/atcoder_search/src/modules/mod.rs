@@ -1,4 +1,13 @@
+pub mod access_log;
+pub mod core_status;
+pub mod crawl_runs;
+pub mod dump;
 pub mod handlers;
+pub mod jobs;
 pub mod migration;
 pub mod problems;
+pub mod profile;
+pub mod recommend;
+pub mod stats_views;
 pub mod users;
+pub mod warmup;
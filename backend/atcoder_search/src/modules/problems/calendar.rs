@@ -0,0 +1,59 @@
+use crate::types::{contest::ContestStatus, tables::Contest};
+use anyhow::Result;
+use sqlx::{postgres::Postgres, Pool};
+
+/// `contests`テーブルから予定されているコンテストを取得する構造体
+#[derive(Debug, Clone)]
+pub struct ContestCalendar {
+    pool: Pool<Postgres>,
+}
+
+impl ContestCalendar {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Unix Epoch Timeで`from`以降`to`より前に開始する、予定されているコンテストを開始日時の順に取得するメソッド
+    ///
+    /// `includes`が空でなければそのカテゴリのコンテストだけを、`excludes`のカテゴリのコンテストは除いて返す。
+    pub async fn upcoming(
+        &self,
+        from: i64,
+        to: i64,
+        includes: &[&str],
+        excludes: &[&str],
+    ) -> Result<Vec<Contest>> {
+        let contests = sqlx::query_as(
+            "
+            SELECT
+                contest_id,
+                start_epoch_second,
+                duration_second,
+                title,
+                rate_change,
+                category,
+                status
+            FROM
+                contests
+            WHERE
+                status = $1
+                AND start_epoch_second >= $2
+                AND start_epoch_second < $3
+                AND (cardinality($4::TEXT[]) = 0 OR category = ANY($4))
+                AND NOT (category = ANY($5))
+            ORDER BY
+                start_epoch_second,
+                contest_id;
+            ",
+        )
+        .bind(ContestStatus::UPCOMING.as_str())
+        .bind(from)
+        .bind(to)
+        .bind(includes)
+        .bind(excludes)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(contests)
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+
+/// リポジトリに同梱しているカテゴリ間の関連の重み
+///
+/// `from_category,to_category,weight`の形式のCSVで、`#`で始まる行はコメントとして読み飛ばす。
+pub const BUNDLED_CATEGORY_RELATIONSHIPS: &str = include_str!("category_relationships.csv");
+
+// CSVのヘッダ行
+const CSV_HEADER: &str = "from_category,to_category,weight";
+
+/// `category_relationships`テーブルの行
+///
+/// `from_category`の問題に対して`to_category`の問題をレコメンドするときの重みを0.0〜1.0で表す。
+#[derive(Debug, Clone, FromRow, PartialEq, Serialize, Deserialize)]
+pub struct CategoryRelationship {
+    pub from_category: String,
+    pub to_category: String,
+    pub weight: f64,
+}
+
+impl CategoryRelationship {
+    /// カテゴリが空でなく、重みが0.0〜1.0の範囲にあるかを検査するメソッド
+    pub fn check(&self) -> Result<()> {
+        if self.from_category.trim().is_empty() || self.to_category.trim().is_empty() {
+            anyhow::bail!("category must not be empty");
+        }
+        if !(0.0..=1.0).contains(&self.weight) {
+            anyhow::bail!(
+                "weight {} of {} -> {} must be between 0.0 and 1.0",
+                self.weight,
+                self.from_category,
+                self.to_category
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `from_category,to_category,weight`の形式のCSVを読み込む関数
+///
+/// 先頭のヘッダ行は省略できる。空行と`#`で始まる行は読み飛ばす。
+pub fn parse_category_relationships(csv: &str) -> Result<Vec<CategoryRelationship>> {
+    let mut relationships = Vec::new();
+    for (i, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line == CSV_HEADER) {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [from_category, to_category, weight] = columns[..] else {
+            anyhow::bail!("line {} must have 3 columns: {}", i + 1, line);
+        };
+        let relationship = CategoryRelationship {
+            from_category: String::from(from_category),
+            to_category: String::from(to_category),
+            weight: weight
+                .parse()
+                .with_context(|| format!("invalid weight at line {}: {}", i + 1, weight))?,
+        };
+        relationship
+            .check()
+            .with_context(|| format!("invalid relationship at line {}", i + 1))?;
+        relationships.push(relationship);
+    }
+
+    Ok(relationships)
+}
+
+/// カテゴリ間の関連の重みを保存する`category_relationships`テーブルへのアクセスを提供する構造体
+///
+/// レコメンドのドキュメントの生成で参照する重みなので、変更はドキュメントを生成し直したときに反映される。
+#[derive(Debug, Clone)]
+pub struct CategoryRelationshipStore {
+    pool: Pool<Postgres>,
+}
+
+impl CategoryRelationshipStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// 全ての重みをカテゴリの順に取得するメソッド
+    pub async fn list(&self) -> Result<Vec<CategoryRelationship>> {
+        let relationships = sqlx::query_as(
+            "
+            SELECT
+                from_category,
+                to_category,
+                weight
+            FROM
+                category_relationships
+            ORDER BY
+                from_category,
+                to_category;
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(relationships)
+    }
+
+    /// 重みを保存するメソッド。既に保存されているカテゴリの組は重みを置き換える
+    ///
+    /// `replace`が`true`の場合、`relationships`に含まれないカテゴリの組を削除する。
+    /// いずれかの重みが不正であれば何も保存しない。保存した件数を返す。
+    pub async fn save(&self, relationships: &[CategoryRelationship], replace: bool) -> Result<u64> {
+        for relationship in relationships.iter() {
+            relationship.check()?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+        if replace {
+            sqlx::query("DELETE FROM category_relationships;")
+                .execute(&mut tx)
+                .await?;
+        }
+        let mut saved = 0;
+        for relationship in relationships.iter() {
+            let result = sqlx::query(
+                "
+                INSERT INTO category_relationships (from_category, to_category, weight)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (from_category, to_category) DO UPDATE SET
+                    weight = EXCLUDED.weight;
+                ",
+            )
+            .bind(&relationship.from_category)
+            .bind(&relationship.to_category)
+            .bind(relationship.weight)
+            .execute(&mut tx)
+            .await?;
+            saved += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(saved)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_category_relationships() {
+        let relationships = parse_category_relationships(
+            "from_category,to_category,weight\n# comment\n\nABC, ARC ,0.5\nOther Contests,Other Sponsored,1",
+        )
+        .unwrap();
+        assert_eq!(
+            relationships,
+            vec![
+                CategoryRelationship {
+                    from_category: String::from("ABC"),
+                    to_category: String::from("ARC"),
+                    weight: 0.5,
+                },
+                CategoryRelationship {
+                    from_category: String::from("Other Contests"),
+                    to_category: String::from("Other Sponsored"),
+                    weight: 1.0,
+                },
+            ]
+        );
+
+        assert!(parse_category_relationships("ABC,ARC").is_err());
+        assert!(parse_category_relationships("ABC,ARC,high").is_err());
+        assert!(parse_category_relationships("ABC,ARC,1.5").is_err());
+        assert!(parse_category_relationships(",ARC,0.5").is_err());
+    }
+
+    #[test]
+    fn test_bundled_category_relationships() {
+        let relationships = parse_category_relationships(BUNDLED_CATEGORY_RELATIONSHIPS).unwrap();
+        assert!(relationships
+            .iter()
+            .any(|relationship| relationship.from_category == "ABC"
+                && relationship.to_category == "ABC"
+                && relationship.weight == 1.0));
+    }
+}
@@ -1,36 +1,64 @@
-use crate::types::{
-    contest::ContestJson,
-    problem::{ProblemDifficulty, ProblemJson},
-    tables::Contest,
+use crate::{
+    modules::{
+        crawl_runs::CrawlStats,
+        problems::{
+            extractor::{ExtractionCoverage, FullTextExtractor},
+            html_storage::HtmlStorage,
+        },
+    },
+    types::{
+        contest::{CategoryRules, ContestJson, ContestStatus},
+        problem::{ProblemDifficulty, ProblemJson},
+        tables::Contest,
+    },
 };
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use minify_html::{minify, Cfg};
 use reqwest::Client;
 use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+use serde_json::Value;
 use sqlx::{
     self,
     postgres::{PgRow, Postgres},
     Pool, Row,
 };
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 use tokio::time::{self, Duration};
 
 pub struct ContestCrawler<'a> {
     url: Url,
+    schedule_url: Url,
     pool: &'a Pool<Postgres>,
     client: Client,
+    category_rules: CategoryRules,
 }
 
 impl<'a> ContestCrawler<'a> {
     pub fn new(pool: &'a Pool<Postgres>) -> Self {
         ContestCrawler {
             url: Url::parse("https://kenkoooo.com/atcoder/resources/contests.json").unwrap(),
+            schedule_url: Url::parse("https://atcoder.jp/contests/?lang=ja").unwrap(),
             pool,
             client: Client::builder()
                 .gzip(true)
                 .timeout(Duration::from_secs(10))
                 .build()
                 .unwrap(),
+            category_rules: CategoryRules::default(),
+        }
+    }
+
+    /// 組み込みの識別より先に適用するカテゴリのルールを設定する
+    pub fn with_category_rules(self, category_rules: CategoryRules) -> Self {
+        Self {
+            category_rules,
+            ..self
         }
     }
 
@@ -48,20 +76,57 @@ impl<'a> ContestCrawler<'a> {
         Ok(contests)
     }
 
-    /// AtCoderProblemsから取得したコンテスト情報からデータベースへ格納する用のモデルを作って返すメソッド
+    /// AtCoderのコンテスト一覧ページから予定されているコンテストの情報を取得するメソッド
+    pub async fn fetch_upcoming_contest_list(&self) -> Result<Vec<ContestJson>> {
+        tracing::info!("Start to retrieve upcoming contests information from AtCoder");
+        let res = self.client.get(self.schedule_url.clone()).send().await?;
+        let html = res.text().await?;
+        let contests = parse_upcoming_contests(&html)?;
+
+        tracing::info!(
+            "{} upcoming contests information successfully retrieved.",
+            contests.len()
+        );
+
+        Ok(contests)
+    }
+
+    /// AtCoderProblemsとAtCoderから取得したコンテスト情報からデータベースへ格納する用のモデルを作って返すメソッド
+    ///
+    /// AtCoderProblemsにまだ載っていない予定されているコンテストはAtCoderのコンテスト一覧ページから補う。
     pub async fn crawl(&self) -> Result<Vec<Contest>> {
         tracing::info!("Start to crawl contests information.");
-        let contests: Vec<Contest> = self
-            .fetch_contest_list()
-            .await?
+        let mut contests = self.fetch_contest_list().await?;
+
+        // 予定されているコンテストが取得できなくても、終了したコンテストの情報は保存できるようにする
+        match self.fetch_upcoming_contest_list().await {
+            Ok(upcoming) => {
+                let known: HashSet<String> =
+                    contests.iter().map(|contest| contest.id.clone()).collect();
+                contests.extend(
+                    upcoming
+                        .into_iter()
+                        .filter(|contest| !known.contains(&contest.id)),
+                );
+            }
+            Err(e) => {
+                tracing::warn!("failed to retrieve upcoming contests: {:?}", e);
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let contests: Vec<Contest> = contests
             .iter()
             .map(|contest| Contest {
                 contest_id: contest.id.clone(),
-                start_epoch_second: contest.start_epoch_second.clone(),
-                duration_second: contest.duration_second.clone(),
+                start_epoch_second: contest.start_epoch_second,
+                duration_second: contest.duration_second,
                 title: contest.title.clone(),
                 rate_change: contest.rate_change.clone(),
-                category: contest.categorize(),
+                category: contest.categorize_with(&self.category_rules),
+                status: ContestStatus::at(contest.start_epoch_second, contest.duration_second, now)
+                    .as_str()
+                    .to_string(),
             })
             .collect();
         tracing::info!(
@@ -77,7 +142,7 @@ impl<'a> ContestCrawler<'a> {
     /// データの保存にMERGE INTO文(PostgreSQL 15から)を使用している
     /// コンテスト情報の存在判定にIDを使用し、IDが存在すればUPDATE、IDが存在しなければINSERTを実行する
     /// UPDATE時はすべての情報をUPDATEするようにしている
-    pub async fn save(&self, contests: &Vec<Contest>) -> Result<()> {
+    pub async fn save(&self, contests: &[Contest]) -> Result<()> {
         tracing::info!("Start to save contests information.");
         // トランザクション開始
         let mut tx = self.pool.begin().await.with_context(|| {
@@ -92,21 +157,23 @@ impl<'a> ContestCrawler<'a> {
             let result = sqlx::query("
                 MERGE INTO contests
                 USING
-                    (VALUES($1, $2, $3, $4, $5, $6)) AS contest(contest_id, start_epoch_second, duration_second, title, rate_change, category)
+                    (VALUES($1, $2, $3, $4, $5, $6, $7, $8)) AS contest(contest_id, start_epoch_second, duration_second, title, rate_change, category, status, category_rules_version)
                 ON
                     contests.contest_id = contest.contest_id
                 WHEN MATCHED THEN
-                    UPDATE SET (contest_id, start_epoch_second, duration_second, title, rate_change, category) = (contest.contest_id, contest.start_epoch_second, contest.duration_second, contest.title, contest.rate_change, contest.category)
+                    UPDATE SET (contest_id, start_epoch_second, duration_second, title, rate_change, category, status, category_rules_version) = (contest.contest_id, contest.start_epoch_second, contest.duration_second, contest.title, contest.rate_change, contest.category, contest.status, contest.category_rules_version)
                 WHEN NOT MATCHED THEN
-                    INSERT (contest_id, start_epoch_second, duration_second, title, rate_change, category)
-                    VALUES (contest.contest_id, contest.start_epoch_second, contest.duration_second, contest.title, contest.rate_change, contest.category);
+                    INSERT (contest_id, start_epoch_second, duration_second, title, rate_change, category, status, category_rules_version)
+                    VALUES (contest.contest_id, contest.start_epoch_second, contest.duration_second, contest.title, contest.rate_change, contest.category, contest.status, contest.category_rules_version);
                 ")
                 .bind(&contest.contest_id)
-                .bind(&contest.start_epoch_second)
-                .bind(&contest.duration_second)
+                .bind(contest.start_epoch_second)
+                .bind(contest.duration_second)
                 .bind(&contest.title)
                 .bind(&contest.rate_change)
                 .bind(&contest.category)
+                .bind(&contest.status)
+                .bind(&self.category_rules.version)
                 .execute(&mut tx)
                 .await;
 
@@ -126,30 +193,135 @@ impl<'a> ContestCrawler<'a> {
         Ok(())
     }
 
+    // コンテストのうち、新しく保存されるものと更新されるものの数を数える関数
+    async fn count_existing(&self, contests: &[Contest]) -> Result<(usize, usize)> {
+        let exists: HashSet<String> = sqlx::query_scalar("SELECT contest_id FROM contests;")
+            .fetch_all(self.pool)
+            .await?
+            .into_iter()
+            .collect();
+        let updated = contests
+            .iter()
+            .filter(|contest| exists.contains(&contest.contest_id))
+            .count();
+
+        Ok((contests.len() - updated, updated))
+    }
+
+    /// 取得したコンテストのうち、新しく保存されるものと更新されるものの数を返すメソッド
+    ///
+    /// データベースへは書き込まない。
+    pub async fn plan(&self) -> Result<(usize, usize)> {
+        let contests = self.crawl().await?;
+        self.count_existing(&contests).await
+    }
+
     /// コンテスト情報の取得からデータベースへの保存までの一連の処理を行うメソッド
-    pub async fn run(&self) -> Result<()> {
+    pub async fn run(&self) -> Result<CrawlStats> {
         let contests = self.crawl().await?;
+        let (inserted, updated) = self.count_existing(&contests).await?;
         self.save(&contests).await?;
 
-        Ok(())
+        Ok(CrawlStats {
+            fetched: contests.len() as i32,
+            inserted: inserted as i32,
+            updated: updated as i32,
+            errors: 0,
+        })
+    }
+}
+/// AtCoderのコンテスト一覧ページの「予定されたコンテスト」の表からコンテスト情報を取り出す関数
+///
+/// レーティング対象の表記はAtCoderProblemsに合わせて`-`を`~`に置き換える。
+pub fn parse_upcoming_contests(html: &str) -> Result<Vec<ContestJson>> {
+    let html = Html::parse_document(html);
+    let row = Selector::parse("#contest-table-upcoming tbody tr")
+        .expect("failed to create a selector for upcoming contests");
+    let cell = Selector::parse("td").expect("failed to create a selector for 'td'");
+    let time = Selector::parse("time").expect("failed to create a selector for 'time'");
+    let link = Selector::parse("a[href^='/contests/']")
+        .expect("failed to create a selector for contest links");
+
+    let text = |element: ElementRef| element.text().collect::<String>().trim().to_string();
+
+    let mut contests = Vec::new();
+    for row in html.select(&row) {
+        let cells = row.select(&cell).collect::<Vec<ElementRef>>();
+        if cells.len() < 4 {
+            continue;
+        }
+
+        let start_at = cells[0]
+            .select(&time)
+            .next()
+            .map(text)
+            .with_context(|| "start time of an upcoming contest is not found")?;
+        let start_at = DateTime::parse_from_str(&start_at, "%Y-%m-%d %H:%M:%S%z")
+            .with_context(|| format!("failed to parse the start time {}", start_at))?;
+
+        let link = cells[1]
+            .select(&link)
+            .next()
+            .with_context(|| "link of an upcoming contest is not found")?;
+        let id = link
+            .value()
+            .attr("href")
+            .and_then(|href| href.trim_start_matches("/contests/").split('/').next())
+            .unwrap_or_default()
+            .to_string();
+        let title = text(link);
+
+        let duration = text(cells[2]);
+        let (hours, minutes) = duration
+            .split_once(':')
+            .and_then(|(hours, minutes)| {
+                Some((hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?))
+            })
+            .with_context(|| format!("failed to parse the duration {}", duration))?;
+
+        let rate_change = match text(cells[3]).as_str() {
+            rate_change @ ("-" | "All") => rate_change.to_string(),
+            rate_change => rate_change.replace('-', "~"),
+        };
+
+        contests.push(ContestJson {
+            id,
+            start_epoch_second: start_at.timestamp(),
+            duration_second: hours * 3600 + minutes * 60,
+            title,
+            rate_change,
+        });
     }
+
+    Ok(contests)
 }
+
 pub struct ProblemCrawler<'a> {
     url: Url,
     pool: &'a Pool<Postgres>,
     client: Client,
+    html_storage: HtmlStorage,
 }
 
 impl<'a> ProblemCrawler<'a> {
     pub fn new(pool: &'a Pool<Postgres>) -> Self {
         ProblemCrawler {
             url: Url::parse("https://kenkoooo.com/atcoder/resources/problems.json").unwrap(),
-            pool: pool,
+            pool,
             client: Client::builder()
                 .gzip(true)
                 .timeout(Duration::from_secs(10))
                 .build()
                 .unwrap(),
+            html_storage: HtmlStorage::default(),
+        }
+    }
+
+    /// 問題ページのHTMLの保存先を指定するメソッド。指定しなければデータベースに保存する
+    pub fn with_html_storage(self, html_storage: HtmlStorage) -> Self {
+        Self {
+            html_storage,
+            ..self
         }
     }
 
@@ -173,15 +345,14 @@ impl<'a> ProblemCrawler<'a> {
         tracing::info!("Crawl {}", url);
         let res = self.client.get(url).send().await?;
         let body = res.bytes().await?;
-        let html = String::from_utf8(minify(&body, &config))?;
+        let html = String::from_utf8(minify(&body, config))?;
 
         Ok(html)
     }
 
-    /// AtCoder Problemsから得た一覧情報とデータベースにある情報を比較し、
-    /// 未取得の問題を検出するメソッド
-    pub async fn detect_diff(&self) -> Result<Vec<ProblemJson>> {
-        let exists_problems: HashSet<String> = HashSet::from_iter(
+    // データベースに保存済みの問題のIDを取得する関数
+    async fn existing_problem_ids(&self) -> Result<HashSet<String>> {
+        Ok(HashSet::from_iter(
             sqlx::query(
                 r#"
             SELECT problem_id FROM problems;
@@ -192,7 +363,13 @@ impl<'a> ProblemCrawler<'a> {
             .await?
             .iter()
             .cloned(),
-        );
+        ))
+    }
+
+    /// AtCoder Problemsから得た一覧情報とデータベースにある情報を比較し、
+    /// 未取得の問題を検出するメソッド
+    pub async fn detect_diff(&self) -> Result<Vec<ProblemJson>> {
+        let exists_problems = self.existing_problem_ids().await?;
 
         let target: Vec<ProblemJson> = self
             .fetch_problem_list()
@@ -206,21 +383,8 @@ impl<'a> ProblemCrawler<'a> {
         Ok(target)
     }
 
-    /// 問題の難易度情報を取得してハッシュマップとして返すメソッド
-    async fn fetch_difficulties(&self) -> Result<HashMap<String, ProblemDifficulty>> {
-        tracing::info!("Attempting to get difficulties from AtCoder Problems...");
-        let res = self
-            .client
-            .get("https://kenkoooo.com/atcoder/resources/problem-models.json")
-            .send()
-            .await?;
-        let difficulties: HashMap<String, ProblemDifficulty> = res.json().await?;
-
-        Ok(difficulties)
-    }
-
     /// 問題データをデータベースに格納するメソッド
-    pub async fn save(&self, targets: &Vec<ProblemJson>, duration: Duration) -> Result<()> {
+    pub async fn save(&self, targets: &[ProblemJson], duration: Duration) -> Result<()> {
         let config = Cfg {
             do_not_minify_doctype: true,
             ensure_spec_compliant_unquoted_attribute_values: false,
@@ -236,31 +400,41 @@ impl<'a> ProblemCrawler<'a> {
             minify_css_level_2: false,
             minify_css_level_3: false,
         };
-        let difficulties = self.fetch_difficulties().await?;
+        let extractor = FullTextExtractor::new();
+        let mut coverage = ExtractionCoverage::default();
 
         for problem in targets.iter() {
             let mut tx = self.pool.begin().await?;
 
-            let difficulty = difficulties
-                .get(&problem.id)
-                .and_then(|difficulty| difficulty.difficulty);
             let url = format!(
                 "https://atcoder.jp/contests/{}/tasks/{}",
                 problem.contest_id, problem.id
             );
             let html = self.crawl(&url, &config).await?;
+            // 問題文を抽出できないレイアウトのページに気付けるよう、抽出できた割合を集計する
+            match extractor.extract_all(&html) {
+                Ok(statement) => coverage.record(&statement),
+                Err(e) => {
+                    tracing::warn!("failed to extract the statement of {}: {}", problem.id, e)
+                }
+            }
+
+            // 外部のストレージに保存した場合は、`html`カラムの代わりにオブジェクトのキーを保存する
+            let html_key = self.html_storage.put(&problem.id, &html).await?;
+            let size = html.len() as i64;
+            let html = if html_key.is_some() { None } else { Some(html) };
 
             let result = sqlx::query(r"
                 MERGE INTO problems
                 USING
-                    (VALUES($1, $2, $3, $4, $5, $6, $7, $8)) AS problem(problem_id, contest_id, problem_index, name, title, url, html, difficulty)
+                    (VALUES($1, $2, $3, $4, $5, $6, $7, $8)) AS problem(problem_id, contest_id, problem_index, name, title, url, html, html_key)
                 ON
                     problems.problem_id = problem.problem_id
                 WHEN MATCHED THEN
-                    UPDATE SET (problem_id, contest_id, problem_index, name, title, url, html, difficulty) = (problem.problem_id, problem.contest_id, problem.problem_index, problem.name, problem.title, problem.url, problem.html, problem.difficulty)
+                    UPDATE SET (problem_id, contest_id, problem_index, name, title, url, html, html_key) = (problem.problem_id, problem.contest_id, problem.problem_index, problem.name, problem.title, problem.url, problem.html, problem.html_key)
                 WHEN NOT MATCHED THEN
-                    INSERT (problem_id, contest_id, problem_index, name, title, url, html, difficulty)
-                    VALUES (problem.problem_id, problem.contest_id, problem.problem_index, problem.name, problem.title, problem.url, problem.html, problem.difficulty);
+                    INSERT (problem_id, contest_id, problem_index, name, title, url, html, html_key)
+                    VALUES (problem.problem_id, problem.contest_id, problem.problem_index, problem.name, problem.title, problem.url, problem.html, problem.html_key);
                 ")
                 .bind(&problem.id)
                 .bind(&problem.contest_id)
@@ -269,12 +443,28 @@ impl<'a> ProblemCrawler<'a> {
                 .bind(&problem.title)
                 .bind(&url)
                 .bind(html)
-                .bind(difficulty)
+                .bind(&html_key)
                 .execute(&mut tx)
                 .await;
 
             match result {
                 Ok(_) => {
+                    // 古い版のHTMLを`vacuum`で削除できるよう、保存したオブジェクトを記録しておく
+                    if let Some(html_key) = &html_key {
+                        sqlx::query(
+                            r#"
+                            INSERT INTO "problem_html_objects" ("html_key", "problem_id", "size")
+                            VALUES ($1, $2, $3)
+                            ON CONFLICT ("html_key") DO UPDATE SET
+                                "stored_at" = CURRENT_TIMESTAMP;
+                            "#,
+                        )
+                        .bind(html_key)
+                        .bind(&problem.id)
+                        .bind(size)
+                        .execute(&mut tx)
+                        .await?;
+                    }
                     tracing::info!("Problem {} was saved.", problem.id);
                     tx.commit().await?;
                 }
@@ -288,22 +478,337 @@ impl<'a> ProblemCrawler<'a> {
             time::sleep(duration).await;
         }
 
+        if coverage.problems > 0 {
+            tracing::info!("Extraction coverage of the crawled problems: {}", coverage);
+        }
+
         Ok(())
     }
 
+    /// クロール対象の問題のリストを返すメソッド
+    ///
+    /// - allがtrueのときはすべての問題を対象にする
+    /// - allがfalseのときは未取得の問題だけを対象にする
+    pub async fn targets(&self, all: bool) -> Result<Vec<ProblemJson>> {
+        if all {
+            self.fetch_problem_list().await
+        } else {
+            self.detect_diff().await
+        }
+    }
+
     /// 問題情報の取得からデータベースへの保存までの一連の処理を行うメソッド
     ///
     /// - allがtrueのときはすべての問題を対象にクロールを行う
     /// - allがfalseのときは差分取得のみを行う
-    pub async fn run(&self, all: bool, duration: Duration) -> Result<()> {
-        let targets = if all {
-            self.fetch_problem_list().await?
-        } else {
-            self.detect_diff().await?
-        };
+    pub async fn run(&self, all: bool, duration: Duration) -> Result<CrawlStats> {
+        let targets = self.targets(all).await?;
+        let exists_problems = self.existing_problem_ids().await?;
+        let updated = targets
+            .iter()
+            .filter(|problem| exists_problems.contains(&problem.id))
+            .count();
 
         self.save(&targets, duration).await?;
 
+        Ok(CrawlStats {
+            fetched: targets.len() as i32,
+            inserted: (targets.len() - updated) as i32,
+            updated: updated as i32,
+            errors: 0,
+        })
+    }
+}
+
+/// 難易度情報の取り込み結果の集計
+///
+/// - total: AtCoder Problemsから取得したエントリ数
+/// - complete: 全てのフィールドの値が揃っていたエントリ数
+/// - partial: 一部のフィールドが欠けていた、または型が合わなかったエントリ数
+/// - skipped: JSONオブジェクトでなかったため取り込まなかったエントリ数
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProblemModelSummary {
+    pub total: usize,
+    pub complete: usize,
+    pub partial: usize,
+    pub skipped: usize,
+}
+
+impl fmt::Display for ProblemModelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} entries ({} complete, {} partial, {} skipped)",
+            self.total, self.complete, self.partial, self.skipped
+        )
+    }
+}
+
+/// 取り込む問題の難易度情報と、その元のJSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemModel {
+    pub problem_id: String,
+    pub model: ProblemDifficulty,
+    pub raw: Value,
+}
+
+/// 問題IDと難易度情報のJSONの組から、取り込む難易度情報と集計結果を作る関数
+pub fn parse_problem_models(
+    entries: impl IntoIterator<Item = (String, Value)>,
+) -> (Vec<ProblemModel>, ProblemModelSummary) {
+    let mut summary = ProblemModelSummary::default();
+    let mut models = Vec::new();
+    for (problem_id, raw) in entries {
+        summary.total += 1;
+        let model = match ProblemDifficulty::deserialize(&raw) {
+            Ok(model) => model,
+            Err(e) => {
+                tracing::warn!("skip the model of {} cause: {}", problem_id, e);
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        if model.is_complete() {
+            summary.complete += 1;
+        } else {
+            summary.partial += 1;
+        }
+        models.push(ProblemModel {
+            problem_id,
+            model,
+            raw,
+        });
+    }
+    models.sort_by(|a, b| a.problem_id.cmp(&b.problem_id));
+
+    (models, summary)
+}
+
+/// AtCoder Problemsの問題の難易度情報を全て`problem_models`テーブルへ取り込む構造体
+///
+/// 難易度が推定されていない問題も、取得できた値と元のJSONを保存する。
+pub struct DifficultyCrawler<'a> {
+    url: Url,
+    pool: &'a Pool<Postgres>,
+    client: Client,
+}
+
+impl<'a> DifficultyCrawler<'a> {
+    pub fn new(pool: &'a Pool<Postgres>) -> Self {
+        DifficultyCrawler {
+            url: Url::parse("https://kenkoooo.com/atcoder/resources/problem-models.json").unwrap(),
+            pool,
+            client: Client::builder()
+                .gzip(true)
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// 問題IDと難易度情報のJSONのハッシュマップを取得するメソッド
+    pub async fn fetch(&self) -> Result<HashMap<String, Value>> {
+        tracing::info!("Attempting to get difficulties from AtCoder Problems...");
+        let res = self.client.get(self.url.clone()).send().await?;
+        let models: HashMap<String, Value> = res.json().await?;
+
+        Ok(models)
+    }
+
+    /// 難易度情報を`problem_models`テーブルへ保存し、`problems`テーブルの難易度を更新するメソッド
+    pub async fn save(&self, models: &[ProblemModel]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for model in models.iter() {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO "problem_models" (
+                    "problem_id",
+                    "slope",
+                    "intercept",
+                    "variance",
+                    "difficulty",
+                    "discrimination",
+                    "irt_loglikelihood",
+                    "irt_users",
+                    "is_experimental",
+                    "raw"
+                )
+                VALUES ($1, $2, $3, $4, $5::integer, $6, $7, $8::integer, $9, $10::jsonb)
+                ON CONFLICT ("problem_id") DO UPDATE SET
+                    "slope" = EXCLUDED."slope",
+                    "intercept" = EXCLUDED."intercept",
+                    "variance" = EXCLUDED."variance",
+                    "difficulty" = EXCLUDED."difficulty",
+                    "discrimination" = EXCLUDED."discrimination",
+                    "irt_loglikelihood" = EXCLUDED."irt_loglikelihood",
+                    "irt_users" = EXCLUDED."irt_users",
+                    "is_experimental" = EXCLUDED."is_experimental",
+                    "raw" = EXCLUDED."raw";
+                "#,
+            )
+            .bind(&model.problem_id)
+            .bind(model.model.slope)
+            .bind(model.model.intercept)
+            .bind(model.model.variance)
+            .bind(model.model.difficulty)
+            .bind(model.model.discrimination)
+            .bind(model.model.irt_loglikelihood)
+            .bind(model.model.irt_users)
+            .bind(model.model.is_experimental)
+            .bind(model.raw.to_string())
+            .execute(&mut tx)
+            .await;
+
+            if let Err(e) = result {
+                let message = format!(
+                    "an error occurred: {:?}, at saving the model of {}",
+                    e, model.problem_id
+                );
+                tracing::error!(message);
+                tx.rollback().await?;
+
+                anyhow::bail!(message);
+            }
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE "problems"
+            SET
+                "difficulty" = "problem_models"."difficulty"
+            FROM
+                "problem_models"
+            WHERE
+                "problems"."problem_id" = "problem_models"."problem_id"
+                AND "problems"."difficulty" IS DISTINCT FROM "problem_models"."difficulty";
+            "#,
+        )
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
         Ok(())
     }
+
+    /// 取得した難易度情報の集計結果と、難易度が変わる問題の数を返すメソッド
+    ///
+    /// データベースへは書き込まない。
+    pub async fn plan(&self) -> Result<(ProblemModelSummary, usize)> {
+        let (models, summary) = parse_problem_models(self.fetch().await?);
+        let difficulties: HashMap<String, Option<i32>> =
+            sqlx::query_as("SELECT problem_id, difficulty FROM problems;")
+                .fetch_all(self.pool)
+                .await?
+                .into_iter()
+                .collect();
+        let changed = models
+            .iter()
+            .filter(|model| match difficulties.get(&model.problem_id) {
+                Some(difficulty) => difficulty.map(i64::from) != model.model.difficulty,
+                None => false,
+            })
+            .count();
+
+        Ok((summary, changed))
+    }
+
+    /// 難易度情報の取得から保存までの一連の処理を行うメソッド
+    ///
+    /// JSONオブジェクトでなかったため取り込まなかったエントリはエラーとして数える。
+    pub async fn run(&self) -> Result<CrawlStats> {
+        let (models, summary) = parse_problem_models(self.fetch().await?);
+        let exists: HashSet<String> = sqlx::query_scalar("SELECT problem_id FROM problem_models;")
+            .fetch_all(self.pool)
+            .await?
+            .into_iter()
+            .collect();
+        let updated = models
+            .iter()
+            .filter(|model| exists.contains(&model.problem_id))
+            .count();
+        self.save(&models).await?;
+
+        tracing::info!("Problem models successfully saved: {}", summary);
+        Ok(CrawlStats {
+            fetched: summary.total as i32,
+            inserted: (models.len() - updated) as i32,
+            updated: updated as i32,
+            errors: summary.skipped as i32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_upcoming_contests() {
+        let html = r##"<html><body>
+<div id="contest-table-upcoming">
+<h3>予定されたコンテスト</h3>
+<table class="table"><thead><tr><th>開始時刻</th><th>コンテスト名</th><th>時間</th><th>Rated対象</th></tr></thead>
+<tbody>
+<tr>
+<td class="text-center"><a href="http://www.timeanddate.com/worldclock/fixedtime.html?iso=20230805T2100&p1=248" target="blank"><time class="fixtime fixtime-full">2023-08-05 21:00:00+0900</time></a></td>
+<td><span title="Algorithm">Ⓐ</span> <span class="user-blue">◉</span> <a href="/contests/abc313">AtCoder Beginner Contest 313</a></td>
+<td class="text-center">01:40</td>
+<td class="text-center"> - 1999</td>
+</tr>
+<tr>
+<td class="text-center"><a href="#"><time class="fixtime fixtime-full">2023-08-12 12:00:00+0900</time></a></td>
+<td><a href="/contests/ahc023">AtCoder Heuristic Contest 023</a></td>
+<td class="text-center">240:00</td>
+<td class="text-center">All</td>
+</tr>
+</tbody></table>
+</div>
+<div id="contest-table-recent"><table><tbody><tr>
+<td><time>2023-07-29 21:00:00+0900</time></td><td><a href="/contests/abc312">AtCoder Beginner Contest 312</a></td><td>01:40</td><td> - 1999</td>
+</tr></tbody></table></div>
+</body></html>"##;
+
+        let contests = parse_upcoming_contests(html).unwrap();
+        assert_eq!(contests.len(), 2);
+        assert_eq!(contests[0].id, "abc313");
+        assert_eq!(contests[0].title, "AtCoder Beginner Contest 313");
+        assert_eq!(contests[0].start_epoch_second, 1691236800);
+        assert_eq!(contests[0].duration_second, 6000);
+        assert_eq!(contests[0].rate_change, "~ 1999");
+        assert_eq!(contests[0].categorize(), "ABC");
+        assert_eq!(contests[1].id, "ahc023");
+        assert_eq!(contests[1].duration_second, 864000);
+        assert_eq!(contests[1].rate_change, "All");
+    }
+
+    #[test]
+    fn test_parse_problem_models() {
+        let entries: HashMap<String, Value> = serde_json::from_str(
+            r#"{
+                "abc300_a": {"slope": -0.0006, "intercept": 8.6, "variance": 0.1, "difficulty": -1044, "discrimination": 0.004, "irt_loglikelihood": -0.1, "irt_users": 9000, "is_experimental": false},
+                "abc300_h": {"is_experimental": true, "difficulty": "unknown", "new_field": 1},
+                "practice_1": null
+            }"#,
+        )
+        .unwrap();
+
+        let (models, summary) = parse_problem_models(entries);
+        assert_eq!(
+            summary,
+            ProblemModelSummary {
+                total: 3,
+                complete: 1,
+                partial: 1,
+                skipped: 1,
+            }
+        );
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].problem_id, "abc300_a");
+        assert_eq!(models[0].model.difficulty, Some(-1044));
+        assert_eq!(models[1].problem_id, "abc300_h");
+        assert_eq!(models[1].model.difficulty, None);
+        assert_eq!(models[1].model.is_experimental, Some(true));
+        assert_eq!(models[1].raw["new_field"], 1);
+    }
 }
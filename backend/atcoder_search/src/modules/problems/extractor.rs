@@ -1,15 +1,99 @@
+use ammonia::{Builder, UrlRelative};
 use anyhow::Result;
+use atcoder_search_libs::language::{detect_language, Language};
 use ego_tree::NodeRef;
+use once_cell::sync::Lazy;
 use scraper::node::Node;
 use scraper::{ElementRef, Html, Selector};
+use std::fmt;
 use url::Url;
 
+// 問題文のHTMLから危険な要素や属性を取り除くサニタイザ
+static SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(["section", "var"])
+        .link_rel(Some("noopener noreferrer"))
+        .url_relative(UrlRelative::RewriteWithBase(
+            Url::parse("https://atcoder.jp").expect("failed to parse the base url of AtCoder"),
+        ));
+    builder
+});
+
+// インタラクティブな問題にだけある、ジャッジとのやり取りを説明するsectionの見出し
+const INTERACTIVE_HEADINGS: [&str; 2] = ["入出力", "Input and Output"];
+
+// インタラクティブな問題の問題文に含まれる語句
+const INTERACTIVE_WORDS: [&str; 2] = ["インタラクティブ", "interactive"];
+
+/// HTMLから抽出した問題文と制約
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractedStatement {
+    pub statement_ja: Vec<String>,
+    pub statement_en: Vec<String>,
+    pub constraints_ja: Vec<String>,
+    pub constraints_en: Vec<String>,
+    pub html_ja: Vec<String>,
+    pub html_en: Vec<String>,
+    /// ジャッジと対話するインタラクティブな問題かどうか
+    pub is_interactive: bool,
+    /// 問題文に画像や図が含まれるかどうか
+    pub has_figures: bool,
+    /// 言語ごとのspanタグがないページから、sectionごとに言語を判定して抽出したかどうか
+    pub fallback: bool,
+}
+
+/// 抽出した問題文がある問題の割合を言語ごとに集計する構造体
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractionCoverage {
+    pub problems: usize,
+    pub with_ja: usize,
+    pub with_en: usize,
+    pub fallback: usize,
+}
+
+impl ExtractionCoverage {
+    pub fn record(&mut self, statement: &ExtractedStatement) {
+        let has_text = |texts: &[String]| texts.iter().any(|text| !text.trim().is_empty());
+
+        self.problems += 1;
+        self.with_ja += has_text(&statement.statement_ja) as usize;
+        self.with_en += has_text(&statement.statement_en) as usize;
+        self.fallback += statement.fallback as usize;
+    }
+}
+
+impl fmt::Display for ExtractionCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rate = |count: usize| {
+            if self.problems == 0 {
+                0.0
+            } else {
+                count as f64 / self.problems as f64 * 100.0
+            }
+        };
+        write!(
+            f,
+            "ja {}/{} ({:.1}%), en {}/{} ({:.1}%), {} by fallback",
+            self.with_ja,
+            self.problems,
+            rate(self.with_ja),
+            self.with_en,
+            self.problems,
+            rate(self.with_en),
+            self.fallback
+        )
+    }
+}
+
 /// HTMLから問題文を取得する構造体
 pub struct FullTextExtractor {
     span_ja: Selector,
     span_en: Selector,
     section: Selector,
     h3: Selector,
+    figure: Selector,
+    html: Selector,
 }
 
 impl FullTextExtractor {
@@ -21,12 +105,17 @@ impl FullTextExtractor {
         let section =
             Selector::parse("section").expect("failed to create a selector for 'section'");
         let h3 = Selector::parse("h3").expect("failed to create a selector for 'h3'");
+        let figure = Selector::parse("img, svg, figure")
+            .expect("failed to create a selector for 'img, svg, figure'");
+        let html = Selector::parse("html").expect("failed to create a selector for 'html'");
 
         FullTextExtractor {
             span_ja,
             span_en,
             section,
             h3,
+            figure,
+            html,
         }
     }
 
@@ -42,6 +131,14 @@ impl FullTextExtractor {
                         // varタグの値の周りには空白を空ける
                         result.push(format!(" {} ", self.dfs(&child)));
                     }
+                    "img" => {
+                        // 図だけで説明している問題も検索できるように、画像の代替テキストを収集する
+                        if let Some(alt) =
+                            e.attr("alt").map(str::trim).filter(|alt| !alt.is_empty())
+                        {
+                            result.push(format!(" {} ", alt));
+                        }
+                    }
                     _ => {
                         result.push(self.dfs(&child));
                     }
@@ -56,6 +153,21 @@ impl FullTextExtractor {
         result.join("")
     }
 
+    // 言語の判定に使うため、数式やコードを除いた地の文のテキストを集めるメソッド
+    fn prose(&self, element: &NodeRef<Node>) -> String {
+        element
+            .children()
+            .map(|child| match child.value() {
+                Node::Element(e) => match e.name() {
+                    "pre" | "h3" | "var" | "code" => String::new(),
+                    _ => self.prose(&child),
+                },
+                Node::Text(text) => text.to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
     fn get_problem_id(&self, html: &str) -> Result<Option<String>> {
         let html = Html::parse_document(html);
         let meta = Selector::parse("meta").expect("failed to create a selector for 'meta'");
@@ -65,19 +177,15 @@ impl FullTextExtractor {
             .filter(|meta| {
                 meta.value()
                     .attr("property")
-                    .and_then(|property| Some(property == "og:url"))
-                    .unwrap_or_else(|| false)
+                    .map(|property| property == "og:url")
+                    .unwrap_or(false)
             })
             .collect::<Vec<ElementRef>>();
 
         for meta in meta {
             if let Some(content) = meta.value().attr("content") {
                 let url = Url::parse(content)?;
-                let id = url
-                    .path()
-                    .rsplit('/')
-                    .next()
-                    .and_then(|id| Some(id.to_owned()));
+                let id = url.path().rsplit('/').next().map(|id| id.to_owned());
                 return Ok(id);
             }
         }
@@ -85,56 +193,378 @@ impl FullTextExtractor {
         Ok(None)
     }
 
-    /// HTML本文から問題文を取得するメソッド
-    pub fn extract(&self, html: &str) -> Result<(Vec<String>, Vec<String>)> {
-        let problem_id = self.get_problem_id(html)?.unwrap_or("[No ID]".to_string());
+    // ページ全体の言語を、htmlタグのlang属性かmetaタグのロケールから判定するメソッド
+    fn page_language(&self, html: &Html) -> Option<Language> {
+        let meta = Selector::parse("meta").expect("failed to create a selector for 'meta'");
 
-        let html = Html::parse_document(html);
+        let lang = html
+            .select(&self.html)
+            .next()
+            .and_then(|html| html.value().attr("lang"));
+        let locale = html.select(&meta).find_map(|meta| {
+            let name = meta
+                .value()
+                .attr("property")
+                .or(meta.value().attr("http-equiv"))?;
+            if name == "og:locale" || name.eq_ignore_ascii_case("content-language") {
+                meta.value().attr("content")
+            } else {
+                None
+            }
+        });
+
+        lang.into_iter()
+            .chain(locale)
+            .find_map(|locale| match locale.get(..2) {
+                Some("ja") => Some(Language::Japanese),
+                Some("en") => Some(Language::English),
+                _ => None,
+            })
+    }
 
-        let mut text_ja: Vec<String> = Vec::new();
-        let mut text_en: Vec<String> = Vec::new();
+    // 日本語・英語それぞれについて、見出しのh3タグのボディが`heading_ja`・`heading_en`を含むsectionタグを探すメソッド
+    fn sections<'a>(
+        &self,
+        html: &'a Html,
+        problem_id: &str,
+        heading_ja: &str,
+        heading_en: &str,
+    ) -> (Vec<ElementRef<'a>>, Vec<ElementRef<'a>>) {
+        let mut sections_ja: Vec<ElementRef> = Vec::new();
+        let mut sections_en: Vec<ElementRef> = Vec::new();
 
         // 日本語版の問題文は<span class="lang-ja">タグ内に定義されている。そのため日本語版の問題文を取得したい場合はこのタグの子要素を探しにいけばよい。
-        // 英語版の問題文が用意されていない問題はこのタグが存在しないので、その場合はsectionタグを走査し、ボディが「問題文」であるh3タグを持つsectionをパースする。
-        if let Some(ja) = html.select(&self.span_ja).next() {
-            for section in ja.select(&self.section) {
-                let Some(h3) = section.select(&self.h3).next() else {continue};
-                let Some(h3) = h3.text().next() else {continue};
-
-                // ボディに「問題文」を含むh3タグだった場合にその本文を取得する
-                // 単に等価比較していないのはどっかの問題で「問題分」と誤字っている問題があった気がしたのと、両端に空白が含まれている場合でも対応するため。
-                if h3.contains("問題") {
-                    tracing::debug!("Retrieve japanese problem statement. [{}]", problem_id);
-                    text_ja.push(self.dfs(&section));
-                }
-            }
-        } else {
-            for section in html.select(&self.section) {
-                let Some(h3) = section.select(&self.h3).next() else {continue};
-                let Some(h3) = h3.text().next() else {continue};
-
-                // ボディに「問題文」を含むh3タグだった場合にその本文を取得する
-                // 単に等価比較していないのはどっかの問題で「問題分」と誤字っている問題があった気がしたのと、両端に空白が含まれている場合でも対応するため。
-                if h3.contains("問題") {
-                    tracing::debug!("Retrieve japanese problem statement. [{}]", problem_id);
-                    text_ja.push(self.dfs(&section));
-                }
+        // 古い問題にはこのタグが存在しないので、その場合はsectionごとに言語を判定する。
+        let Some(ja) = html.select(&self.span_ja).next() else {
+            return self.sections_without_lang(html, problem_id, heading_ja, heading_en);
+        };
+        for section in ja.select(&self.section) {
+            let Some(h3) = section.select(&self.h3).next() else {
+                continue;
+            };
+            let Some(h3) = h3.text().next() else { continue };
+
+            // 単に等価比較していないのはどっかの問題で「問題分」と誤字っている問題があった気がしたのと、両端に空白が含まれている場合でも対応するため。
+            if h3.contains(heading_ja) {
+                tracing::debug!("Retrieve japanese section {}. [{}]", heading_ja, problem_id);
+                sections_ja.push(section);
             }
         }
 
-        // 英語版の問題分を取得する
+        // 英語版のsectionを取得する
         if let Some(en) = html.select(&self.span_en).next() {
             for section in en.select(&self.section) {
-                let Some(h3) = section.select(&self.h3).next() else {continue};
-                let Some(h3) = h3.text().next() else {continue};
+                let Some(h3) = section.select(&self.h3).next() else {
+                    continue;
+                };
+                let Some(h3) = h3.text().next() else { continue };
 
-                if h3.contains("Statement") {
-                    tracing::debug!("Retrieve english problem statement. [{}]", problem_id);
-                    text_en.push(self.dfs(&section));
+                if h3.contains(heading_en) {
+                    tracing::debug!("Retrieve english section {}. [{}]", heading_en, problem_id);
+                    sections_en.push(section);
                 }
             }
         }
 
-        Ok((text_ja, text_en))
+        (sections_ja, sections_en)
+    }
+
+    // 言語ごとのspanタグがないページから、見出しが一致するsectionを探して言語ごとに振り分けるメソッド
+    //
+    // 日本語の見出しの下に英語の本文が書かれているなど、見出しと本文の言語が一致しない問題があるので、本文の文字から言語を判定する。
+    // 本文から判定できない場合はページのロケール、それもなければ見出しの言語に従う。
+    fn sections_without_lang<'a>(
+        &self,
+        html: &'a Html,
+        problem_id: &str,
+        heading_ja: &str,
+        heading_en: &str,
+    ) -> (Vec<ElementRef<'a>>, Vec<ElementRef<'a>>) {
+        let mut sections_ja: Vec<ElementRef> = Vec::new();
+        let mut sections_en: Vec<ElementRef> = Vec::new();
+        let page_language = self.page_language(html);
+
+        for section in html.select(&self.section) {
+            let Some(h3) = section.select(&self.h3).next() else {
+                continue;
+            };
+            let Some(h3) = h3.text().next() else { continue };
+
+            let heading_language = if h3.contains(heading_ja) {
+                Language::Japanese
+            } else if h3.contains(heading_en) {
+                Language::English
+            } else {
+                continue;
+            };
+            // 英語の問題文にはかなや漢字が含まれないので、それらを含む本文は日本語とみなす
+            let language = match detect_language(&self.prose(&section)) {
+                Some(Language::English) => Language::English,
+                Some(_) => Language::Japanese,
+                None => page_language.unwrap_or(heading_language),
+            };
+
+            if language == Language::English {
+                tracing::debug!("Retrieve english section {}. [{}]", h3.trim(), problem_id);
+                sections_en.push(section);
+            } else {
+                tracing::debug!("Retrieve japanese section {}. [{}]", h3.trim(), problem_id);
+                sections_ja.push(section);
+            }
+        }
+
+        (sections_ja, sections_en)
+    }
+
+    // 日本語・英語それぞれの問題文のsectionタグを探すメソッド
+    fn statement_sections<'a>(
+        &self,
+        html: &'a Html,
+        problem_id: &str,
+    ) -> (Vec<ElementRef<'a>>, Vec<ElementRef<'a>>) {
+        self.sections(html, problem_id, "問題", "Statement")
+    }
+
+    // 日本語・英語それぞれの制約のsectionタグを探すメソッド
+    fn constraint_sections<'a>(
+        &self,
+        html: &'a Html,
+        problem_id: &str,
+    ) -> (Vec<ElementRef<'a>>, Vec<ElementRef<'a>>) {
+        self.sections(html, problem_id, "制約", "Constraints")
+    }
+
+    // sectionタグをテキストにするメソッド
+    fn texts(&self, sections: &[ElementRef]) -> Vec<String> {
+        sections.iter().map(|section| self.dfs(section)).collect()
+    }
+
+    // インタラクティブな問題かどうかを、入出力の見出しか問題文中の語句から判定するメソッド
+    fn is_interactive(
+        &self,
+        html: &Html,
+        statement_ja: &[String],
+        statement_en: &[String],
+    ) -> bool {
+        let has_heading = html.select(&self.h3).any(|h3| {
+            let heading = h3.text().collect::<String>();
+            INTERACTIVE_HEADINGS
+                .iter()
+                .any(|interactive| heading.trim() == *interactive)
+        });
+
+        has_heading
+            || statement_ja
+                .iter()
+                .chain(statement_en)
+                .map(|sentence| sentence.to_lowercase())
+                .any(|sentence| INTERACTIVE_WORDS.iter().any(|word| sentence.contains(word)))
+    }
+
+    // 問題文のsectionに画像や図が含まれるかどうかを判定するメソッド
+    fn has_figures(&self, sections: &[ElementRef]) -> bool {
+        sections
+            .iter()
+            .any(|section| section.select(&self.figure).next().is_some())
+    }
+
+    // sectionタグをサニタイズしたHTMLにするメソッド
+    fn sanitized_htmls(&self, sections: &[ElementRef]) -> Vec<String> {
+        sections
+            .iter()
+            .map(|section| SANITIZER.clean(&section.html()).to_string())
+            .collect()
+    }
+
+    /// HTML本文から問題文のテキストと制約、表示用のHTML、問題の特徴をまとめて取得するメソッド
+    ///
+    /// 表示用のHTMLはスクリプトやイベントハンドラなどを取り除き、相対パスの画像やリンクはAtCoderのURLに書き換える。
+    pub fn extract_all(&self, html: &str) -> Result<ExtractedStatement> {
+        let problem_id = self.get_problem_id(html)?.unwrap_or("[No ID]".to_string());
+
+        let html = Html::parse_document(html);
+        let (statement_ja, statement_en) = self.statement_sections(&html, &problem_id);
+        let (constraints_ja, constraints_en) = self.constraint_sections(&html, &problem_id);
+
+        let texts_ja = self.texts(&statement_ja);
+        let texts_en = self.texts(&statement_en);
+        let is_interactive = self.is_interactive(&html, &texts_ja, &texts_en);
+        let has_figures = self.has_figures(&statement_ja) || self.has_figures(&statement_en);
+        let fallback = html.select(&self.span_ja).next().is_none();
+
+        Ok(ExtractedStatement {
+            statement_ja: texts_ja,
+            statement_en: texts_en,
+            constraints_ja: self.texts(&constraints_ja),
+            constraints_en: self.texts(&constraints_en),
+            html_ja: self.sanitized_htmls(&statement_ja),
+            html_en: self.sanitized_htmls(&statement_en),
+            is_interactive,
+            has_figures,
+            fallback,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HTML: &str = r#"<html><head><meta property="og:url" content="https://atcoder.jp/contests/abc001/tasks/abc001_a"></head>
+<body>
+<span class="lang">
+<span class="lang-ja">
+<div class="part"><section><h3>問題文</h3><p>整数 <var>N</var> が与えられます。<img src="/img/abc001_a.png" onerror="alert(1)"></p><script>alert(1)</script></section></div>
+<div class="part"><section><h3>制約</h3><ul><li><var>1 \leq N \leq 100</var></li></ul></section></div>
+<div class="part"><section><h3>入力例 1</h3><pre>3</pre></section></div>
+</span>
+<span class="lang-en">
+<div class="part"><section><h3>Problem Statement</h3><p>Given an integer <var>N</var>.<a href="javascript:alert(1)">link</a></p></section></div>
+<div class="part"><section><h3>Constraints</h3><ul><li><var>1 \leq N \leq 100</var></li></ul></section></div>
+</span>
+</span>
+</body></html>"#;
+
+    #[test]
+    fn test_extract_html() {
+        let statement = FullTextExtractor::new().extract_all(HTML).unwrap();
+        assert_eq!(
+            statement.html_ja,
+            vec![String::from(
+                r#"<section><h3>問題文</h3><p>整数 <var>N</var> が与えられます。<img src="https://atcoder.jp/img/abc001_a.png"></p></section>"#
+            )]
+        );
+        assert_eq!(
+            statement.html_en,
+            vec![String::from(
+                r#"<section><h3>Problem Statement</h3><p>Given an integer <var>N</var>.<a rel="noopener noreferrer">link</a></p></section>"#
+            )]
+        );
+    }
+
+    #[test]
+    fn test_extract_all() {
+        let html = HTML.replace("<script>alert(1)</script>", "");
+        let statement = FullTextExtractor::new().extract_all(&html).unwrap();
+
+        assert_eq!(
+            statement.statement_ja,
+            vec![String::from("整数 N が与えられます。")]
+        );
+        assert_eq!(
+            statement.statement_en,
+            vec![String::from("Given an integer N .link")]
+        );
+        assert_eq!(
+            statement.constraints_ja,
+            vec![String::from(" 1 \\leq N \\leq 100 ")]
+        );
+        assert_eq!(
+            statement.constraints_en,
+            vec![String::from(" 1 \\leq N \\leq 100 ")]
+        );
+        assert!(!statement.is_interactive);
+        assert!(statement.has_figures);
+    }
+
+    #[test]
+    fn test_page_language() {
+        let extractor = FullTextExtractor::new();
+        let cases = [
+            (
+                r#"<html lang="en"><head></head></html>"#,
+                Some(Language::English),
+            ),
+            (
+                r#"<html><head><meta property="og:locale" content="ja_JP"></head></html>"#,
+                Some(Language::Japanese),
+            ),
+            (
+                r#"<html><head><meta http-equiv="Content-Language" content="en-US"></head></html>"#,
+                Some(Language::English),
+            ),
+            ("<html><head></head></html>", None),
+        ];
+        for (html, expected) in cases {
+            let html = Html::parse_document(html);
+            assert_eq!(extractor.page_language(&html), expected);
+        }
+    }
+
+    #[test]
+    fn test_extraction_coverage() {
+        let mut coverage = ExtractionCoverage::default();
+        assert_eq!(
+            coverage.to_string(),
+            "ja 0/0 (0.0%), en 0/0 (0.0%), 0 by fallback"
+        );
+
+        let extractor = FullTextExtractor::new();
+        coverage.record(&extractor.extract_all(HTML).unwrap());
+        coverage.record(&ExtractedStatement {
+            statement_ja: vec![String::from("問題文")],
+            statement_en: vec![String::from(" ")],
+            fallback: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            coverage,
+            ExtractionCoverage {
+                problems: 2,
+                with_ja: 2,
+                with_en: 1,
+                fallback: 1,
+            }
+        );
+        assert_eq!(
+            coverage.to_string(),
+            "ja 2/2 (100.0%), en 1/2 (50.0%), 1 by fallback"
+        );
+    }
+
+    // 抽出結果のテキスト部分をゴールデンファイルと比較する形式にする関数
+    fn golden(statement: &ExtractedStatement) -> serde_json::Value {
+        serde_json::json!({
+            "statement_ja": statement.statement_ja,
+            "statement_en": statement.statement_en,
+            "constraints_ja": statement.constraints_ja,
+            "constraints_en": statement.constraints_en,
+            "is_interactive": statement.is_interactive,
+            "has_figures": statement.has_figures,
+            "fallback": statement.fallback,
+        })
+    }
+
+    /// `testdata/extractor`以下の問題ページのHTMLから抽出したテキストを、同名のJSONファイルの期待値と比較する。
+    ///
+    /// 抽出処理を変更して期待値が変わる場合は、`UPDATE_GOLDEN=1`を付けてテストを実行するとJSONファイルを書き換える。
+    #[test]
+    fn test_extract_golden_files() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/extractor");
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let extractor = FullTextExtractor::new();
+
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+            .collect::<Vec<_>>();
+        files.sort();
+        assert!(!files.is_empty());
+
+        for file in files {
+            let html = std::fs::read_to_string(&file).unwrap();
+            let actual = golden(&extractor.extract_all(&html).unwrap());
+
+            let expected_file = file.with_extension("json");
+            if update {
+                let json = serde_json::to_string_pretty(&actual).unwrap();
+                std::fs::write(&expected_file, json + "\n").unwrap();
+                continue;
+            }
+            let expected: serde_json::Value =
+                serde_json::from_str(&std::fs::read_to_string(&expected_file).unwrap()).unwrap();
+            assert_eq!(actual, expected, "{}", file.display());
+        }
     }
 }
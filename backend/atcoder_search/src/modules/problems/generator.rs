@@ -1,22 +1,96 @@
-use crate::modules::problems::extractor::FullTextExtractor;
+use crate::{
+    modules::problems::{extractor::FullTextExtractor, html_storage::HtmlStorage},
+    types::contest::duration_bucket,
+};
 use anyhow::Result;
 use async_trait::async_trait;
-use atcoder_search_libs::{ExpandField, GenerateDocument, ReadRows, ToDocument};
+use atcoder_search_libs::{
+    solr::model::SolrSchemaField, DocumentFormat, ExpandField, ExpandMode, GenerateDocument,
+    ProgressReporter, ReadRows, SolrSchema, ToDocument,
+};
 use chrono::{DateTime, Local, TimeZone, Utc};
+use futures::StreamExt;
 use once_cell::sync::Lazy;
+use regex::Regex;
 use serde_json::Value;
 use sqlx::{postgres::Postgres, FromRow, Pool};
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 use tokio::macros::support::Pin;
 use tokio_stream::Stream;
 
-static EXTRACTOR: Lazy<FullTextExtractor> = Lazy::new(|| FullTextExtractor::new());
+static EXTRACTOR: Lazy<FullTextExtractor> = Lazy::new(FullTextExtractor::new);
+static EXTRACTION_COUNTER: ExtractionCounter = ExtractionCounter::new();
+// `abc300`のように英字の略称と回数からなるコンテストID
+static CONTEST_ID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([a-z]+)(\d+)$").unwrap());
+// コンテスト名に含まれるAtCoder主催のコンテストの正式名称
+static CONTEST_TITLE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"AtCoder (Beginner|Regular|Grand|Heuristic) Contest (\d+)").unwrap());
+
+/// ドキュメント生成中に問題文を抽出できなかった問題の数を数える構造体
+///
+/// HTMLのレイアウトが想定と異なると問題文が空になるので、生成の最後に件数をログに出して気付けるようにする。
+struct ExtractionCounter {
+    problems: AtomicUsize,
+    empty_ja: AtomicUsize,
+    empty_en: AtomicUsize,
+}
+
+/// 抽出結果の集計
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ExtractionSummary {
+    problems: usize,
+    empty_ja: usize,
+    empty_en: usize,
+}
+
+impl ExtractionCounter {
+    const fn new() -> Self {
+        Self {
+            problems: AtomicUsize::new(0),
+            empty_ja: AtomicUsize::new(0),
+            empty_en: AtomicUsize::new(0),
+        }
+    }
+
+    // 1問分の抽出結果を数え、問題文が空なら警告を出すメソッド
+    fn record(&self, problem_id: &str, statement_ja: &[String], statement_en: &[String]) {
+        self.problems.fetch_add(1, Ordering::Relaxed);
+        if is_empty_statement(statement_ja) {
+            tracing::warn!("japanese statement of {} is empty", problem_id);
+            self.empty_ja.fetch_add(1, Ordering::Relaxed);
+        }
+        if is_empty_statement(statement_en) {
+            tracing::warn!("english statement of {} is empty", problem_id);
+            self.empty_en.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // 集計結果を取り出してカウンタを0に戻すメソッド
+    fn take(&self) -> ExtractionSummary {
+        ExtractionSummary {
+            problems: self.problems.swap(0, Ordering::Relaxed),
+            empty_ja: self.empty_ja.swap(0, Ordering::Relaxed),
+            empty_en: self.empty_en.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+// 空白だけの文しかない問題文も空とみなす
+fn is_empty_statement(statement: &[String]) -> bool {
+    statement.iter().all(|sentence| sentence.trim().is_empty())
+}
 
 #[derive(FromRow, Debug)]
 pub struct Row {
     pub problem_id: String,
+    /// 同じ問題文を持つ問題のうち、問題IDが最小のもの
+    pub canonical_problem_id: String,
     pub problem_title: String,
     pub problem_url: String,
+    pub problem_index: String,
     pub contest_id: String,
     pub contest_title: String,
     pub difficulty: Option<i32>,
@@ -24,14 +98,55 @@ pub struct Row {
     pub duration: i64,
     pub rate_change: String,
     pub category: String,
+    /// `problem_statements`テーブルに保存されている問題文。保存後にHTMLが更新されている場合は`None`
+    pub statement_ja: Option<Vec<String>>,
+    pub statement_en: Option<Vec<String>>,
+    /// 保存されている問題の特徴。保存されている問題文を使えない場合は`None`
+    pub is_interactive: Option<bool>,
+    pub has_figures: Option<bool>,
+    /// 難易度が試験的な推定値かどうか。難易度が推定されていない問題は`false`
+    pub is_experimental: bool,
+    /// 正解したユーザの数。`solved_counts`ビューの集計時点の値
+    pub solved_count: i64,
+    /// 提出の数。`problem_stats`ビューの集計時点の値
+    pub submission_count: i64,
+    /// 保存されている問題文を使う場合は空文字列
     pub html: String,
+    /// HTMLを外部のストレージに保存している場合のオブジェクトのキー。保存されている問題文を使う場合は`None`
+    pub html_key: Option<String>,
+    /// 最初に抽出した後で問題文が最後に更新された日時
+    pub last_updated_at: Option<DateTime<Utc>>,
 }
 
 impl ToDocument for Row {
     type Document = Value;
 
     fn to_document(self) -> Result<Value> {
-        let (statement_ja, statement_en) = EXTRACTOR.extract(&self.html)?;
+        self.to_document_with(ExpandMode::Inline)
+    }
+
+    fn to_document_with(self, mode: ExpandMode) -> Result<Value> {
+        // 保存済みの問題文があればHTMLのパースを省略する
+        let (statement_ja, statement_en, is_interactive, has_figures) = match (
+            self.statement_ja,
+            self.statement_en,
+            self.is_interactive,
+            self.has_figures,
+        ) {
+            (Some(statement_ja), Some(statement_en), Some(is_interactive), Some(has_figures)) => {
+                (statement_ja, statement_en, is_interactive, has_figures)
+            }
+            _ => {
+                let statement = EXTRACTOR.extract_all(&self.html)?;
+                (
+                    statement.statement_ja,
+                    statement.statement_en,
+                    statement.is_interactive,
+                    statement.has_figures,
+                )
+            }
+        };
+        EXTRACTION_COUNTER.record(&self.problem_id, &statement_ja, &statement_en);
         let contest_url: String = format!("https://atcoder.jp/contests/{}", self.contest_id);
 
         let start_at = Local
@@ -39,50 +154,150 @@ impl ToDocument for Row {
             .earliest()
             .unwrap_or(DateTime::<Utc>::MIN_UTC.with_timezone(&Local));
 
-        let document = IndexingDocument {
+        let contest_aliases = contest_aliases(&self.contest_id, &self.contest_title);
+        let statement_length = statement_length(&statement_ja);
+        let statement_word_count = word_count(&statement_en);
+        let has_english = !is_empty_statement(&statement_en);
+
+        let document = ProblemIndex {
             problem_id: self.problem_id,
+            canonical_problem_id: self.canonical_problem_id,
             problem_title: self.problem_title,
             problem_url: self.problem_url,
+            problem_index: self.problem_index,
             contest_id: self.contest_id,
             contest_title: self.contest_title,
             contest_url,
+            contest_aliases,
             difficulty: self.difficulty,
-            start_at: start_at,
+            start_at,
             duration: self.duration,
+            duration_bucket: String::from(duration_bucket(self.duration)),
             rate_change: self.rate_change,
             category: self.category,
-            statement_ja: statement_ja,
-            statement_en: statement_en,
+            statement_ja,
+            statement_en,
+            statement_length,
+            statement_word_count,
+            is_interactive,
+            has_figures,
+            has_english,
+            is_experimental: self.is_experimental,
+            solved_count: self.solved_count,
+            submission_count: self.submission_count,
+            last_updated_at: self
+                .last_updated_at
+                .map(|last_updated_at| last_updated_at.with_timezone(&Local)),
         };
 
-        Ok(document.expand())
+        Ok(document.expand_with(mode))
     }
 }
 
-#[derive(ExpandField)]
-pub struct IndexingDocument {
+#[derive(ExpandField, SolrSchema)]
+pub struct ProblemIndex {
     pub problem_id: String,
+    pub canonical_problem_id: String,
     #[suffix(text_ja, text_en)]
     pub problem_title: String,
+    #[solr(indexed = false)]
     pub problem_url: String,
+    pub problem_index: String,
     pub contest_id: String,
     #[suffix(text_ja, text_en)]
     pub contest_title: String,
+    #[solr(indexed = false)]
     pub contest_url: String,
+    #[solr(field_type = "ContestAlias")]
+    pub contest_aliases: Vec<String>,
     pub difficulty: Option<i32>,
     pub start_at: DateTime<Local>,
     pub duration: i64,
+    /// コンテスト時間の区分。`DURATION_BUCKETS`のいずれか
+    pub duration_bucket: String,
     pub rate_change: String,
     pub category: String,
     #[suffix(text_ja, text_reading)]
+    #[solr(field_type = "TextJa")]
     pub statement_ja: Vec<String>,
     #[suffix(text_en)]
+    #[solr(field_type = "TextEn")]
     pub statement_en: Vec<String>,
+    pub statement_length: i32,
+    pub statement_word_count: i32,
+    pub is_interactive: bool,
+    pub has_figures: bool,
+    /// 英語の問題文があるかどうか
+    pub has_english: bool,
+    pub is_experimental: bool,
+    pub solved_count: i64,
+    pub submission_count: i64,
+    pub last_updated_at: Option<DateTime<Local>>,
+}
+
+// 「abc300」「ABC 300」「AtCoder Beginner Contest 300」のような、コンテストを指す表記の揺れを列挙する関数
+//
+// 回数の0埋めを外した表記(`abc001`に対する`ABC 1`など)も含める。
+fn contest_aliases(contest_id: &str, contest_title: &str) -> Vec<String> {
+    // `001`と`1`のように0埋めの有無が異なる回数の表記
+    fn numbers(number: &str) -> Vec<&str> {
+        let trimmed = number.trim_start_matches('0');
+        if trimmed.is_empty() || trimmed == number {
+            vec![number]
+        } else {
+            vec![number, trimmed]
+        }
+    }
+
+    let mut aliases: Vec<String> = Vec::new();
+    let mut push = |alias: String| {
+        if !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    };
+
+    if let Some(captures) = CONTEST_ID_RE.captures(contest_id) {
+        let abbreviation = captures[1].to_uppercase();
+        for number in numbers(&captures[2]) {
+            push(format!("{}{}", abbreviation, number));
+            push(format!("{} {}", abbreviation, number));
+        }
+    }
+    for captures in CONTEST_TITLE_RE.captures_iter(contest_title) {
+        let kind = &captures[1];
+        let abbreviation = format!("A{}C", &kind[..1]);
+        for number in numbers(&captures[2]) {
+            push(format!("AtCoder {} Contest {}", kind, number));
+            push(format!("{}{}", abbreviation, number));
+            push(format!("{} {}", abbreviation, number));
+        }
+    }
+
+    aliases
+}
+
+// 日本語の問題文の、空白を除いた文字数を数える関数
+fn statement_length(statement: &[String]) -> i32 {
+    statement
+        .iter()
+        .map(|sentence| sentence.chars().filter(|c| !c.is_whitespace()).count())
+        .sum::<usize>() as i32
+}
+
+// 英語の問題文の単語数を数える関数
+fn word_count(statement: &[String]) -> i32 {
+    statement
+        .iter()
+        .map(|sentence| sentence.split_whitespace().count())
+        .sum::<usize>() as i32
 }
 
 pub struct ProblemDocumentGenerator<'a> {
     pool: &'a Pool<Postgres>,
     save_dir: PathBuf,
+    progress: Option<ProgressReporter>,
+    html_storage: HtmlStorage,
+    expand_mode: ExpandMode,
 }
 
 impl<'a> ProblemDocumentGenerator<'a> {
@@ -90,10 +305,34 @@ impl<'a> ProblemDocumentGenerator<'a> {
         Self {
             pool,
             save_dir: save_dir.to_owned(),
+            progress: None,
+            html_storage: HtmlStorage::default(),
+            expand_mode: ExpandMode::default(),
+        }
+    }
+
+    /// サフィックス付きフィールドを`expand_mode`に従って出力する
+    pub fn with_expand_mode(self, expand_mode: ExpandMode) -> Self {
+        Self {
+            expand_mode,
+            ..self
         }
     }
 
-    pub async fn run(&self) -> Result<()> {
+    /// 外部のストレージに保存されている問題ページのHTMLを`html_storage`から読み込む
+    pub fn with_html_storage(self, html_storage: HtmlStorage) -> Self {
+        Self {
+            html_storage,
+            ..self
+        }
+    }
+
+    /// 生成の進捗を`progress`へ通知する
+    pub fn with_progress(self, progress: Option<ProgressReporter>) -> Self {
+        Self { progress, ..self }
+    }
+
+    pub async fn run(&self, format: DocumentFormat, compress: bool, workers: usize) -> Result<()> {
         match self.clean(&self.save_dir).await {
             Ok(_) => {}
             Err(e) => {
@@ -102,7 +341,12 @@ impl<'a> ProblemDocumentGenerator<'a> {
             }
         };
 
-        match self.generate(&self.save_dir, 1000).await {
+        // 前回の生成で途中まで数えた分を捨てる
+        EXTRACTION_COUNTER.take();
+        match self
+            .generate(&self.save_dir, 1000, format, compress, workers)
+            .await
+        {
             Ok(_) => {}
             Err(e) => {
                 tracing::error!("failed to generate document: {:?}", e);
@@ -110,6 +354,21 @@ impl<'a> ProblemDocumentGenerator<'a> {
             }
         };
 
+        let summary = EXTRACTION_COUNTER.take();
+        if summary.empty_ja > 0 || summary.empty_en > 0 {
+            tracing::warn!(
+                "statements of {} problems have been extracted: {} japanese and {} english statements are empty",
+                summary.problems,
+                summary.empty_ja,
+                summary.empty_en
+            );
+        } else {
+            tracing::info!(
+                "statements of {} problems have been extracted",
+                summary.problems
+            );
+        }
+
         Ok(())
     }
 }
@@ -122,12 +381,34 @@ impl<'a> ReadRows<'a> for ProblemDocumentGenerator<'a> {
         &'a self,
     ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Self::Row, sqlx::Error>> + Send + 'a>>>
     {
+        // 提出の集計は`refresh-stats`コマンドで更新するマテリアライズドビューから読み込む。
+        // ABCとARCの共通問題のように複数のコンテストで出題された問題は問題文が一致するので、
+        // 問題文のハッシュ値でまとめて代表の問題IDを決める。
+        // 問題文が保存されていない問題や空の問題は、その問題自身を代表とする。
+        // 抽出済みの問題文は問題ページを取得し直した後に抽出したものだけを使い、そうでなければHTMLから抽出し直す。
         let stream = sqlx::query_as(
             "
+            WITH statement_hashes AS (
+                SELECT
+                    problem_id,
+                    md5(array_to_string(statement_ja || statement_en, E'\\n')) AS statement_hash
+                FROM
+                    problem_statements
+                WHERE
+                    cardinality(statement_ja) > 0
+            ), canonical_problems AS (
+                SELECT
+                    problem_id,
+                    MIN(problem_id) OVER (PARTITION BY statement_hash) AS canonical_problem_id
+                FROM
+                    statement_hashes
+            )
             SELECT
                 problems.problem_id AS problem_id,
+                COALESCE(canonical_problems.canonical_problem_id, problems.problem_id) AS canonical_problem_id,
                 problems.title AS problem_title,
                 problems.url AS problem_url,
+                problems.problem_index AS problem_index,
                 contests.contest_id AS contest_id,
                 contests.title AS contest_title,
                 problems.difficulty AS difficulty,
@@ -135,17 +416,165 @@ impl<'a> ReadRows<'a> for ProblemDocumentGenerator<'a> {
                 contests.duration_second AS duration,
                 contests.rate_change AS rate_change,
                 contests.category AS category,
-                problems.html AS html
+                fresh_statements.statement_ja AS statement_ja,
+                fresh_statements.statement_en AS statement_en,
+                fresh_statements.is_interactive AS is_interactive,
+                fresh_statements.has_figures AS has_figures,
+                CASE WHEN fresh_statements.fresh THEN '' ELSE COALESCE(problems.html, '') END AS html,
+                CASE WHEN fresh_statements.fresh THEN NULL ELSE problems.html_key END AS html_key,
+                COALESCE(problem_models.is_experimental, FALSE) AS is_experimental,
+                COALESCE(solved_counts.solved_count, 0) AS solved_count,
+                COALESCE(problem_stats.submission_count, 0) AS submission_count,
+                problem_statements.statement_updated_at AS last_updated_at
             FROM
                 problems
-                JOIN contests ON problems.contest_id = contests.contest_id;
+                JOIN contests ON problems.contest_id = contests.contest_id
+                LEFT JOIN problem_statements ON problems.problem_id = problem_statements.problem_id
+                LEFT JOIN LATERAL (
+                    SELECT
+                        TRUE AS fresh,
+                        problem_statements.statement_ja,
+                        problem_statements.statement_en,
+                        problem_statements.is_interactive,
+                        problem_statements.has_figures
+                    WHERE
+                        problem_statements.updated_at >= problems.updated_at
+                        AND problem_statements.is_interactive IS NOT NULL
+                ) AS fresh_statements ON TRUE
+                LEFT JOIN problem_models ON problems.problem_id = problem_models.problem_id
+                LEFT JOIN solved_counts ON problems.problem_id = solved_counts.problem_id
+                LEFT JOIN problem_stats ON problems.problem_id = problem_stats.problem_id
+                LEFT JOIN canonical_problems ON problems.problem_id = canonical_problems.problem_id;
             ",
         )
-        .fetch(self.pool);
+        .fetch(self.pool)
+        .then(move |row: std::result::Result<Row, sqlx::Error>| async move {
+            let mut row = row?;
+            // 外部のストレージに保存されているHTMLは、問題文を抽出する必要がある場合だけ読み込む
+            if let Some(key) = row.html_key.take() {
+                row.html = self.html_storage.get(&key).await.map_err(|e| {
+                    tracing::error!("failed to read the HTML of {}: {:?}", row.problem_id, e);
+                    sqlx::Error::Io(std::io::Error::other(e))
+                })?;
+            }
+            Ok(row)
+        });
 
-        Ok(stream)
+        Ok(Box::pin(stream))
     }
 }
 
 #[async_trait]
-impl<'a> GenerateDocument<'a> for ProblemDocumentGenerator<'a> {}
+impl<'a> GenerateDocument<'a> for ProblemDocumentGenerator<'a> {
+    fn progress(&self) -> Option<&ProgressReporter> {
+        self.progress.as_ref()
+    }
+
+    fn expand_mode(&self) -> ExpandMode {
+        self.expand_mode
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_contest_aliases() {
+        assert_eq!(
+            contest_aliases("abc300", "AtCoder Beginner Contest 300"),
+            vec!["ABC300", "ABC 300", "AtCoder Beginner Contest 300",]
+        );
+        assert_eq!(
+            contest_aliases("arc001", "AtCoder Regular Contest 001"),
+            vec![
+                "ARC001",
+                "ARC 001",
+                "ARC1",
+                "ARC 1",
+                "AtCoder Regular Contest 001",
+                "AtCoder Regular Contest 1",
+            ]
+        );
+        assert_eq!(
+            contest_aliases(
+                "abc305",
+                "京セラプログラミングコンテスト2023（AtCoder Beginner Contest 305）"
+            ),
+            vec!["ABC305", "ABC 305", "AtCoder Beginner Contest 305"]
+        );
+        assert!(contest_aliases("past202104-open", "第六回 アルゴリズム実技検定").is_empty());
+    }
+
+    #[test]
+    fn test_statement_length() {
+        // 空白を含まない日本語の文は文字数をそのまま数える
+        assert_eq!(
+            statement_length(&[String::from("整数NとKが与えられます。")]),
+            13
+        );
+        // 日本語と英語が混ざった文は空白を除いた文字数を数える
+        assert_eq!(
+            statement_length(&[
+                String::from("長さ N の数列 A が与えられます。"),
+                String::from("Print the answer."),
+            ]),
+            30
+        );
+        assert_eq!(statement_length(&[]), 0);
+        assert_eq!(statement_length(&[String::new(), String::from(" \n")]), 0);
+    }
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(
+            word_count(&[
+                String::from("You are given integers N and K."),
+                String::from("Print the answer."),
+            ]),
+            10
+        );
+        // 空白を含まない日本語の文は1語として数える
+        assert_eq!(word_count(&[String::from("整数NとKが与えられます。")]), 1);
+        assert_eq!(
+            word_count(&[String::from("長さ N の数列 A が与えられます。")]),
+            5
+        );
+        assert_eq!(word_count(&[]), 0);
+        assert_eq!(word_count(&[String::new(), String::from(" \n")]), 0);
+    }
+
+    #[test]
+    fn test_to_document_without_statement() {
+        // 保存されている問題文がなく、HTMLからも問題文を抽出できない問題は長さと単語数が0になる
+        let row = Row {
+            problem_id: String::from("abc300_a"),
+            canonical_problem_id: String::from("abc300_a"),
+            problem_title: String::from("A. N-choice question"),
+            problem_url: String::from("https://atcoder.jp/contests/abc300/tasks/abc300_a"),
+            problem_index: String::from("A"),
+            contest_id: String::from("abc300"),
+            contest_title: String::from("AtCoder Beginner Contest 300"),
+            difficulty: None,
+            start_at: 1682769600,
+            duration: 6000,
+            rate_change: String::from(" ~ 1999"),
+            category: String::from("ABC"),
+            statement_ja: None,
+            statement_en: None,
+            is_interactive: None,
+            has_figures: None,
+            is_experimental: false,
+            solved_count: 0,
+            submission_count: 0,
+            html: String::new(),
+            html_key: None,
+            last_updated_at: None,
+        };
+        let document = row.to_document().unwrap();
+
+        assert_eq!(document["statement_length"], 0);
+        assert_eq!(document["statement_word_count"], 0);
+        assert_eq!(document["has_english"], false);
+    }
+}
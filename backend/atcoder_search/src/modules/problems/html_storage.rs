@@ -0,0 +1,441 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+use std::{env, fmt, path::PathBuf};
+use tokio::time::Duration;
+
+// S3のオブジェクトキーでエスケープしない文字
+const KEY_SAFE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~')
+    .remove(b'/');
+// リージョンを指定しない場合のS3のリージョン
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// 問題ページのHTMLの保存先
+///
+/// - Database: これまで通り`problems.html`カラムに保存する
+/// - Local: ローカルのディレクトリに保存する
+/// - S3: S3互換のオブジェクトストレージに保存する
+///
+/// データベース以外に保存する場合は、`problems.html_key`カラムにオブジェクトのキーだけを保存する。
+#[derive(Debug, Clone, Default)]
+pub enum HtmlStorage {
+    #[default]
+    Database,
+    Local(PathBuf),
+    S3(S3Storage),
+}
+
+impl HtmlStorage {
+    /// 環境変数から保存先を決める関数
+    ///
+    /// `HTML_STORAGE`に`database`(省略時)、`local`、`s3`のいずれかを指定する。
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// `var`で参照した設定から保存先を決める関数
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let required = |key: &str| {
+            var(key).with_context(|| {
+                let message = format!("{} must be set to store the HTML", key);
+                tracing::error!(message);
+                message
+            })
+        };
+
+        match var("HTML_STORAGE").as_deref() {
+            None | Some("database") => Ok(HtmlStorage::Database),
+            Some("local") => Ok(HtmlStorage::Local(PathBuf::from(required(
+                "HTML_STORAGE_DIRECTORY",
+            )?))),
+            Some("s3") => Ok(HtmlStorage::S3(S3Storage::new(
+                &required("HTML_STORAGE_S3_ENDPOINT")?,
+                &required("HTML_STORAGE_S3_BUCKET")?,
+                &var("HTML_STORAGE_S3_REGION").unwrap_or(String::from(DEFAULT_REGION)),
+                &required("HTML_STORAGE_S3_ACCESS_KEY")?,
+                &required("HTML_STORAGE_S3_SECRET_KEY")?,
+            )?)),
+            Some(storage) => {
+                let message = format!("unknown HTML storage {}", storage);
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        }
+    }
+
+    /// 問題IDとHTMLの内容のハッシュ値から、オブジェクトのキーを作る関数
+    pub fn key(problem_id: &str, html: &str) -> String {
+        format!("{}/{:x}.html", problem_id, Sha256::digest(html.as_bytes()))
+    }
+
+    /// `problem_id`の問題のHTMLを保存し、オブジェクトのキーを返すメソッド
+    ///
+    /// データベースに保存する場合は何もせずに`None`を返す。
+    pub async fn put(&self, problem_id: &str, html: &str) -> Result<Option<String>> {
+        let key = Self::key(problem_id, html);
+        match self {
+            HtmlStorage::Database => return Ok(None),
+            HtmlStorage::Local(dir) => {
+                let path = dir.join(&key);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&path, html)
+                    .await
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+            HtmlStorage::S3(storage) => storage.put(&key, html).await?,
+        }
+
+        Ok(Some(key))
+    }
+
+    /// `key`のオブジェクトのHTMLを取得するメソッド
+    pub async fn get(&self, key: &str) -> Result<String> {
+        match self {
+            HtmlStorage::Database => {
+                anyhow::bail!(
+                    "HTML {} is stored outside the database but HTML_STORAGE is not set",
+                    key
+                )
+            }
+            HtmlStorage::Local(dir) => {
+                let path = dir.join(key);
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("failed to read {}", path.display()))
+            }
+            HtmlStorage::S3(storage) => storage.get(key).await,
+        }
+    }
+
+    /// `key`のオブジェクトを削除するメソッド。既に存在しない場合は何もしない
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        match self {
+            HtmlStorage::Database => {
+                anyhow::bail!(
+                    "HTML {} is stored outside the database but HTML_STORAGE is not set",
+                    key
+                )
+            }
+            HtmlStorage::Local(dir) => {
+                let path = dir.join(key);
+                match tokio::fs::remove_file(&path).await {
+                    Ok(()) => Ok(()),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    Err(e) => {
+                        Err(e).with_context(|| format!("failed to remove {}", path.display()))
+                    }
+                }
+            }
+            HtmlStorage::S3(storage) => storage.delete(key).await,
+        }
+    }
+
+    /// `problems`テーブルの行のHTMLを返すメソッド
+    ///
+    /// `html_key`があればオブジェクトから、なければ`html`カラムの値をそのまま返す。
+    pub async fn resolve(&self, html: Option<String>, html_key: Option<&str>) -> Result<String> {
+        match html_key {
+            Some(key) => self.get(key).await,
+            None => Ok(html.unwrap_or_default()),
+        }
+    }
+}
+
+/// S3互換のオブジェクトストレージのクライアント
+///
+/// 署名バージョン4で署名し、パス形式のURL(`{endpoint}/{bucket}/{key}`)でアクセスする。
+#[derive(Clone)]
+pub struct S3Storage {
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    client: Client,
+}
+
+// シークレットキーをログに出さないようにする
+impl fmt::Debug for S3Storage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("S3Storage")
+            .field("endpoint", &self.endpoint.as_str())
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"********")
+            .finish()
+    }
+}
+
+impl S3Storage {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<Self> {
+        let endpoint = Url::parse(endpoint).with_context(|| {
+            let message = format!("invalid S3 endpoint {}", endpoint);
+            tracing::error!(message);
+            message
+        })?;
+        Ok(Self {
+            endpoint,
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+        })
+    }
+
+    /// オブジェクトのURLのパス
+    fn path(&self, key: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            utf8_percent_encode(&self.bucket, KEY_SAFE),
+            utf8_percent_encode(key, KEY_SAFE)
+        )
+    }
+
+    /// リクエストに付ける`Authorization`などのヘッダを作るメソッド
+    fn signed_headers(
+        &self,
+        method: &Method,
+        path: &str,
+        payload: &[u8],
+        now: DateTime<Utc>,
+    ) -> Vec<(&'static str, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{}", self.endpoint.host_str().unwrap_or_default(), port),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let payload_hash = format!("{:x}", Sha256::digest(payload));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+            amz_date,
+            scope,
+            Sha256::digest(canonical_request.as_bytes())
+        );
+        let key = signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        vec![
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            ),
+            ("x-amz-content-sha256", payload_hash),
+            ("x-amz-date", amz_date),
+        ]
+    }
+
+    async fn request(&self, method: Method, key: &str, payload: Vec<u8>) -> Result<String> {
+        let path = self.path(key);
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+
+        let mut request = self.client.request(method.clone(), url);
+        for (name, value) in self.signed_headers(&method, &path, &payload, Utc::now()) {
+            request = request.header(name, value);
+        }
+        let res = request.body(payload).send().await?;
+
+        if let Err(e) = res.error_for_status_ref() {
+            let message = format!(
+                "error response returned from the object storage for {} {}: {:?}",
+                method, key, e
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        }
+        Ok(res.text().await?)
+    }
+
+    /// `key`のオブジェクトとして`html`を保存するメソッド
+    pub async fn put(&self, key: &str, html: &str) -> Result<()> {
+        self.request(Method::PUT, key, html.as_bytes().to_vec())
+            .await?;
+        Ok(())
+    }
+
+    /// `key`のオブジェクトを取得するメソッド
+    pub async fn get(&self, key: &str) -> Result<String> {
+        self.request(Method::GET, key, Vec::new()).await
+    }
+
+    /// `key`のオブジェクトを削除するメソッド
+    ///
+    /// S3は存在しないオブジェクトの削除も成功として扱う。
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.request(Method::DELETE, key, Vec::new()).await?;
+        Ok(())
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// 署名バージョン4の署名鍵を導出する関数
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+    use std::{collections::HashMap, path::Path};
+
+    #[test]
+    fn test_from_vars() {
+        let vars = |pairs: &[(&str, &str)]| {
+            let vars: HashMap<String, String> = pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            move |key: &str| vars.get(key).cloned()
+        };
+
+        assert!(matches!(
+            HtmlStorage::from_vars(vars(&[])).unwrap(),
+            HtmlStorage::Database
+        ));
+        assert!(matches!(
+            HtmlStorage::from_vars(vars(&[
+                ("HTML_STORAGE", "local"),
+                ("HTML_STORAGE_DIRECTORY", "/var/tmp/html")
+            ]))
+            .unwrap(),
+            HtmlStorage::Local(dir) if dir == Path::new("/var/tmp/html")
+        ));
+        assert!(HtmlStorage::from_vars(vars(&[("HTML_STORAGE", "local")])).is_err());
+        assert!(HtmlStorage::from_vars(vars(&[("HTML_STORAGE", "gcs")])).is_err());
+
+        let storage = HtmlStorage::from_vars(vars(&[
+            ("HTML_STORAGE", "s3"),
+            ("HTML_STORAGE_S3_ENDPOINT", "http://localhost:9000"),
+            ("HTML_STORAGE_S3_BUCKET", "problems"),
+            ("HTML_STORAGE_S3_ACCESS_KEY", "minio"),
+            ("HTML_STORAGE_S3_SECRET_KEY", "minio-secret-key"),
+        ]))
+        .unwrap();
+        let HtmlStorage::S3(storage) = storage else {
+            panic!("S3 storage is expected");
+        };
+        assert_eq!(storage.region, DEFAULT_REGION);
+        assert_eq!(
+            storage.path("abc300_a/0f.html"),
+            "/problems/abc300_a/0f.html"
+        );
+        assert!(!format!("{:?}", storage).contains("minio-secret-key"));
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // AWSのドキュメントにある署名鍵の導出の例
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+
+        let storage = S3Storage::new(
+            "http://localhost:9000",
+            "problems",
+            "us-east-1",
+            "minio",
+            "secret",
+        )
+        .unwrap();
+        let now = Utc.with_ymd_and_hms(2023, 9, 25, 12, 0, 0).unwrap();
+        let headers = storage.signed_headers(&Method::GET, "/problems/abc300_a.html", b"", now);
+        assert!(headers[0]
+            .1
+            .starts_with("AWS4-HMAC-SHA256 Credential=minio/20230925/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="));
+        assert_eq!(
+            headers[1].1,
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(headers[2].1, "20230925T120000Z");
+    }
+
+    #[tokio::test]
+    async fn test_local_storage() {
+        let dir = std::env::temp_dir().join(format!(
+            "atcoder_search_test_html_storage_{}",
+            std::process::id()
+        ));
+        let storage = HtmlStorage::Local(dir.clone());
+
+        let key = storage
+            .put("abc300_a", "<html></html>")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(key, HtmlStorage::key("abc300_a", "<html></html>"));
+        assert!(key.starts_with("abc300_a/"));
+        assert_eq!(
+            storage.resolve(None, Some(&key)).await.unwrap(),
+            "<html></html>"
+        );
+
+        storage.delete(&key).await.unwrap();
+        assert!(storage.get(&key).await.is_err());
+        // 削除済みのオブジェクトを削除してもエラーにしない
+        storage.delete(&key).await.unwrap();
+
+        let database = HtmlStorage::Database;
+        assert_eq!(
+            database.put("abc300_a", "<html></html>").await.unwrap(),
+            None
+        );
+        assert_eq!(
+            database
+                .resolve(Some(String::from("<p></p>")), None)
+                .await
+                .unwrap(),
+            "<p></p>"
+        );
+        assert!(database.resolve(None, Some(&key)).await.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
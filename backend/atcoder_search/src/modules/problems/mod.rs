@@ -1,3 +1,11 @@
+pub mod calendar;
+pub mod category_relationship;
 pub mod crawler;
 pub mod extractor;
 pub mod generator;
+pub mod html_storage;
+pub mod recategorize;
+pub mod saved_search;
+pub mod statement;
+pub mod trending;
+pub mod vacuum;
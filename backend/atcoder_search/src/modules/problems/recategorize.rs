@@ -0,0 +1,165 @@
+use crate::types::contest::{CategoryRules, ContestJson};
+use anyhow::Result;
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::fmt;
+
+/// `contests`テーブルに保存されているコンテストのカテゴリ
+#[derive(Debug, Clone, FromRow)]
+pub struct StoredContest {
+    pub contest_id: String,
+    pub start_epoch_second: i64,
+    pub duration_second: i64,
+    pub title: String,
+    pub rate_change: String,
+    pub category: String,
+}
+
+impl StoredContest {
+    fn to_json(&self) -> ContestJson {
+        ContestJson {
+            id: self.contest_id.clone(),
+            start_epoch_second: self.start_epoch_second,
+            duration_second: self.duration_second,
+            title: self.title.clone(),
+            rate_change: self.rate_change.clone(),
+        }
+    }
+}
+
+/// カテゴリが変わるコンテスト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryChange {
+    pub contest_id: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for CategoryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.contest_id, self.from, self.to)
+    }
+}
+
+/// 保存されているコンテストのうち、`rules`で識別し直すとカテゴリが変わるものを列挙する関数
+pub fn category_changes(contests: &[StoredContest], rules: &CategoryRules) -> Vec<CategoryChange> {
+    contests
+        .iter()
+        .filter_map(|contest| {
+            let category = contest.to_json().categorize_with(rules);
+            (category != contest.category).then(|| CategoryChange {
+                contest_id: contest.contest_id.clone(),
+                from: contest.category.clone(),
+                to: category,
+            })
+        })
+        .collect()
+}
+
+/// 収集し直さずに、保存されているコンテストのカテゴリを識別し直す構造体
+///
+/// 問題のドキュメントのカテゴリはコンテストのカテゴリから作るので、検索に反映するにはドキュメントを生成し直す必要がある。
+#[derive(Debug, Clone)]
+pub struct ContestRecategorizer {
+    pool: Pool<Postgres>,
+}
+
+impl ContestRecategorizer {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    async fn stored(&self) -> Result<Vec<StoredContest>> {
+        let contests = sqlx::query_as(
+            "
+            SELECT
+                contest_id,
+                start_epoch_second,
+                duration_second,
+                title,
+                rate_change,
+                category
+            FROM
+                contests
+            ORDER BY
+                contest_id;
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(contests)
+    }
+
+    /// カテゴリが変わるコンテストを返すメソッド。データベースへは書き込まない
+    pub async fn plan(&self, rules: &CategoryRules) -> Result<Vec<CategoryChange>> {
+        Ok(category_changes(&self.stored().await?, rules))
+    }
+
+    /// 全てのコンテストを`rules`で識別し直し、カテゴリとルールのバージョンを更新するメソッド
+    ///
+    /// カテゴリが変わったコンテストを返す。
+    pub async fn run(&self, rules: &CategoryRules) -> Result<Vec<CategoryChange>> {
+        let changes = category_changes(&self.stored().await?, rules);
+
+        let mut tx = self.pool.begin().await?;
+        for change in changes.iter() {
+            sqlx::query("UPDATE contests SET category = $2 WHERE contest_id = $1;")
+                .bind(&change.contest_id)
+                .bind(&change.to)
+                .execute(&mut tx)
+                .await?;
+        }
+        // カテゴリが変わらなかったコンテストも、どのルールで識別したかを記録する
+        sqlx::query(
+            "UPDATE contests SET category_rules_version = $1 WHERE category_rules_version <> $1;",
+        )
+        .bind(&rules.version)
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_category_changes() {
+        let contest = |contest_id: &str, title: &str, category: &str| StoredContest {
+            contest_id: String::from(contest_id),
+            start_epoch_second: 1687060800,
+            duration_second: 10800,
+            title: String::from(title),
+            rate_change: String::from("-"),
+            category: String::from(category),
+        };
+        let contests = vec![
+            contest("abc300", "AtCoder Beginner Contest 300", "ABC"),
+            contest("joi2024yo1a", "JOI 2024 一次予選 第1回", "JOI"),
+            contest("jag2023summer", "JAG 夏合宿 2023", "JAG"),
+        ];
+        assert!(category_changes(&contests, &CategoryRules::default()).is_empty());
+
+        let rules = CategoryRules::parse(
+            r#"
+            version = "2023-10-20"
+
+            [[rules]]
+            category = "Other Contests"
+            title = "夏合宿"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            category_changes(&contests, &rules),
+            vec![CategoryChange {
+                contest_id: String::from("jag2023summer"),
+                from: String::from("JAG"),
+                to: String::from("Other Contests"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::time::Duration;
+
+// トークンの文字数
+pub const TOKEN_LENGTH: usize = 16;
+
+/// `saved_searches`テーブルの行
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct SavedSearch {
+    pub token: String,
+    pub query: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 問題検索のクエリ文字列をトークンに紐づけて保存する`saved_searches`テーブルへのアクセスを提供する構造体
+#[derive(Debug, Clone)]
+pub struct SavedSearchStore {
+    pool: Pool<Postgres>,
+}
+
+impl SavedSearchStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// クエリ文字列を保存し、発行したトークンを返すメソッド
+    ///
+    /// トークンはクエリ文字列のハッシュ値から作るので、同じ検索を保存すると同じトークンの有効期限が延びる。
+    /// 保存するときに期限切れの検索を削除する。
+    pub async fn save(&self, query: &str, ttl: Duration) -> Result<SavedSearch> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM saved_searches WHERE expires_at <= NOW();")
+            .execute(&mut tx)
+            .await?;
+
+        let saved = sqlx::query_as(
+            "
+            INSERT INTO saved_searches (token, query, expires_at)
+            VALUES (LEFT(MD5($1), $2), $1, NOW() + MAKE_INTERVAL(secs => $3))
+            ON CONFLICT (token) DO UPDATE SET
+                query = EXCLUDED.query,
+                expires_at = EXCLUDED.expires_at
+            RETURNING
+                token,
+                query,
+                expires_at;
+            ",
+        )
+        .bind(query)
+        .bind(TOKEN_LENGTH as i32)
+        .bind(ttl.as_secs() as f64)
+        .fetch_one(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(saved)
+    }
+
+    /// 有効期限内の`token`の検索を取得するメソッド
+    pub async fn get(&self, token: &str) -> Result<Option<SavedSearch>> {
+        let saved = sqlx::query_as(
+            "
+            SELECT
+                token,
+                query,
+                expires_at
+            FROM
+                saved_searches
+            WHERE
+                token = $1
+                AND expires_at > NOW();
+            ",
+        )
+        .bind(token)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(saved)
+    }
+}
+
+/// `token`が保存時に発行される形式のトークンかどうか
+pub fn is_valid_token(token: &str) -> bool {
+    token.len() == TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_token() {
+        assert!(is_valid_token("0123456789abcdef"));
+        assert!(!is_valid_token("0123456789abcde"));
+        assert!(!is_valid_token("0123456789abcdeg"));
+        assert!(!is_valid_token("../../etc/passwd"));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::modules::problems::{extractor::FullTextExtractor, html_storage::HtmlStorage};
+use anyhow::Result;
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use tokio_stream::StreamExt;
+
+/// `problem_statements`テーブルの行に問題のタイトルを加えたもの
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct ProblemStatement {
+    pub problem_id: String,
+    pub title: String,
+    pub statement_ja: Vec<String>,
+    pub statement_en: Vec<String>,
+    pub constraints_ja: Vec<String>,
+    pub constraints_en: Vec<String>,
+    pub html_ja: Vec<String>,
+    pub html_en: Vec<String>,
+}
+
+/// 問題文を取り出す元になる`problems`テーブルの行と、保存済みの問題文
+#[derive(Debug, FromRow)]
+struct ProblemHtml {
+    problem_id: String,
+    html: Option<String>,
+    html_key: Option<String>,
+    version: Option<i32>,
+    previous_ja: Option<Vec<String>>,
+    previous_en: Option<Vec<String>>,
+}
+
+/// 問題のHTMLから抽出した問題文を保存する`problem_statements`テーブルへのアクセスを提供する構造体
+#[derive(Debug, Clone)]
+pub struct StatementStore {
+    pool: Pool<Postgres>,
+    html_storage: HtmlStorage,
+}
+
+impl StatementStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            html_storage: HtmlStorage::default(),
+        }
+    }
+
+    /// 外部のストレージに保存されている問題ページのHTMLを`html_storage`から読み込む
+    pub fn with_html_storage(self, html_storage: HtmlStorage) -> Self {
+        Self {
+            html_storage,
+            ..self
+        }
+    }
+
+    /// 問題文がまだ保存されていないか、保存した後にHTMLが更新された問題の問題文を抽出して保存するメソッド
+    ///
+    /// 問題の特徴(インタラクティブかどうかなど)を判定する前に保存された問題文も抽出し直す。
+    ///
+    /// `all`が`true`の場合は保存済みのものも含めてすべての問題から抽出し直す。保存した問題の数を返す。
+    pub async fn refresh(&self, all: bool) -> Result<usize> {
+        let extractor = FullTextExtractor::new();
+        let mut rows = sqlx::query_as::<_, ProblemHtml>(
+            "
+            SELECT
+                problems.problem_id AS problem_id,
+                problems.html AS html,
+                problems.html_key AS html_key,
+                problem_statements.version AS version,
+                problem_statements.statement_ja AS previous_ja,
+                problem_statements.statement_en AS previous_en
+            FROM
+                problems
+                LEFT JOIN problem_statements ON problems.problem_id = problem_statements.problem_id
+            WHERE
+                $1
+                OR problem_statements.problem_id IS NULL
+                OR problems.updated_at > problem_statements.updated_at
+                OR problem_statements.is_interactive IS NULL;
+            ",
+        )
+        .bind(all)
+        .fetch(&self.pool);
+
+        let mut count = 0;
+        while let Some(row) = rows.try_next().await? {
+            let html = self
+                .html_storage
+                .resolve(row.html, row.html_key.as_deref())
+                .await?;
+            let statement = extractor.extract_all(&html)?;
+
+            // 問題文のテキストが前回から変わっていたら新しい版として記録する。
+            // 難易度の更新などでHTML以外が変わった場合や、HTMLの変更が表示にしか影響しない場合は版を上げない。
+            let (version, summary) = match (row.version, row.previous_ja, row.previous_en) {
+                (Some(version), Some(previous_ja), Some(previous_en)) => {
+                    if previous_ja == statement.statement_ja
+                        && previous_en == statement.statement_en
+                    {
+                        (version, None)
+                    } else {
+                        let summary = diff_summary(
+                            &previous_ja,
+                            &previous_en,
+                            &statement.statement_ja,
+                            &statement.statement_en,
+                        );
+                        (version + 1, Some(summary))
+                    }
+                }
+                _ => (1, None),
+            };
+            let is_new_version = row.version != Some(version);
+
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                "
+                INSERT INTO problem_statements (problem_id, statement_ja, statement_en, constraints_ja, constraints_en, html_ja, html_en, version, is_interactive, has_figures)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (problem_id) DO UPDATE SET
+                    statement_ja = EXCLUDED.statement_ja,
+                    statement_en = EXCLUDED.statement_en,
+                    constraints_ja = EXCLUDED.constraints_ja,
+                    constraints_en = EXCLUDED.constraints_en,
+                    html_ja = EXCLUDED.html_ja,
+                    html_en = EXCLUDED.html_en,
+                    version = EXCLUDED.version,
+                    is_interactive = EXCLUDED.is_interactive,
+                    has_figures = EXCLUDED.has_figures,
+                    statement_updated_at = CASE
+                        WHEN problem_statements.version <> EXCLUDED.version THEN CURRENT_TIMESTAMP
+                        ELSE problem_statements.statement_updated_at
+                    END;
+                ",
+            )
+            .bind(&row.problem_id)
+            .bind(&statement.statement_ja)
+            .bind(&statement.statement_en)
+            .bind(&statement.constraints_ja)
+            .bind(&statement.constraints_en)
+            .bind(&statement.html_ja)
+            .bind(&statement.html_en)
+            .bind(version)
+            .bind(statement.is_interactive)
+            .bind(statement.has_figures)
+            .execute(&mut tx)
+            .await?;
+
+            if is_new_version {
+                sqlx::query(
+                    "
+                    INSERT INTO problem_statement_versions (problem_id, version, statement_ja, statement_en, diff_summary)
+                    VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT (problem_id, version) DO NOTHING;
+                    ",
+                )
+                .bind(&row.problem_id)
+                .bind(version)
+                .bind(&statement.statement_ja)
+                .bind(&statement.statement_en)
+                .bind(&summary)
+                .execute(&mut tx)
+                .await?;
+
+                if let Some(summary) = &summary {
+                    tracing::info!(
+                        "The statement of {} has been updated to version {} ({})",
+                        row.problem_id,
+                        version,
+                        summary
+                    );
+                }
+            }
+            tx.commit().await?;
+
+            tracing::debug!("Save the statement of {}", row.problem_id);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// 保存されている問題文を取得するメソッド
+    pub async fn get(&self, problem_id: &str) -> Result<Option<ProblemStatement>> {
+        let statement = sqlx::query_as(
+            "
+            SELECT
+                problem_statements.problem_id AS problem_id,
+                problems.title AS title,
+                problem_statements.statement_ja AS statement_ja,
+                problem_statements.statement_en AS statement_en,
+                problem_statements.constraints_ja AS constraints_ja,
+                problem_statements.constraints_en AS constraints_en,
+                problem_statements.html_ja AS html_ja,
+                problem_statements.html_en AS html_en
+            FROM
+                problem_statements
+                JOIN problems ON problem_statements.problem_id = problems.problem_id
+            WHERE
+                problem_statements.problem_id = $1;
+            ",
+        )
+        .bind(problem_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(statement)
+    }
+}
+
+// 前の版と新しい版の問題文の差分を、言語ごとに追加・削除されたセクションの数で要約する関数
+//
+// 例: `ja: +1 -1 sections, en: unchanged`
+fn diff_summary(
+    previous_ja: &[String],
+    previous_en: &[String],
+    current_ja: &[String],
+    current_en: &[String],
+) -> String {
+    [
+        ("ja", previous_ja, current_ja),
+        ("en", previous_en, current_en),
+    ]
+    .iter()
+    .map(|(lang, previous, current)| {
+        let common = common_sections(previous, current);
+        let added = current.len() - common;
+        let removed = previous.len() - common;
+        if added == 0 && removed == 0 {
+            format!("{}: unchanged", lang)
+        } else {
+            format!("{}: +{} -{} sections", lang, added, removed)
+        }
+    })
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+// 2つの版で変わらなかったセクションの数(最長共通部分列の長さ)を求める関数
+fn common_sections(previous: &[String], current: &[String]) -> usize {
+    let mut dp = vec![vec![0; current.len() + 1]; previous.len() + 1];
+    for (i, p) in previous.iter().enumerate() {
+        for (j, c) in current.iter().enumerate() {
+            dp[i + 1][j + 1] = if p == c {
+                dp[i][j] + 1
+            } else {
+                dp[i][j + 1].max(dp[i + 1][j])
+            };
+        }
+    }
+    dp[previous.len()][current.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sections(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff_summary() {
+        let previous = sections(&["A", "B", "C"]);
+        assert_eq!(
+            diff_summary(
+                &previous,
+                &sections(&["X"]),
+                &sections(&["A", "B'", "C", "D"]),
+                &sections(&["X"]),
+            ),
+            "ja: +2 -1 sections, en: unchanged"
+        );
+        assert_eq!(
+            diff_summary(&previous, &[], &previous, &sections(&["X"])),
+            "ja: unchanged, en: +1 -0 sections"
+        );
+    }
+
+    #[test]
+    fn test_common_sections() {
+        assert_eq!(
+            common_sections(&sections(&["A", "B", "C"]), &sections(&["B", "C", "A"])),
+            2
+        );
+        assert_eq!(common_sections(&[], &sections(&["A"])), 0);
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+
+/// 直近の期間に多くのユーザに解かれた問題
+#[derive(Debug, Clone, FromRow, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrendingProblem {
+    pub problem_id: String,
+    pub contest_id: String,
+    pub problem_title: String,
+    /// 直近の期間に正解したユーザの数
+    pub recent_solved_count: i64,
+    /// その1つ前の同じ長さの期間に正解したユーザの数
+    pub previous_solved_count: i64,
+}
+
+/// `submissions`テーブルから直近に解かれている問題を集計する構造体
+#[derive(Debug, Clone)]
+pub struct TrendingProblems {
+    pool: Pool<Postgres>,
+}
+
+impl TrendingProblems {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Unix Epoch Timeで`now`までの`window`秒間に正解したユーザの多い順に、最大`limit`件の問題を取得するメソッド
+    ///
+    /// 比較のため、その前の`window`秒間に正解したユーザの数も合わせて集計する。
+    /// 直近の期間に誰も正解していない問題は含めない。
+    pub async fn top(&self, now: i64, window: i64, limit: i64) -> Result<Vec<TrendingProblem>> {
+        let problems = sqlx::query_as(
+            "
+            SELECT
+                submissions.problem_id,
+                problems.contest_id,
+                problems.title AS problem_title,
+                COUNT(DISTINCT submissions.user_id) FILTER (
+                    WHERE submissions.epoch_second >= $2
+                ) AS recent_solved_count,
+                COUNT(DISTINCT submissions.user_id) FILTER (
+                    WHERE submissions.epoch_second < $2
+                ) AS previous_solved_count
+            FROM
+                submissions
+                JOIN problems ON submissions.problem_id = problems.problem_id
+            WHERE
+                submissions.result = 'AC'
+                AND submissions.epoch_second >= $1
+                AND submissions.epoch_second < $3
+            GROUP BY
+                submissions.problem_id,
+                problems.contest_id,
+                problems.title
+            HAVING
+                COUNT(DISTINCT submissions.user_id) FILTER (
+                    WHERE submissions.epoch_second >= $2
+                ) > 0
+            ORDER BY
+                recent_solved_count DESC,
+                submissions.problem_id
+            LIMIT $4;
+            ",
+        )
+        .bind(now - 2 * window)
+        .bind(now - window)
+        .bind(now)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(problems)
+    }
+}
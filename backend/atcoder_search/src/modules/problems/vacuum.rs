@@ -0,0 +1,246 @@
+use crate::modules::problems::html_storage::HtmlStorage;
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::fmt;
+
+// 新しい版に置き換わってから保持期間が過ぎた、古い版の問題文の条件。
+// `problem_statements`に保存されている現在の版より前の版だけを対象にする
+const SUPERSEDED_STATEMENT_VERSIONS: &str = r#"
+    "problem_statement_versions"."problem_id" = "problem_statements"."problem_id"
+    AND "problem_statement_versions"."version" < "problem_statements"."version"
+    AND EXISTS (
+        SELECT
+            1
+        FROM
+            "problem_statement_versions" AS "newer"
+        WHERE
+            "newer"."problem_id" = "problem_statement_versions"."problem_id"
+            AND "newer"."version" > "problem_statement_versions"."version"
+            AND "newer"."created_at" < $1
+    )
+"#;
+
+// 新しいHTMLに置き換わってから保持期間が過ぎた、古いHTMLのオブジェクト。
+// `problems`テーブルが現在参照しているオブジェクトは対象にしない
+const SUPERSEDED_HTML_OBJECTS: &str = r#"
+    SELECT
+        "objects"."html_key",
+        "objects"."problem_id",
+        "objects"."size"
+    FROM
+        "problem_html_objects" AS "objects"
+        JOIN "problems" USING ("problem_id")
+    WHERE
+        "objects"."html_key" IS DISTINCT FROM "problems"."html_key"
+        AND EXISTS (
+            SELECT
+                1
+            FROM
+                "problem_html_objects" AS "newer"
+            WHERE
+                "newer"."problem_id" = "objects"."problem_id"
+                AND "newer"."stored_at" > "objects"."stored_at"
+                AND "newer"."stored_at" < $1
+        )
+    ORDER BY
+        "objects"."problem_id",
+        "objects"."stored_at"
+"#;
+
+/// 削除する(した)古いデータの件数と大きさ
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VacuumReport {
+    pub statement_versions: i64,
+    pub statement_bytes: i64,
+    pub html_objects: i64,
+    pub html_bytes: i64,
+}
+
+impl VacuumReport {
+    /// 削除する(した)データの大きさの合計
+    pub fn total_bytes(&self) -> i64 {
+        self.statement_bytes + self.html_bytes
+    }
+}
+
+impl fmt::Display for VacuumReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} statement versions ({} kB), {} HTML objects ({} kB), {} kB in total",
+            self.statement_versions,
+            self.statement_bytes / 1024,
+            self.html_objects,
+            self.html_bytes / 1024,
+            self.total_bytes() / 1024
+        )
+    }
+}
+
+/// `problem_html_objects`テーブルの行
+#[derive(Debug, FromRow)]
+struct HtmlObject {
+    html_key: String,
+    problem_id: String,
+    size: i64,
+}
+
+/// 保持期間を過ぎた古い版の問題文と問題ページのHTMLを削除する構造体
+///
+/// 新しい版に置き換わった日時から`retention`が過ぎたものだけを削除し、現在の版は削除しない。
+pub struct Vacuum<'a> {
+    pool: &'a Pool<Postgres>,
+    html_storage: HtmlStorage,
+    retention: ChronoDuration,
+}
+
+impl<'a> Vacuum<'a> {
+    pub fn new(pool: &'a Pool<Postgres>, retention: ChronoDuration) -> Self {
+        Self {
+            pool,
+            html_storage: HtmlStorage::default(),
+            retention,
+        }
+    }
+
+    /// 外部のストレージに保存されている古いHTMLを`html_storage`から削除する
+    pub fn with_html_storage(self, html_storage: HtmlStorage) -> Self {
+        Self {
+            html_storage,
+            ..self
+        }
+    }
+
+    // この日時より前に置き換わったものを削除する
+    fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - self.retention
+    }
+
+    /// 削除の対象になる古いデータの件数と大きさを、削除せずに数えるメソッド
+    pub async fn plan(&self) -> Result<VacuumReport> {
+        let cutoff = self.cutoff(Utc::now());
+
+        let (statement_versions, statement_bytes): (i64, i64) = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(pg_column_size("problem_statement_versions".*)), 0)::BIGINT
+            FROM
+                "problem_statement_versions",
+                "problem_statements"
+            WHERE
+                {};
+            "#,
+            SUPERSEDED_STATEMENT_VERSIONS
+        ))
+        .bind(cutoff)
+        .fetch_one(self.pool)
+        .await?;
+
+        let objects: Vec<HtmlObject> = sqlx::query_as(SUPERSEDED_HTML_OBJECTS)
+            .bind(cutoff)
+            .fetch_all(self.pool)
+            .await?;
+
+        Ok(VacuumReport {
+            statement_versions,
+            statement_bytes,
+            html_objects: objects.len() as i64,
+            html_bytes: objects.iter().map(|object| object.size).sum(),
+        })
+    }
+
+    /// 古いデータを削除し、削除した件数と大きさを返すメソッド
+    ///
+    /// 削除に失敗したHTMLのオブジェクトは警告を出して読み飛ばし、次回に再び対象にする。
+    pub async fn run(&self) -> Result<VacuumReport> {
+        let cutoff = self.cutoff(Utc::now());
+
+        let (statement_versions, statement_bytes): (i64, i64) = sqlx::query_as(&format!(
+            r#"
+            WITH "deleted" AS (
+                DELETE FROM
+                    "problem_statement_versions"
+                USING
+                    "problem_statements"
+                WHERE
+                    {}
+                RETURNING
+                    pg_column_size("problem_statement_versions".*) AS "size"
+            )
+            SELECT
+                COUNT(*),
+                COALESCE(SUM("size"), 0)::BIGINT
+            FROM
+                "deleted";
+            "#,
+            SUPERSEDED_STATEMENT_VERSIONS
+        ))
+        .bind(cutoff)
+        .fetch_one(self.pool)
+        .await?;
+        tracing::info!(
+            "{} old statement versions have been deleted",
+            statement_versions
+        );
+
+        let objects: Vec<HtmlObject> = sqlx::query_as(SUPERSEDED_HTML_OBJECTS)
+            .bind(cutoff)
+            .fetch_all(self.pool)
+            .await?;
+
+        let mut report = VacuumReport {
+            statement_versions,
+            statement_bytes,
+            ..Default::default()
+        };
+        for object in objects.iter() {
+            // 記録を残したままオブジェクトだけが消えることのないよう、オブジェクトを先に削除する
+            if let Err(e) = self.html_storage.delete(&object.html_key).await {
+                tracing::warn!(
+                    "failed to delete the old HTML {} of {} cause: {:?}",
+                    object.html_key,
+                    object.problem_id,
+                    e
+                );
+                continue;
+            }
+            sqlx::query(r#"DELETE FROM "problem_html_objects" WHERE "html_key" = $1;"#)
+                .bind(&object.html_key)
+                .execute(self.pool)
+                .await?;
+
+            report.html_objects += 1;
+            report.html_bytes += object.size;
+        }
+        tracing::info!("{} old HTML objects have been deleted", report.html_objects);
+
+        // 削除した行の領域を再利用できるようにする
+        sqlx::query(r#"VACUUM "problem_statement_versions", "problem_html_objects";"#)
+            .execute(self.pool)
+            .await?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display_report() {
+        let report = VacuumReport {
+            statement_versions: 3,
+            statement_bytes: 4096,
+            html_objects: 2,
+            html_bytes: 10240,
+        };
+        assert_eq!(report.total_bytes(), 14336);
+        assert_eq!(
+            report.to_string(),
+            "3 statement versions (4 kB), 2 HTML objects (10 kB), 14 kB in total"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+// プロファイル名はURLのパスに含めるので英小文字・数字・`-`・`_`に限る
+static PROFILE_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z0-9_-]+$").unwrap());
+
+/// 1つのプロファイルで検索に使うSolrコアの名前
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProfileConfig {
+    pub problems: String,
+    pub users: String,
+    pub recommends: String,
+}
+
+/// プロファイルの設定ファイル
+///
+/// ```toml
+/// [profiles.experimental]
+/// problems = "problems_experimental"
+/// users = "users"
+/// recommends = "recommends"
+/// ```
+///
+/// プロファイル名をキーに、`/api/v1/{profile}/...`で検索するコアを対応付ける。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ProfilesConfig {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+impl ProfilesConfig {
+    /// 設定ファイルを読み込む関数
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| {
+            let message = format!("failed to read the profile config {}", path.display());
+            tracing::error!(message);
+            message
+        })?;
+        Self::parse(&content)
+    }
+
+    /// TOML形式の設定をパースしてプロファイル名を検証する関数
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: ProfilesConfig = toml::from_str(content).with_context(|| {
+            let message = "invalid profile config";
+            tracing::error!(message);
+            message
+        })?;
+
+        if let Some(name) = config
+            .profiles
+            .keys()
+            .find(|name| !PROFILE_NAME_RE.is_match(name))
+        {
+            let message = format!(
+                "profile name `{}` must consist of lowercase letters, digits, `-` and `_`",
+                name
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_profiles() {
+        let config = ProfilesConfig::parse(
+            r#"
+            [profiles.production]
+            problems = "problems"
+            users = "users"
+            recommends = "recommends"
+
+            [profiles.experimental]
+            problems = "problems_experimental"
+            users = "users"
+            recommends = "recommends"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.profiles.keys().collect::<Vec<_>>(),
+            vec!["experimental", "production"]
+        );
+        assert_eq!(
+            config.profiles["experimental"].problems,
+            "problems_experimental"
+        );
+        assert_eq!(
+            ProfilesConfig::parse("").unwrap(),
+            ProfilesConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_profiles() {
+        assert!(ProfilesConfig::parse(
+            r#"
+            [profiles."new profile"]
+            problems = "problems"
+            users = "users"
+            recommends = "recommends"
+            "#
+        )
+        .is_err());
+        assert!(ProfilesConfig::parse(
+            r#"
+            [profiles.experimental]
+            problems = "problems"
+            "#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,514 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use atcoder_search_libs::{
+    DocumentFormat, GenerateDocument, ProgressReporter, ReadRows, ToDocument,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::path::{Path, PathBuf};
+use tokio::macros::support::Pin;
+use tokio_stream::Stream;
+
+// 難易度の差をこの値で割ったものの2乗が重みの指数になる
+const DIFFICULTY_SCALE: f64 = 400.0;
+
+// 難易度の近い問題とみなす難易度の差の上限
+//
+// これより差が大きいと重みが小数点以下4桁に丸めて0になるので、組み合わせを作る前に除いておく。
+const MAX_DIFFICULTY_DISTANCE: i32 = 1600;
+
+// 1問あたりに保存する難易度の近い問題の数
+const MAX_DIFFICULTY_CORRELATIONS: i64 = 50;
+
+// 1問あたりに保存する一緒に解かれている問題の数
+const MAX_COSOLVE_CORRELATIONS: i64 = 50;
+
+// 一緒に解かれているとみなすのに必要な、両方の問題を解いたユーザの数
+//
+// 少人数の偶然の重なりで重みが大きくならないようにする。
+const MIN_COSOLVERS: i64 = 5;
+
+// 一緒に解かれている問題を数えるときに使う、ユーザあたりの最近解いた問題の数
+//
+// 組み合わせの数はユーザごとに解いた問題の数の2乗で増えるので、たくさん解いているユーザの分を抑える。
+const MAX_SOLVED_PER_USER: i64 = 200;
+
+/// レコメンドのドキュメントの元になる問題と、その問題を推薦する問題・カテゴリの重み
+///
+/// `difficulty_problem_ids`と`difficulty_weights`、`categories`と`category_weights`、
+/// `cosolve_problem_ids`と`cosolve_weights`はそれぞれ同じ順序で対応する。
+#[derive(Debug, FromRow)]
+pub struct Row {
+    pub problem_id: String,
+    pub problem_title: String,
+    pub problem_url: String,
+    pub contest_id: String,
+    pub contest_title: String,
+    pub category: String,
+    pub difficulty: Option<i32>,
+    pub difficulty_problem_ids: Vec<String>,
+    pub difficulty_weights: Vec<f64>,
+    pub categories: Vec<String>,
+    pub category_weights: Vec<f64>,
+    pub cosolve_problem_ids: Vec<String>,
+    pub cosolve_weights: Vec<f64>,
+}
+
+impl ToDocument for Row {
+    type Document = RecommendIndex;
+
+    fn to_document(self) -> Result<RecommendIndex> {
+        Ok(RecommendIndex {
+            difficulty_correlation: payloads(
+                &self.difficulty_problem_ids,
+                &self.difficulty_weights,
+            ),
+            category_correlation: payloads(&self.categories, &self.category_weights),
+            cosolve_correlation: payloads(&self.cosolve_problem_ids, &self.cosolve_weights),
+            problem_id: self.problem_id,
+            problem_title: self.problem_title,
+            problem_url: self.problem_url,
+            contest_id: self.contest_id,
+            contest_title: self.contest_title,
+            category: self.category,
+            difficulty: self.difficulty,
+        })
+    }
+}
+
+/// レコメンド用のコアのドキュメント
+///
+/// 関連の重みは`<キー>|<重み>`の形でペイロードとして索引し、検索時に`payload()`関数で取り出す。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecommendIndex {
+    pub problem_id: String,
+    pub problem_title: String,
+    pub problem_url: String,
+    pub contest_id: String,
+    pub contest_title: String,
+    pub category: String,
+    pub difficulty: Option<i32>,
+    /// この問題を推薦する、難易度の近い問題のIDと重み
+    pub difficulty_correlation: Vec<String>,
+    /// この問題を推薦するカテゴリと重み
+    pub category_correlation: Vec<String>,
+    /// この問題を推薦する、一緒に解かれている問題のIDと重み
+    pub cosolve_correlation: Vec<String>,
+}
+
+// キーと重みの組を`<キー>|<重み>`の形の文字列に変換する関数
+fn payloads(keys: &[String], weights: &[f64]) -> Vec<String> {
+    keys.iter()
+        .zip(weights.iter())
+        .map(|(key, weight)| format!("{}|{:.4}", key, weight))
+        .collect()
+}
+
+pub struct RecommendDocumentGenerator<'a> {
+    pool: &'a Pool<Postgres>,
+    save_dir: PathBuf,
+    progress: Option<ProgressReporter>,
+}
+
+impl<'a> RecommendDocumentGenerator<'a> {
+    pub fn new(pool: &'a Pool<Postgres>, save_dir: &Path) -> Self {
+        Self {
+            pool,
+            save_dir: save_dir.to_owned(),
+            progress: None,
+        }
+    }
+
+    pub async fn run(&self, format: DocumentFormat, compress: bool, workers: usize) -> Result<()> {
+        match self.clean(&self.save_dir).await {
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("failed to delete existing document: {:?}", e);
+                return Err(anyhow::anyhow!(e));
+            }
+        };
+
+        match self
+            .generate(&self.save_dir, 10000, format, compress, workers)
+            .await
+        {
+            Ok(_) => {}
+            Err(e) => {
+                tracing::error!("failed to generate document: {:?}", e);
+                return Err(anyhow::anyhow!(e));
+            }
+        };
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<'a> ReadRows<'a> for RecommendDocumentGenerator<'a> {
+    type Row = Row;
+
+    async fn read_rows(
+        &'a self,
+    ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Self::Row, sqlx::Error>> + Send + 'a>>>
+    {
+        // 問題ごとに関連を問い合わせると問題数だけ往復が発生するので、すべての問題の関連を1つのクエリでまとめて計算する。
+        //
+        // 一緒に解かれている問題の重みは、両方の問題を解いたユーザの数をそれぞれの問題を解いたユーザの数の幾何平均で割って正規化する。
+        // よく解かれている問題どうしが常に上位にならないようにするため。
+        // 組み合わせを作る前に、重みの付かない解いたユーザの少ない問題と、各ユーザの古い正解を除いておく。
+        // 組み合わせは問題IDの小さい方から数えて、数えた後で両方向に展開する。
+        //
+        // 正解470万件(2万ユーザ、6000問、ユーザごとの解いた問題の数の2乗の和が約30億)のデータでは、
+        // 絞り込みで組み合わせが約1.4億件になり、`EXPLAIN ANALYZE`で約7分(PostgreSQL 15、work_memは既定の4MB)で終わる。
+        // カテゴリ間の関連は推薦元のカテゴリから推薦先のカテゴリへの重みなので、問題のカテゴリを推薦先とする。
+        let stream = sqlx::query_as(
+            r#"
+            WITH "difficulty_pairs" AS (
+                SELECT
+                    "target"."problem_id",
+                    "source"."problem_id" AS "source_problem_id",
+                    EXP(-POWER(("source"."difficulty" - "target"."difficulty")::DOUBLE PRECISION / $1, 2)) AS "weight",
+                    ROW_NUMBER() OVER (
+                        PARTITION BY "target"."problem_id"
+                        ORDER BY ABS("source"."difficulty" - "target"."difficulty"), "source"."problem_id"
+                    ) AS "rank"
+                FROM
+                    "problems" AS "target"
+                    JOIN "problems" AS "source"
+                        ON "source"."problem_id" <> "target"."problem_id"
+                        AND ABS("source"."difficulty" - "target"."difficulty") <= $2
+            ), "difficulty_correlations" AS (
+                SELECT
+                    "problem_id",
+                    ARRAY_AGG("source_problem_id" ORDER BY "rank") AS "difficulty_problem_ids",
+                    ARRAY_AGG("weight" ORDER BY "rank") AS "difficulty_weights"
+                FROM
+                    "difficulty_pairs"
+                WHERE
+                    "rank" <= $3
+                GROUP BY
+                    "problem_id"
+            ), "category_correlations" AS (
+                SELECT
+                    "to_category" AS "category",
+                    ARRAY_AGG("from_category" ORDER BY "from_category") AS "categories",
+                    ARRAY_AGG("weight" ORDER BY "from_category") AS "category_weights"
+                FROM
+                    "category_relationships"
+                WHERE
+                    "weight" > 0
+                GROUP BY
+                    "to_category"
+            ), "solved" AS (
+                SELECT
+                    "user_id",
+                    "problem_id"
+                FROM (
+                    SELECT
+                        "submissions"."user_id",
+                        "submissions"."problem_id",
+                        ROW_NUMBER() OVER (
+                            PARTITION BY "submissions"."user_id"
+                            ORDER BY MIN("submissions"."epoch_second") DESC, "submissions"."problem_id"
+                        ) AS "rank"
+                    FROM
+                        "submissions"
+                        JOIN "solved_counts" ON "solved_counts"."problem_id" = "submissions"."problem_id"
+                    WHERE
+                        "submissions"."result" = 'AC'
+                        AND "solved_counts"."solved_count" >= $4
+                    GROUP BY
+                        "submissions"."user_id",
+                        "submissions"."problem_id"
+                ) AS "recent"
+                WHERE
+                    "rank" <= $6
+            ), "cosolved_pairs" AS (
+                SELECT
+                    "target"."problem_id",
+                    "source"."problem_id" AS "source_problem_id",
+                    COUNT(*) AS "cosolver_count"
+                FROM
+                    "solved" AS "target"
+                    JOIN "solved" AS "source"
+                        ON "source"."user_id" = "target"."user_id"
+                        AND "source"."problem_id" > "target"."problem_id"
+                GROUP BY
+                    "target"."problem_id",
+                    "source"."problem_id"
+                HAVING
+                    COUNT(*) >= $4
+            ), "cosolved" AS (
+                SELECT
+                    "problem_id",
+                    "source_problem_id",
+                    "cosolver_count"
+                FROM
+                    "cosolved_pairs"
+                UNION ALL
+                SELECT
+                    "source_problem_id",
+                    "problem_id",
+                    "cosolver_count"
+                FROM
+                    "cosolved_pairs"
+            ), "cosolve_pairs" AS (
+                SELECT
+                    "problem_id",
+                    "source_problem_id",
+                    "weight",
+                    ROW_NUMBER() OVER (
+                        PARTITION BY "problem_id"
+                        ORDER BY "weight" DESC, "source_problem_id"
+                    ) AS "rank"
+                FROM (
+                    SELECT
+                        "cosolved"."problem_id",
+                        "cosolved"."source_problem_id",
+                        "cosolved"."cosolver_count"::DOUBLE PRECISION / SQRT(
+                            ("target"."solved_count" * "source"."solved_count")::DOUBLE PRECISION
+                        ) AS "weight"
+                    FROM
+                        "cosolved"
+                        JOIN "solved_counts" AS "target" ON "target"."problem_id" = "cosolved"."problem_id"
+                        JOIN "solved_counts" AS "source" ON "source"."problem_id" = "cosolved"."source_problem_id"
+                ) AS "weighted"
+            ), "cosolve_correlations" AS (
+                SELECT
+                    "problem_id",
+                    ARRAY_AGG("source_problem_id" ORDER BY "rank") AS "cosolve_problem_ids",
+                    ARRAY_AGG("weight" ORDER BY "rank") AS "cosolve_weights"
+                FROM
+                    "cosolve_pairs"
+                WHERE
+                    "rank" <= $5
+                GROUP BY
+                    "problem_id"
+            )
+            SELECT
+                "problems"."problem_id",
+                "problems"."title" AS "problem_title",
+                "problems"."url" AS "problem_url",
+                "contests"."contest_id",
+                "contests"."title" AS "contest_title",
+                "contests"."category",
+                "problems"."difficulty",
+                COALESCE("difficulty_correlations"."difficulty_problem_ids", ARRAY[]::TEXT[]) AS "difficulty_problem_ids",
+                COALESCE("difficulty_correlations"."difficulty_weights", ARRAY[]::DOUBLE PRECISION[]) AS "difficulty_weights",
+                COALESCE("category_correlations"."categories", ARRAY[]::TEXT[]) AS "categories",
+                COALESCE("category_correlations"."category_weights", ARRAY[]::DOUBLE PRECISION[]) AS "category_weights",
+                COALESCE("cosolve_correlations"."cosolve_problem_ids", ARRAY[]::TEXT[]) AS "cosolve_problem_ids",
+                COALESCE("cosolve_correlations"."cosolve_weights", ARRAY[]::DOUBLE PRECISION[]) AS "cosolve_weights"
+            FROM
+                "problems"
+                JOIN "contests" ON "contests"."contest_id" = "problems"."contest_id"
+                LEFT JOIN "difficulty_correlations" ON "difficulty_correlations"."problem_id" = "problems"."problem_id"
+                LEFT JOIN "category_correlations" ON "category_correlations"."category" = "contests"."category"
+                LEFT JOIN "cosolve_correlations" ON "cosolve_correlations"."problem_id" = "problems"."problem_id"
+            "#,
+        )
+        .bind(DIFFICULTY_SCALE)
+        .bind(MAX_DIFFICULTY_DISTANCE)
+        .bind(MAX_DIFFICULTY_CORRELATIONS)
+        .bind(MIN_COSOLVERS)
+        .bind(MAX_COSOLVE_CORRELATIONS)
+        .bind(MAX_SOLVED_PER_USER)
+        .fetch(self.pool);
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[async_trait]
+impl<'a> GenerateDocument<'a> for RecommendDocumentGenerator<'a> {
+    fn progress(&self) -> Option<&ProgressReporter> {
+        self.progress.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_document() {
+        let row = Row {
+            problem_id: String::from("abc300_d"),
+            problem_title: String::from("D. AABCC"),
+            problem_url: String::from("https://atcoder.jp/contests/abc300/tasks/abc300_d"),
+            contest_id: String::from("abc300"),
+            contest_title: String::from("AtCoder Beginner Contest 300"),
+            category: String::from("ABC"),
+            difficulty: Some(1076),
+            difficulty_problem_ids: vec![String::from("abc299_d"), String::from("abc298_e")],
+            difficulty_weights: vec![0.99871, 0.5],
+            categories: vec![String::from("ABC"), String::from("Other Sponsored")],
+            category_weights: vec![1.0, 0.3],
+            cosolve_problem_ids: vec![String::from("abc300_c")],
+            cosolve_weights: vec![0.42],
+        };
+
+        let document = row.to_document().unwrap();
+        assert_eq!(
+            document.difficulty_correlation,
+            vec!["abc299_d|0.9987", "abc298_e|0.5000"]
+        );
+        assert_eq!(
+            document.category_correlation,
+            vec!["ABC|1.0000", "Other Sponsored|0.3000"]
+        );
+        assert_eq!(document.cosolve_correlation, vec!["abc300_c|0.4200"]);
+        assert_eq!(document.problem_id, "abc300_d");
+        assert_eq!(document.difficulty, Some(1076));
+    }
+
+    #[cfg(feature = "integration")]
+    #[tokio::test]
+    async fn test_read_rows() {
+        use crate::modules::migration::MIGRATOR;
+        use atcoder_search_libs::testing::containers::PostgresContainer;
+        use futures::TryStreamExt;
+        use sqlx::{postgres::PgPoolOptions, Executor};
+
+        let postgres = PostgresContainer::start().await;
+        let pool = PgPoolOptions::new().connect(&postgres.url()).await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        // abc001_aは11人、abc001_bは6人、arc001_cは8人が解いていて、
+        // abc001_aとabc001_bは6人、abc001_aとarc001_cは5人、abc001_bとarc001_cは0人が一緒に解いている
+        pool.execute(
+            r#"
+            INSERT INTO contests (contest_id, start_epoch_second, duration_second, title, rate_change, category)
+            VALUES
+                ('abc001', 0, 6000, 'AtCoder Beginner Contest 001', '-', 'ABC'),
+                ('arc001', 0, 6000, 'AtCoder Regular Contest 001', '-', 'ARC');
+            INSERT INTO problems (problem_id, contest_id, problem_index, name, title, url, html, difficulty)
+            VALUES
+                ('abc001_a', 'abc001', 'A', 'a', 'A. a', 'https://atcoder.jp/contests/abc001/tasks/abc001_a', '', 1000),
+                ('abc001_b', 'abc001', 'B', 'b', 'B. b', 'https://atcoder.jp/contests/abc001/tasks/abc001_b', '', 1100),
+                ('arc001_c', 'arc001', 'C', 'c', 'C. c', 'https://atcoder.jp/contests/arc001/tasks/arc001_c', '', 2500),
+                ('arc001_d', 'arc001', 'D', 'd', 'D. d', 'https://atcoder.jp/contests/arc001/tasks/arc001_d', '', NULL);
+            INSERT INTO category_relationships (from_category, to_category, weight)
+            VALUES ('ABC', 'ABC', 1.0), ('ARC', 'ABC', 0.3), ('ABC', 'ARC', 0.0);
+            INSERT INTO submissions (id, epoch_second, problem_id, contest_id, user_id, language, point, length, result)
+            SELECT ROW_NUMBER() OVER (), 0, problem_id, SPLIT_PART(problem_id, '_', 1), user_id, 'C++', 100, 1000, 'AC'
+            FROM (
+                SELECT 'user' || n AS user_id, 'abc001_a' AS problem_id FROM GENERATE_SERIES(1, 11) AS n
+                UNION ALL SELECT 'user' || n, 'abc001_b' FROM GENERATE_SERIES(1, 6) AS n
+                UNION ALL SELECT 'user' || n, 'arc001_c' FROM GENERATE_SERIES(7, 14) AS n
+                UNION ALL SELECT 'user1', 'abc001_a'
+            ) AS solved;
+            REFRESH MATERIALIZED VIEW solved_counts;
+            "#,
+        )
+        .await
+        .unwrap();
+
+        let generator = RecommendDocumentGenerator::new(&pool, Path::new("."));
+        let mut rows: Vec<Row> = generator
+            .read_rows()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        rows.sort_by(|a, b| a.problem_id.cmp(&b.problem_id));
+        let round = |weights: &[f64]| -> Vec<f64> {
+            weights
+                .iter()
+                .map(|weight| (weight * 10000.0).round() / 10000.0)
+                .collect()
+        };
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].problem_id, "abc001_a");
+        assert_eq!(rows[0].difficulty_problem_ids, vec!["abc001_b", "arc001_c"]);
+        assert_eq!(round(&rows[0].difficulty_weights), vec![0.9394, 0.0]);
+        assert_eq!(rows[0].categories, vec!["ABC", "ARC"]);
+        assert_eq!(rows[0].category_weights, vec![1.0, 0.3]);
+        assert_eq!(rows[0].cosolve_problem_ids, vec!["abc001_b", "arc001_c"]);
+        assert_eq!(round(&rows[0].cosolve_weights), vec![0.7385, 0.533]);
+
+        // 一緒に解いたユーザが少ない問題と、重みが0のカテゴリは含めない
+        assert_eq!(rows[1].problem_id, "abc001_b");
+        assert_eq!(rows[1].cosolve_problem_ids, vec!["abc001_a"]);
+        assert_eq!(rows[2].problem_id, "arc001_c");
+        assert!(rows[2].categories.is_empty());
+
+        // 難易度のない問題は難易度の近い問題を持たない
+        assert_eq!(rows[3].problem_id, "arc001_d");
+        assert!(rows[3].difficulty_problem_ids.is_empty());
+        assert!(rows[3].cosolve_problem_ids.is_empty());
+    }
+
+    #[cfg(feature = "integration")]
+    #[tokio::test]
+    async fn test_read_rows_cosolve() {
+        use crate::modules::migration::MIGRATOR;
+        use atcoder_search_libs::testing::containers::PostgresContainer;
+        use futures::TryStreamExt;
+        use sqlx::{postgres::PgPoolOptions, Executor};
+
+        let postgres = PostgresContainer::start().await;
+        let pool = PgPoolOptions::new().connect(&postgres.url()).await.unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+        // abc100_aは60人が解いている。abc100_q00からabc100_q54はそれぞれ5人から59人が解いていて、その全員がabc100_aも解いている。
+        // abc100_rは14人が解いているが、そのうちabc100_aも解いているのは4人だけ
+        pool.execute(
+            r#"
+            INSERT INTO contests (contest_id, start_epoch_second, duration_second, title, rate_change, category)
+            VALUES ('abc100', 0, 6000, 'AtCoder Beginner Contest 100', '-', 'ABC');
+            INSERT INTO problems (problem_id, contest_id, problem_index, name, title, url, html, difficulty)
+            SELECT problem_id, 'abc100', 'A', 'a', 'A. a', 'https://atcoder.jp/contests/abc100/tasks/' || problem_id, '', NULL
+            FROM (
+                SELECT 'abc100_a' AS problem_id
+                UNION ALL SELECT 'abc100_r'
+                UNION ALL SELECT 'abc100_q' || LPAD(n::TEXT, 2, '0') FROM GENERATE_SERIES(0, 54) AS n
+            ) AS problems;
+            INSERT INTO submissions (id, epoch_second, problem_id, contest_id, user_id, language, point, length, result)
+            SELECT ROW_NUMBER() OVER (), 0, problem_id, 'abc100', user_id, 'C++', 100, 1000, 'AC'
+            FROM (
+                SELECT 'user' || u AS user_id, 'abc100_a' AS problem_id FROM GENERATE_SERIES(1, 60) AS u
+                UNION ALL
+                SELECT 'user' || u, 'abc100_q' || LPAD(n::TEXT, 2, '0')
+                FROM GENERATE_SERIES(0, 54) AS n, GENERATE_SERIES(1, 5 + n) AS u
+                UNION ALL
+                SELECT 'user' || u, 'abc100_r' FROM GENERATE_SERIES(57, 70) AS u
+            ) AS solved;
+            REFRESH MATERIALIZED VIEW solved_counts;
+            "#,
+        )
+        .await
+        .unwrap();
+
+        let generator = RecommendDocumentGenerator::new(&pool, Path::new("."));
+        let rows: Vec<Row> = generator
+            .read_rows()
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        let row = rows
+            .into_iter()
+            .find(|row| row.problem_id == "abc100_a")
+            .unwrap();
+
+        // 重みの大きい順に50問だけ残り、一緒に解いたユーザが5人に満たないabc100_rは含まれない
+        let kept = (5..55).rev();
+        assert_eq!(
+            row.cosolve_problem_ids,
+            kept.clone()
+                .map(|n| format!("abc100_q{:02}", n))
+                .collect::<Vec<String>>()
+        );
+        // abc100_qNNを解いた5+NN人は全員abc100_aも解いているので、重みは(5+NN)/sqrt(60*(5+NN))
+        assert_eq!(
+            row.cosolve_weights,
+            kept.map(|n| {
+                let cosolvers = (5 + n) as f64;
+                cosolvers / (60.0 * cosolvers).sqrt()
+            })
+            .collect::<Vec<f64>>()
+        );
+    }
+}
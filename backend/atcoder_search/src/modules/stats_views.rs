@@ -0,0 +1,42 @@
+use anyhow::Result;
+use sqlx::{postgres::Postgres, Pool};
+use std::time::Instant;
+
+/// 提出の集計結果を保持するマテリアライズドビュー
+///
+/// ドキュメントを生成するたびに`submissions`テーブル全体を集計し直さないように、生成のクエリはこれらを参照する。
+pub const STATS_VIEWS: [&str; 2] = ["solved_counts", "problem_stats"];
+
+/// 提出の集計結果のマテリアライズドビューを更新する構造体
+#[derive(Debug, Clone)]
+pub struct StatsViews {
+    pool: Pool<Postgres>,
+}
+
+impl StatsViews {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// 全てのビューを集計し直すメソッド
+    ///
+    /// 一意なインデックスを持つビューなので、更新中も古い集計結果を読めるようにCONCURRENTLYで更新する。
+    pub async fn refresh(&self) -> Result<()> {
+        for view in STATS_VIEWS {
+            let start = Instant::now();
+            sqlx::query(&format!(
+                r#"REFRESH MATERIALIZED VIEW CONCURRENTLY "{}";"#,
+                view
+            ))
+            .execute(&self.pool)
+            .await?;
+            tracing::info!(
+                "materialized view {} has been refreshed in {} ms",
+                view,
+                start.elapsed().as_millis()
+            );
+        }
+
+        Ok(())
+    }
+}
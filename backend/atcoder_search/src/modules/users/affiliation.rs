@@ -0,0 +1,202 @@
+use anyhow::Result;
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+// 表記の揺れとして無視する記号
+const IGNORED_PUNCTUATIONS: [char; 5] = ['.', ',', '\'', '’', '・'];
+
+/// `affiliation_aliases`テーブルの行
+///
+/// `alias`には[`alias_key`]で正規化した所属の表記を保存する。
+#[derive(Debug, Clone, FromRow, PartialEq, Eq)]
+pub struct AffiliationAlias {
+    pub alias: String,
+    pub affiliation: String,
+}
+
+/// 所属の表記の揺れを吸収して照合するためのキーを作る関数
+///
+/// NFKC正規化で全角と半角の違いを、小文字化で大文字と小文字の違いをなくし、記号を除いて空白をまとめる。
+pub fn alias_key(affiliation: &str) -> String {
+    affiliation
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .map(|c| {
+            if IGNORED_PUNCTUATIONS.contains(&c) {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// 対応付けがない所属は、NFKC正規化して空白をまとめた表記にする
+fn clean(affiliation: &str) -> String {
+    affiliation
+        .nfkc()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 所属の表記を対応付けに従って正規化する構造体
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AffiliationNormalizer {
+    aliases: HashMap<String, String>,
+}
+
+impl AffiliationNormalizer {
+    pub fn new(aliases: impl IntoIterator<Item = AffiliationAlias>) -> Self {
+        Self {
+            aliases: aliases
+                .into_iter()
+                .map(|alias| (alias_key(&alias.alias), alias.affiliation))
+                .collect(),
+        }
+    }
+
+    /// 所属の正規化した表記を返すメソッド。空の所属は`None`になる
+    pub fn normalize(&self, affiliation: &str) -> Option<String> {
+        let key = alias_key(affiliation);
+        if key.is_empty() {
+            return None;
+        }
+        match self.aliases.get(&key) {
+            Some(normalized) => Some(normalized.clone()),
+            None => Some(clean(affiliation)),
+        }
+    }
+}
+
+/// 所属の表記の対応付けを保存する`affiliation_aliases`テーブルへのアクセスを提供する構造体
+#[derive(Debug, Clone)]
+pub struct AffiliationAliasStore {
+    pool: Pool<Postgres>,
+}
+
+impl AffiliationAliasStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// 全ての対応付けを正規化後の表記の順に取得するメソッド
+    pub async fn list(&self) -> Result<Vec<AffiliationAlias>> {
+        let aliases = sqlx::query_as(
+            "
+            SELECT
+                alias,
+                affiliation
+            FROM
+                affiliation_aliases
+            ORDER BY
+                affiliation,
+                alias;
+            ",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(aliases)
+    }
+
+    /// `alias`の表記を`affiliation`に対応付けるメソッド。既に対応付けがあれば置き換える
+    pub async fn add(&self, alias: &str, affiliation: &str) -> Result<AffiliationAlias> {
+        let key = alias_key(alias);
+        if key.is_empty() {
+            anyhow::bail!("alias must not be empty");
+        }
+        let affiliation = clean(affiliation);
+        if affiliation.is_empty() {
+            anyhow::bail!("affiliation must not be empty");
+        }
+
+        let alias = sqlx::query_as(
+            "
+            INSERT INTO affiliation_aliases (alias, affiliation)
+            VALUES ($1, $2)
+            ON CONFLICT (alias) DO UPDATE SET
+                affiliation = EXCLUDED.affiliation
+            RETURNING
+                alias,
+                affiliation;
+            ",
+        )
+        .bind(key)
+        .bind(affiliation)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(alias)
+    }
+
+    /// `alias`の表記の対応付けを削除するメソッド。削除した場合は`true`を返す
+    pub async fn remove(&self, alias: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM affiliation_aliases WHERE alias = $1;")
+            .bind(alias_key(alias))
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 保存されている対応付けで正規化する[`AffiliationNormalizer`]を作るメソッド
+    pub async fn normalizer(&self) -> Result<AffiliationNormalizer> {
+        Ok(AffiliationNormalizer::new(self.list().await?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_alias_key() {
+        assert_eq!(alias_key("Univ. of Tokyo"), "univ of tokyo");
+        assert_eq!(alias_key("  UNIV  OF\tTOKYO "), "univ of tokyo");
+        assert_eq!(alias_key("東京大学"), "東京大学");
+        assert_eq!(alias_key("ＡｔＣｏｄｅｒ"), "atcoder");
+        assert_eq!(alias_key(" . "), "");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalizer = AffiliationNormalizer::new([
+            AffiliationAlias {
+                alias: String::from("univ of tokyo"),
+                affiliation: String::from("東京大学"),
+            },
+            AffiliationAlias {
+                alias: String::from("The University of Tokyo"),
+                affiliation: String::from("東京大学"),
+            },
+        ]);
+
+        assert_eq!(
+            normalizer.normalize("Univ. of Tokyo").as_deref(),
+            Some("東京大学")
+        );
+        assert_eq!(
+            normalizer.normalize("the university of tokyo").as_deref(),
+            Some("東京大学")
+        );
+        assert_eq!(
+            normalizer.normalize("東京大学").as_deref(),
+            Some("東京大学")
+        );
+        assert_eq!(
+            normalizer.normalize(" Kyoto  University ").as_deref(),
+            Some("Kyoto University")
+        );
+        assert_eq!(
+            normalizer.normalize("ＡｔＣｏｄｅｒ").as_deref(),
+            Some("AtCoder")
+        );
+        assert_eq!(normalizer.normalize(" "), None);
+    }
+}
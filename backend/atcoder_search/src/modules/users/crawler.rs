@@ -1,4 +1,7 @@
-use crate::{modules::users::scraper::RankingPageScraper, types::tables::User};
+use crate::{
+    modules::{crawl_runs::CrawlStats, users::scraper::RankingPageScraper},
+    types::tables::User,
+};
 use anyhow::Result;
 use once_cell::sync::Lazy;
 use reqwest::Client;
@@ -6,7 +9,7 @@ use reqwest::Url;
 use sqlx::{self, postgres::Postgres, Pool};
 use tokio::time::{self, Duration};
 
-static SCRAPER: Lazy<RankingPageScraper> = Lazy::new(|| RankingPageScraper::new());
+static SCRAPER: Lazy<RankingPageScraper> = Lazy::new(RankingPageScraper::new);
 
 pub struct UserCrawler<'a> {
     url: Url,
@@ -58,12 +61,10 @@ impl<'a> UserCrawler<'a> {
         ))
     }
 
-    pub async fn save(&self, users: &Vec<User>) -> Result<()> {
-        let first = users
-            .first()
-            .and_then(|first| Some(first.rank))
-            .unwrap_or(0);
-        let last = users.last().and_then(|last| Some(last.rank)).unwrap_or(0);
+    /// ユーザ情報を保存し、新しく保存したユーザと更新したユーザの数を返すメソッド
+    pub async fn save(&self, users: &[User]) -> Result<CrawlStats> {
+        let first = users.first().map(|first| first.rank).unwrap_or(0);
+        let last = users.last().map(|last| last.rank).unwrap_or(0);
         tracing::info!("Start to save user information from {} to {}.", first, last);
 
         let mut tx = match self.pool.begin().await {
@@ -75,6 +76,16 @@ impl<'a> UserCrawler<'a> {
             }
         };
 
+        let user_names = users
+            .iter()
+            .map(|user| user.user_name.as_str())
+            .collect::<Vec<_>>();
+        let updated: i64 =
+            sqlx::query_scalar(r#"SELECT COUNT(*) FROM "users" WHERE "user_name" = ANY($1);"#)
+                .bind(&user_names)
+                .fetch_one(&mut tx)
+                .await?;
+
         for user in users.iter() {
             let result = sqlx::query(
                 r#"
@@ -157,15 +168,15 @@ impl<'a> UserCrawler<'a> {
                 "#,
             )
             .bind(&user.user_name)
-            .bind(&user.rating)
-            .bind(&user.highest_rating)
+            .bind(user.rating)
+            .bind(user.highest_rating)
             .bind(&user.affiliation)
-            .bind(&user.birth_year)
+            .bind(user.birth_year)
             .bind(&user.country)
             .bind(&user.crown)
-            .bind(&user.join_count)
-            .bind(&user.rank)
-            .bind(&user.wins)
+            .bind(user.join_count)
+            .bind(user.rank)
+            .bind(user.wins)
             .execute(&mut tx)
             .await;
 
@@ -182,25 +193,39 @@ impl<'a> UserCrawler<'a> {
         tx.commit().await?;
         tracing::info!("Users from {} to {} successfully saved.", first, last);
 
-        Ok(())
+        Ok(CrawlStats {
+            fetched: users.len() as i32,
+            inserted: users.len() as i32 - updated as i32,
+            updated: updated as i32,
+            errors: 0,
+        })
+    }
+
+    /// 保存済みのユーザ数を返すメソッド。ランキングのクロールではこれらのユーザの情報が更新される
+    pub async fn stored_users(&self) -> Result<i64> {
+        let count = sqlx::query_scalar(r#"SELECT COUNT(*) FROM "users";"#)
+            .fetch_one(self.pool)
+            .await?;
+        Ok(count)
     }
 
-    pub async fn crawl(&self) -> Result<()> {
+    pub async fn crawl(&self) -> Result<CrawlStats> {
         tracing::info!("Start to crawl active user information");
 
+        let mut stats = CrawlStats::default();
         let mut i = 994;
         while let Ok(users) = self.fetch_page(i).await {
             if users.is_empty() {
                 break;
             }
             tracing::info!("Crawl ranking page {}", i);
-            self.save(&users).await?;
+            stats += self.save(&users).await?;
 
             time::sleep(Duration::from_secs(1)).await;
             i += 1;
         }
 
         tracing::info!("Finish crawling active user information");
-        Ok(())
+        Ok(stats)
     }
 }
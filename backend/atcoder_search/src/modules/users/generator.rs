@@ -1,30 +1,63 @@
-use crate::types::tables::User;
+use crate::{
+    modules::users::{
+        affiliation::{AffiliationAliasStore, AffiliationNormalizer},
+        rating_history::RatingTrend,
+    },
+    types::tables::User,
+};
 use anyhow::Result;
 use async_trait::async_trait;
-use atcoder_search_libs::{GenerateDocument, ReadRows, ToDocument};
+use atcoder_search_libs::{
+    color::Color, kana::to_reading, solr::model::SolrSchemaField, DocumentFormat, GenerateDocument,
+    ProgressReporter, ReadRows, SolrSchema, ToDocument,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::Postgres, Pool};
+use sqlx::{postgres::Postgres, FromRow, Pool};
 use std::path::{Path, PathBuf};
 use tokio::macros::support::Pin;
-use tokio_stream::Stream;
-
-fn rate_to_color(rate: i32) -> String {
-    match rate {
-        0..=399 => "gray",
-        400..=799 => "brown",
-        800..=1199 => "green",
-        1200..=1599 => "cyan",
-        1600..=1999 => "blue",
-        2000..=2399 => "yellow",
-        2400..=2799 => "orange",
-        2800..=3199 => "red",
-        3200..=3599 => "silver",
-        _ => "gold",
+use tokio_stream::{Stream, StreamExt};
+
+/// ドキュメントの元になるユーザと、正規化した所属・コンテスト成績
+#[derive(Debug, FromRow)]
+pub struct UserRow {
+    #[sqlx(flatten)]
+    pub user: User,
+    /// Ratedなコンテストの終了時刻(昇順)
+    pub end_times: Vec<DateTime<Utc>>,
+    pub old_ratings: Vec<i32>,
+    pub new_ratings: Vec<i32>,
+    #[sqlx(default)]
+    pub affiliation_normalized: Option<String>,
+}
+
+impl UserRow {
+    fn normalize_affiliation(self, normalizer: &AffiliationNormalizer) -> Self {
+        let affiliation_normalized = self
+            .user
+            .affiliation
+            .as_deref()
+            .and_then(|affiliation| normalizer.normalize(affiliation));
+        Self {
+            affiliation_normalized,
+            ..self
+        }
+    }
+
+    /// `now`の時点でのレーティングの推移の傾向を計算するメソッド
+    fn trend(&self, now: DateTime<Utc>) -> RatingTrend {
+        let changes = self
+            .end_times
+            .iter()
+            .zip(self.old_ratings.iter())
+            .zip(self.new_ratings.iter())
+            .map(|((end_time, old_rating), new_rating)| (*end_time, *old_rating, *new_rating))
+            .collect::<Vec<_>>();
+        RatingTrend::compute(&changes, now)
     }
-    .to_string()
 }
 
-impl ToDocument for User {
+impl ToDocument for UserRow {
     type Document = UserIndex;
 
     fn to_document(self) -> Result<UserIndex> {
@@ -32,40 +65,53 @@ impl ToDocument for User {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, SolrSchema)]
 pub struct UserIndex {
+    #[solr(field_type = "TextUniGram", doc_values = false)]
     pub user_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[solr(field_type = "TextUniGram", doc_values = false)]
+    pub user_name_reading: Option<String>,
     pub rating: i32,
     pub color: String,
     pub highest_rating: i32,
     pub highest_color: String,
     pub affiliation: Option<String>,
+    pub affiliation_normalized: Option<String>,
     pub birth_year: Option<i32>,
     pub country: Option<String>,
     pub crown: Option<String>,
     pub join_count: i32,
     pub rank: i32,
     pub wins: i32,
+    pub rating_delta_3m: Option<i32>,
+    pub max_streak: i32,
 }
 
-impl From<User> for UserIndex {
-    fn from(value: User) -> Self {
-        let color = rate_to_color(value.rating);
-        let highest_color = rate_to_color(value.highest_rating);
+impl From<UserRow> for UserIndex {
+    fn from(row: UserRow) -> Self {
+        let trend = row.trend(Utc::now());
+        let value = row.user;
+        let color = Color::from_rating(value.rating).to_string();
+        let highest_color = Color::from_rating(value.highest_rating).to_string();
 
         Self {
+            user_name_reading: to_reading(&value.user_name),
             user_name: value.user_name,
             rating: value.rating,
             color,
             highest_rating: value.highest_rating,
             highest_color,
             affiliation: value.affiliation,
+            affiliation_normalized: row.affiliation_normalized,
             birth_year: value.birth_year,
             country: value.country,
             crown: value.crown,
             join_count: value.join_count,
             rank: value.rank,
             wins: value.wins,
+            rating_delta_3m: trend.rating_delta_3m,
+            max_streak: trend.max_streak,
         }
     }
 }
@@ -73,6 +119,7 @@ impl From<User> for UserIndex {
 pub struct UserDocumentGenerator<'a> {
     pool: &'a Pool<Postgres>,
     save_dir: PathBuf,
+    progress: Option<ProgressReporter>,
 }
 
 impl<'a> UserDocumentGenerator<'a> {
@@ -80,10 +127,16 @@ impl<'a> UserDocumentGenerator<'a> {
         Self {
             pool,
             save_dir: save_dir.to_owned(),
+            progress: None,
         }
     }
 
-    pub async fn run(&self) -> Result<()> {
+    /// 生成の進捗を`progress`へ通知する
+    pub fn with_progress(self, progress: Option<ProgressReporter>) -> Self {
+        Self { progress, ..self }
+    }
+
+    pub async fn run(&self, format: DocumentFormat, compress: bool, workers: usize) -> Result<()> {
         match self.clean(&self.save_dir).await {
             Ok(_) => {}
             Err(e) => {
@@ -92,7 +145,10 @@ impl<'a> UserDocumentGenerator<'a> {
             }
         };
 
-        match self.generate(&self.save_dir, 10000).await {
+        match self
+            .generate(&self.save_dir, 10000, format, compress, workers)
+            .await
+        {
             Ok(_) => {}
             Err(e) => {
                 tracing::error!("failed to generate document: {:?}", e);
@@ -106,34 +162,57 @@ impl<'a> UserDocumentGenerator<'a> {
 
 #[async_trait]
 impl<'a> ReadRows<'a> for UserDocumentGenerator<'a> {
-    type Row = User;
+    type Row = UserRow;
 
     async fn read_rows(
         &'a self,
     ) -> Result<Pin<Box<dyn Stream<Item = std::result::Result<Self::Row, sqlx::Error>> + Send + 'a>>>
     {
-        let stream = sqlx::query_as(
+        // 所属の表記の対応付けは件数が少ないので、先に全て読み込んでおく
+        let normalizer = AffiliationAliasStore::new(self.pool.clone())
+            .normalizer()
+            .await?;
+        let stream = sqlx::query_as::<_, UserRow>(
             r#"
             SELECT
-                "user_name",
-                "rating",
-                "highest_rating",
-                "affiliation",
-                "birth_year",
-                "country",
-                "crown",
-                "join_count",
-                "rank",
-                "wins"
+                "users"."user_name",
+                "users"."rating",
+                "users"."highest_rating",
+                "users"."affiliation",
+                "users"."birth_year",
+                "users"."country",
+                "users"."crown",
+                "users"."join_count",
+                "users"."rank",
+                "users"."wins",
+                COALESCE("histories"."end_times", '{}') AS "end_times",
+                COALESCE("histories"."old_ratings", '{}') AS "old_ratings",
+                COALESCE("histories"."new_ratings", '{}') AS "new_ratings"
             FROM
                 "users"
+                LEFT JOIN (
+                    SELECT
+                        "user_name",
+                        ARRAY_AGG("end_time" ORDER BY "end_time") AS "end_times",
+                        ARRAY_AGG("old_rating" ORDER BY "end_time") AS "old_ratings",
+                        ARRAY_AGG("new_rating" ORDER BY "end_time") AS "new_ratings"
+                    FROM
+                        "rating_histories"
+                    GROUP BY
+                        "user_name"
+                ) AS "histories" USING ("user_name")
             "#,
         )
-        .fetch(self.pool);
+        .fetch(self.pool)
+        .map(move |row| row.map(|row| row.normalize_affiliation(&normalizer)));
 
-        Ok(stream)
+        Ok(Box::pin(stream))
     }
 }
 
 #[async_trait]
-impl<'a> GenerateDocument<'a> for UserDocumentGenerator<'a> {}
+impl<'a> GenerateDocument<'a> for UserDocumentGenerator<'a> {
+    fn progress(&self) -> Option<&ProgressReporter> {
+        self.progress.as_ref()
+    }
+}
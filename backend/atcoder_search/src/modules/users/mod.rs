@@ -1,3 +1,6 @@
+pub mod affiliation;
 pub mod crawler;
 pub mod generator;
+pub mod rating_history;
 pub mod scraper;
+pub mod submissions;
@@ -0,0 +1,314 @@
+use crate::modules::crawl_runs::CrawlStats;
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, FixedOffset, Utc};
+use reqwest::Client;
+use serde::Deserialize;
+use sqlx::{postgres::Postgres, Pool};
+use tokio::time::{self, Duration};
+
+// 直近のレーティングの変化を集計する期間の日数
+const TREND_WINDOW_DAYS: i64 = 90;
+
+/// AtCoderのユーザページの`/history/json`が返すコンテスト成績の1件
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct RatingHistory {
+    pub is_rated: bool,
+    pub place: i32,
+    pub old_rating: i32,
+    pub new_rating: i32,
+    pub performance: i32,
+    /// `abc300.contest.atcoder.jp`のような、コンテストのホスト名
+    pub contest_screen_name: String,
+    pub end_time: DateTime<FixedOffset>,
+}
+
+impl RatingHistory {
+    /// `contest_screen_name`からコンテストIDを取り出すメソッド
+    pub fn contest_id(&self) -> &str {
+        self.contest_screen_name
+            .split_once('.')
+            .map(|(contest_id, _)| contest_id)
+            .unwrap_or(&self.contest_screen_name)
+    }
+}
+
+/// レーティングの推移の傾向を表す値
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatingTrend {
+    /// 直近90日間のレーティングの変化量。90日より前に参加したコンテストがなければ`None`
+    pub rating_delta_3m: Option<i32>,
+    /// レーティングが連続して上がったコンテスト数の最大値
+    pub max_streak: i32,
+}
+
+impl RatingTrend {
+    /// コンテストの終了時刻の順に並んだレーティングの変化から傾向を計算する関数
+    ///
+    /// `changes`の各要素は`(終了時刻, 変化前のレーティング, 変化後のレーティング)`の組。
+    pub fn compute(changes: &[(DateTime<Utc>, i32, i32)], now: DateTime<Utc>) -> Self {
+        let since = now - ChronoDuration::days(TREND_WINDOW_DAYS);
+
+        let rating_delta_3m = changes
+            .iter()
+            .take_while(|(end_time, _, _)| *end_time < since)
+            .last()
+            .zip(changes.last())
+            .map(|((_, _, before), (_, _, latest))| latest - before);
+
+        let mut max_streak = 0;
+        let mut streak = 0;
+        for (_, old_rating, new_rating) in changes.iter() {
+            if new_rating > old_rating {
+                streak += 1;
+                max_streak = max_streak.max(streak);
+            } else {
+                streak = 0;
+            }
+        }
+
+        Self {
+            rating_delta_3m,
+            max_streak,
+        }
+    }
+}
+
+/// ユーザごとのコンテスト成績を取得して`rating_histories`テーブルへ保存する構造体
+pub struct RatingHistoryCrawler<'a> {
+    pool: &'a Pool<Postgres>,
+    client: Client,
+}
+
+impl<'a> RatingHistoryCrawler<'a> {
+    pub fn new(pool: &'a Pool<Postgres>) -> Self {
+        RatingHistoryCrawler {
+            pool,
+            client: Client::builder()
+                .gzip(true)
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// 成績を取得し直す必要があるユーザ名を取得するメソッド
+    ///
+    /// 参加数と保存済みのRatedな成績の数が一致しないユーザだけを、参加数の多い順に対象にする。
+    pub async fn targets(&self, limit: Option<i64>) -> Result<Vec<String>> {
+        let user_names = sqlx::query_scalar(
+            r#"
+            SELECT
+                "users"."user_name"
+            FROM
+                "users"
+                LEFT JOIN (
+                    SELECT
+                        "user_name",
+                        COUNT(*) AS "count"
+                    FROM
+                        "rating_histories"
+                    GROUP BY
+                        "user_name"
+                ) AS "histories" USING ("user_name")
+            WHERE
+                "users"."join_count" <> COALESCE("histories"."count", 0)
+            ORDER BY
+                "users"."join_count" DESC,
+                "users"."user_name" ASC
+            LIMIT $1;
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.pool)
+        .await?;
+
+        Ok(user_names)
+    }
+
+    /// `user_name`のユーザのコンテスト成績を取得するメソッド
+    pub async fn fetch_history(&self, user_name: &str) -> Result<Vec<RatingHistory>> {
+        let url = format!("https://atcoder.jp/users/{}/history/json", user_name);
+        let res = self.client.get(url).send().await?;
+
+        match res.error_for_status_ref() {
+            Ok(_) => {}
+            Err(e) => {
+                let message = format!(
+                    "error response returned from AtCoder rating history of {}: {:?}",
+                    user_name, e
+                );
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        };
+
+        let histories: Vec<RatingHistory> = res.json().await?;
+        Ok(histories)
+    }
+
+    /// `user_name`のユーザのRatedなコンテスト成績を保存し、新しく保存した成績と更新した成績の数を返すメソッド
+    pub async fn save(&self, user_name: &str, histories: &[RatingHistory]) -> Result<CrawlStats> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                let message = format!("failed to start transaction cause: {:?}", e);
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        };
+
+        let mut stats = CrawlStats::default();
+        for history in histories.iter().filter(|history| history.is_rated) {
+            // 挿入された行はxmaxが0になるので、挿入と更新を区別できる
+            let result = sqlx::query_scalar::<_, bool>(
+                r#"
+                INSERT INTO "rating_histories" (
+                    "user_name",
+                    "contest_id",
+                    "end_time",
+                    "place",
+                    "old_rating",
+                    "new_rating",
+                    "performance"
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT ("user_name", "contest_id") DO UPDATE SET
+                    "end_time" = EXCLUDED."end_time",
+                    "place" = EXCLUDED."place",
+                    "old_rating" = EXCLUDED."old_rating",
+                    "new_rating" = EXCLUDED."new_rating",
+                    "performance" = EXCLUDED."performance"
+                RETURNING ("xmax" = 0);
+                "#,
+            )
+            .bind(user_name)
+            .bind(history.contest_id())
+            .bind(history.end_time.with_timezone(&Utc))
+            .bind(history.place)
+            .bind(history.old_rating)
+            .bind(history.new_rating)
+            .bind(history.performance)
+            .fetch_one(&mut tx)
+            .await;
+
+            stats.fetched += 1;
+            match result {
+                Ok(true) => stats.inserted += 1,
+                Ok(false) => stats.updated += 1,
+                // エラーが発生したらトランザクションをロールバックしてエラーを早期リターンする
+                Err(e) => {
+                    let message = format!(
+                        "an error occurred: {:?}, at saving rating history of {} in {}",
+                        e,
+                        user_name,
+                        history.contest_id()
+                    );
+                    tracing::error!(message);
+                    tx.rollback().await?;
+
+                    anyhow::bail!(message);
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(stats)
+    }
+
+    /// 成績を取得し直す必要があるユーザのコンテスト成績を、`duration`の間隔を空けて収集するメソッド
+    ///
+    /// 取得に失敗したユーザは警告を出して読み飛ばし、次回のクロールで再び対象にする。
+    pub async fn crawl(&self, limit: Option<i64>, duration: Duration) -> Result<CrawlStats> {
+        let targets = self.targets(limit).await?;
+        tracing::info!("Start to crawl rating histories of {} users", targets.len());
+
+        let mut stats = CrawlStats::default();
+        for user_name in targets.iter() {
+            match self.fetch_history(user_name).await {
+                Ok(histories) => {
+                    stats += self.save(user_name, &histories).await?;
+                    tracing::info!("Rating history of {} successfully saved.", user_name);
+                }
+                Err(e) => {
+                    stats.errors += 1;
+                    tracing::warn!(
+                        "failed to fetch rating history of {} cause: {:?}",
+                        user_name,
+                        e
+                    );
+                }
+            }
+
+            time::sleep(duration).await;
+        }
+
+        tracing::info!("Finish crawling rating histories");
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_deserialize_history() {
+        let histories: Vec<RatingHistory> = serde_json::from_str(
+            r#"[{"IsRated":true,"Place":120,"OldRating":1500,"NewRating":1580,"Performance":1900,"InnerPerformance":1900,"ContestScreenName":"abc300.contest.atcoder.jp","ContestName":"AtCoder Beginner Contest 300","ContestNameEn":"","EndTime":"2023-04-29T22:40:00+09:00"}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(histories.len(), 1);
+        assert_eq!(histories[0].contest_id(), "abc300");
+        assert_eq!(
+            histories[0].end_time.with_timezone(&Utc),
+            Utc.with_ymd_and_hms(2023, 4, 29, 13, 40, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compute_trend() {
+        let now = date(2023, 9, 1);
+        let changes = vec![
+            (date(2023, 1, 1), 0, 400),
+            (date(2023, 2, 1), 400, 800),
+            (date(2023, 3, 1), 800, 700),
+            (date(2023, 7, 1), 700, 900),
+            (date(2023, 8, 1), 900, 1000),
+            (date(2023, 8, 15), 1000, 1100),
+        ];
+        assert_eq!(
+            RatingTrend::compute(&changes, now),
+            RatingTrend {
+                rating_delta_3m: Some(400),
+                max_streak: 3,
+            }
+        );
+
+        // 90日より前のコンテストに参加していなければ変化量は計算しない
+        assert_eq!(
+            RatingTrend::compute(&changes[3..], now),
+            RatingTrend {
+                rating_delta_3m: None,
+                max_streak: 3,
+            }
+        );
+
+        // 直近90日間のコンテストに参加していなければ変化量は0
+        assert_eq!(
+            RatingTrend::compute(&changes[..3], now),
+            RatingTrend {
+                rating_delta_3m: Some(0),
+                max_streak: 2,
+            }
+        );
+
+        assert_eq!(RatingTrend::compute(&[], now), RatingTrend::default());
+    }
+}
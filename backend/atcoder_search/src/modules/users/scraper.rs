@@ -51,7 +51,7 @@ impl RankingPageScraper {
             let td: Vec<ElementRef<'_>> = tr.select(&self.td).collect();
 
             let rank: i32 = td
-                .get(0)
+                .first()
                 .and_then(|elem| elem.text().next())
                 .and_then(|text| text.parse::<i32>().ok())
                 .unwrap_or_else(|| {
@@ -60,20 +60,20 @@ impl RankingPageScraper {
                 });
             let (country, user_name, affiliation, crown) = td
                 .get(1)
-                .and_then(|td_1| {
+                .map(|td_1| {
                     let a: Vec<ElementRef<'_>> = td_1.select(&self.td_a).collect();
                     let country = a
-                        .get(0)
+                        .first()
                         .and_then(|a| a.select(&self.a_img).next())
                         .and_then(|img| img.value().attr("src"))
                         .and_then(|src| Path::new(src).file_stem())
                         .and_then(|stem| stem.to_str())
-                        .and_then(|country| Some(country.to_string()));
+                        .map(|country| country.to_string());
                     let user_name = a
                         .get(1)
                         .and_then(|a| a.select(&self.a_span).next())
                         .and_then(|span| span.text().next())
-                        .and_then(|text| Some(text.to_string()))
+                        .map(|text| text.to_string())
                         .unwrap_or_else(|| {
                             tracing::warn!("failed to extract user name at {}", i);
                             String::default()
@@ -82,16 +82,16 @@ impl RankingPageScraper {
                         .get(2)
                         .and_then(|a| a.select(&self.a_span).next())
                         .and_then(|span| span.text().next())
-                        .and_then(|text| Some(text.to_string()));
+                        .map(|text| text.to_string());
                     let crown = td_1
                         .select(&self.td_img)
                         .next()
                         .and_then(|img| img.value().attr("src"))
                         .and_then(|src| Path::new(src).file_stem())
                         .and_then(|stem| stem.to_str())
-                        .and_then(|crown| Some(crown.to_string()));
+                        .map(|crown| crown.to_string());
 
-                    Some((country, user_name, affiliation, crown))
+                    (country, user_name, affiliation, crown)
                 })
                 .unwrap_or_else(|| {
                     tracing::warn!(
@@ -0,0 +1,349 @@
+use crate::modules::crawl_runs::CrawlStats;
+use anyhow::Result;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::Postgres, FromRow, Pool};
+use std::collections::HashMap;
+use tokio::time::{self, Duration};
+use utoipa::ToSchema;
+
+// 正解した提出のジャッジ結果
+const ACCEPTED: &str = "AC";
+
+// AtCoder Problemsの提出APIが1回に返す提出の数の上限
+const SUBMISSIONS_PER_PAGE: usize = 1000;
+
+/// ユーザがその問題を解いたかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SolveStatus {
+    /// The user has an accepted submission
+    Solved,
+    /// The user has submissions but none of them is accepted
+    Attempted,
+}
+
+/// 問題ごとの提出の集計結果
+#[derive(Debug, FromRow)]
+struct SubmissionSummary {
+    problem_id: String,
+    solved: bool,
+}
+
+/// `submissions`テーブルからユーザの問題ごとの提出状況を取得する構造体
+#[derive(Debug, Clone)]
+pub struct SubmissionStore {
+    pool: Pool<Postgres>,
+}
+
+impl SubmissionStore {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// `problem_ids`の問題それぞれについて`user_id`のユーザの提出状況を1回のクエリでまとめて取得するメソッド
+    ///
+    /// 提出していない問題は結果に含まれない。
+    pub async fn statuses(
+        &self,
+        user_id: &str,
+        problem_ids: &[&str],
+    ) -> Result<HashMap<String, SolveStatus>> {
+        if problem_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let summaries: Vec<SubmissionSummary> = sqlx::query_as(
+            "
+            SELECT
+                problem_id,
+                BOOL_OR(result = $3) AS solved
+            FROM
+                submissions
+            WHERE
+                user_id = $1
+                AND problem_id = ANY($2)
+            GROUP BY
+                problem_id;
+            ",
+        )
+        .bind(user_id)
+        .bind(problem_ids)
+        .bind(ACCEPTED)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries
+            .into_iter()
+            .map(|summary| {
+                let status = if summary.solved {
+                    SolveStatus::Solved
+                } else {
+                    SolveStatus::Attempted
+                };
+                (summary.problem_id, status)
+            })
+            .collect())
+    }
+
+    /// `user_id`のユーザが正解した問題のIDを取得するメソッド
+    pub async fn solved_problem_ids(&self, user_id: &str) -> Result<Vec<String>> {
+        let problem_ids = sqlx::query_scalar(
+            "
+            SELECT DISTINCT
+                problem_id
+            FROM
+                submissions
+            WHERE
+                user_id = $1
+                AND result = $2
+            ORDER BY
+                problem_id;
+            ",
+        )
+        .bind(user_id)
+        .bind(ACCEPTED)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(problem_ids)
+    }
+}
+
+/// AtCoder Problemsの提出APIが返す提出の1件
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SubmissionJson {
+    pub id: i64,
+    pub epoch_second: i64,
+    pub problem_id: String,
+    pub contest_id: String,
+    pub user_id: String,
+    pub language: String,
+    pub point: f64,
+    pub length: i32,
+    pub result: String,
+    pub execution_time: Option<i32>,
+}
+
+// 次に取得を始める時刻を決める関数
+//
+// 同じ時刻の提出がページをまたぐことがあるので、最後の提出の時刻から取得し直す。
+// ページ全体が同じ時刻の提出で埋まっているときは、同じページを取得し続けないよう1秒進める。
+fn next_from_second(submissions: &[SubmissionJson], from_second: i64) -> i64 {
+    let latest = submissions
+        .iter()
+        .map(|submission| submission.epoch_second)
+        .max()
+        .unwrap_or(from_second);
+    if latest <= from_second {
+        from_second + 1
+    } else {
+        latest
+    }
+}
+
+/// AtCoder Problemsから提出を取得して`submissions`テーブルへ保存する構造体
+pub struct SubmissionCrawler<'a> {
+    url: Url,
+    pool: &'a Pool<Postgres>,
+    client: Client,
+}
+
+impl<'a> SubmissionCrawler<'a> {
+    pub fn new(pool: &'a Pool<Postgres>) -> Self {
+        SubmissionCrawler {
+            url: Url::parse("https://kenkoooo.com/atcoder/atcoder-api/v3/from/").unwrap(),
+            pool,
+            client: Client::builder()
+                .gzip(true)
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// 保存済みの提出のうち最も新しいものの時刻を取得するメソッド
+    ///
+    /// 提出が1件も保存されていなければ0を返す。
+    pub async fn latest_epoch_second(&self) -> Result<i64> {
+        let epoch_second: Option<i64> =
+            sqlx::query_scalar(r#"SELECT MAX("epoch_second") FROM "submissions";"#)
+                .fetch_one(self.pool)
+                .await?;
+
+        Ok(epoch_second.unwrap_or(0))
+    }
+
+    /// `from_second`以降の提出を古い順に最大1000件取得するメソッド
+    pub async fn fetch_submissions(&self, from_second: i64) -> Result<Vec<SubmissionJson>> {
+        let url = self.url.join(&from_second.to_string())?;
+        let res = self.client.get(url).send().await?;
+
+        match res.error_for_status_ref() {
+            Ok(_) => {}
+            Err(e) => {
+                let message = format!(
+                    "error response returned from AtCoder Problems submissions from {}: {:?}",
+                    from_second, e
+                );
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        };
+
+        let submissions: Vec<SubmissionJson> = res.json().await?;
+        Ok(submissions)
+    }
+
+    /// 提出を保存し、新しく保存した提出と更新した提出の数を返すメソッド
+    ///
+    /// ジャッジ中の提出は後で結果が変わるので、既に保存されている提出も上書きする。
+    pub async fn save(&self, submissions: &[SubmissionJson]) -> Result<CrawlStats> {
+        let mut tx = match self.pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                let message = format!("failed to start transaction cause: {:?}", e);
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+        };
+
+        let mut stats = CrawlStats::default();
+        for submission in submissions.iter() {
+            // 挿入された行はxmaxが0になるので、挿入と更新を区別できる
+            let result = sqlx::query_scalar::<_, bool>(
+                r#"
+                INSERT INTO "submissions" (
+                    "id",
+                    "epoch_second",
+                    "problem_id",
+                    "contest_id",
+                    "user_id",
+                    "language",
+                    "point",
+                    "length",
+                    "result",
+                    "execution_time"
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT ("id") DO UPDATE SET
+                    "point" = EXCLUDED."point",
+                    "result" = EXCLUDED."result",
+                    "execution_time" = EXCLUDED."execution_time"
+                RETURNING ("xmax" = 0);
+                "#,
+            )
+            .bind(submission.id)
+            .bind(submission.epoch_second)
+            .bind(&submission.problem_id)
+            .bind(&submission.contest_id)
+            .bind(&submission.user_id)
+            .bind(&submission.language)
+            .bind(submission.point)
+            .bind(submission.length)
+            .bind(&submission.result)
+            .bind(submission.execution_time)
+            .fetch_one(&mut tx)
+            .await;
+
+            stats.fetched += 1;
+            match result {
+                Ok(true) => stats.inserted += 1,
+                Ok(false) => stats.updated += 1,
+                // エラーが発生したらトランザクションをロールバックしてエラーを早期リターンする
+                Err(e) => {
+                    let message = format!(
+                        "an error occurred: {:?}, at saving submission {}",
+                        e, submission.id
+                    );
+                    tracing::error!(message);
+                    tx.rollback().await?;
+
+                    anyhow::bail!(message);
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(stats)
+    }
+
+    /// 保存済みの最も新しい提出以降の提出を、`duration`の間隔を空けて現在まで収集するメソッド
+    ///
+    /// `all`が`true`のときは最初の提出から収集し直す。
+    pub async fn crawl(&self, all: bool, duration: Duration) -> Result<CrawlStats> {
+        let mut from_second = if all {
+            0
+        } else {
+            self.latest_epoch_second().await?
+        };
+        tracing::info!("Start to crawl submissions from {}", from_second);
+
+        let mut stats = CrawlStats::default();
+        loop {
+            let submissions = self.fetch_submissions(from_second).await?;
+            if submissions.is_empty() {
+                break;
+            }
+            stats += self.save(&submissions).await?;
+            tracing::info!(
+                "{} submissions from {} successfully saved.",
+                submissions.len(),
+                from_second
+            );
+
+            if submissions.len() < SUBMISSIONS_PER_PAGE {
+                break;
+            }
+            from_second = next_from_second(&submissions, from_second);
+            time::sleep(duration).await;
+        }
+
+        tracing::info!("Finish crawling submissions: {}", stats);
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn submission(id: i64, epoch_second: i64) -> SubmissionJson {
+        SubmissionJson {
+            id,
+            epoch_second,
+            problem_id: String::from("abc300_a"),
+            contest_id: String::from("abc300"),
+            user_id: String::from("fjnkt98"),
+            language: String::from("Rust (rustc 1.70.0)"),
+            point: 100.0,
+            length: 300,
+            result: String::from("AC"),
+            execution_time: Some(5),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_submission() {
+        let submissions: Vec<SubmissionJson> = serde_json::from_str(
+            r#"[{"id":41284529,"epoch_second":1682773200,"problem_id":"abc300_a","contest_id":"abc300","user_id":"fjnkt98","language":"Rust (rustc 1.70.0)","point":100.0,"length":300,"result":"AC","execution_time":5},{"id":41284530,"epoch_second":1682773201,"problem_id":"abc300_b","contest_id":"abc300","user_id":"fjnkt98","language":"Rust (rustc 1.70.0)","point":0.0,"length":512,"result":"WJ","execution_time":null}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(submissions.len(), 2);
+        assert_eq!(submissions[0], submission(41284529, 1682773200));
+        assert_eq!(submissions[1].result, "WJ");
+        assert_eq!(submissions[1].execution_time, None);
+    }
+
+    #[test]
+    fn test_next_from_second() {
+        let submissions = vec![submission(1, 100), submission(2, 105), submission(3, 103)];
+        assert_eq!(next_from_second(&submissions, 100), 105);
+
+        // ページ全体が同じ時刻の提出なら1秒進める
+        let submissions = vec![submission(1, 100), submission(2, 100)];
+        assert_eq!(next_from_second(&submissions, 100), 101);
+        assert_eq!(next_from_second(&[], 100), 101);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    cmd::TargetDomain,
+    modules::handlers::{problem::ProblemSearchParameter, user::UserSearchParameter, ServerConfig},
+    types::request::{parse_search_query, PaginatedParameter},
+};
+use anyhow::{Context, Result};
+use atcoder_search_libs::{
+    solr::{core::SolrCore, model::SolrSelectResponse},
+    ToQueryParameter,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use std::{path::Path, time::Instant};
+use validator::ValidateArgs;
+
+/// ウォームアップの設定ファイル
+///
+/// ```toml
+/// problems = ["keyword=dp", "filter.category=ABC&sort=-difficulty"]
+/// users = ["keyword=tourist"]
+/// ```
+///
+/// 各ドメインの検索APIに送るクエリ文字列を並べる。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WarmupConfig {
+    #[serde(default)]
+    pub problems: Vec<String>,
+    #[serde(default)]
+    pub users: Vec<String>,
+}
+
+impl WarmupConfig {
+    /// 設定ファイルを読み込む関数
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| {
+            let message = format!("failed to read the warmup config {}", path.display());
+            tracing::error!(message);
+            message
+        })?;
+        Self::parse(&content)
+    }
+
+    /// TOML形式の設定をパースする関数
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).with_context(|| {
+            let message = "invalid warmup config";
+            tracing::error!(message);
+            message
+        })
+    }
+}
+
+/// Solrに送るパラメータに変換したウォームアップのクエリ
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WarmupQuery {
+    /// 設定ファイルに書かれたクエリ文字列
+    query: String,
+    params: Vec<(String, String)>,
+}
+
+/// 起動時やコアの入れ替え後に代表的なクエリを実行し、Solrのキャッシュを温める構造体
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Warmup {
+    problems: Vec<WarmupQuery>,
+    users: Vec<WarmupQuery>,
+}
+
+impl Warmup {
+    /// 設定のクエリ文字列を検索APIと同じようにバリデーションしてSolrのパラメータに変換する関数
+    pub fn new(config: &WarmupConfig, server: &ServerConfig) -> Result<Self> {
+        let problems = config
+            .problems
+            .iter()
+            .map(|query| {
+                let params: ProblemSearchParameter = parse_query(query, server)?;
+                Ok(WarmupQuery {
+                    query: query.clone(),
+                    params: params.to_query(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let users = config
+            .users
+            .iter()
+            .map(|query| {
+                let params: UserSearchParameter = parse_query(query, server)?;
+                Ok(WarmupQuery {
+                    query: query.clone(),
+                    params: params.to_query(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { problems, users })
+    }
+
+    fn queries(&self, domain: &TargetDomain) -> &[WarmupQuery] {
+        match domain {
+            TargetDomain::Problems => &self.problems,
+            TargetDomain::Users => &self.users,
+            TargetDomain::Recommend => &[],
+        }
+    }
+
+    /// `domain`のクエリを`core`で順に実行し、それぞれの所要時間をログに出力する関数
+    ///
+    /// ウォームアップは検索の可否に影響しないので、失敗しても警告を出すだけにする。
+    pub async fn run<C>(&self, domain: &TargetDomain, core: &C)
+    where
+        C: SolrCore + Sync,
+    {
+        let queries = self.queries(domain);
+        if queries.is_empty() {
+            return;
+        }
+
+        let start = Instant::now();
+        for query in queries {
+            let start_query = Instant::now();
+            let result: Result<SolrSelectResponse<Value, Value>, _> =
+                core.select(&query.params).await;
+            let elapsed = start_query.elapsed().as_millis();
+            match result {
+                Ok(response) => tracing::info!(
+                    "warmup query `{}` for {} took {} ms (QTime {} ms)",
+                    query.query,
+                    domain,
+                    elapsed,
+                    response.header.qtime
+                ),
+                Err(e) => tracing::warn!(
+                    "warmup query `{}` for {} failed in {} ms cause: {:?}",
+                    query.query,
+                    domain,
+                    elapsed,
+                    e
+                ),
+            }
+        }
+        tracing::info!(
+            "{} warmup queries for {} finished in {} ms",
+            queries.len(),
+            domain,
+            start.elapsed().as_millis()
+        );
+    }
+}
+
+fn parse_query<T>(query: &str, server: &ServerConfig) -> Result<T>
+where
+    T: DeserializeOwned + for<'a> ValidateArgs<'a, Args = &'a ServerConfig> + PaginatedParameter,
+{
+    parse_search_query(query, server).map_err(|e| {
+        let message = format!("invalid warmup query `{}`: {}", query, e.message);
+        tracing::error!(message);
+        anyhow::anyhow!(message)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use atcoder_search_libs::testing::MockSolrCore;
+
+    #[test]
+    fn test_parse_warmup() {
+        let config = WarmupConfig::parse(
+            r#"
+            problems = ["keyword=dp", "filter.category=ABC&sort=-difficulty"]
+            users = ["keyword=tourist"]
+            "#,
+        )
+        .unwrap();
+        let warmup = Warmup::new(&config, &ServerConfig::default()).unwrap();
+        assert_eq!(warmup.problems.len(), 2);
+        assert_eq!(warmup.users.len(), 1);
+        assert!(warmup.queries(&TargetDomain::Recommend).is_empty());
+
+        assert_eq!(WarmupConfig::parse("").unwrap(), WarmupConfig::default());
+        assert!(WarmupConfig::parse(r#"recommends = ["keyword=dp"]"#).is_err());
+
+        let invalid = WarmupConfig::parse(r#"problems = ["limit=100000"]"#).unwrap();
+        assert!(Warmup::new(&invalid, &ServerConfig::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_warmup() {
+        let config =
+            WarmupConfig::parse(r#"problems = ["keyword=choice", "keyword=graph"]"#).unwrap();
+        let warmup = Warmup::new(&config, &ServerConfig::default()).unwrap();
+
+        let core = MockSolrCore::new("problems");
+        warmup.run(&TargetDomain::Problems, &core).await;
+        let selects = core.selects();
+        assert_eq!(selects.len(), 2);
+        assert!(selects[0].contains(&(String::from("q"), String::from("choice"))));
+
+        // 失敗しても残りのクエリを実行する
+        let core = MockSolrCore::new("problems");
+        core.set_available(false);
+        warmup.run(&TargetDomain::Problems, &core).await;
+        assert_eq!(core.selects().len(), 2);
+
+        let core = MockSolrCore::new("users");
+        warmup.run(&TargetDomain::Users, &core).await;
+        assert!(core.selects().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+use anyhow::{Context, Result};
 use regex::Regex;
 use serde::Deserialize;
+use std::{env, path::Path};
 
 /// コンテストのレーティング対象の種類の列挙型
 ///
@@ -8,6 +10,7 @@ use serde::Deserialize;
 /// - UPPERBOUND: あるレート以下のユーザがレーティング対象であるコンテスト(e.g. ~ 1999)
 /// - LOWERBOUND: あるレート以上のユーザがレーティング対象であるコンテスト(e.g. 2000 ~)
 ///
+#[allow(clippy::upper_case_acronyms)]
 #[derive(PartialEq, Debug)]
 pub enum RatedTargetType {
     ALL,
@@ -16,10 +19,207 @@ pub enum RatedTargetType {
     LOWERBOUND(i64),
 }
 
+/// コンテストの開催状況
+///
+/// - UPCOMING: 開始前のコンテスト
+/// - RUNNING: 開催中のコンテスト
+/// - FINISHED: 終了したコンテスト
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ContestStatus {
+    UPCOMING,
+    RUNNING,
+    FINISHED,
+}
+
+impl ContestStatus {
+    /// Unix Epoch Time`now`の時点での、`start_epoch_second`から`duration_second`秒間開催されるコンテストの開催状況を求める
+    pub fn at(start_epoch_second: i64, duration_second: i64, now: i64) -> Self {
+        if now < start_epoch_second {
+            ContestStatus::UPCOMING
+        } else if now < start_epoch_second + duration_second {
+            ContestStatus::RUNNING
+        } else {
+            ContestStatus::FINISHED
+        }
+    }
+
+    /// データベースの`status`カラムに保存する文字列
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContestStatus::UPCOMING => "upcoming",
+            ContestStatus::RUNNING => "running",
+            ContestStatus::FINISHED => "finished",
+        }
+    }
+}
+
+/// コンテスト時間の区分。短いものから順に並べる
+///
+/// - `~100min`: 100分以下のコンテスト(ABCなど)
+/// - `100-180min`: 100分を超え180分以下のコンテスト(ARC・AGCなど)
+/// - `long/marathon`: 180分を超えるコンテスト(長時間のコンテストやマラソン)
+pub const DURATION_BUCKETS: [&str; 3] = ["~100min", "100-180min", "long/marathon"];
+
+/// 開催時間`duration_second`秒のコンテストの区分を返す関数
+pub fn duration_bucket(duration_second: i64) -> &'static str {
+    if duration_second <= 100 * 60 {
+        DURATION_BUCKETS[0]
+    } else if duration_second <= 180 * 60 {
+        DURATION_BUCKETS[1]
+    } else {
+        DURATION_BUCKETS[2]
+    }
+}
+
 /// AGC001が開始された日時のUnix Epoch Time。
 /// AtCoderのレーティングはこの大会以降から開始されたので、これより前のコンテストは無条件にUnratedコンテストであると言える。
 const AGC001_STARTED_AT: i64 = 1468670400;
 
+/// ルールファイルを使わずに組み込みの識別だけでカテゴリを決めたときに保存するルールのバージョン
+pub const BUILTIN_CATEGORY_RULES_VERSION: &str = "builtin";
+
+// ルールファイルの設定ファイル内での表現
+#[derive(Deserialize)]
+struct CategoryRulesFile {
+    version: String,
+    #[serde(default)]
+    rules: Vec<CategoryRuleEntry>,
+}
+
+#[derive(Deserialize)]
+struct CategoryRuleEntry {
+    category: String,
+    id: Option<String>,
+    title: Option<String>,
+}
+
+/// コンテストIDとタイトルの正規表現からカテゴリを決めるルール
+///
+/// 両方の正規表現が指定されている場合は両方にマッチしたときに適用する。
+#[derive(Debug, Clone)]
+pub struct CategoryRule {
+    pub category: String,
+    pub id: Option<Regex>,
+    pub title: Option<Regex>,
+}
+
+impl CategoryRule {
+    fn is_match(&self, contest: &ContestJson) -> bool {
+        self.id.as_ref().is_none_or(|id| id.is_match(&contest.id))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| title.is_match(&contest.title))
+    }
+}
+
+/// 組み込みの識別より先に適用するカテゴリのルール
+///
+/// ```toml
+/// version = "2023-10-20"
+///
+/// [[rules]]
+/// category = "JOI"
+/// id = "^joi"
+///
+/// [[rules]]
+/// category = "PAST"
+/// title = "アルゴリズム実技検定"
+/// ```
+///
+/// ルールは上から順に試し、最初にマッチしたルールのカテゴリを使う。
+/// どのルールにもマッチしないコンテストは組み込みの識別でカテゴリを決める。
+/// `version`はルールを変更するたびに更新し、各コンテストのカテゴリをどのルールで決めたかの記録に使う。
+#[derive(Debug, Clone)]
+pub struct CategoryRules {
+    pub version: String,
+    pub rules: Vec<CategoryRule>,
+}
+
+impl Default for CategoryRules {
+    fn default() -> Self {
+        Self {
+            version: String::from(BUILTIN_CATEGORY_RULES_VERSION),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl CategoryRules {
+    /// 環境変数`CATEGORY_RULES_FILE`に指定されたルールファイルを読み込む関数
+    ///
+    /// 指定されていなければルールのない組み込みの識別だけを使う。
+    pub fn from_env() -> Result<Self> {
+        match env::var("CATEGORY_RULES_FILE") {
+            Ok(path) if !path.is_empty() => Self::load(Path::new(&path)),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// ルールファイルを読み込む関数
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| {
+            let message = format!("failed to read the category rules {}", path.display());
+            tracing::error!(message);
+            message
+        })?;
+        Self::parse(&content)
+    }
+
+    /// TOML形式のルールをパースして正規表現をコンパイルする関数
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: CategoryRulesFile = toml::from_str(content).with_context(|| {
+            let message = "invalid category rules";
+            tracing::error!(message);
+            message
+        })?;
+
+        let version = file.version.trim();
+        if version.is_empty() || version == BUILTIN_CATEGORY_RULES_VERSION {
+            let message = format!(
+                "version of the category rules must not be empty or `{}`",
+                BUILTIN_CATEGORY_RULES_VERSION
+            );
+            tracing::error!(message);
+            anyhow::bail!(message)
+        }
+
+        let compile = |pattern: Option<String>, i: usize| {
+            pattern
+                .map(|pattern| {
+                    Regex::new(&pattern).with_context(|| {
+                        let message = format!("invalid pattern in rule {}: {}", i + 1, pattern);
+                        tracing::error!(message);
+                        message
+                    })
+                })
+                .transpose()
+        };
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for (i, rule) in file.rules.into_iter().enumerate() {
+            if rule.category.trim().is_empty() || (rule.id.is_none() && rule.title.is_none()) {
+                let message = format!(
+                    "rule {} must have a category and either of id or title",
+                    i + 1
+                );
+                tracing::error!(message);
+                anyhow::bail!(message)
+            }
+            rules.push(CategoryRule {
+                category: rule.category,
+                id: compile(rule.id, i)?,
+                title: compile(rule.title, i)?,
+            });
+        }
+
+        Ok(Self {
+            version: String::from(version),
+            rules,
+        })
+    }
+}
+
 /// AtCoderProblemsから取得できるコンテスト情報のJSONスキーマ
 ///
 /// - id: コンテストのID。コンテストのURIに使用されている文字列
@@ -65,11 +265,23 @@ impl ContestJson {
                     return RatedTargetType::UPPERBOUND(upper_bound);
                 }
 
-                return RatedTargetType::UNRATED;
+                RatedTargetType::UNRATED
             }
         }
     }
 
+    /// `rules`のルールを先に適用してコンテストの種類を識別するメソッド
+    ///
+    /// どのルールにもマッチしなければ`categorize`と同じ結果を返す。
+    pub fn categorize_with(&self, rules: &CategoryRules) -> String {
+        rules
+            .rules
+            .iter()
+            .find(|rule| rule.is_match(self))
+            .map(|rule| rule.category.clone())
+            .unwrap_or_else(|| self.categorize())
+    }
+
     /// コンテストの種類を識別するメソッド
     /// 識別アルゴリズムは`https://github.com/kenkoooo/AtCoderProblems/blob/master/atcoder-problems-frontend/src/utils/ContestClassifier.ts`に倣う
     pub fn categorize(&self) -> String {
@@ -87,15 +299,9 @@ impl ContestJson {
         }
 
         match self.rated_target() {
-            RatedTargetType::ALL => {
-                return String::from("AGC-Like");
-            }
-            RatedTargetType::UPPERBOUND(_) => {
-                return String::from("ABC-Like");
-            }
-            RatedTargetType::LOWERBOUND(_) => {
-                return String::from("ARC-Like");
-            }
+            RatedTargetType::ALL => String::from("AGC-Like"),
+            RatedTargetType::UPPERBOUND(_) => String::from("ABC-Like"),
+            RatedTargetType::LOWERBOUND(_) => String::from("ARC-Like"),
             RatedTargetType::UNRATED => {
                 if self.id.starts_with("past") {
                     return String::from("PAST");
@@ -122,7 +328,7 @@ impl ContestJson {
                     "kuronekoyamato-self2019",
                     "wn2017_1",
                 ]
-                .contains(&&self.id.as_str())
+                .contains(&self.id.as_str() )
             {
                 return String::from("Marathon");
             }
@@ -141,9 +347,9 @@ impl ContestJson {
                     return String::from("Other Sponsored");
                 }
 
-                return String::from("Other Contests");
+                String::from("Other Contests")
             }
-        };
+        }
     }
 }
 
@@ -216,4 +422,101 @@ mod tests {
 
         assert_eq!(contest.categorize(), String::from("Other Contests"));
     }
+
+    #[test]
+    fn categorize_with_rules() {
+        let rules = CategoryRules::parse(
+            r#"
+            version = "2023-10-20"
+
+            [[rules]]
+            category = "JOI"
+            id = "^joi"
+
+            [[rules]]
+            category = "PAST"
+            id = "-open$"
+            title = "アルゴリズム実技検定"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(rules.version, "2023-10-20");
+
+        // Ratedのコンテストもルールが優先される
+        let contest = ContestJson {
+            id: String::from("joi2024yo1a"),
+            start_epoch_second: 1687060800,
+            duration_second: 10800,
+            title: String::from("JOI 2024 一次予選 第1回"),
+            rate_change: String::from(" ~ 1999"),
+        };
+        assert_eq!(contest.categorize(), String::from("ABC-Like"));
+        assert_eq!(contest.categorize_with(&rules), String::from("JOI"));
+
+        let contest = ContestJson {
+            id: String::from("past202206-open"),
+            start_epoch_second: 1654999200,
+            duration_second: 18000,
+            title: String::from("第十一回 アルゴリズム実技検定 過去問"),
+            rate_change: String::from("-"),
+        };
+        assert_eq!(contest.categorize_with(&rules), String::from("PAST"));
+        // 全ての正規表現にマッチしなければ組み込みの識別を使う
+        let contest = ContestJson {
+            id: String::from("ttpc2019"),
+            start_epoch_second: 1567224300,
+            duration_second: 18000,
+            title: String::from("アルゴリズム実技検定"),
+            rate_change: String::from("-"),
+        };
+        assert_eq!(
+            contest.categorize_with(&rules),
+            String::from("Other Contests")
+        );
+        assert_eq!(
+            contest.categorize_with(&CategoryRules::default()),
+            contest.categorize()
+        );
+    }
+
+    #[test]
+    fn invalid_category_rules() {
+        assert!(CategoryRules::parse("").is_err());
+        assert!(CategoryRules::parse(r#"version = "builtin""#).is_err());
+        assert!(CategoryRules::parse(
+            r#"
+            version = "1"
+            [[rules]]
+            category = "JOI"
+            "#
+        )
+        .is_err());
+        assert!(CategoryRules::parse(
+            r#"
+            version = "1"
+            [[rules]]
+            category = "JOI"
+            id = "^joi("
+            "#
+        )
+        .is_err());
+        assert!(CategoryRules::parse(r#"version = "1""#).is_ok());
+    }
+
+    #[test]
+    fn contest_duration_bucket() {
+        assert_eq!(duration_bucket(6000), "~100min");
+        assert_eq!(duration_bucket(6001), "100-180min");
+        assert_eq!(duration_bucket(10800), "100-180min");
+        assert_eq!(duration_bucket(14400), "long/marathon");
+    }
+
+    #[test]
+    fn contest_status() {
+        assert_eq!(ContestStatus::at(1000, 100, 999), ContestStatus::UPCOMING);
+        assert_eq!(ContestStatus::at(1000, 100, 1000), ContestStatus::RUNNING);
+        assert_eq!(ContestStatus::at(1000, 100, 1099), ContestStatus::RUNNING);
+        assert_eq!(ContestStatus::at(1000, 100, 1100), ContestStatus::FINISHED);
+        assert_eq!(ContestStatus::UPCOMING.as_str(), "upcoming");
+    }
 }
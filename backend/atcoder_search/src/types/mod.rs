@@ -1,5 +1,4 @@
 pub mod contest;
 pub mod problem;
 pub mod request;
-pub mod response;
 pub mod tables;
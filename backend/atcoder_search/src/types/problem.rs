@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use serde_with::{serde_as, DefaultOnError};
 
 /// AtCoderProblemsから取得できる問題情報のJSONスキーマ
 ///
@@ -19,15 +20,47 @@ pub struct ProblemJson {
 /// 問題の難易度情報
 /// `https://kenkoooo.com/atcoder/resources/problem-models.json`から得られるJSONスキーマ
 ///
-/// 問題によっては難易度情報が無いことがあるので、Option型でフィールドを定義している
-#[derive(Deserialize)]
+/// 問題によっては難易度情報が無いことがあるので、Option型でフィールドを定義している。
+/// 型が合わない値も、エントリ全体を捨てずにそのフィールドだけを`None`として読み込む。
+#[serde_as]
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
 pub struct ProblemDifficulty {
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub slope: Option<f64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub intercept: Option<f64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub variance: Option<f64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub difficulty: Option<i64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub discrimination: Option<f64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub irt_loglikelihood: Option<f64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub irt_users: Option<i64>,
+    #[serde_as(as = "DefaultOnError")]
+    #[serde(default)]
     pub is_experimental: Option<bool>,
 }
+
+impl ProblemDifficulty {
+    /// 全てのフィールドの値が揃っているかどうかを返すメソッド
+    pub fn is_complete(&self) -> bool {
+        self.slope.is_some()
+            && self.intercept.is_some()
+            && self.variance.is_some()
+            && self.difficulty.is_some()
+            && self.discrimination.is_some()
+            && self.irt_loglikelihood.is_some()
+            && self.irt_users.is_some()
+            && self.is_experimental.is_some()
+    }
+}
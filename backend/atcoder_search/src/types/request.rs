@@ -1,156 +1,260 @@
-use crate::types::response::{ResponseDocument, SearchResultResponse};
-use atcoder_search_libs::{
-    solr::query::{sanitize, EDisMaxQueryBuilder, Operator},
-    FieldList, ToQueryParameter,
+use crate::modules::handlers::{ServerConfig, DEFAULT_ROWS, MAX_ROWS};
+use async_graphql::InputObject;
+use atcoder_search_libs::{api::FilterExpr, ApiError};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
 };
-use axum::{async_trait, extract::FromRequestParts, http::StatusCode, Json};
 use http::request::Parts;
 use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
-use std::collections::{BTreeMap, HashSet};
-use validator::{Validate, ValidationError};
-
-// ソート順に指定できるフィールドの集合
-static VALID_SORT_OPTIONS: Lazy<HashSet<&str>> = Lazy::new(|| {
-    HashSet::from([
-        "start_at",
-        "-start_at",
-        "difficulty",
-        "-difficulty",
-        "-score",
-    ])
-});
-
-// 絞り込みに指定できるカテゴリの集合
-static VALID_CATEGORY_OPTIONS: Lazy<HashSet<&str>> = Lazy::new(|| {
-    HashSet::from([
-        "ABC",
-        "ARC",
-        "AGC",
-        "AHC",
-        "AGC-Like",
-        "ABC-Like",
-        "ARC-Like",
-        "PAST",
-        "JOI",
-        "JAG",
-        "Marathon",
-        "Other Sponsored",
-        "Other Contests",
-    ])
-});
-
-// ファセットカウントに指定できるフィールドの集合
-static VALID_FACET_FIELDS: Lazy<HashSet<&str>> =
-    Lazy::new(|| HashSet::from(["category", "difficulty"]));
-
-// ソート順指定パラメータの値をバリデーションする関数
-fn validate_sort_field(value: &str) -> Result<(), ValidationError> {
-    if VALID_SORT_OPTIONS.contains(value) {
-        Ok(())
-    } else {
-        Err(ValidationError::new("invalid sort field"))
+use sha2::{Digest, Sha256};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashSet},
+};
+use unicode_normalization::UnicodeNormalization;
+use utoipa::openapi::{
+    path::{Parameter, ParameterBuilder, ParameterIn, ParameterStyle},
+    ArrayBuilder, ObjectBuilder, Required, SchemaType,
+};
+use validator::{Validate, ValidateArgs, ValidationError};
+
+#[derive(Debug, Serialize, Deserialize, Validate, InputObject, PartialEq, Eq, Clone)]
+#[graphql(name = "RangeFilter")]
+pub struct RangeFilterParameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<i32>,
+}
+
+impl RangeFilterParameter {
+    /// `field`の範囲の絞り込み条件に変換する。どちらの端も指定されていなければ`None`
+    pub fn to_filter(&self, field: &str) -> Option<FilterExpr> {
+        if self.from.is_none() && self.to.is_none() {
+            return None;
+        }
+        Some(FilterExpr::range(field, self.from, self.to))
     }
 }
 
-// カテゴリ絞り込みパラメータの値をバリデーションする関数
-fn validate_category_filtering(values: &Vec<String>) -> Result<(), ValidationError> {
-    if values
-        .iter()
-        .all(|value| VALID_CATEGORY_OPTIONS.contains(value.as_str()))
-    {
-        Ok(())
-    } else {
-        Err(ValidationError::new("invalid category field"))
+// `100m`や`1h40m`のような時間の表記
+static DURATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap());
+
+/// 時間の表記を秒数に変換する関数
+///
+/// 単位のない数値は秒数とみなし、`2h`、`100m`、`1h40m`、`90s`のような表記も受け付ける。
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return (seconds >= 0).then_some(seconds);
+    }
+
+    let captures = DURATION_RE.captures(value)?;
+    if captures.iter().skip(1).all(|capture| capture.is_none()) {
+        return None;
     }
+    let unit = |i: usize| -> Option<i64> {
+        captures
+            .get(i)
+            .map(|capture| capture.as_str().parse::<i64>().ok())
+            .unwrap_or(Some(0))
+    };
+    Some(unit(1)? * 3600 + unit(2)? * 60 + unit(3)?)
 }
 
-// ファセットカウント指定パラメータの値をバリデーションする関数
-fn validate_facet_fields(values: &Vec<String>) -> Result<(), ValidationError> {
-    if values
-        .iter()
-        .all(|value| VALID_FACET_FIELDS.contains(value.as_str()))
-    {
-        Ok(())
-    } else {
-        Err(ValidationError::new("invalid facet field"))
+// 時間の指定をバリデーションする関数
+pub fn validate_duration(value: &str) -> Result<(), ValidationError> {
+    match parse_duration(value) {
+        Some(_) => Ok(()),
+        None => Err(ValidationError::new("invalid duration")),
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
-pub struct SearchQueryParameters {
-    #[validate(length(max = 200))]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub keyword: Option<String>,
-    #[validate(range(min = 1, max = 200))]
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub limit: Option<u32>,
-    #[validate(range(min = 1))]
+/// 時間の範囲で絞り込むパラメータ
+///
+/// 値は秒数か`100m`のような表記で指定する。
+#[derive(Debug, Serialize, Deserialize, Validate, InputObject, PartialEq, Eq, Clone)]
+#[graphql(name = "DurationFilter")]
+pub struct DurationFilterParameter {
+    #[validate(custom = "validate_duration")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub page: Option<u32>,
+    pub from: Option<String>,
+    #[validate(custom = "validate_duration")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub filter: Option<FilterParameters>,
-    #[validate(custom = "validate_sort_field")]
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub sort: Option<String>,
-    #[validate(custom = "validate_facet_fields")]
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        deserialize_with = "comma_separated_values"
-    )]
-    pub facet: Option<Vec<String>>,
+    pub to: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
-pub struct FilterParameters {
-    #[validate(custom = "validate_category_filtering")]
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        deserialize_with = "comma_separated_values"
-    )]
-    category: Option<Vec<String>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    difficulty: Option<RangeFilterParameter>,
+impl DurationFilterParameter {
+    /// `field`の秒数の範囲の絞り込み条件に変換する。バリデーションを通らない値は指定されなかったものとして扱う
+    pub fn to_filter(&self, field: &str) -> Option<FilterExpr> {
+        let from = self.from.as_deref().and_then(parse_duration);
+        let to = self.to.as_deref().and_then(parse_duration);
+        if from.is_none() && to.is_none() {
+            return None;
+        }
+        Some(FilterExpr::range(field, from, to))
+    }
 }
 
+// 範囲ファセットのバケット数の上限
+pub const MAX_RANGE_FACET_BUCKETS: i32 = 100;
+
+/// 範囲ファセットの区間の指定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FacetRange {
+    pub start: i32,
+    pub end: i32,
+    pub gap: i32,
+}
+
+impl FacetRange {
+    // 区間の指定が妥当かどうかを検査する関数
+    pub fn check(&self) -> Result<(), ValidationError> {
+        let code = if self.start >= self.end {
+            "start must be less than end"
+        } else if self.gap < 1 {
+            "gap must be positive"
+        } else if (self.end - self.start + self.gap - 1) / self.gap > MAX_RANGE_FACET_BUCKETS {
+            "too many buckets"
+        } else {
+            return Ok(());
+        };
+
+        let mut error = ValidationError::new(code);
+        error.add_param(Cow::from("start"), &self.start);
+        error.add_param(Cow::from("end"), &self.end);
+        error.add_param(Cow::from("gap"), &self.gap);
+        error.add_param(Cow::from("max_buckets"), &MAX_RANGE_FACET_BUCKETS);
+        Err(error)
+    }
+}
+
+/// 範囲ファセットの区間を変更するパラメータ(`range_facet.<field>.start|end|gap`)
+///
+/// `facet`パラメータはカンマ区切りの値を取るため、`facet.<field>.start`のように入れ子にすることができない。
+/// そのため別の名前空間に定義している。
 #[derive(Debug, Serialize, Deserialize, Validate, PartialEq, Eq, Clone)]
-pub struct RangeFilterParameter {
+pub struct RangeFacetParameter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    from: Option<i32>,
+    pub end: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    to: Option<i32>,
+    pub gap: Option<i32>,
 }
 
-impl RangeFilterParameter {
-    pub fn to_range(&self) -> Option<String> {
-        if self.from.is_none() && self.to.is_none() {
-            return None;
+impl RangeFacetParameter {
+    // 指定されなかった値をデフォルト値で補った区間を返す関数
+    pub fn resolve(parameter: Option<&Self>, default: FacetRange) -> FacetRange {
+        match parameter {
+            Some(parameter) => FacetRange {
+                start: parameter.start.unwrap_or(default.start),
+                end: parameter.end.unwrap_or(default.end),
+                gap: parameter.gap.unwrap_or(default.gap),
+            },
+            None => default,
         }
+    }
+}
+
+// 語のファセットで返すバケット数のデフォルトの上限
+pub const DEFAULT_TERM_FACET_LIMIT: u32 = 200;
 
-        let from = &self
-            .from
-            .and_then(|from| Some(from.to_string()))
-            .unwrap_or(String::from("*"));
-        let to = &self
-            .to
-            .and_then(|to| Some(to.to_string()))
-            .unwrap_or(String::from("*"));
-        Some(format!("[{} TO {}}}", from, to))
+// json.facetパラメータの長さの上限
+pub const MAX_JSON_FACET_LENGTH: usize = 8192;
+
+// `limits`に指定されたフィールドごとの語のファセットのバケット数の上限を返す関数
+//
+// 指定されていないフィールドはデフォルトの上限にする。
+pub fn term_facet_limit(limits: &[(&str, u32)], field: &str) -> u32 {
+    limits
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, limit)| *limit)
+        .unwrap_or(DEFAULT_TERM_FACET_LIMIT)
+}
+
+// 値ごとの件数を上位`limit`件まで集計する語のファセットのJSON Facetを生成する関数
+//
+// `limit: -1`ですべての値を返すと、所属のように値の種類が多いフィールドでレスポンスが膨れ上がるため上限を設ける。
+pub fn term_facet(field: &str, limit: u32) -> Value {
+    json!({
+        "type": "terms",
+        "field": field,
+        "limit": limit,
+        "mincount": 0,
+        "domain": {
+            "excludeTags": [field]
+        }
+    })
+}
+
+/// ファセットの名前とJSON Facetの組をjson.facetパラメータの値にする関数
+///
+/// 長さが[`MAX_JSON_FACET_LENGTH`]を超えるファセットは含めず、ログに出力する。
+/// ファセットがなければ空文字列を返す。
+pub fn json_facet(facets: BTreeMap<String, Value>) -> String {
+    let mut params: BTreeMap<String, Value> = BTreeMap::new();
+    for (name, facet) in facets {
+        params.insert(name.clone(), facet);
+        let length = serde_json::to_string(&params)
+            .map(|json| json.len())
+            .unwrap_or(usize::MAX);
+        if length > MAX_JSON_FACET_LENGTH {
+            tracing::warn!(
+                "facet {} is omitted because json.facet exceeds {} bytes",
+                name,
+                MAX_JSON_FACET_LENGTH
+            );
+            params.remove(&name);
+        }
+    }
+
+    if params.is_empty() {
+        String::from("")
+    } else {
+        serde_json::to_string(&params).unwrap_or(String::from(""))
     }
 }
 
+// 統計量として集計するパーセンタイル
+pub const STATS_PERCENTILES: [u32; 3] = [25, 50, 75];
+
+// 数値フィールドの統計量(最小値・最大値・平均値・パーセンタイル)を集計するJSON Facetを生成する関数
+//
+// 絞り込み後の検索結果全体を対象にするため、ファセットと違ってexcludeTagsは指定しない。
+pub fn stats_facet(field: &str) -> Value {
+    let percentiles = STATS_PERCENTILES
+        .iter()
+        .map(|percentile| percentile.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    json!({
+        "type": "query",
+        "q": "*:*",
+        "facet": {
+            "min": format!("min({})", field),
+            "max": format!("max({})", field),
+            "avg": format!("avg({})", field),
+            "percentiles": format!("percentile({},{})", field, percentiles),
+        }
+    })
+}
+
 // カンマ区切りの文字列フィールドをベクタに変換するカスタムデシリアライズ関数
-fn comma_separated_values<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+pub fn comma_separated_values<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let value = String::deserialize(deserializer)?;
     let values = value
         .split(',')
-        .into_iter()
         .map(|v| v.trim())
         .filter(|v| !v.is_empty())
         .map(String::from)
@@ -163,189 +267,653 @@ where
     }
 }
 
-impl ToQueryParameter for SearchQueryParameters {
-    fn to_query(&self) -> Vec<(String, String)> {
-        let rows = self.limit.unwrap_or(20);
-        let page = self.page.unwrap_or(1);
-        let start = (page - 1) * rows;
-        let keyword = self
-            .keyword
-            .as_ref()
-            .map(|keyword| sanitize(keyword))
-            .unwrap_or(String::from(""));
-        let sort = self
-            .sort
-            .as_ref()
-            .and_then(|sort| {
-                if sort.starts_with("-") {
-                    Some(format!("{} desc", &sort[1..]))
-                } else {
-                    Some(format!("{} asc", sort))
-                }
-            })
-            .unwrap_or(String::from(""));
-        let fq = self
-            .filter
-            .as_ref()
-            .and_then(|filter| Some(filter.to_query()))
-            .unwrap_or(vec![]);
-
-        let facet = self
-            .facet
-            .as_ref()
-            .and_then(|facet| {
-                let mut facet_params: BTreeMap<&str, Value> = BTreeMap::new();
-                for field in facet.iter() {
-                    match field.as_str() {
-                        "category" => {
-                            facet_params.insert(
-                                field,
-                                json!({
-                                    "type": "terms",
-                                    "field": "category",
-                                    "limit": -1,
-                                    "mincount": 0,
-                                    "domain": {
-                                        "excludeTags": ["category"]
-                                    }
-                                }),
-                            );
-                        }
-                        "difficulty" => {
-                            facet_params.insert(
-                                field,
-                                json!({
-                                    "type": "range",
-                                    "field": "difficulty",
-                                    "start": 0,
-                                    "end": 4000,
-                                    "gap": 400,
-                                    "other": "all",
-                                    "domain": {
-                                        "excludeTags": ["difficulty"]
-                                    }
-                                }),
-                            );
-                        }
-                        _ => {}
-                    };
-                }
-                serde_json::to_string(&facet_params).ok()
-            })
-            .unwrap_or(String::from(""));
-
-        EDisMaxQueryBuilder::new()
-            .facet(facet)
-            .fl(ResponseDocument::field_list())
-            .fq(&fq)
-            .op(Operator::AND)
-            .q(keyword)
-            .q_alt("*:*")
-            .qf("text_ja text_en text_1gram")
-            .rows(rows)
-            .sort(sort)
-            .sow(true)
-            .start(start)
+// 絞り込みパラメータの値を、含める値と`-`で始まる除外する値に振り分ける関数
+pub fn split_negation(values: &[String]) -> (Vec<&str>, Vec<&str>) {
+    let mut includes = Vec::new();
+    let mut excludes = Vec::new();
+    for value in values {
+        match value.strip_prefix('-') {
+            Some(value) => excludes.push(value),
+            None => includes.push(value.as_str()),
+        }
+    }
+    (includes, excludes)
+}
+
+/// 同じ検索になるパラメータを1つの形にそろえるトレイト
+///
+/// キャッシュのキー、ETag、クエリログには正規化したパラメータを使い、同じ意味のリクエストを同じものとして扱う。
+pub trait Canonicalize: Serialize + Sized {
+    /// 正規化したパラメータを返すメソッド。省略された件数は`config`のデフォルト値で補う
+    fn canonicalize(&self, config: &ServerConfig) -> Self;
+
+    /// 正規化したパラメータのJSON表現
+    fn canonical_form(&self, config: &ServerConfig) -> String {
+        serde_json::to_string(&self.canonicalize(config)).unwrap_or_default()
+    }
+
+    /// 正規化したパラメータのハッシュ値
+    fn canonical_key(&self, config: &ServerConfig) -> String {
+        hex::encode(Sha256::digest(self.canonical_form(config).as_bytes()))
+    }
+}
+
+// キーワードをNFKC正規化し、連続する空白を1つにまとめる関数。空白だけのキーワードは指定されなかったものとみなす
+pub fn canonical_keyword(keyword: &Option<String>) -> Option<String> {
+    keyword
+        .as_ref()
+        .map(|keyword| {
+            keyword
+                .nfkc()
+                .collect::<String>()
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|keyword| !keyword.is_empty())
+}
+
+// 順序に意味のない値のリストを並べ替えて重複を除く関数
+pub fn canonical_values(values: &Option<Vec<String>>) -> Option<Vec<String>> {
+    values
+        .as_ref()
+        .map(|values| {
+            let mut values = values.clone();
+            values.sort();
+            values.dedup();
+            values
+        })
+        .filter(|values| !values.is_empty())
+}
+
+// 文字列フィールドの絞り込みパラメータをタグ付きのfqに変換する関数
+//
+// 除外する値は`-field:(...)`の形式で別のfqにするが、ファセットのexcludeTagsが効くように同じタグを付ける。
+pub fn term_filter_queries(field: &str, values: &[String]) -> Vec<String> {
+    FilterExpr::term_set(field, values)
+        .iter()
+        .map(|filter| filter.to_fq(Some(field)))
+        .collect()
+}
+
+// ソート順指定パラメータの値をSolrのソート式に変換する関数
+//
+// 同順位の並びが安定するように、最後にユニークキーの昇順を付け加える。
+// ソート順が指定されていない場合はスコアの降順を優先する。
+pub fn to_sort_expression(sort: &Option<Vec<String>>, unique_key: &str) -> String {
+    let mut expressions: Vec<String> = sort
+        .as_ref()
+        .map(|sort| {
+            sort.iter()
+                .map(|sort| {
+                    if let Some(field) = sort.strip_prefix('-') {
+                        format!("{} desc", field)
+                    } else {
+                        format!("{} asc", sort)
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if expressions.is_empty() {
+        expressions.push(String::from("score desc"));
+    }
+    expressions.push(format!("{} asc", unique_key));
+
+    expressions.join(",")
+}
+
+// ソート順指定パラメータの各値をバリデーションする関数
+//
+// 許可されていないフィールドに加え、同じフィールドが複数回指定された場合もエラーとする。
+pub fn validate_sort_keys(
+    values: &[String],
+    valid_options: &HashSet<&str>,
+    allowed: &[&str],
+) -> Result<(), ValidationError> {
+    let invalid_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.as_str())
+        .filter(|value| !valid_options.contains(value))
+        .collect();
+    if !invalid_values.is_empty() {
+        let mut error = ValidationError::new("invalid sort field");
+        error.add_param(Cow::from("invalid_values"), &invalid_values);
+        error.add_param(Cow::from("allowed"), &allowed);
+        return Err(error);
+    }
+
+    let mut fields = HashSet::new();
+    let duplicated_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.strip_prefix('-').unwrap_or(value))
+        .filter(|field| !fields.insert(*field))
+        .collect();
+    if !duplicated_values.is_empty() {
+        let mut error = ValidationError::new("duplicated sort field");
+        error.add_param(Cow::from("invalid_values"), &duplicated_values);
+        return Err(error);
+    }
+
+    Ok(())
+}
+
+// レスポンスのフィールド選択パラメータの各値を、レスポンスの構造体のフィールドリストに含まれるかバリデーションする関数
+pub fn validate_response_fields(
+    values: &[String],
+    field_list: &str,
+) -> Result<(), ValidationError> {
+    let allowed: Vec<&str> = field_list.split(',').collect();
+    let invalid_values: Vec<&str> = values
+        .iter()
+        .map(|value| value.as_str())
+        .filter(|value| !allowed.contains(value))
+        .collect();
+
+    if invalid_values.is_empty() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("invalid response field");
+        error.add_param(Cow::from("invalid_values"), &invalid_values);
+        error.add_param(Cow::from("allowed"), &allowed);
+        Err(error)
+    }
+}
+
+// フィールド選択パラメータで指定されたフィールドだけに絞ったflを返す関数
+//
+// 指定されていない場合はレスポンスの構造体の全てのフィールドを返す。
+pub fn select_field_list(fields: &Option<Vec<String>>, field_list: &str) -> String {
+    match fields {
+        Some(fields) if !fields.is_empty() => field_list
+            .split(',')
+            .filter(|field| fields.iter().any(|selected| selected == field))
+            .collect::<Vec<&str>>()
+            .join(","),
+        _ => String::from(field_list),
+    }
+}
+
+// OpenAPIのクエリパラメータ定義を生成する関数
+//
+// `values`が与えられた場合は列挙値として、`comma_separated`の場合はカンマ区切りの配列として定義する。
+pub fn query_parameter(
+    name: &str,
+    description: &str,
+    schema_type: SchemaType,
+    values: Option<&[&str]>,
+    comma_separated: bool,
+) -> Parameter {
+    let mut schema = ObjectBuilder::new().schema_type(schema_type);
+    if let Some(values) = values {
+        schema = schema.enum_values(Some(values.iter().copied()));
+    }
+
+    let builder = ParameterBuilder::new()
+        .name(name)
+        .parameter_in(ParameterIn::Query)
+        .required(Required::False)
+        .description(Some(description));
+
+    if comma_separated {
+        builder
+            .schema(Some(ArrayBuilder::new().items(schema)))
+            .style(Some(ParameterStyle::Form))
+            .explode(Some(false))
             .build()
+    } else {
+        builder.schema(Some(schema)).build()
     }
 }
 
-impl FilterParameters {
-    pub fn to_query(&self) -> Vec<String> {
-        let mut query = vec![];
-        if let Some(categories) = &self.category {
-            query.push(format!(
-                "{{!tag=category}}category:({})",
-                categories.join(" OR ")
-            ));
-        }
-        if let Some(difficulty) = &self.difficulty {
-            if let Some(range) = difficulty.to_range() {
-                query.push(format!("{{!tag=difficulty}}difficulty:{}", range));
-            }
-        }
+// 範囲指定の絞り込みパラメータ(`<name>.from`と`<name>.to`)のOpenAPI定義を生成する関数
+pub fn range_query_parameters(name: &str, description: &str) -> Vec<Parameter> {
+    vec![
+        query_parameter(
+            &format!("{}.from", name),
+            &format!("{} (inclusive lower bound)", description),
+            SchemaType::Integer,
+            None,
+            false,
+        ),
+        query_parameter(
+            &format!("{}.to", name),
+            &format!("{} (exclusive upper bound)", description),
+            SchemaType::Integer,
+            None,
+            false,
+        ),
+    ]
+}
 
-        query
+// 範囲ファセットの区間を変更するパラメータ(`range_facet.<field>.start|end|gap`)のOpenAPI定義を生成する関数
+pub fn range_facet_parameters(field: &str, default: FacetRange) -> Vec<Parameter> {
+    [
+        ("start", "Lower bound of the range facet", default.start),
+        ("end", "Upper bound of the range facet", default.end),
+        (
+            "gap",
+            "Width of each bucket of the range facet",
+            default.gap,
+        ),
+    ]
+    .into_iter()
+    .map(|(name, description, value)| {
+        query_parameter(
+            &format!("range_facet.{}.{}", field, name),
+            &format!("{} `{}` (default {})", description, field, value),
+            SchemaType::Integer,
+            None,
+            false,
+        )
+    })
+    .collect()
+}
+
+// 全ドメインの検索APIに共通するクエリパラメータのOpenAPI定義を生成する関数
+pub fn common_search_parameters(
+    sort_options: &[&str],
+    facet_fields: &[&str],
+    stats_fields: &[&str],
+    field_list: &str,
+) -> Vec<Parameter> {
+    let response_fields: Vec<&str> = field_list.split(',').collect();
+
+    vec![
+        query_parameter(
+            "keyword",
+            "Search keyword (max 200 characters)",
+            SchemaType::String,
+            None,
+            false,
+        ),
+        query_parameter(
+            "limit",
+            &format!(
+                "Number of items per page (1 to {}, default {} unless configured otherwise)",
+                MAX_ROWS, DEFAULT_ROWS
+            ),
+            SchemaType::Integer,
+            None,
+            false,
+        ),
+        query_parameter(
+            "page",
+            "Page number starting from 1",
+            SchemaType::Integer,
+            None,
+            false,
+        ),
+        query_parameter(
+            "sort",
+            "Comma separated sort keys in priority order. `-` prefix means descending order",
+            SchemaType::String,
+            Some(sort_options),
+            true,
+        ),
+        query_parameter(
+            "facet",
+            "Comma separated field names to count facets",
+            SchemaType::String,
+            Some(facet_fields),
+            true,
+        ),
+        query_parameter(
+            "stats",
+            "Comma separated numeric field names to aggregate min, max, avg and percentiles (25, 50, 75). The result is returned as `<field>_stats` in the facet",
+            SchemaType::String,
+            Some(stats_fields),
+            true,
+        ),
+        query_parameter(
+            "fields",
+            "Comma separated field names of the items to return. All fields are returned when omitted",
+            SchemaType::String,
+            Some(&response_fields),
+            true,
+        ),
+        query_parameter(
+            "count_only",
+            "Return only the total count and the facets without items, e.g. to show the counts on filter UIs",
+            SchemaType::Boolean,
+            None,
+            false,
+        ),
+        query_parameter(
+            "debug",
+            "Return the score explanation of each item in `stats.explain`. Requires the admin token",
+            SchemaType::Boolean,
+            None,
+            false,
+        ),
+    ]
+}
+
+// 1ページあたりの件数指定パラメータをサーバの設定の上限でバリデーションする関数
+pub fn validate_limit(value: u32, config: &ServerConfig) -> Result<(), ValidationError> {
+    if (1..=config.max_rows).contains(&value) {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("range");
+        error.add_param(Cow::from("min"), &1);
+        error.add_param(Cow::from("max"), &config.max_rows);
+        Err(error)
     }
 }
 
+/// 1ページあたりの件数指定パラメータを持つ検索パラメータ
+pub trait PaginatedParameter {
+    fn limit_mut(&mut self) -> &mut Option<u32>;
+}
+
+/// クエリ文字列をデシリアライズし、サーバの設定を使ってバリデーションした検索パラメータ
+///
+/// `limit`が指定されていなければサーバの設定のデフォルト値で補う。
 pub struct ValidatedSearchQueryParameters<T>(pub T);
 
 #[async_trait]
 impl<T, S> FromRequestParts<S> for ValidatedSearchQueryParameters<T>
 where
-    T: DeserializeOwned + Validate + Serialize,
+    T: DeserializeOwned
+        + for<'a> ValidateArgs<'a, Args = &'a ServerConfig>
+        + PaginatedParameter
+        + Serialize,
+    ServerConfig: FromRef<S>,
     S: Send + Sync,
 {
-    type Rejection = (StatusCode, Json<SearchResultResponse>);
+    type Rejection = ApiError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        let query = parts.uri.query().unwrap_or_default();
-        let value: T = serde_structuredqs::from_str(query).map_err(|rejection| {
-            tracing::error!("Parsing error: {}", rejection);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(SearchResultResponse::error(
-                    &Value::Null,
-                    format!("invalid format query string: [{}]", rejection),
-                )),
-            )
-        })?;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let config = ServerConfig::from_ref(state);
+        let value = parse_search_query(parts.uri.query().unwrap_or_default(), &config)?;
+
+        Ok(ValidatedSearchQueryParameters(value))
+    }
+}
+
+/// クエリ文字列`query`を検索パラメータにデシリアライズし、サーバの設定`config`を使ってバリデーションする関数
+///
+/// 保存された検索のように、リクエスト以外から取り出したクエリ文字列を検索パラメータにするときにも使う。
+pub fn parse_search_query<T>(query: &str, config: &ServerConfig) -> Result<T, ApiError>
+where
+    T: DeserializeOwned + for<'a> ValidateArgs<'a, Args = &'a ServerConfig> + PaginatedParameter,
+{
+    let mut value: T = parse_query(query)?;
+
+    value.validate_args(config).map_err(|rejection| {
+        tracing::error!("Validation error: {}", rejection);
+        ApiError::from(rejection)
+    })?;
+    value.limit_mut().get_or_insert(config.default_rows);
+
+    Ok(value)
+}
+
+/// クエリ文字列をデシリアライズしてバリデーションしたパラメータ
+///
+/// サーバの設定に依存しないパラメータに使う。
+pub struct ValidatedQueryParameters<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQueryParameters<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let value: T = parse_query(parts.uri.query().unwrap_or_default())?;
 
         value.validate().map_err(|rejection| {
             tracing::error!("Validation error: {}", rejection);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(SearchResultResponse::error(
-                    &value,
-                    format!("Validation error: [{}]", rejection).replace('\n', ", "),
-                )),
-            )
+            ApiError::from(rejection)
         })?;
 
-        Ok(ValidatedSearchQueryParameters(value))
+        Ok(ValidatedQueryParameters(value))
     }
 }
 
+// クエリ文字列をデシリアライズする関数
+fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, ApiError> {
+    serde_structuredqs::from_str(query).map_err(|rejection| {
+        tracing::error!("Parsing error: {}", rejection);
+        ApiError::validation_error(
+            format!("invalid format query string: [{}]", rejection),
+            Vec::new(),
+        )
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
-    fn test_deserialize() {
-        let query = "keyword=OR&facet=category,difficulty&filter.category=ABC,ARC&filter.difficulty.from=800&sort=-score";
-        let params: SearchQueryParameters = serde_structuredqs::from_str(query).unwrap();
-
-        let expected = SearchQueryParameters {
-            keyword: Some(String::from("OR")),
-            limit: None,
-            page: None,
-            filter: Some(FilterParameters {
-                category: Some(vec![String::from("ABC"), String::from("ARC")]),
-                difficulty: Some(RangeFilterParameter {
-                    from: Some(800),
-                    to: None,
-                }),
-            }),
-            sort: Some(String::from("-score")),
-            facet: Some(vec![String::from("category"), String::from("difficulty")]),
-        };
+    fn test_json_facet() {
+        assert_eq!(json_facet(BTreeMap::new()), "");
+        assert_eq!(term_facet_limit(&[("country", 300)], "country"), 300);
+        assert_eq!(
+            term_facet_limit(&[("country", 300)], "affiliation"),
+            DEFAULT_TERM_FACET_LIMIT
+        );
 
-        assert_eq!(params, expected);
+        let facets = BTreeMap::from([
+            (String::from("affiliation"), term_facet("affiliation", 200)),
+            (
+                String::from("huge"),
+                json!({ "type": "query", "q": "x".repeat(MAX_JSON_FACET_LENGTH) }),
+            ),
+            (String::from("rating_stats"), stats_facet("rating")),
+        ]);
+        let facet: BTreeMap<String, Value> = serde_json::from_str(&json_facet(facets)).unwrap();
+        assert_eq!(
+            facet.keys().collect::<Vec<_>>(),
+            vec!["affiliation", "rating_stats"]
+        );
+        assert_eq!(facet["affiliation"]["limit"], json!(200));
     }
 
     #[test]
-    fn empty_query_string() {
-        let params: SearchQueryParameters = serde_structuredqs::from_str("").unwrap();
-        let expected = SearchQueryParameters {
-            keyword: None,
-            limit: None,
-            page: None,
-            filter: None,
-            sort: None,
-            facet: None,
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("6000"), Some(6000));
+        assert_eq!(parse_duration("100m"), Some(6000));
+        assert_eq!(parse_duration("1h40m"), Some(6000));
+        assert_eq!(parse_duration("2h"), Some(7200));
+        assert_eq!(parse_duration(" 90s "), Some(90));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("-60"), None);
+        assert_eq!(parse_duration("100min"), None);
+        assert_eq!(parse_duration("40m1h"), None);
+
+        let parameter = DurationFilterParameter {
+            from: Some(String::from("100m")),
+            to: None,
+        };
+        assert_eq!(
+            parameter.to_filter("duration").unwrap().to_fq(None),
+            "duration:[6000 TO *}"
+        );
+        let parameter = DurationFilterParameter {
+            from: None,
+            to: Some(String::from("3h")),
+        };
+        assert_eq!(
+            parameter.to_filter("duration").unwrap().to_fq(None),
+            "duration:[* TO 10800}"
+        );
+    }
+
+    #[test]
+    fn test_resolve_facet_range() {
+        let default = FacetRange {
+            start: 0,
+            end: 4000,
+            gap: 400,
+        };
+        let parameter = RangeFacetParameter {
+            start: Some(800),
+            end: None,
+            gap: Some(100),
         };
 
-        assert_eq!(params, expected);
+        let range = RangeFacetParameter::resolve(Some(&parameter), default);
+        assert_eq!(
+            range,
+            FacetRange {
+                start: 800,
+                end: 4000,
+                gap: 100
+            }
+        );
+        assert!(range.check().is_ok());
+        assert_eq!(RangeFacetParameter::resolve(None, default), default);
+    }
+
+    #[test]
+    fn test_check_facet_range() {
+        let check = |start, end, gap| FacetRange { start, end, gap }.check().map_err(|e| e.code);
+
+        assert_eq!(
+            check(4000, 0, 400),
+            Err(Cow::from("start must be less than end"))
+        );
+        assert_eq!(check(0, 4000, 0), Err(Cow::from("gap must be positive")));
+        assert_eq!(check(0, 4000, 1), Err(Cow::from("too many buckets")));
+        assert_eq!(check(0, 4000, 40), Ok(()));
+    }
+
+    #[test]
+    fn test_stats_facet() {
+        assert_eq!(
+            stats_facet("difficulty"),
+            json!({
+                "type": "query",
+                "q": "*:*",
+                "facet": {
+                    "min": "min(difficulty)",
+                    "max": "max(difficulty)",
+                    "avg": "avg(difficulty)",
+                    "percentiles": "percentile(difficulty,25,50,75)",
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_sort_expression() {
+        let sort = Some(vec![String::from("-difficulty"), String::from("start_at")]);
+
+        assert_eq!(
+            to_sort_expression(&sort, "problem_id"),
+            "difficulty desc,start_at asc,problem_id asc"
+        );
+        assert_eq!(
+            to_sort_expression(&None, "problem_id"),
+            "score desc,problem_id asc"
+        );
+    }
+
+    #[test]
+    fn test_validate_sort_keys() {
+        let valid = HashSet::from(["difficulty", "-difficulty", "start_at"]);
+        let allowed = ["difficulty", "-difficulty", "start_at"];
+
+        assert!(validate_sort_keys(
+            &[String::from("-difficulty"), String::from("start_at")],
+            &valid,
+            &allowed
+        )
+        .is_ok());
+        assert_eq!(
+            validate_sort_keys(&[String::from("title")], &valid, &allowed)
+                .unwrap_err()
+                .code,
+            "invalid sort field"
+        );
+        assert_eq!(
+            validate_sort_keys(
+                &[String::from("difficulty"), String::from("-difficulty")],
+                &valid,
+                &allowed
+            )
+            .unwrap_err()
+            .code,
+            "duplicated sort field"
+        );
+    }
+
+    #[test]
+    fn test_response_fields() {
+        let field_list = "problem_id,problem_title,difficulty";
+
+        assert!(validate_response_fields(
+            &[String::from("difficulty"), String::from("problem_id")],
+            field_list
+        )
+        .is_ok());
+        assert_eq!(
+            validate_response_fields(&[String::from("score")], field_list)
+                .unwrap_err()
+                .code,
+            "invalid response field"
+        );
+
+        assert_eq!(
+            select_field_list(
+                &Some(vec![String::from("difficulty"), String::from("problem_id")]),
+                field_list
+            ),
+            "problem_id,difficulty"
+        );
+        assert_eq!(select_field_list(&Some(vec![]), field_list), field_list);
+        assert_eq!(select_field_list(&None, field_list), field_list);
+    }
+
+    #[test]
+    fn test_canonical_values() {
+        assert_eq!(
+            canonical_keyword(&Some(String::from("  ＡＢＣ\u{3000}300   D "))),
+            Some(String::from("ABC 300 D"))
+        );
+        assert_eq!(canonical_keyword(&Some(String::from(" \t"))), None);
+        assert_eq!(canonical_keyword(&None), None);
+
+        assert_eq!(
+            canonical_values(&Some(vec![
+                String::from("difficulty"),
+                String::from("category"),
+                String::from("difficulty"),
+            ])),
+            Some(vec![String::from("category"), String::from("difficulty")])
+        );
+        assert_eq!(canonical_values(&Some(vec![])), None);
+    }
+
+    #[test]
+    fn test_term_filter_queries() {
+        let values = vec![
+            String::from("ABC"),
+            String::from("-AHC"),
+            String::from("Other Sponsored"),
+        ];
+
+        assert_eq!(
+            term_filter_queries("category", &values),
+            vec![
+                String::from(r#"{!tag=category}category:("ABC" OR "Other Sponsored")"#),
+                String::from(r#"{!tag=category}-category:("AHC")"#),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_term_filter_queries_only_negation() {
+        let values = vec![String::from("-gray"), String::from("-brown")];
+
+        assert_eq!(
+            term_filter_queries("color", &values),
+            vec![String::from(r#"{!tag=color}-color:("gray" OR "brown")"#)]
+        );
     }
 }
@@ -8,9 +8,11 @@ pub struct Contest {
     pub title: String,
     pub rate_change: String,
     pub category: String,
+    pub status: String,
 }
 
-#[derive(Debug, FromRow, Type)]
+#[allow(dead_code)]
+#[derive(Debug, FromRow)]
 pub struct Problem {
     pub problem_id: String,
     pub contest_id: String,
@@ -18,8 +20,9 @@ pub struct Problem {
     pub name: String,
     pub title: String,
     pub url: String,
-    pub html: String,
-    pub difficulty: i32,
+    pub html: Option<String>,
+    pub html_key: Option<String>,
+    pub difficulty: Option<i32>,
 }
 
 #[derive(Debug, FromRow)]
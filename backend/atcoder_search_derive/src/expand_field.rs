@@ -1,7 +1,8 @@
 use crate::helper;
 use proc_macro2::TokenStream;
-use quote::format_ident;
-use syn::{punctuated::Punctuated, AttrStyle, DeriveInput, Ident, Meta, Token};
+use syn::{
+    punctuated::Punctuated, AttrStyle, DeriveInput, Expr, ExprLit, Ident, Lit, Meta, Path, Token,
+};
 
 pub fn impl_expand_field(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input.into()).expect("failed to parse input token stream");
@@ -15,9 +16,9 @@ pub fn impl_expand_field(input: TokenStream) -> TokenStream {
         .map(|field| {
             let ident = &field.ident.to_owned().unwrap();
             let ty = &field.ty;
-            let ident_str = ident.to_string();
             let attrs = &field.attrs;
 
+            let name = helper::serde_rename(attrs).unwrap_or_else(|| ident.to_string());
             let suffixes = attrs
                 .iter()
                 .filter_map(|attr| {
@@ -26,14 +27,13 @@ pub fn impl_expand_field(input: TokenStream) -> TokenStream {
                             AttrStyle::Outer => match &attr.meta {
                                 Meta::List(metalist) => {
                                     let parser =
-                                        Punctuated::<Ident, Token![,]>::parse_separated_nonempty;
+                                        Punctuated::<Meta, Token![,]>::parse_separated_nonempty;
                                     Some(
                                         metalist
                                             .parse_args_with(parser)
                                             .expect("couldn't parse field attribute")
                                             .iter()
-                                            .cloned()
-                                            .map(|suffix| suffix)
+                                            .map(parse_suffix)
                                             .collect::<Vec<_>>(),
                                     )
                                 }
@@ -45,46 +45,108 @@ pub fn impl_expand_field(input: TokenStream) -> TokenStream {
                         None
                     }
                 })
-                .flat_map(|s| s)
+                .flatten()
                 .collect::<Vec<_>>();
 
-            if suffixes.is_empty() {
-                if helper::is_contained_by(ty, "DateTime") {
-                    vec![quote::quote! {
-                        #ident_str: self.#ident.with_timezone(&chrono::Utc).to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-                    }]
-                } else {
-                    vec![quote::quote! {
-                        #ident_str: self.#ident,
-                    }]
+            // 変換関数が指定されていないサフィックス付きフィールドには、元のフィールドと同じ値を入れる。
+            // copyFieldモードではSolrが複製するので出力しない
+            let mut names = Vec::new();
+            let mut transformed = Vec::new();
+            for (suffix, transformer) in suffixes.iter() {
+                let suffixed_name = format!("{}__{}", name, suffix);
+                match transformer {
+                    Some(transformer) => transformed.push(quote::quote! {
+                        map.insert(String::from(#suffixed_name), serde_json::json!(#transformer(value)));
+                    }),
+                    None => names.push(suffixed_name),
                 }
+            }
+
+            // Optionのフィールドは値がNoneのとき出力しない
+            let inner_ty = helper::unwrap_generic_type(ty, "Option");
+            let expanded = if helper::is_contained_by(inner_ty, "DateTime") {
+                quote::quote! {
+                    serde_json::Value::from(value.with_timezone(&chrono::Utc).to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                }
+            } else {
+                quote::quote! {
+                    serde_json::json!(value)
+                }
+            };
+            let copies = if names.is_empty() {
+                quote::quote! {}
             } else {
-                let mut expanded_field_assignations: Vec<TokenStream> = suffixes
-                    .iter()
-                    .map(|suffix| {
-                        let suffixed_ident_str = format_ident!("{}__{}", ident, suffix).to_string();
+                quote::quote! {
+                    if mode == ExpandMode::Inline {
+                        #(map.insert(String::from(#names), expanded.clone());)*
+                    }
+                }
+            };
+            let insertions = quote::quote! {
+                #(#transformed)*
+                let expanded = #expanded;
+                #copies
+                map.insert(String::from(#name), expanded);
+            };
 
-                        quote::quote! {
-                            #suffixed_ident_str: self.#ident,
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                expanded_field_assignations.push(quote::quote! {
-                    #ident_str: self.#ident,
-                });
-                expanded_field_assignations
+            if helper::is_option(ty) {
+                quote::quote! {
+                    if let Some(value) = &self.#ident {
+                        #insertions
+                    }
+                }
+            } else {
+                quote::quote! {
+                    {
+                        let value = &self.#ident;
+                        #insertions
+                    }
+                }
             }
         })
-        .flat_map(|s| s)
         .collect::<Vec<_>>();
 
     quote::quote! {
         impl ExpandField for #struct_name {
-            fn expand(&self) -> serde_json::Value {
-                serde_json::json!({
-                    #(#setters)*
-                })
+            #[allow(unused_variables)]
+            fn expand_with(&self, mode: ExpandMode) -> serde_json::Value {
+                let mut map = serde_json::Map::new();
+                #(#setters)*
+                serde_json::Value::Object(map)
             }
         }
     }
 }
+
+// `#[suffix(...)]`の要素をサフィックス名と変換関数のパスに分解する関数
+//
+// `text_ja`のような名前だけの要素と、`text_reading = "to_reading"`のように変換関数を指定する要素を受け付ける。
+// 変換関数はフィールドの値の参照を受け取り、シリアライズ可能な値を返す関数でなければならない。
+fn parse_suffix(meta: &Meta) -> (Ident, Option<Path>) {
+    match meta {
+        Meta::Path(path) => (
+            path.get_ident()
+                .expect("suffix name must be an identifier")
+                .clone(),
+            None,
+        ),
+        Meta::NameValue(name_value) => {
+            let suffix = name_value
+                .path
+                .get_ident()
+                .expect("suffix name must be an identifier")
+                .clone();
+            let transformer = match &name_value.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(transformer),
+                    ..
+                }) => transformer
+                    .parse::<Path>()
+                    .expect("transformer must be a path to a function"),
+                _ => panic!("transformer must be a string literal"),
+            };
+            (suffix, Some(transformer))
+        }
+        _ => panic!("couldn't parse field attribute"),
+    }
+}
@@ -1,28 +1,100 @@
 use crate::helper;
 use proc_macro2::TokenStream;
-use syn::DeriveInput;
+use syn::{punctuated::Punctuated, Attribute, DeriveInput, Expr, ExprLit, Lit, Meta, Token, Type};
+
+// `#[field_list(...)]`で指定されたフィールドの扱い
+enum FieldEntry {
+    Name(String),
+    Flatten(Type),
+}
 
 pub fn impl_field_list(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input.into()).expect("failed to parse input token stream");
 
     let struct_name = &ast.ident;
-    let fields = helper::extract_fields(&ast.data)
+    let entries = helper::extract_fields(&ast.data)
         .named
         .iter()
         .filter_map(|field| {
-            field
-                .ident
-                .to_owned()
-                .and_then(|ident| Some(ident.to_string()))
+            let ident = field.ident.to_owned()?;
+            let mut name = ident.to_string();
+            for meta in field_list_attributes(&field.attrs) {
+                match meta {
+                    Meta::Path(path) if path.is_ident("skip") => return None,
+                    Meta::Path(path) if path.is_ident("flatten") => {
+                        return Some(FieldEntry::Flatten(field.ty.clone()))
+                    }
+                    Meta::NameValue(name_value) if name_value.path.is_ident("rename") => {
+                        match name_value.value {
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Str(rename),
+                                ..
+                            }) => name = rename.value(),
+                            _ => panic!("rename must be a string literal"),
+                        }
+                    }
+                    _ => panic!("unknown field_list attribute"),
+                }
+            }
+            Some(FieldEntry::Name(name))
         })
-        .collect::<Vec<String>>();
-    let field_list = fields.join(",");
+        .collect::<Vec<_>>();
+
+    // フラット化するフィールドが無ければ、コンパイル時にフィールドリストの文字列を確定させる
+    if entries
+        .iter()
+        .all(|entry| matches!(entry, FieldEntry::Name(_)))
+    {
+        let field_list = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                FieldEntry::Name(name) => Some(name.as_str()),
+                FieldEntry::Flatten(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        return quote::quote! {
+            impl FieldList for #struct_name {
+                fn field_list() -> &'static str {
+                    #field_list
+                }
+            }
+        };
+    }
+
+    let parts = entries
+        .iter()
+        .map(|entry| match entry {
+            FieldEntry::Name(name) => quote::quote! { #name },
+            FieldEntry::Flatten(ty) => quote::quote! { <#ty as FieldList>::field_list() },
+        })
+        .collect::<Vec<_>>();
 
     quote::quote! {
         impl FieldList for #struct_name {
             fn field_list() -> &'static str {
-                #field_list
+                static FIELD_LIST: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+                FIELD_LIST.get_or_init(|| {
+                    [#(#parts),*]
+                        .into_iter()
+                        .filter(|part| !part.is_empty())
+                        .collect::<Vec<&str>>()
+                        .join(",")
+                })
             }
         }
     }
 }
+
+// フィールドに付与された`#[field_list(...)]`の要素を取り出す関数
+fn field_list_attributes(attrs: &[Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("field_list"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("couldn't parse field attribute")
+        })
+        .collect()
+}
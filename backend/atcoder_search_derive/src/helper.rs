@@ -1,6 +1,7 @@
 use syn::{
-    AngleBracketedGenericArguments, Data, Fields, FieldsNamed, GenericArgument, Path,
-    PathArguments, PathSegment, Type, TypePath,
+    punctuated::Punctuated, AngleBracketedGenericArguments, Attribute, Data, Expr, ExprLit, Fields,
+    FieldsNamed, GenericArgument, Lit, Meta, Path, PathArguments, PathSegment, Token, Type,
+    TypePath,
 };
 
 #[allow(dead_code)]
@@ -74,7 +75,7 @@ pub fn unwrap_generic_type<T: AsRef<str>>(ty: &Type, container_type: T) -> &Type
                     ref args,
                     gt_token: _,
                 }) => args.first().and_then(|a| match a {
-                    &GenericArgument::Type(ref inner_ty) => Some(inner_ty),
+                    GenericArgument::Type(inner_ty) => Some(inner_ty),
                     _ => None,
                 }),
                 _ => None,
@@ -106,3 +107,27 @@ pub fn extract_fields(data: &Data) -> &FieldsNamed {
         _ => panic!("struct expected, but got other item."),
     }
 }
+
+// `#[serde(rename = "...")]`で指定されたフィールド名を取り出す関数
+pub fn serde_rename(attrs: &[Attribute]) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .find_map(|meta| match meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("rename") => {
+                match name_value.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(rename),
+                        ..
+                    }) => Some(rename.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+}
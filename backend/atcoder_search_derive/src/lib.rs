@@ -1,17 +1,24 @@
 mod expand_field;
 mod field_list;
 mod helper;
+mod solr_schema;
 
 use expand_field::impl_expand_field;
 use field_list::impl_field_list;
 use proc_macro::TokenStream;
+use solr_schema::impl_solr_schema;
 
-#[proc_macro_derive(FieldList)]
+#[proc_macro_derive(FieldList, attributes(field_list))]
 pub fn derive_field_list(input: TokenStream) -> TokenStream {
     impl_field_list(input.into()).into()
 }
 
-#[proc_macro_derive(ExpandField, attributes(suffix))]
+#[proc_macro_derive(ExpandField, attributes(suffix, serde))]
 pub fn derive_expand_field(input: TokenStream) -> TokenStream {
     impl_expand_field(input.into()).into()
 }
+
+#[proc_macro_derive(SolrSchema, attributes(suffix, solr, serde))]
+pub fn derive_solr_schema(input: TokenStream) -> TokenStream {
+    impl_solr_schema(input.into()).into()
+}
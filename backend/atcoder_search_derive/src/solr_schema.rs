@@ -0,0 +1,177 @@
+use crate::helper;
+use proc_macro2::TokenStream;
+use syn::{punctuated::Punctuated, Attribute, DeriveInput, Expr, ExprLit, Lit, Meta, Token, Type};
+
+// `#[solr(...)]`で上書きできるフィールドのプロパティ
+#[derive(Default)]
+struct FieldOptions {
+    field_type: Option<String>,
+    indexed: Option<bool>,
+    stored: Option<bool>,
+    doc_values: Option<bool>,
+}
+
+pub fn impl_solr_schema(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input.into()).expect("failed to parse input token stream");
+
+    let struct_name = &ast.ident;
+    let fields = helper::extract_fields(&ast.data);
+
+    let mut suffixes: Vec<String> = Vec::new();
+    let mut copied: Vec<(String, String)> = Vec::new();
+    let definitions = fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.to_owned().unwrap();
+            let name = helper::serde_rename(&field.attrs).unwrap_or_else(|| ident.to_string());
+            let options = field_options(&field.attrs);
+
+            for suffix in suffix_names(&field.attrs) {
+                if !suffixes.contains(&suffix) {
+                    suffixes.push(suffix);
+                }
+            }
+            for suffix in copied_suffix_names(&field.attrs) {
+                copied.push((name.clone(), suffix));
+            }
+
+            // Option<T>は必須でないフィールド、Vec<T>は複数値のフィールドとする
+            let required = !helper::is_option(&field.ty);
+            let ty = helper::unwrap_generic_type(&field.ty, "Option");
+            let multi_valued = helper::is_vec(ty);
+            let ty = helper::unwrap_vec(ty);
+
+            let field_type = options.field_type.unwrap_or_else(|| {
+                solr_type(ty).map(String::from).unwrap_or_else(|| {
+                    panic!(
+                        "couldn't map the type of field `{}` to Solr field type. specify it with #[solr(field_type = \"...\")]",
+                        ident
+                    )
+                })
+            });
+            let indexed = options.indexed.unwrap_or(true);
+            let stored = options.stored.unwrap_or(true);
+            let doc_values = match options.doc_values {
+                Some(doc_values) => quote::quote! { Some(#doc_values) },
+                None => quote::quote! { None },
+            };
+
+            quote::quote! {
+                SolrSchemaField {
+                    name: String::from(#name),
+                    field_type: String::from(#field_type),
+                    indexed: Some(#indexed),
+                    stored: Some(#stored),
+                    required: Some(#required),
+                    multi_valued: Some(#multi_valued),
+                    doc_values: #doc_values,
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (copied_names, copied_suffixes): (Vec<_>, Vec<_>) = copied.into_iter().unzip();
+    quote::quote! {
+        impl SolrSchema for #struct_name {
+            fn fields() -> Vec<SolrSchemaField> {
+                vec![#(#definitions)*]
+            }
+
+            fn suffixes() -> Vec<&'static str> {
+                vec![#(#suffixes),*]
+            }
+
+            fn copied_suffixes() -> Vec<(&'static str, &'static str)> {
+                vec![#((#copied_names, #copied_suffixes)),*]
+            }
+        }
+    }
+}
+
+// Rustの型に対応するSolrのフィールド型の名前を返す関数
+//
+// フィールド型の名前は`middleware/solr/*/conf/schema.xml`で定義しているものに合わせる。
+fn solr_type(ty: &Type) -> Option<&'static str> {
+    if helper::type_is_in(ty, &["i8", "i16", "i32", "u8", "u16"]) {
+        Some("i32")
+    } else if helper::type_is_in(ty, &["i64", "u32", "u64"]) {
+        Some("i64")
+    } else if helper::type_is(ty, "f32") {
+        Some("f32")
+    } else if helper::type_is(ty, "f64") {
+        Some("f64")
+    } else if helper::is_bool(ty) {
+        Some("bool")
+    } else if helper::is_string(ty) {
+        Some("String")
+    } else if helper::is_contained_by(ty, "DateTime") {
+        Some("DateTime")
+    } else {
+        None
+    }
+}
+
+// フィールドに付与された`#[solr(...)]`を解析する関数
+fn field_options(attrs: &[Attribute]) -> FieldOptions {
+    let mut options = FieldOptions::default();
+    for meta in attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("solr"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("couldn't parse field attribute")
+        })
+    {
+        let Meta::NameValue(name_value) = meta else {
+            panic!("solr attribute must be a form of `key = value`");
+        };
+        let Expr::Lit(ExprLit { lit, .. }) = name_value.value else {
+            panic!("solr attribute value must be a literal");
+        };
+        let key = name_value
+            .path
+            .get_ident()
+            .map(|ident| ident.to_string())
+            .unwrap_or_default();
+        match (key.as_str(), lit) {
+            ("field_type", Lit::Str(value)) => options.field_type = Some(value.value()),
+            ("indexed", Lit::Bool(value)) => options.indexed = Some(value.value),
+            ("stored", Lit::Bool(value)) => options.stored = Some(value.value),
+            ("doc_values", Lit::Bool(value)) => options.doc_values = Some(value.value),
+            _ => panic!("unknown solr attribute `{}`", key),
+        }
+    }
+    options
+}
+
+// `#[suffix(...)]`で指定されたサフィックス名を取り出す関数
+//
+// `ExpandField`の変換関数の指定(`text_reading = "to_reading"`)はスキーマに関係しないので無視する。
+fn suffix_names(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("suffix"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("couldn't parse field attribute")
+        })
+        .filter_map(|meta| meta.path().get_ident().map(|ident| ident.to_string()))
+        .collect()
+}
+
+// `#[suffix(...)]`のうち変換関数が指定されていない、元のフィールドと同じ値を持つサフィックス名を取り出す関数
+fn copied_suffix_names(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("suffix"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .expect("couldn't parse field attribute")
+        })
+        .filter_map(|meta| match meta {
+            Meta::Path(path) => path.get_ident().map(|ident| ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
@@ -1,3 +1,23 @@
+pub mod filter;
+pub mod v2;
+
+pub use filter::FilterExpr;
+
+use crate::solr::model::{
+    SolrExplanation, SolrRangeFacetCount, SolrStatsFacetCount, SolrTermFacetCount,
+};
+use crate::{language::Language, query_expansion::QueryExpansion};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
 pub trait ToQueryParameter {
     fn to_query(&self) -> Vec<(String, String)>;
 }
@@ -5,3 +25,432 @@ pub trait ToQueryParameter {
 pub trait FieldList {
     fn field_list() -> &'static str;
 }
+
+/// 検索APIのレスポンス
+#[derive(Debug, Serialize)]
+pub struct SearchResultResponse<D, F> {
+    pub stats: SearchResultStats<F>,
+    pub items: Vec<D>,
+}
+
+/// 検索結果の統計情報
+#[derive(Debug, Serialize)]
+pub struct SearchResultStats<F> {
+    pub time: u32,
+    pub total: u32,
+    pub index: u32,
+    pub pages: u32,
+    pub count: u32,
+    pub params: Value,
+    pub facet: Option<F>,
+    /// キーワードから判定した言語
+    pub language: Option<Language>,
+    /// キーワードに適用した同義語展開
+    pub expansions: Vec<QueryExpansion>,
+    /// キーワードを`abc300 d`のような問題の指定として解釈して絞り込んだかどうか
+    pub shortcut: bool,
+    /// `debug=true`のときのドキュメントごとのスコアの内訳
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explain: Option<BTreeMap<String, SolrExplanation>>,
+}
+
+/// 文字列フィールドのファセットカウントの値ごとの件数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldFacetEntry {
+    pub label: String,
+    pub count: u32,
+}
+
+/// 文字列フィールドのファセットカウント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldFacetCount {
+    pub counts: Vec<FieldFacetEntry>,
+}
+
+impl From<SolrTermFacetCount> for FieldFacetCount {
+    fn from(facet: SolrTermFacetCount) -> Self {
+        Self {
+            counts: facet
+                .buckets
+                .into_iter()
+                .map(|bucket| FieldFacetEntry {
+                    label: bucket.val,
+                    count: bucket.count,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 範囲ファセットカウントの区間`[begin, end)`ごとの件数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RangeFacetEntry {
+    pub begin: i32,
+    pub end: i32,
+    pub count: u32,
+}
+
+/// 数値フィールドの範囲ファセットカウント
+///
+/// `before`/`after`は全区間より前/後の件数、`between`は全区間に含まれる件数を表す。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RangeFacetCount {
+    pub counts: Vec<RangeFacetEntry>,
+    pub before: Option<u32>,
+    pub after: Option<u32>,
+    pub between: Option<u32>,
+}
+
+impl RangeFacetCount {
+    // Solrのレスポンスには区間の上端が含まれないので、区間の幅`gap`から計算する
+    pub fn from_solr(facet: SolrRangeFacetCount<i32>, gap: i32) -> Self {
+        Self {
+            counts: facet
+                .buckets
+                .into_iter()
+                .map(|bucket| RangeFacetEntry {
+                    begin: bucket.val,
+                    end: bucket.val + gap,
+                    count: bucket.count,
+                })
+                .collect(),
+            before: facet.before.map(|info| info.count),
+            after: facet.after.map(|info| info.count),
+            between: facet.between.map(|info| info.count),
+        }
+    }
+}
+
+/// パーセンタイル値
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PercentileValue {
+    pub percentile: u32,
+    pub value: f64,
+}
+
+/// 数値フィールドの統計量
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct StatsFacetCount {
+    pub count: u32,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub avg: Option<f64>,
+    pub percentiles: Vec<PercentileValue>,
+}
+
+impl StatsFacetCount {
+    // `percentiles`はSolrに要求したパーセンタイルと同じ順序で与える
+    pub fn from_solr(facet: SolrStatsFacetCount, percentiles: &[u32]) -> Self {
+        Self {
+            count: facet.count,
+            min: facet.min,
+            max: facet.max,
+            avg: facet.avg,
+            percentiles: percentiles
+                .iter()
+                .zip(facet.percentiles.unwrap_or_default())
+                .map(|(&percentile, value)| PercentileValue { percentile, value })
+                .collect(),
+        }
+    }
+}
+
+/// APIのエラーレスポンスに含める機械可読なエラーコード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    ValidationError,
+    SolrUnavailable,
+    RateLimited,
+    NotFound,
+    Forbidden,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ErrorCode::ValidationError => StatusCode::BAD_REQUEST,
+            ErrorCode::SolrUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// バリデーションに失敗したフィールドの情報
+///
+/// `field`はクエリパラメータと同じドット区切りのパス(例: `filter.category`)で表す。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub constraint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, Value>,
+}
+
+// バリデーションエラーのツリーを辿ってフィールドごとのエラー情報に平坦化する関数
+fn collect_field_errors(path: &str, errors: &ValidationErrors, details: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", path, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    let mut params: BTreeMap<String, Value> = error
+                        .params
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.clone()))
+                        .collect();
+                    let value = params.remove("value");
+                    details.push(FieldError {
+                        field: path.clone(),
+                        constraint: error.code.to_string(),
+                        value,
+                        message: error.message.as_ref().map(|message| message.to_string()),
+                        params,
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&path, errors, details),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_field_errors(&format!("{}.{}", path, index), errors, details);
+                }
+            }
+        }
+    }
+}
+
+/// APIのエラーレスポンス
+///
+/// `{"error": {"code": ..., "message": ..., "details": [...]}}`の形式でシリアライズされる。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn validation_error(message: impl ToString, details: Vec<FieldError>) -> Self {
+        Self {
+            code: ErrorCode::ValidationError,
+            message: message.to_string(),
+            details,
+        }
+    }
+
+    pub fn solr_unavailable(message: impl ToString) -> Self {
+        Self::new(ErrorCode::SolrUnavailable, message)
+    }
+
+    pub fn rate_limited(message: impl ToString) -> Self {
+        Self::new(ErrorCode::RateLimited, message)
+    }
+
+    pub fn not_found(message: impl ToString) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn forbidden(message: impl ToString) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn internal_error(message: impl ToString) -> Self {
+        Self::new(ErrorCode::InternalError, message)
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut details: Vec<FieldError> = Vec::new();
+        collect_field_errors("", &errors, &mut details);
+        details.sort_by(|a, b| a.field.cmp(&b.field));
+
+        Self::validation_error("invalid request parameters", details)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.code.status(), Json(json!({ "error": self }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Parameter {
+        #[validate(range(min = 1, max = 200))]
+        limit: u32,
+        #[validate(length(max = 3))]
+        keyword: String,
+        #[validate]
+        filter: Option<Filter>,
+    }
+
+    #[derive(Validate)]
+    struct Filter {
+        #[validate(range(min = 0))]
+        from: i32,
+    }
+
+    #[test]
+    fn test_field_facet_count_from_solr() {
+        let facet: SolrTermFacetCount = serde_json::from_value(json!({
+            "buckets": [{"val": "ABC", "count": 10}, {"val": "ARC", "count": 3}]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            FieldFacetCount::from(facet),
+            FieldFacetCount {
+                counts: vec![
+                    FieldFacetEntry {
+                        label: String::from("ABC"),
+                        count: 10
+                    },
+                    FieldFacetEntry {
+                        label: String::from("ARC"),
+                        count: 3
+                    },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_range_facet_count_from_solr() {
+        let facet: SolrRangeFacetCount<i32> = serde_json::from_value(json!({
+            "buckets": [{"val": 0, "count": 10}, {"val": 400, "count": 3}],
+            "before": {"count": 1},
+            "after": {"count": 2},
+            "between": {"count": 13}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            RangeFacetCount::from_solr(facet, 400),
+            RangeFacetCount {
+                counts: vec![
+                    RangeFacetEntry {
+                        begin: 0,
+                        end: 400,
+                        count: 10
+                    },
+                    RangeFacetEntry {
+                        begin: 400,
+                        end: 800,
+                        count: 3
+                    },
+                ],
+                before: Some(1),
+                after: Some(2),
+                between: Some(13),
+            }
+        );
+    }
+
+    #[test]
+    fn test_stats_facet_count_from_solr() {
+        let facet: SolrStatsFacetCount = serde_json::from_value(json!({
+            "count": 3, "min": 1.0, "max": 3.0, "avg": 2.0, "percentiles": [1.0, 2.0, 3.0]
+        }))
+        .unwrap();
+
+        let stats = StatsFacetCount::from_solr(facet, &[25, 50, 75]);
+        assert_eq!(
+            stats.percentiles[1],
+            PercentileValue {
+                percentile: 50,
+                value: 2.0
+            }
+        );
+        assert_eq!(stats.max, Some(3.0));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let error = ApiError::solr_unavailable("core problems is not available");
+        assert_eq!(
+            serde_json::to_string(&json!({ "error": error })).unwrap(),
+            r#"{"error":{"code":"solr_unavailable","message":"core problems is not available"}}"#
+        );
+    }
+
+    #[test]
+    fn test_from_validation_errors() {
+        let parameter = Parameter {
+            limit: 0,
+            keyword: String::from("abcd"),
+            filter: Some(Filter { from: -1 }),
+        };
+        let error = ApiError::from(parameter.validate().unwrap_err());
+
+        assert_eq!(error.code, ErrorCode::ValidationError);
+        assert_eq!(
+            error.details,
+            vec![
+                FieldError {
+                    field: String::from("filter.from"),
+                    constraint: String::from("range"),
+                    value: Some(json!(-1)),
+                    message: None,
+                    params: BTreeMap::from([(String::from("min"), json!(0.0))]),
+                },
+                FieldError {
+                    field: String::from("keyword"),
+                    constraint: String::from("length"),
+                    value: Some(json!("abcd")),
+                    message: None,
+                    params: BTreeMap::from([(String::from("max"), json!(3))]),
+                },
+                FieldError {
+                    field: String::from("limit"),
+                    constraint: String::from("range"),
+                    value: Some(json!(0)),
+                    message: None,
+                    params: BTreeMap::from([
+                        (String::from("max"), json!(200.0)),
+                        (String::from("min"), json!(1.0)),
+                    ]),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        assert_eq!(
+            ApiError::not_found("no route").into_response().status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            ApiError::rate_limited("slow down").into_response().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::solr::query::sanitize;
+use std::fmt;
+
+/// 検索APIの絞り込み条件
+///
+/// ハンドラごとのパラメータをこの形に変換してから、[`FilterExpr::to_fq`]でSolrのfqにする。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterExpr {
+    /// `field`の値が`values`のいずれかに一致する
+    Terms { field: String, values: Vec<String> },
+    /// `field`の値が`[from, to)`の範囲に含まれる。`None`の端は制限しない
+    Range {
+        field: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// 真偽値のフィールドの値が`value`に一致する
+    Bool { field: String, value: bool },
+    /// 条件を満たさない
+    Not(Box<FilterExpr>),
+}
+
+impl FilterExpr {
+    pub fn terms(field: &str, values: &[impl AsRef<str>]) -> Self {
+        FilterExpr::Terms {
+            field: field.to_string(),
+            values: values
+                .iter()
+                .map(|value| value.as_ref().to_string())
+                .collect(),
+        }
+    }
+
+    pub fn range(field: &str, from: Option<impl ToString>, to: Option<impl ToString>) -> Self {
+        FilterExpr::Range {
+            field: field.to_string(),
+            from: from.map(|from| from.to_string()),
+            to: to.map(|to| to.to_string()),
+        }
+    }
+
+    pub fn boolean(field: &str, value: bool) -> Self {
+        FilterExpr::Bool {
+            field: field.to_string(),
+            value,
+        }
+    }
+
+    pub fn negate(self) -> Self {
+        match self {
+            FilterExpr::Not(expr) => *expr,
+            expr => FilterExpr::Not(Box::new(expr)),
+        }
+    }
+
+    /// 値のリストを、含める値の条件と`-`で始まる除外する値の条件に変換する関数
+    ///
+    /// どちらかの値がなければその条件は返さない。
+    pub fn term_set(field: &str, values: &[impl AsRef<str>]) -> Vec<Self> {
+        let mut includes = Vec::new();
+        let mut excludes = Vec::new();
+        for value in values.iter().map(|value| value.as_ref()) {
+            match value.strip_prefix('-') {
+                Some(value) => excludes.push(value),
+                None => includes.push(value),
+            }
+        }
+
+        let mut filters = Vec::new();
+        if !includes.is_empty() {
+            filters.push(FilterExpr::terms(field, &includes));
+        }
+        if !excludes.is_empty() {
+            filters.push(FilterExpr::terms(field, &excludes).negate());
+        }
+        filters
+    }
+
+    /// Solrのfqに変換するメソッド
+    ///
+    /// `tag`を指定すると、ファセットの`excludeTags`で除外できるようにタグを付ける。
+    pub fn to_fq(&self, tag: Option<&str>) -> String {
+        match tag {
+            Some(tag) => format!("{{!tag={}}}{}", tag, self),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FilterExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FilterExpr::Terms { field, values } => {
+                let values = values
+                    .iter()
+                    .map(|value| format!("\"{}\"", sanitize(value)))
+                    .collect::<Vec<String>>()
+                    .join(" OR ");
+                write!(f, "{}:({})", field, values)
+            }
+            FilterExpr::Range { field, from, to } => write!(
+                f,
+                "{}:[{} TO {}}}",
+                field,
+                from.as_deref().unwrap_or("*"),
+                to.as_deref().unwrap_or("*")
+            ),
+            FilterExpr::Bool { field, value } => write!(f, "{}:{}", field, value),
+            FilterExpr::Not(expr) => write!(f, "-{}", expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_fq() {
+        assert_eq!(
+            FilterExpr::terms("category", &["ABC", "ARC"]).to_fq(Some("category")),
+            r#"{!tag=category}category:("ABC" OR "ARC")"#
+        );
+        assert_eq!(
+            FilterExpr::range("difficulty", Some(800), None::<i32>).to_fq(None),
+            "difficulty:[800 TO *}"
+        );
+        assert_eq!(
+            FilterExpr::boolean("is_interactive", true).to_fq(None),
+            "is_interactive:true"
+        );
+        assert_eq!(
+            FilterExpr::boolean("is_experimental", true)
+                .negate()
+                .to_fq(Some("is_experimental")),
+            "{!tag=is_experimental}-is_experimental:true"
+        );
+        assert_eq!(
+            FilterExpr::boolean("has_figures", false).negate().negate(),
+            FilterExpr::boolean("has_figures", false)
+        );
+    }
+
+    #[test]
+    fn test_term_set() {
+        let filters = FilterExpr::term_set("color", &["blue", "-gray", "-brown"]);
+        assert_eq!(
+            filters,
+            vec![
+                FilterExpr::terms("color", &["blue"]),
+                FilterExpr::terms("color", &["gray", "brown"]).negate(),
+            ]
+        );
+        assert_eq!(
+            filters[1].to_fq(Some("color")),
+            r#"{!tag=color}-color:("gray" OR "brown")"#
+        );
+        assert!(FilterExpr::term_set("color", &[] as &[&str]).is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::api::{FieldFacetCount, RangeFacetCount, StatsFacetCount};
+use crate::{language::Language, query_expansion::QueryExpansion, solr::model::SolrExplanation};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// v2の検索APIのレスポンス
+///
+/// v1ではファセットの種類をフィールド名から判断する必要があったので、種類を`type`で明示した配列で返す。
+/// ページングの情報と検索の付帯情報も`stats`から分けて返す。
+#[derive(Debug, Serialize)]
+pub struct SearchResultResponse<D> {
+    pub items: Vec<D>,
+    pub page: Page,
+    pub facets: Vec<Facet>,
+    pub meta: SearchMeta,
+}
+
+/// 検索結果のページングの情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Page {
+    /// ヒットしたドキュメント数
+    pub total: u32,
+    /// 現在のページ番号
+    pub index: u32,
+    /// 総ページ数
+    pub pages: u32,
+    /// このページのドキュメント数
+    pub count: u32,
+}
+
+/// 検索の付帯情報
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchMeta {
+    /// 検索にかかった時間(ミリ秒)
+    pub time: u32,
+    /// 受け付けたリクエストパラメータ
+    pub params: Value,
+    /// キーワードから判定した言語
+    pub language: Option<Language>,
+    /// キーワードに適用した同義語展開
+    pub expansions: Vec<QueryExpansion>,
+    /// キーワードを`abc300 d`のような問題の指定として解釈して絞り込んだかどうか
+    pub shortcut: bool,
+    /// `debug=true`のときのドキュメントごとのスコアの内訳
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub explain: Option<BTreeMap<String, SolrExplanation>>,
+}
+
+/// フィールドのファセットカウント
+///
+/// `{"field": "difficulty", "type": "range", "counts": [...], ...}`の形式でシリアライズされる。
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct Facet {
+    pub field: String,
+    #[serde(flatten)]
+    pub count: FacetCount,
+}
+
+impl Facet {
+    pub fn new(field: impl ToString, count: impl Into<FacetCount>) -> Self {
+        Self {
+            field: field.to_string(),
+            count: count.into(),
+        }
+    }
+}
+
+/// 種類ごとのファセットカウント
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FacetCount {
+    Terms(FieldFacetCount),
+    Range(RangeFacetCount),
+    Stats(StatsFacetCount),
+}
+
+impl From<FieldFacetCount> for FacetCount {
+    fn from(count: FieldFacetCount) -> Self {
+        FacetCount::Terms(count)
+    }
+}
+
+impl From<RangeFacetCount> for FacetCount {
+    fn from(count: RangeFacetCount) -> Self {
+        FacetCount::Range(count)
+    }
+}
+
+impl From<StatsFacetCount> for FacetCount {
+    fn from(count: StatsFacetCount) -> Self {
+        FacetCount::Stats(count)
+    }
+}
+
+impl<D, F> From<super::SearchResultResponse<D, F>> for SearchResultResponse<D>
+where
+    F: Into<Vec<Facet>>,
+{
+    fn from(response: super::SearchResultResponse<D, F>) -> Self {
+        let stats = response.stats;
+        Self {
+            items: response.items,
+            page: Page {
+                total: stats.total,
+                index: stats.index,
+                pages: stats.pages,
+                count: stats.count,
+            },
+            facets: stats.facet.map(Into::into).unwrap_or_default(),
+            meta: SearchMeta {
+                time: stats.time,
+                params: stats.params,
+                language: stats.language,
+                expansions: stats.expansions,
+                shortcut: stats.shortcut,
+                explain: stats.explain,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::api::{FieldFacetEntry, SearchResultStats};
+    use serde_json::json;
+
+    struct Facets(FieldFacetCount);
+
+    impl From<Facets> for Vec<Facet> {
+        fn from(facets: Facets) -> Self {
+            vec![Facet::new("category", facets.0)]
+        }
+    }
+
+    #[test]
+    fn test_from_v1_response() {
+        let response = super::super::SearchResultResponse {
+            stats: SearchResultStats {
+                time: 3,
+                total: 21,
+                index: 2,
+                pages: 2,
+                count: 1,
+                params: json!({"page": 2}),
+                facet: Some(Facets(FieldFacetCount {
+                    counts: vec![FieldFacetEntry {
+                        label: String::from("ABC"),
+                        count: 21,
+                    }],
+                })),
+                language: None,
+                expansions: Vec::new(),
+                shortcut: false,
+                explain: None,
+            },
+            items: vec![json!({"problem_id": "abc300_a"})],
+        };
+
+        let response = SearchResultResponse::from(response);
+        assert_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "items": [{"problem_id": "abc300_a"}],
+                "page": {"total": 21, "index": 2, "pages": 2, "count": 1},
+                "facets": [
+                    {"field": "category", "type": "terms", "counts": [{"label": "ABC", "count": 21}]}
+                ],
+                "meta": {"time": 3, "params": {"page": 2}, "language": null, "expansions": [], "shortcut": false}
+            })
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use std::{fmt, str::FromStr};
+
+/// Names of the rating colors, in ascending order of rating.
+pub const COLOR_NAMES: [&str; 10] = [
+    "gray", "brown", "green", "cyan", "blue", "yellow", "orange", "red", "silver", "gold",
+];
+
+/// Width of the rating range of each color except gold.
+const COLOR_WIDTH: i32 = 400;
+
+/// Rating color of an AtCoder user.
+///
+/// Colors are ordered by the rating they represent, so `Color::Gray < Color::Gold`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Color {
+    Gray,
+    Brown,
+    Green,
+    Cyan,
+    Blue,
+    Yellow,
+    Orange,
+    Red,
+    Silver,
+    Gold,
+}
+
+impl Color {
+    /// All colors in ascending order of rating.
+    pub const ALL: [Color; 10] = [
+        Color::Gray,
+        Color::Brown,
+        Color::Green,
+        Color::Cyan,
+        Color::Blue,
+        Color::Yellow,
+        Color::Orange,
+        Color::Red,
+        Color::Silver,
+        Color::Gold,
+    ];
+
+    /// Returns the color of `rating`. Negative ratings are treated as gray.
+    pub fn from_rating(rating: i32) -> Self {
+        let index = (rating.max(0) / COLOR_WIDTH) as usize;
+        Color::ALL[index.min(Color::ALL.len() - 1)]
+    }
+
+    /// Returns the name used in the index and query parameters, e.g. `gray`.
+    pub fn as_str(&self) -> &'static str {
+        COLOR_NAMES[*self as usize]
+    }
+
+    /// Returns the English display name, e.g. `Gray`.
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Color::Gray => "Gray",
+            Color::Brown => "Brown",
+            Color::Green => "Green",
+            Color::Cyan => "Cyan",
+            Color::Blue => "Blue",
+            Color::Yellow => "Yellow",
+            Color::Orange => "Orange",
+            Color::Red => "Red",
+            Color::Silver => "Silver",
+            Color::Gold => "Gold",
+        }
+    }
+
+    /// Returns the Japanese display name, e.g. `灰色`.
+    pub fn display_name_ja(&self) -> &'static str {
+        match self {
+            Color::Gray => "灰色",
+            Color::Brown => "茶色",
+            Color::Green => "緑色",
+            Color::Cyan => "水色",
+            Color::Blue => "青色",
+            Color::Yellow => "黄色",
+            Color::Orange => "橙色",
+            Color::Red => "赤色",
+            Color::Silver => "銀色",
+            Color::Gold => "金色",
+        }
+    }
+
+    /// Returns the rating range `[lower, upper)` of the color. Gold has no upper bound.
+    pub fn rating_range(&self) -> (i32, Option<i32>) {
+        let lower = *self as i32 * COLOR_WIDTH;
+        match self {
+            Color::Gold => (lower, None),
+            _ => (lower, Some(lower + COLOR_WIDTH)),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Error returned when parsing an unknown color name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownColor(pub String);
+
+impl fmt::Display for UnknownColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown color {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownColor {}
+
+impl FromStr for Color {
+    type Err = UnknownColor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COLOR_NAMES
+            .iter()
+            .position(|name| *name == s)
+            .map(|index| Color::ALL[index])
+            .ok_or_else(|| UnknownColor(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_rating() {
+        let cases = [
+            (-100, Color::Gray),
+            (0, Color::Gray),
+            (399, Color::Gray),
+            (400, Color::Brown),
+            (799, Color::Brown),
+            (800, Color::Green),
+            (1199, Color::Green),
+            (1200, Color::Cyan),
+            (1599, Color::Cyan),
+            (1600, Color::Blue),
+            (1999, Color::Blue),
+            (2000, Color::Yellow),
+            (2399, Color::Yellow),
+            (2400, Color::Orange),
+            (2799, Color::Orange),
+            (2800, Color::Red),
+            (3199, Color::Red),
+            (3200, Color::Silver),
+            (3599, Color::Silver),
+            (3600, Color::Gold),
+            (4229, Color::Gold),
+        ];
+        for (rating, color) in cases {
+            assert_eq!(Color::from_rating(rating), color, "rating {}", rating);
+        }
+    }
+
+    #[test]
+    fn test_rating_range() {
+        for color in Color::ALL {
+            let (lower, upper) = color.rating_range();
+            assert_eq!(Color::from_rating(lower), color);
+            match upper {
+                Some(upper) => {
+                    assert_eq!(Color::from_rating(upper - 1), color);
+                    assert_ne!(Color::from_rating(upper), color);
+                }
+                None => assert_eq!(color, Color::Gold),
+            }
+        }
+        assert_eq!(Color::Cyan.rating_range(), (1200, Some(1600)));
+        assert_eq!(Color::Gold.rating_range(), (3600, None));
+    }
+
+    #[test]
+    fn test_names() {
+        for (color, name) in Color::ALL.iter().zip(COLOR_NAMES) {
+            assert_eq!(color.as_str(), name);
+            assert_eq!(color.to_string(), name);
+            assert_eq!(name.parse::<Color>(), Ok(*color));
+            assert_eq!(color.display_name().to_lowercase(), name);
+            assert!(color.display_name_ja().ends_with('色'));
+        }
+        assert_eq!(
+            "Red".parse::<Color>(),
+            Err(UnknownColor(String::from("Red")))
+        );
+        assert!("purple".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut colors = vec![Color::Gold, Color::Gray, Color::Red, Color::Cyan];
+        colors.sort();
+        assert_eq!(
+            colors,
+            vec![Color::Gray, Color::Cyan, Color::Red, Color::Gold]
+        );
+        assert!(Color::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
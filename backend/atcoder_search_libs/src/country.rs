@@ -0,0 +1,349 @@
+//! Country codes of AtCoder users and their display names.
+//!
+//! AtCoder stores the country of a user as the stem of the flag image, which is an ISO 3166-1
+//! alpha-2 code such as `JP`. Kosovo has the user-assigned code `XK`.
+use unicode_normalization::UnicodeNormalization;
+
+/// A country or region with its ISO 3166-1 alpha-2 code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Country {
+    /// Upper case alpha-2 code, e.g. `JP`.
+    pub code: &'static str,
+    /// English display name, e.g. `Japan`.
+    pub name: &'static str,
+    /// Japanese display name, e.g. `日本`.
+    pub name_ja: &'static str,
+}
+
+/// All countries and regions, in the order of their codes.
+#[rustfmt::skip]
+pub const COUNTRIES: [Country; 250] = [
+    Country { code: "AD", name: "Andorra", name_ja: "アンドラ" },
+    Country { code: "AE", name: "United Arab Emirates", name_ja: "アラブ首長国連邦" },
+    Country { code: "AF", name: "Afghanistan", name_ja: "アフガニスタン" },
+    Country { code: "AG", name: "Antigua and Barbuda", name_ja: "アンティグア・バーブーダ" },
+    Country { code: "AI", name: "Anguilla", name_ja: "アンギラ" },
+    Country { code: "AL", name: "Albania", name_ja: "アルバニア" },
+    Country { code: "AM", name: "Armenia", name_ja: "アルメニア" },
+    Country { code: "AO", name: "Angola", name_ja: "アンゴラ" },
+    Country { code: "AQ", name: "Antarctica", name_ja: "南極" },
+    Country { code: "AR", name: "Argentina", name_ja: "アルゼンチン" },
+    Country { code: "AS", name: "American Samoa", name_ja: "アメリカ領サモア" },
+    Country { code: "AT", name: "Austria", name_ja: "オーストリア" },
+    Country { code: "AU", name: "Australia", name_ja: "オーストラリア" },
+    Country { code: "AW", name: "Aruba", name_ja: "アルバ" },
+    Country { code: "AX", name: "Åland Islands", name_ja: "オーランド諸島" },
+    Country { code: "AZ", name: "Azerbaijan", name_ja: "アゼルバイジャン" },
+    Country { code: "BA", name: "Bosnia and Herzegovina", name_ja: "ボスニア・ヘルツェゴビナ" },
+    Country { code: "BB", name: "Barbados", name_ja: "バルバドス" },
+    Country { code: "BD", name: "Bangladesh", name_ja: "バングラデシュ" },
+    Country { code: "BE", name: "Belgium", name_ja: "ベルギー" },
+    Country { code: "BF", name: "Burkina Faso", name_ja: "ブルキナファソ" },
+    Country { code: "BG", name: "Bulgaria", name_ja: "ブルガリア" },
+    Country { code: "BH", name: "Bahrain", name_ja: "バーレーン" },
+    Country { code: "BI", name: "Burundi", name_ja: "ブルンジ" },
+    Country { code: "BJ", name: "Benin", name_ja: "ベナン" },
+    Country { code: "BL", name: "Saint Barthélemy", name_ja: "サン・バルテルミー" },
+    Country { code: "BM", name: "Bermuda", name_ja: "バミューダ" },
+    Country { code: "BN", name: "Brunei Darussalam", name_ja: "ブルネイ" },
+    Country { code: "BO", name: "Bolivia", name_ja: "ボリビア" },
+    Country { code: "BQ", name: "Bonaire, Sint Eustatius and Saba", name_ja: "ボネール、シント・ユースタティウスおよびサバ" },
+    Country { code: "BR", name: "Brazil", name_ja: "ブラジル" },
+    Country { code: "BS", name: "Bahamas", name_ja: "バハマ" },
+    Country { code: "BT", name: "Bhutan", name_ja: "ブータン" },
+    Country { code: "BV", name: "Bouvet Island", name_ja: "ブーベ島" },
+    Country { code: "BW", name: "Botswana", name_ja: "ボツワナ" },
+    Country { code: "BY", name: "Belarus", name_ja: "ベラルーシ" },
+    Country { code: "BZ", name: "Belize", name_ja: "ベリーズ" },
+    Country { code: "CA", name: "Canada", name_ja: "カナダ" },
+    Country { code: "CC", name: "Cocos (Keeling) Islands", name_ja: "ココス(キーリング)諸島" },
+    Country { code: "CD", name: "Congo, Democratic Republic of the", name_ja: "コンゴ民主共和国" },
+    Country { code: "CF", name: "Central African Republic", name_ja: "中央アフリカ共和国" },
+    Country { code: "CG", name: "Congo", name_ja: "コンゴ共和国" },
+    Country { code: "CH", name: "Switzerland", name_ja: "スイス" },
+    Country { code: "CI", name: "Côte d'Ivoire", name_ja: "コートジボワール" },
+    Country { code: "CK", name: "Cook Islands", name_ja: "クック諸島" },
+    Country { code: "CL", name: "Chile", name_ja: "チリ" },
+    Country { code: "CM", name: "Cameroon", name_ja: "カメルーン" },
+    Country { code: "CN", name: "China", name_ja: "中国" },
+    Country { code: "CO", name: "Colombia", name_ja: "コロンビア" },
+    Country { code: "CR", name: "Costa Rica", name_ja: "コスタリカ" },
+    Country { code: "CU", name: "Cuba", name_ja: "キューバ" },
+    Country { code: "CV", name: "Cabo Verde", name_ja: "カーボベルデ" },
+    Country { code: "CW", name: "Curaçao", name_ja: "キュラソー" },
+    Country { code: "CX", name: "Christmas Island", name_ja: "クリスマス島" },
+    Country { code: "CY", name: "Cyprus", name_ja: "キプロス" },
+    Country { code: "CZ", name: "Czechia", name_ja: "チェコ" },
+    Country { code: "DE", name: "Germany", name_ja: "ドイツ" },
+    Country { code: "DJ", name: "Djibouti", name_ja: "ジブチ" },
+    Country { code: "DK", name: "Denmark", name_ja: "デンマーク" },
+    Country { code: "DM", name: "Dominica", name_ja: "ドミニカ国" },
+    Country { code: "DO", name: "Dominican Republic", name_ja: "ドミニカ共和国" },
+    Country { code: "DZ", name: "Algeria", name_ja: "アルジェリア" },
+    Country { code: "EC", name: "Ecuador", name_ja: "エクアドル" },
+    Country { code: "EE", name: "Estonia", name_ja: "エストニア" },
+    Country { code: "EG", name: "Egypt", name_ja: "エジプト" },
+    Country { code: "EH", name: "Western Sahara", name_ja: "西サハラ" },
+    Country { code: "ER", name: "Eritrea", name_ja: "エリトリア" },
+    Country { code: "ES", name: "Spain", name_ja: "スペイン" },
+    Country { code: "ET", name: "Ethiopia", name_ja: "エチオピア" },
+    Country { code: "FI", name: "Finland", name_ja: "フィンランド" },
+    Country { code: "FJ", name: "Fiji", name_ja: "フィジー" },
+    Country { code: "FK", name: "Falkland Islands (Malvinas)", name_ja: "フォークランド(マルビナス)諸島" },
+    Country { code: "FM", name: "Micronesia", name_ja: "ミクロネシア連邦" },
+    Country { code: "FO", name: "Faroe Islands", name_ja: "フェロー諸島" },
+    Country { code: "FR", name: "France", name_ja: "フランス" },
+    Country { code: "GA", name: "Gabon", name_ja: "ガボン" },
+    Country { code: "GB", name: "United Kingdom", name_ja: "イギリス" },
+    Country { code: "GD", name: "Grenada", name_ja: "グレナダ" },
+    Country { code: "GE", name: "Georgia", name_ja: "ジョージア" },
+    Country { code: "GF", name: "French Guiana", name_ja: "フランス領ギアナ" },
+    Country { code: "GG", name: "Guernsey", name_ja: "ガーンジー" },
+    Country { code: "GH", name: "Ghana", name_ja: "ガーナ" },
+    Country { code: "GI", name: "Gibraltar", name_ja: "ジブラルタル" },
+    Country { code: "GL", name: "Greenland", name_ja: "グリーンランド" },
+    Country { code: "GM", name: "Gambia", name_ja: "ガンビア" },
+    Country { code: "GN", name: "Guinea", name_ja: "ギニア" },
+    Country { code: "GP", name: "Guadeloupe", name_ja: "グアドループ" },
+    Country { code: "GQ", name: "Equatorial Guinea", name_ja: "赤道ギニア" },
+    Country { code: "GR", name: "Greece", name_ja: "ギリシャ" },
+    Country { code: "GS", name: "South Georgia and the South Sandwich Islands", name_ja: "サウスジョージア・サウスサンドウィッチ諸島" },
+    Country { code: "GT", name: "Guatemala", name_ja: "グアテマラ" },
+    Country { code: "GU", name: "Guam", name_ja: "グアム" },
+    Country { code: "GW", name: "Guinea-Bissau", name_ja: "ギニアビサウ" },
+    Country { code: "GY", name: "Guyana", name_ja: "ガイアナ" },
+    Country { code: "HK", name: "Hong Kong", name_ja: "香港" },
+    Country { code: "HM", name: "Heard Island and McDonald Islands", name_ja: "ハード島とマクドナルド諸島" },
+    Country { code: "HN", name: "Honduras", name_ja: "ホンジュラス" },
+    Country { code: "HR", name: "Croatia", name_ja: "クロアチア" },
+    Country { code: "HT", name: "Haiti", name_ja: "ハイチ" },
+    Country { code: "HU", name: "Hungary", name_ja: "ハンガリー" },
+    Country { code: "ID", name: "Indonesia", name_ja: "インドネシア" },
+    Country { code: "IE", name: "Ireland", name_ja: "アイルランド" },
+    Country { code: "IL", name: "Israel", name_ja: "イスラエル" },
+    Country { code: "IM", name: "Isle of Man", name_ja: "マン島" },
+    Country { code: "IN", name: "India", name_ja: "インド" },
+    Country { code: "IO", name: "British Indian Ocean Territory", name_ja: "イギリス領インド洋地域" },
+    Country { code: "IQ", name: "Iraq", name_ja: "イラク" },
+    Country { code: "IR", name: "Iran", name_ja: "イラン" },
+    Country { code: "IS", name: "Iceland", name_ja: "アイスランド" },
+    Country { code: "IT", name: "Italy", name_ja: "イタリア" },
+    Country { code: "JE", name: "Jersey", name_ja: "ジャージー" },
+    Country { code: "JM", name: "Jamaica", name_ja: "ジャマイカ" },
+    Country { code: "JO", name: "Jordan", name_ja: "ヨルダン" },
+    Country { code: "JP", name: "Japan", name_ja: "日本" },
+    Country { code: "KE", name: "Kenya", name_ja: "ケニア" },
+    Country { code: "KG", name: "Kyrgyzstan", name_ja: "キルギス" },
+    Country { code: "KH", name: "Cambodia", name_ja: "カンボジア" },
+    Country { code: "KI", name: "Kiribati", name_ja: "キリバス" },
+    Country { code: "KM", name: "Comoros", name_ja: "コモロ" },
+    Country { code: "KN", name: "Saint Kitts and Nevis", name_ja: "セントクリストファー・ネイビス" },
+    Country { code: "KP", name: "North Korea", name_ja: "北朝鮮" },
+    Country { code: "KR", name: "South Korea", name_ja: "韓国" },
+    Country { code: "KW", name: "Kuwait", name_ja: "クウェート" },
+    Country { code: "KY", name: "Cayman Islands", name_ja: "ケイマン諸島" },
+    Country { code: "KZ", name: "Kazakhstan", name_ja: "カザフスタン" },
+    Country { code: "LA", name: "Laos", name_ja: "ラオス" },
+    Country { code: "LB", name: "Lebanon", name_ja: "レバノン" },
+    Country { code: "LC", name: "Saint Lucia", name_ja: "セントルシア" },
+    Country { code: "LI", name: "Liechtenstein", name_ja: "リヒテンシュタイン" },
+    Country { code: "LK", name: "Sri Lanka", name_ja: "スリランカ" },
+    Country { code: "LR", name: "Liberia", name_ja: "リベリア" },
+    Country { code: "LS", name: "Lesotho", name_ja: "レソト" },
+    Country { code: "LT", name: "Lithuania", name_ja: "リトアニア" },
+    Country { code: "LU", name: "Luxembourg", name_ja: "ルクセンブルク" },
+    Country { code: "LV", name: "Latvia", name_ja: "ラトビア" },
+    Country { code: "LY", name: "Libya", name_ja: "リビア" },
+    Country { code: "MA", name: "Morocco", name_ja: "モロッコ" },
+    Country { code: "MC", name: "Monaco", name_ja: "モナコ" },
+    Country { code: "MD", name: "Moldova", name_ja: "モルドバ" },
+    Country { code: "ME", name: "Montenegro", name_ja: "モンテネグロ" },
+    Country { code: "MF", name: "Saint Martin (French part)", name_ja: "サン・マルタン(フランス領)" },
+    Country { code: "MG", name: "Madagascar", name_ja: "マダガスカル" },
+    Country { code: "MH", name: "Marshall Islands", name_ja: "マーシャル諸島" },
+    Country { code: "MK", name: "North Macedonia", name_ja: "北マケドニア" },
+    Country { code: "ML", name: "Mali", name_ja: "マリ" },
+    Country { code: "MM", name: "Myanmar", name_ja: "ミャンマー" },
+    Country { code: "MN", name: "Mongolia", name_ja: "モンゴル" },
+    Country { code: "MO", name: "Macao", name_ja: "マカオ" },
+    Country { code: "MP", name: "Northern Mariana Islands", name_ja: "北マリアナ諸島" },
+    Country { code: "MQ", name: "Martinique", name_ja: "マルティニーク" },
+    Country { code: "MR", name: "Mauritania", name_ja: "モーリタニア" },
+    Country { code: "MS", name: "Montserrat", name_ja: "モントセラト" },
+    Country { code: "MT", name: "Malta", name_ja: "マルタ" },
+    Country { code: "MU", name: "Mauritius", name_ja: "モーリシャス" },
+    Country { code: "MV", name: "Maldives", name_ja: "モルディブ" },
+    Country { code: "MW", name: "Malawi", name_ja: "マラウイ" },
+    Country { code: "MX", name: "Mexico", name_ja: "メキシコ" },
+    Country { code: "MY", name: "Malaysia", name_ja: "マレーシア" },
+    Country { code: "MZ", name: "Mozambique", name_ja: "モザンビーク" },
+    Country { code: "NA", name: "Namibia", name_ja: "ナミビア" },
+    Country { code: "NC", name: "New Caledonia", name_ja: "ニューカレドニア" },
+    Country { code: "NE", name: "Niger", name_ja: "ニジェール" },
+    Country { code: "NF", name: "Norfolk Island", name_ja: "ノーフォーク島" },
+    Country { code: "NG", name: "Nigeria", name_ja: "ナイジェリア" },
+    Country { code: "NI", name: "Nicaragua", name_ja: "ニカラグア" },
+    Country { code: "NL", name: "Netherlands", name_ja: "オランダ" },
+    Country { code: "NO", name: "Norway", name_ja: "ノルウェー" },
+    Country { code: "NP", name: "Nepal", name_ja: "ネパール" },
+    Country { code: "NR", name: "Nauru", name_ja: "ナウル" },
+    Country { code: "NU", name: "Niue", name_ja: "ニウエ" },
+    Country { code: "NZ", name: "New Zealand", name_ja: "ニュージーランド" },
+    Country { code: "OM", name: "Oman", name_ja: "オマーン" },
+    Country { code: "PA", name: "Panama", name_ja: "パナマ" },
+    Country { code: "PE", name: "Peru", name_ja: "ペルー" },
+    Country { code: "PF", name: "French Polynesia", name_ja: "フランス領ポリネシア" },
+    Country { code: "PG", name: "Papua New Guinea", name_ja: "パプアニューギニア" },
+    Country { code: "PH", name: "Philippines", name_ja: "フィリピン" },
+    Country { code: "PK", name: "Pakistan", name_ja: "パキスタン" },
+    Country { code: "PL", name: "Poland", name_ja: "ポーランド" },
+    Country { code: "PM", name: "Saint Pierre and Miquelon", name_ja: "サンピエール島・ミクロン島" },
+    Country { code: "PN", name: "Pitcairn", name_ja: "ピトケアン" },
+    Country { code: "PR", name: "Puerto Rico", name_ja: "プエルトリコ" },
+    Country { code: "PS", name: "Palestine", name_ja: "パレスチナ" },
+    Country { code: "PT", name: "Portugal", name_ja: "ポルトガル" },
+    Country { code: "PW", name: "Palau", name_ja: "パラオ" },
+    Country { code: "PY", name: "Paraguay", name_ja: "パラグアイ" },
+    Country { code: "QA", name: "Qatar", name_ja: "カタール" },
+    Country { code: "RE", name: "Réunion", name_ja: "レユニオン" },
+    Country { code: "RO", name: "Romania", name_ja: "ルーマニア" },
+    Country { code: "RS", name: "Serbia", name_ja: "セルビア" },
+    Country { code: "RU", name: "Russia", name_ja: "ロシア" },
+    Country { code: "RW", name: "Rwanda", name_ja: "ルワンダ" },
+    Country { code: "SA", name: "Saudi Arabia", name_ja: "サウジアラビア" },
+    Country { code: "SB", name: "Solomon Islands", name_ja: "ソロモン諸島" },
+    Country { code: "SC", name: "Seychelles", name_ja: "セーシェル" },
+    Country { code: "SD", name: "Sudan", name_ja: "スーダン" },
+    Country { code: "SE", name: "Sweden", name_ja: "スウェーデン" },
+    Country { code: "SG", name: "Singapore", name_ja: "シンガポール" },
+    Country { code: "SH", name: "Saint Helena, Ascension and Tristan da Cunha", name_ja: "セントヘレナ・アセンションおよびトリスタンダクーニャ" },
+    Country { code: "SI", name: "Slovenia", name_ja: "スロベニア" },
+    Country { code: "SJ", name: "Svalbard and Jan Mayen", name_ja: "スヴァールバル諸島およびヤンマイエン島" },
+    Country { code: "SK", name: "Slovakia", name_ja: "スロバキア" },
+    Country { code: "SL", name: "Sierra Leone", name_ja: "シエラレオネ" },
+    Country { code: "SM", name: "San Marino", name_ja: "サンマリノ" },
+    Country { code: "SN", name: "Senegal", name_ja: "セネガル" },
+    Country { code: "SO", name: "Somalia", name_ja: "ソマリア" },
+    Country { code: "SR", name: "Suriname", name_ja: "スリナム" },
+    Country { code: "SS", name: "South Sudan", name_ja: "南スーダン" },
+    Country { code: "ST", name: "Sao Tome and Principe", name_ja: "サントメ・プリンシペ" },
+    Country { code: "SV", name: "El Salvador", name_ja: "エルサルバドル" },
+    Country { code: "SX", name: "Sint Maarten (Dutch part)", name_ja: "シント・マールテン(オランダ領)" },
+    Country { code: "SY", name: "Syria", name_ja: "シリア" },
+    Country { code: "SZ", name: "Eswatini", name_ja: "エスワティニ" },
+    Country { code: "TC", name: "Turks and Caicos Islands", name_ja: "タークス・カイコス諸島" },
+    Country { code: "TD", name: "Chad", name_ja: "チャド" },
+    Country { code: "TF", name: "French Southern Territories", name_ja: "フランス領南方・南極地域" },
+    Country { code: "TG", name: "Togo", name_ja: "トーゴ" },
+    Country { code: "TH", name: "Thailand", name_ja: "タイ" },
+    Country { code: "TJ", name: "Tajikistan", name_ja: "タジキスタン" },
+    Country { code: "TK", name: "Tokelau", name_ja: "トケラウ" },
+    Country { code: "TL", name: "Timor-Leste", name_ja: "東ティモール" },
+    Country { code: "TM", name: "Turkmenistan", name_ja: "トルクメニスタン" },
+    Country { code: "TN", name: "Tunisia", name_ja: "チュニジア" },
+    Country { code: "TO", name: "Tonga", name_ja: "トンガ" },
+    Country { code: "TR", name: "Türkiye", name_ja: "トルコ" },
+    Country { code: "TT", name: "Trinidad and Tobago", name_ja: "トリニダード・トバゴ" },
+    Country { code: "TV", name: "Tuvalu", name_ja: "ツバル" },
+    Country { code: "TW", name: "Taiwan", name_ja: "台湾" },
+    Country { code: "TZ", name: "Tanzania", name_ja: "タンザニア" },
+    Country { code: "UA", name: "Ukraine", name_ja: "ウクライナ" },
+    Country { code: "UG", name: "Uganda", name_ja: "ウガンダ" },
+    Country { code: "UM", name: "United States Minor Outlying Islands", name_ja: "合衆国領有小離島" },
+    Country { code: "US", name: "United States", name_ja: "アメリカ合衆国" },
+    Country { code: "UY", name: "Uruguay", name_ja: "ウルグアイ" },
+    Country { code: "UZ", name: "Uzbekistan", name_ja: "ウズベキスタン" },
+    Country { code: "VA", name: "Holy See", name_ja: "バチカン" },
+    Country { code: "VC", name: "Saint Vincent and the Grenadines", name_ja: "セントビンセント・グレナディーン" },
+    Country { code: "VE", name: "Venezuela", name_ja: "ベネズエラ" },
+    Country { code: "VG", name: "Virgin Islands (British)", name_ja: "イギリス領ヴァージン諸島" },
+    Country { code: "VI", name: "Virgin Islands (U.S.)", name_ja: "アメリカ領ヴァージン諸島" },
+    Country { code: "VN", name: "Viet Nam", name_ja: "ベトナム" },
+    Country { code: "VU", name: "Vanuatu", name_ja: "バヌアツ" },
+    Country { code: "WF", name: "Wallis and Futuna", name_ja: "ウォリス・フツナ" },
+    Country { code: "WS", name: "Samoa", name_ja: "サモア" },
+    Country { code: "XK", name: "Kosovo", name_ja: "コソボ" },
+    Country { code: "YE", name: "Yemen", name_ja: "イエメン" },
+    Country { code: "YT", name: "Mayotte", name_ja: "マヨット" },
+    Country { code: "ZA", name: "South Africa", name_ja: "南アフリカ" },
+    Country { code: "ZM", name: "Zambia", name_ja: "ザンビア" },
+    Country { code: "ZW", name: "Zimbabwe", name_ja: "ジンバブエ" },
+];
+
+impl Country {
+    /// Returns the country of the alpha-2 code, ignoring case.
+    pub fn from_code(code: &str) -> Option<&'static Country> {
+        let code = code.trim().to_ascii_uppercase();
+        COUNTRIES
+            .binary_search_by(|country| country.code.cmp(code.as_str()))
+            .ok()
+            .map(|index| &COUNTRIES[index])
+    }
+
+    /// Returns the country of a code, an English name or a Japanese name.
+    ///
+    /// Names are compared ignoring case and the difference between full-width and half-width characters.
+    pub fn resolve(value: &str) -> Option<&'static Country> {
+        if let Some(country) = Self::from_code(value) {
+            return Some(country);
+        }
+        let value = normalize(value);
+        COUNTRIES
+            .iter()
+            .find(|country| normalize(country.name) == value || country.name_ja == value)
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().nfkc().collect::<String>().to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_countries_are_sorted() {
+        for pair in COUNTRIES.windows(2) {
+            assert!(
+                pair[0].code < pair[1].code,
+                "{} {}",
+                pair[0].code,
+                pair[1].code
+            );
+        }
+        for country in COUNTRIES {
+            assert_eq!(country.code.len(), 2);
+            assert_eq!(country.code, country.code.to_ascii_uppercase());
+        }
+    }
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(
+            Country::from_code("JP").map(|country| country.name),
+            Some("Japan")
+        );
+        assert_eq!(
+            Country::from_code("jp").map(|country| country.name_ja),
+            Some("日本")
+        );
+        assert_eq!(
+            Country::from_code("XK").map(|country| country.name),
+            Some("Kosovo")
+        );
+        assert_eq!(Country::from_code("ZZ"), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let code = |value: &str| Country::resolve(value).map(|country| country.code);
+        assert_eq!(code("JP"), Some("JP"));
+        assert_eq!(code("japan"), Some("JP"));
+        assert_eq!(code("Ｊａｐａｎ"), Some("JP"));
+        assert_eq!(code("日本"), Some("JP"));
+        assert_eq!(code("United States"), Some("US"));
+        assert_eq!(code("韓国"), Some("KR"));
+        assert_eq!(code("Atlantis"), None);
+    }
+}
@@ -1,18 +1,22 @@
-use crate::solr::core::SolrCore;
+use crate::solr::core::{CommitParams, SolrCore, UpdateParams};
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::stream::FuturesUnordered;
-use serde::Serialize;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use futures::stream::{FuturesOrdered, FuturesUnordered};
+use reqwest::Body;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::{
-    ffi::OsString,
-    fmt::Debug,
+    fmt::{self, Debug},
     fs::File,
-    io::BufWriter,
+    io::{BufWriter, ErrorKind, Read, Write},
     mem,
     path::{Path, PathBuf},
     pin::Pin,
+    str::FromStr,
     sync::Arc,
+    time::Instant,
 };
 use tokio::{
     sync::mpsc::{Receiver, Sender},
@@ -20,8 +24,96 @@ use tokio::{
 };
 use tokio_stream::{Stream, StreamExt};
 
+/// サフィックス付きフィールドの値の出力方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpandMode {
+    /// 元のフィールドと同じ値をサフィックス付きフィールドにも入れてドキュメントに含める
+    #[default]
+    Inline,
+    /// 元のフィールドと同じ値のサフィックス付きフィールドは出力せず、SolrのcopyFieldで複製させる
+    ///
+    /// 変換関数を指定したサフィックス付きフィールドはSolrで計算できないので、このモードでも出力する。
+    CopyField,
+}
+
+impl ExpandMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExpandMode::Inline => "inline",
+            ExpandMode::CopyField => "copyfield",
+        }
+    }
+}
+
+impl fmt::Display for ExpandMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ExpandMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "inline" => Ok(ExpandMode::Inline),
+            "copyfield" => Ok(ExpandMode::CopyField),
+            _ => Err(anyhow::anyhow!("unknown expand mode `{}`", s)),
+        }
+    }
+}
+
+/// 構造体をサフィックス付きフィールドを含むドキュメントのJSONに展開するトレイト
+///
+/// `#[derive(ExpandField)]`で実装する。導出したコードは`ExpandMode`を参照するので、併せてインポートしておくこと。
 pub trait ExpandField {
-    fn expand(&self) -> Value;
+    /// サフィックス付きフィールドを`mode`に従って展開したJSONを返す
+    fn expand_with(&self, mode: ExpandMode) -> Value;
+
+    fn expand(&self) -> Value {
+        self.expand_with(ExpandMode::Inline)
+    }
+}
+
+/// ドキュメントの生成・投入の進捗
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexingProgress {
+    /// データベースから読み込んでドキュメントに変換した行数
+    RowsProcessed { rows: usize },
+    /// ドキュメントファイルを書き出した
+    ChunkSaved { file: String, documents: usize },
+    /// ドキュメントファイルをSolrへ送った
+    ChunkPosted { file: String },
+    /// 生成または投入に失敗した
+    Error { message: String },
+}
+
+/// 進捗を通知するコールバック
+#[derive(Clone)]
+pub struct ProgressReporter(Arc<dyn Fn(IndexingProgress) + Send + Sync>);
+
+impl ProgressReporter {
+    pub fn new(report: impl Fn(IndexingProgress) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, progress: IndexingProgress) {
+        (self.0)(progress)
+    }
+}
+
+impl Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressReporter")
+    }
+}
+
+// 進捗の通知先が設定されていれば通知する関数
+fn report(reporter: Option<&ProgressReporter>, progress: IndexingProgress) {
+    if let Some(reporter) = reporter {
+        reporter.report(progress);
+    }
 }
 
 #[async_trait]
@@ -36,82 +128,502 @@ pub trait ToDocument {
     type Document: Debug + Serialize + Send + Sync + 'static;
 
     fn to_document(self) -> Result<Self::Document>;
-}
 
-#[async_trait]
-pub trait PostDocument {
-    async fn post_documents<C>(&self, core: C, save_dir: &Path, optimize: bool) -> Result<()>
+    /// サフィックス付きフィールドの出力方法を指定してドキュメントに変換する。
+    /// デフォルトでは`mode`を無視して`to_document`を呼ぶ。
+    fn to_document_with(self, mode: ExpandMode) -> Result<Self::Document>
     where
-        C: SolrCore + Sync + Send + 'static,
+        Self: Sized,
     {
-        let core = Arc::new(core);
-        let mut files = tokio::fs::read_dir(save_dir).await?;
+        let _ = mode;
+        self.to_document()
+    }
+}
 
-        let mut tasks: FuturesUnordered<JoinHandle<()>> = FuturesUnordered::new();
-        while let Ok(Some(entry)) = files.next_entry().await {
-            if entry
-                .file_type()
-                .await
-                .and_then(|file_type| Ok(file_type.is_dir()))
-                .unwrap_or(false)
-            {
-                continue;
+/// ドキュメントファイルの形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocumentFormat {
+    /// ドキュメントの配列を1つのJSONとして書き込む形式
+    #[default]
+    Json,
+    /// 1行に1つのドキュメントを書き込む形式。`/update/json/docs`へ送る。
+    Ndjson,
+}
+
+impl DocumentFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentFormat::Json => "json",
+            DocumentFormat::Ndjson => "ndjson",
+        }
+    }
+
+    /// ファイル名の拡張子から形式を判定する。`.gz`は取り除いて判定する。
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy();
+        let name = name.strip_suffix(".gz").unwrap_or(&name);
+        [DocumentFormat::Json, DocumentFormat::Ndjson]
+            .into_iter()
+            .find(|format| name.ends_with(&format!(".{}", format.extension())))
+    }
+}
+
+impl fmt::Display for DocumentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for DocumentFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(DocumentFormat::Json),
+            "ndjson" => Ok(DocumentFormat::Ndjson),
+            _ => Err(anyhow::anyhow!("unknown document format `{}`", s)),
+        }
+    }
+}
+
+/// 検証したドキュメントファイルの情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentFileSummary {
+    pub path: PathBuf,
+    pub documents: usize,
+    pub size: u64,
+}
+
+/// 投入前に検証したドキュメントファイル全体の情報
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationSummary {
+    pub files: Vec<DocumentFileSummary>,
+}
+
+impl ValidationSummary {
+    pub fn total_documents(&self) -> usize {
+        self.files.iter().map(|file| file.documents).sum()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+impl fmt::Display for ValidationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for file in self.files.iter() {
+            writeln!(
+                f,
+                "{}: {} documents, {} kB",
+                file.path.display(),
+                file.documents,
+                file.size / 1024
+            )?;
+        }
+        write!(
+            f,
+            "total: {} files, {} documents, {} kB",
+            self.files.len(),
+            self.total_documents(),
+            self.total_size() / 1024
+        )
+    }
+}
+
+/// ドキュメントファイルの中身を検証し、含まれるドキュメントの数を返す関数
+///
+/// ファイルの中身はオブジェクトの配列(NDJSONの場合は改行区切りのオブジェクト)でなければならず、
+/// 各オブジェクトは`unique_key`のフィールドを持たなければならない。
+fn validate_document_file(
+    content: &[u8],
+    unique_key: &str,
+    format: DocumentFormat,
+) -> Result<usize> {
+    let documents: Vec<Value> = match format {
+        DocumentFormat::Json => serde_json::from_slice(content),
+        DocumentFormat::Ndjson => serde_json::Deserializer::from_slice(content)
+            .into_iter::<Value>()
+            .collect(),
+    }
+    .map_err(|e| anyhow::anyhow!("malformed JSON: {}", e))?;
+
+    for (i, document) in documents.iter().enumerate() {
+        let document = document
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("document #{} is not a JSON object", i))?;
+        match document.get(unique_key) {
+            None | Some(Value::Null) => {
+                anyhow::bail!(
+                    "document #{} doesn't have the unique key `{}`",
+                    i,
+                    unique_key
+                )
             }
-            let file = entry.path();
-            if file.extension() != Some(OsString::from("json").as_ref()) {
-                continue;
+            Some(_) => {}
+        }
+    }
+
+    Ok(documents.len())
+}
+
+/// ドキュメントファイルかどうかを判定する関数。マニフェストファイルは除く。
+fn is_document_file(path: &Path) -> bool {
+    let is_manifest = path
+        .file_name()
+        .map(|name| name == MANIFEST_FILE || name == UPLOADED_MANIFEST_FILE)
+        .unwrap_or(false);
+    !is_manifest && DocumentFormat::from_path(path).is_some()
+}
+
+fn is_compressed(path: &Path) -> bool {
+    DocumentFormat::from_path(path).is_some() && path.to_string_lossy().ends_with(".gz")
+}
+
+/// ドキュメントファイルを読み込む関数。gzip圧縮されたファイルは展開した中身を返す。
+pub(crate) async fn read_document_file(path: &Path) -> Result<Vec<u8>> {
+    let content = tokio::fs::read(path).await?;
+    if !is_compressed(path) {
+        return Ok(content);
+    }
+
+    let mut decoded = Vec::new();
+    GzDecoder::new(content.as_slice()).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+fn write_documents<W: Write, D: Serialize>(
+    writer: &mut W,
+    documents: &[D],
+    format: DocumentFormat,
+) -> Result<()> {
+    match format {
+        DocumentFormat::Json => serde_json::to_writer_pretty(writer, documents)?,
+        DocumentFormat::Ndjson => {
+            for document in documents.iter() {
+                serde_json::to_writer(&mut *writer, document)?;
+                writer.write_all(b"\n")?;
             }
+        }
+    }
+    Ok(())
+}
 
-            let core = core.clone();
-            let task = tokio::spawn(async move {
-                let filename = file.display();
-                let file = match tokio::fs::File::open(&file).await {
-                    Ok(file) => file,
-                    Err(e) => {
-                        let message = format!("failed to open the file {} cause {:?}", filename, e);
-                        tracing::error!(message);
-                        panic!("{}", message);
-                    }
-                };
+/// ドキュメントを指定した形式でファイルに書き込み、マニフェストの項目を返す関数
+fn save_documents<D: Serialize>(
+    filepath: &Path,
+    documents: &[D],
+    format: DocumentFormat,
+    compress: bool,
+) -> ManifestEntry {
+    tracing::info!("Generate document file: {}", filepath.display());
+    let mut content = Vec::new();
+    if let Err(e) = write_documents(&mut content, documents, format) {
+        let message = format!("failed to serialize documents: {:?}", e);
+        tracing::error!(message);
+        panic!("{}", message);
+    }
 
-                let size = file
-                    .metadata()
-                    .await
-                    .and_then(|metadata| Ok(metadata.len()))
-                    .unwrap_or(0);
+    let file = match File::create(filepath) {
+        Ok(file) => file,
+        Err(e) => {
+            let message = format!("failed to create file: {:?}", e);
+            tracing::error!(message);
+            panic!("{}", message);
+        }
+    };
+    let mut writer = BufWriter::new(file);
 
-                match core.post(file).await {
-                    Ok(_) => {
-                        tracing::info!("Post the file: {}, size: {} kB", filename, size / 1024)
-                    }
-                    Err(e) => {
-                        let message = format!("failed to post document: {:?}", e);
-                        tracing::error!(message);
-                        panic!("{}", message)
-                    }
-                }
-            });
-            tasks.push(task);
+    let result = if compress {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        encoder
+            .write_all(&content)
+            .and_then(|_| encoder.finish().map(|_| ()))
+    } else {
+        writer.write_all(&content).and_then(|_| writer.flush())
+    };
+    if let Err(e) = result {
+        let message = format!("failed to write document content: {:?}", e);
+        tracing::error!(message);
+        panic!("{}", message);
+    }
+
+    ManifestEntry {
+        file: filepath
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        checksum: format!("{:x}", Sha256::digest(&content)),
+        documents: documents.len(),
+    }
+}
+
+pub(crate) async fn document_files(save_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = tokio::fs::read_dir(save_dir).await?;
+
+    let mut paths = Vec::new();
+    while let Ok(Some(entry)) = files.next_entry().await {
+        if entry
+            .file_type()
+            .await
+            .map(|file_type| file_type.is_dir())
+            .unwrap_or(false)
+        {
+            continue;
         }
+        let file = entry.path();
+        if !is_document_file(&file) {
+            continue;
+        }
+        paths.push(file);
+    }
+    paths.sort();
 
-        while let Some(task) = tasks.next().await {
-            if let Err(e) = task {
-                core.rollback().await?;
-                return Err(anyhow::anyhow!(e));
+    Ok(paths)
+}
+
+/// `generate`が書き出すマニフェストファイルの名前
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// 最後に投入したドキュメントファイルのマニフェストファイルの名前
+pub const UPLOADED_MANIFEST_FILE: &str = "manifest.uploaded.json";
+
+/// ドキュメントファイル1つ分のマニフェストの項目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: String,
+    /// 圧縮前の内容のSHA-256
+    pub checksum: String,
+    pub documents: usize,
+}
+
+/// 生成したドキュメントファイルのチェックサムとドキュメント数の一覧
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub chunks: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// マニフェストファイルを読み込む。ファイルが無ければ`None`を返す。
+    pub async fn load(path: &Path) -> Result<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::anyhow!(e)),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        tokio::fs::write(path, serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// `uploaded`に同じファイル名・同じチェックサムの項目が無いものを返す
+    pub fn changed_chunks(&self, uploaded: &Manifest) -> Vec<&ManifestEntry> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !uploaded.chunks.contains(chunk))
+            .collect()
+    }
+}
+
+/// 投入が完了したドキュメントファイルのマニフェストを保存する関数
+async fn mark_uploaded(save_dir: &Path) -> Result<()> {
+    if let Some(manifest) = Manifest::load(&save_dir.join(MANIFEST_FILE)).await? {
+        manifest
+            .save(&save_dir.join(UPLOADED_MANIFEST_FILE))
+            .await?;
+    }
+    Ok(())
+}
+
+/// ドキュメントファイルを投入するときのオプション
+#[derive(Debug, Clone, Default)]
+pub struct PostOptions {
+    /// 投入後にコミットではなく最適化する
+    pub optimize: bool,
+    pub update: UpdateParams,
+    pub commit: CommitParams,
+    /// ファイルを送るたびに進捗を通知する先
+    pub progress: Option<ProgressReporter>,
+}
+
+/// ファイルを並行してSolrへ送り、全て成功すればコミットする関数。失敗したらロールバックする。
+///
+/// `commitWithin`を指定した場合はSolrの自動コミットに任せ、最後にコミットしない。
+/// その場合、失敗する前に自動コミットされたドキュメントはロールバックされない。
+async fn post_files<C>(core: Arc<C>, files: Vec<PathBuf>, options: &PostOptions) -> Result<()>
+where
+    C: SolrCore + Sync + Send + 'static,
+{
+    let mut tasks: FuturesUnordered<JoinHandle<()>> = FuturesUnordered::new();
+    for file in files {
+        let core = core.clone();
+        let params = options.update.clone();
+        let progress = options.progress.clone();
+        let task = tokio::spawn(async move {
+            let filename = file.display();
+            let size = tokio::fs::metadata(&file)
+                .await
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+
+            // gzip圧縮されたファイルは展開してから送る。
+            // Solr以外のバックエンドは中身を変換して送るので、ストリームではなくバイト列で渡す。
+            let body = read_document_file(&file).await.map(Body::from);
+            let body = match body {
+                Ok(body) => body,
+                Err(e) => {
+                    let message = format!("failed to open the file {} cause {:?}", filename, e);
+                    tracing::error!(message);
+                    report(
+                        progress.as_ref(),
+                        IndexingProgress::Error {
+                            message: message.clone(),
+                        },
+                    );
+                    panic!("{}", message);
+                }
+            };
+
+            let result = match DocumentFormat::from_path(&file).unwrap_or_default() {
+                DocumentFormat::Json => core.post(body, &params).await,
+                DocumentFormat::Ndjson => core.post_docs(body, &params).await,
+            };
+            match result {
+                Ok(_) => {
+                    tracing::info!("Post the file: {}, size: {} kB", filename, size / 1024);
+                    report(
+                        progress.as_ref(),
+                        IndexingProgress::ChunkPosted {
+                            file: filename.to_string(),
+                        },
+                    );
+                }
+                Err(e) => {
+                    let message = format!("failed to post document: {:?}", e);
+                    tracing::error!(message);
+                    report(
+                        progress.as_ref(),
+                        IndexingProgress::Error {
+                            message: message.clone(),
+                        },
+                    );
+                    panic!("{}", message)
+                }
             }
+        });
+        tasks.push(task);
+    }
+
+    while let Some(task) = tasks.next().await {
+        if let Err(e) = task {
+            core.rollback().await?;
+            return Err(anyhow::anyhow!(e));
         }
+    }
+
+    if options.optimize {
+        core.optimize(&options.commit).await?;
+    } else if options.update.commit_within.is_some() {
+        tracing::info!("Documents will be committed within the specified time by Solr.");
+    } else {
+        core.commit(&options.commit).await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+pub trait PostDocument {
+    /// 投入するドキュメントファイルを全て読み込み、JSONとして正しいか、ユニークキーを持っているかを検証する
+    ///
+    /// 不正なファイルがあれば、Solrへ何も送らないうちにエラーを返す。
+    async fn validate_documents(
+        &self,
+        save_dir: &Path,
+        unique_key: &str,
+    ) -> Result<ValidationSummary> {
+        let mut summary = ValidationSummary::default();
+        for path in document_files(save_dir).await? {
+            let size = tokio::fs::metadata(&path).await?.len();
+            let content = read_document_file(&path).await?;
+            let format = DocumentFormat::from_path(&path).unwrap_or_default();
+            let documents = validate_document_file(&content, unique_key, format).map_err(|e| {
+                let message = format!("invalid document file {}: {}", path.display(), e);
+                tracing::error!(message);
+                anyhow::anyhow!(message)
+            })?;
+            tracing::debug!("{} documents in {}", documents, path.display());
 
-        if optimize {
-            core.optimize().await?;
-        } else {
-            core.commit().await?;
+            summary.files.push(DocumentFileSummary {
+                path,
+                documents,
+                size,
+            });
         }
 
-        Ok(())
+        Ok(summary)
+    }
+
+    async fn post_documents<C>(&self, core: C, save_dir: &Path, options: &PostOptions) -> Result<()>
+    where
+        C: SolrCore + Sync + Send + 'static,
+    {
+        let files = document_files(save_dir).await?;
+        post_files(Arc::new(core), files, options).await?;
+        mark_uploaded(save_dir).await
+    }
+
+    /// 前回投入したときのマニフェストとチェックサムを比較し、内容が変わったドキュメントファイルの一覧を返す
+    ///
+    /// 投入済みのマニフェストが無い場合は全てのファイルを返す。
+    async fn changed_document_files(&self, save_dir: &Path) -> Result<Vec<PathBuf>> {
+        let manifest = match Manifest::load(&save_dir.join(MANIFEST_FILE)).await? {
+            Some(manifest) => manifest,
+            None => anyhow::bail!("{} doesn't exist in {}", MANIFEST_FILE, save_dir.display()),
+        };
+        let uploaded = Manifest::load(&save_dir.join(UPLOADED_MANIFEST_FILE))
+            .await?
+            .unwrap_or_default();
+
+        Ok(manifest
+            .changed_chunks(&uploaded)
+            .into_iter()
+            .map(|chunk| save_dir.join(&chunk.file))
+            .collect())
+    }
+
+    /// 内容が変わったドキュメントファイルだけをSolrへ送る
+    ///
+    /// 既存のドキュメントは削除しないので、生成元から削除されたドキュメントはインデックスに残る。
+    async fn post_changed_documents<C>(
+        &self,
+        core: C,
+        save_dir: &Path,
+        options: &PostOptions,
+    ) -> Result<()>
+    where
+        C: SolrCore + Sync + Send + 'static,
+    {
+        let files = self.changed_document_files(save_dir).await?;
+        if files.is_empty() {
+            tracing::info!("All document files are up to date, so nothing is posted.");
+            return Ok(());
+        }
+
+        tracing::info!("{} document files have been changed", files.len());
+        post_files(Arc::new(core), files, options).await?;
+        mark_uploaded(save_dir).await
     }
 }
 
 pub struct DocumentUploader {}
+impl Default for DocumentUploader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DocumentUploader {
     pub fn new() -> Self {
         Self {}
@@ -119,15 +631,28 @@ impl DocumentUploader {
 }
 impl PostDocument for DocumentUploader {}
 
+/// 生成の進捗をログに出力する間隔(行数)
+const PROGRESS_INTERVAL: usize = 10000;
+
 #[async_trait]
 pub trait GenerateDocument<'a>: ReadRows<'a> {
+    /// 生成の進捗を通知する先。デフォルトでは通知しない。
+    fn progress(&self) -> Option<&ProgressReporter> {
+        None
+    }
+
+    /// サフィックス付きフィールドの出力方法。デフォルトではドキュメントに含める。
+    fn expand_mode(&self) -> ExpandMode {
+        ExpandMode::Inline
+    }
+
     async fn clean(&'a self, save_dir: &Path) -> Result<()> {
         let mut files = tokio::fs::read_dir(save_dir).await?;
 
         tracing::info!("Start to delete existing file in {}.", save_dir.display());
         while let Ok(Some(entry)) = files.next_entry().await {
             let file = entry.path();
-            if file.extension() == Some(OsString::from("json").as_ref()) {
+            if is_document_file(&file) {
                 tracing::info!("delete existing file {}", file.display());
                 tokio::fs::remove_file(file).await?;
             }
@@ -136,106 +661,159 @@ pub trait GenerateDocument<'a>: ReadRows<'a> {
         Ok(())
     }
 
-    async fn generate(&'a self, save_dir: &Path, chunk_size: usize) -> Result<()> {
+    async fn generate(
+        &'a self,
+        save_dir: &Path,
+        chunk_size: usize,
+        format: DocumentFormat,
+        compress: bool,
+        workers: usize,
+    ) -> Result<()> {
         let (tx, mut rx): (
             Sender<<<Self as ReadRows>::Row as ToDocument>::Document>,
             Receiver<<<Self as ReadRows>::Row as ToDocument>::Document>,
         ) = tokio::sync::mpsc::channel(2 * chunk_size);
 
+        let manifest_path = save_dir.join(MANIFEST_FILE);
         let save_dir: PathBuf = save_dir.to_owned();
+        let progress = self.progress().cloned();
         let saver = tokio::task::spawn_blocking(move || {
+            let save = |filepath: PathBuf, documents: &[_]| {
+                let entry = save_documents(&filepath, documents, format, compress);
+                report(
+                    progress.as_ref(),
+                    IndexingProgress::ChunkSaved {
+                        file: filepath.display().to_string(),
+                        documents: documents.len(),
+                    },
+                );
+                entry
+            };
+
+            let mut manifest = Manifest::default();
             let mut suffix: u32 = 0;
             let mut documents: Vec<<<Self as ReadRows>::Row as ToDocument>::Document> =
                 Vec::with_capacity(chunk_size);
+            let extension = if compress {
+                format!("{}.gz", format.extension())
+            } else {
+                format.extension().to_string()
+            };
 
             while let Some(document) = rx.blocking_recv() {
                 suffix += 1;
                 documents.push(document);
 
                 if documents.len() >= chunk_size {
-                    let filepath = save_dir.join(format!("doc-{}.json", suffix));
-
-                    tracing::info!("Generate document file: {}", filepath.display());
-                    let file = match File::create(filepath) {
-                        Ok(file) => file,
-                        Err(e) => {
-                            let message = format!("failed to create file: {:?}", e);
-                            tracing::error!(message);
-                            panic!("{}", message);
-                        }
-                    };
-                    let writer = BufWriter::new(file);
-                    if let Err(e) = serde_json::to_writer_pretty(writer, &documents) {
-                        let message = format!("failed to write document content: {:?}", e);
-                        tracing::error!(message);
-                        panic!("{}", message);
-                    }
-
+                    let filepath = save_dir.join(format!("doc-{}.{}", suffix, extension));
+                    manifest.chunks.push(save(filepath, &documents));
                     documents.clear();
                 }
             }
 
             if !documents.is_empty() {
-                let filepath = save_dir.join(format!("doc-{}.json", suffix));
-
-                tracing::info!("Generate document file: {}", filepath.display());
-                let file = match File::create(filepath) {
-                    Ok(file) => file,
-                    Err(e) => {
-                        let message = format!("failed to create file: {:?}", e);
-                        tracing::error!(message);
-                        panic!("{}", message);
-                    }
-                };
-                let writer = BufWriter::new(file);
-                if let Err(e) = serde_json::to_writer_pretty(writer, &documents) {
-                    let message = format!("failed to write document content: {:?}", e);
-                    tracing::error!(message);
-                    panic!("{}", message);
-                }
-
+                let filepath = save_dir.join(format!("doc-{}.{}", suffix, extension));
+                manifest.chunks.push(save(filepath, &documents));
                 documents.clear();
             }
+
+            manifest
         });
 
+        let workers = workers.max(1);
+        let mode = self.expand_mode();
+        let started_at = Instant::now();
+        let mut processed: usize = 0;
+
         let mut stream = self.read_rows().await?;
-        let mut tasks: FuturesUnordered<JoinHandle<()>> = FuturesUnordered::new();
+        // マニフェストのチェックサムが実行ごとに変わらないよう、読み込んだ行の順序のままドキュメントを送る
+        let mut tasks: FuturesOrdered<
+            JoinHandle<<<Self as ReadRows>::Row as ToDocument>::Document>,
+        > = FuturesOrdered::new();
         while let Some(row) = StreamExt::try_next(&mut stream).await? {
-            let tx = tx.clone();
+            // 同時に実行するタスクが`workers`個を超えないよう、先頭のタスクが終わるまで待つ
+            if tasks.len() >= workers {
+                if let Some(task) = tasks.next().await {
+                    match task {
+                        Ok(document) => tx
+                            .send(document)
+                            .await
+                            .expect("failed to send document to channel"),
+                        Err(e) => {
+                            tracing::error!("an error occurred when generating document: {:?}", e);
+                            report(
+                                self.progress(),
+                                IndexingProgress::Error {
+                                    message: format!("failed to generate document: {:?}", e),
+                                },
+                            );
+                            saver.abort();
+                            return Err(anyhow::anyhow!(e));
+                        }
+                    }
+                }
+            }
+
             let task = tokio::task::spawn(async move {
-                let document = match row.to_document() {
+                match row.to_document_with(mode) {
                     Ok(document) => document,
                     Err(e) => {
-                        let message = format!(
-                            "failed to convert from row into document cause: {}",
-                            e.to_string()
-                        );
+                        let message =
+                            format!("failed to convert from row into document cause: {}", e);
                         tracing::error!(message);
                         panic!("{}", message);
                     }
-                };
-
-                tx.send(document)
-                    .await
-                    .expect("failed to send document to channel");
+                }
             });
-            tasks.push(task);
+            tasks.push_back(task);
+
+            processed += 1;
+            if processed.is_multiple_of(PROGRESS_INTERVAL) {
+                tracing::info!("{} rows processed", processed);
+                report(
+                    self.progress(),
+                    IndexingProgress::RowsProcessed { rows: processed },
+                );
+            }
         }
-        mem::drop(tx);
 
         while let Some(task) = tasks.next().await {
             match task {
-                Ok(()) => {}
+                Ok(document) => tx
+                    .send(document)
+                    .await
+                    .expect("failed to send document to channel"),
                 Err(e) => {
                     tracing::error!("an error occurred when generating document: {:?}", e);
+                    report(
+                        self.progress(),
+                        IndexingProgress::Error {
+                            message: format!("failed to generate document: {:?}", e),
+                        },
+                    );
                     saver.abort();
                     return Err(anyhow::anyhow!(e));
                 }
             }
         }
+        mem::drop(tx);
+        report(
+            self.progress(),
+            IndexingProgress::RowsProcessed { rows: processed },
+        );
+
+        let elapsed = started_at.elapsed();
+        tracing::info!(
+            "{} documents generated in {:.1} seconds ({:.1} documents/sec, {} workers)",
+            processed,
+            elapsed.as_secs_f64(),
+            processed as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            workers
+        );
 
         match saver.await {
-            Ok(_) => {
+            Ok(manifest) => {
+                manifest.save(&manifest_path).await?;
                 tracing::info!("All documents successfully saved.");
                 Ok(())
             }
@@ -246,3 +824,435 @@ pub trait GenerateDocument<'a>: ReadRows<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{MockRequest, MockSolrCore};
+
+    #[derive(Debug)]
+    struct Row(u32);
+
+    impl ToDocument for Row {
+        type Document = Value;
+
+        fn to_document(self) -> Result<Self::Document> {
+            Ok(serde_json::json!({"id": format!("{:03}", self.0)}))
+        }
+    }
+
+    struct Generator(u32);
+
+    #[async_trait]
+    impl<'a> ReadRows<'a> for Generator {
+        type Row = Row;
+
+        async fn read_rows(
+            &'a self,
+        ) -> Result<
+            Pin<Box<dyn Stream<Item = std::result::Result<Self::Row, sqlx::Error>> + Send + 'a>>,
+        > {
+            Ok(Box::pin(tokio_stream::iter(
+                (1..=self.0).map(|i| Ok(Row(i))),
+            )))
+        }
+    }
+
+    impl<'a> GenerateDocument<'a> for Generator {}
+
+    fn prepare_documents(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "atcoder_search_libs_{}_{}",
+            name,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("doc-1.json"), r#"[{"id": "001"}]"#).unwrap();
+        std::fs::write(dir.join("doc-2.json"), r#"[{"id": "002"}]"#).unwrap();
+        std::fs::write(dir.join("README.txt"), "not a document").unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_post_documents() {
+        let dir = prepare_documents("post_documents");
+        let core = MockSolrCore::new("example");
+
+        DocumentUploader::new()
+            .post_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let requests = core.requests();
+        let posts = requests
+            .iter()
+            .filter(|request| matches!(request, MockRequest::Post(_)))
+            .count();
+        assert_eq!(posts, 2);
+        assert_eq!(requests.last(), Some(&MockRequest::Commit));
+    }
+
+    #[tokio::test]
+    async fn test_post_documents_progress() {
+        let dir = prepare_documents("post_documents_progress");
+        let core = MockSolrCore::new("example");
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let progress = {
+            let events = events.clone();
+            ProgressReporter::new(move |progress| events.lock().unwrap().push(progress))
+        };
+
+        DocumentUploader::new()
+            .post_documents(
+                core.clone(),
+                &dir,
+                &PostOptions {
+                    progress: Some(progress),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, IndexingProgress::ChunkPosted { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_post_documents_rollback_on_failure() {
+        let dir = prepare_documents("post_documents_rollback");
+        let core = MockSolrCore::new("example");
+        core.fail_next_posts(2);
+
+        let result = DocumentUploader::new()
+            .post_documents(
+                core.clone(),
+                &dir,
+                &PostOptions {
+                    optimize: true,
+                    ..Default::default()
+                },
+            )
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+        let requests = core.requests();
+        assert!(requests.contains(&MockRequest::Rollback));
+        assert!(!requests.contains(&MockRequest::Optimize));
+    }
+
+    #[tokio::test]
+    async fn test_post_documents_with_commit_within() {
+        let dir = prepare_documents("post_documents_commit_within");
+        let core = MockSolrCore::new("example");
+        let options = PostOptions {
+            update: UpdateParams {
+                commit_within: Some(10000),
+                overwrite: Some(false),
+            },
+            ..Default::default()
+        };
+
+        DocumentUploader::new()
+            .post_documents(core.clone(), &dir, &options)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(core.update_params(), vec![options.update.clone(); 2]);
+        assert!(!core.requests().contains(&MockRequest::Commit));
+    }
+
+    #[tokio::test]
+    async fn test_post_documents_with_soft_commit() {
+        let dir = prepare_documents("post_documents_soft_commit");
+        let core = MockSolrCore::new("example");
+        let options = PostOptions {
+            commit: CommitParams {
+                soft_commit: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        DocumentUploader::new()
+            .post_documents(core.clone(), &dir, &options)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(core.requests().last(), Some(&MockRequest::SoftCommit));
+    }
+
+    #[tokio::test]
+    async fn test_validate_documents() {
+        let dir = prepare_documents("validate_documents");
+
+        let summary = DocumentUploader::new()
+            .validate_documents(&dir, "id")
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.total_documents(), 2);
+        assert!(summary.files[0].path.ends_with("doc-1.json"));
+    }
+
+    #[tokio::test]
+    async fn test_validate_documents_fails_on_invalid_file() {
+        let dir = prepare_documents("validate_documents_invalid");
+        std::fs::write(dir.join("doc-3.json"), r#"[{"id": "003"}, {"title": "a"}]"#).unwrap();
+
+        let result = DocumentUploader::new().validate_documents(&dir, "id").await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_documents() {
+        let dir = prepare_documents("compressed_documents");
+        std::fs::remove_file(dir.join("doc-2.json")).unwrap();
+        save_documents(
+            &dir.join("doc-2.json.gz"),
+            &[
+                serde_json::json!({"id": "002"}),
+                serde_json::json!({"id": "003"}),
+            ],
+            DocumentFormat::Json,
+            true,
+        );
+
+        let summary = DocumentUploader::new()
+            .validate_documents(&dir, "id")
+            .await
+            .unwrap();
+        assert_eq!(summary.files.len(), 2);
+        assert_eq!(summary.total_documents(), 3);
+
+        let core = MockSolrCore::new("example");
+        DocumentUploader::new()
+            .post_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let posted = core
+            .requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                MockRequest::Post(body) => body,
+                _ => None,
+            })
+            .map(|body| serde_json::from_slice::<Value>(&body).unwrap())
+            .collect::<Vec<_>>();
+        assert!(posted.contains(&serde_json::json!([{"id": "002"}, {"id": "003"}])));
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_documents() {
+        let dir = prepare_documents("ndjson_documents");
+        std::fs::remove_file(dir.join("doc-2.json")).unwrap();
+        save_documents(
+            &dir.join("doc-2.ndjson"),
+            &[
+                serde_json::json!({"id": "002"}),
+                serde_json::json!({"id": "003"}),
+            ],
+            DocumentFormat::Ndjson,
+            false,
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("doc-2.ndjson")).unwrap(),
+            "{\"id\":\"002\"}\n{\"id\":\"003\"}\n"
+        );
+
+        let summary = DocumentUploader::new()
+            .validate_documents(&dir, "id")
+            .await
+            .unwrap();
+        assert_eq!(summary.total_documents(), 3);
+
+        let core = MockSolrCore::new("example");
+        DocumentUploader::new()
+            .post_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let requests = core.requests();
+        assert!(requests.contains(&MockRequest::PostDocs(Some(
+            b"{\"id\":\"002\"}\n{\"id\":\"003\"}\n".to_vec()
+        ))));
+        assert_eq!(
+            requests
+                .iter()
+                .filter(|request| matches!(request, MockRequest::Post(_)))
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_generate_with_workers() {
+        let dir = std::env::temp_dir().join(format!(
+            "atcoder_search_libs_generate_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        Generator(25)
+            .generate(&dir, 10, DocumentFormat::Ndjson, false, 2)
+            .await
+            .unwrap();
+        let summary = DocumentUploader::new()
+            .validate_documents(&dir, "id")
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.files.len(), 3);
+        assert_eq!(summary.total_documents(), 25);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_post_changed_documents() {
+        let dir = std::env::temp_dir().join(format!(
+            "atcoder_search_libs_post_changed_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let uploader = DocumentUploader::new();
+
+        Generator(25)
+            .generate(&dir, 10, DocumentFormat::Json, false, 4)
+            .await
+            .unwrap();
+        let manifest = Manifest::load(&dir.join(MANIFEST_FILE))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            manifest
+                .chunks
+                .iter()
+                .map(|chunk| chunk.documents)
+                .collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+
+        // 初回は全てのファイルを送る
+        let core = MockSolrCore::new("example");
+        uploader
+            .post_changed_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        let posts = |core: &MockSolrCore| {
+            core.requests()
+                .iter()
+                .filter(|request| matches!(request, MockRequest::Post(_)))
+                .count()
+        };
+        assert_eq!(posts(&core), 3);
+
+        // 同じ内容で生成し直した場合は何も送らない
+        Generator(25)
+            .generate(&dir, 10, DocumentFormat::Json, false, 4)
+            .await
+            .unwrap();
+        let core = MockSolrCore::new("example");
+        uploader
+            .post_changed_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        assert!(core.requests().is_empty());
+
+        // 最後のチャンクだけ変わった場合はそのファイルだけを送る
+        Generator(27)
+            .generate(&dir, 10, DocumentFormat::Json, false, 4)
+            .await
+            .unwrap();
+        let changed = uploader.changed_document_files(&dir).await.unwrap();
+        let core = MockSolrCore::new("example");
+        uploader
+            .post_changed_documents(core.clone(), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(changed.len(), 1);
+        assert_eq!(posts(&core), 1);
+        assert_eq!(core.requests().last(), Some(&MockRequest::Commit));
+    }
+
+    #[test]
+    fn test_document_format_from_path() {
+        for (path, expected) in [
+            ("doc-1.json", Some(DocumentFormat::Json)),
+            ("doc-1.json.gz", Some(DocumentFormat::Json)),
+            ("doc-1.ndjson", Some(DocumentFormat::Ndjson)),
+            ("doc-1.ndjson.gz", Some(DocumentFormat::Ndjson)),
+            ("README.txt", None),
+            ("doc-1.gz", None),
+        ] {
+            assert_eq!(DocumentFormat::from_path(Path::new(path)), expected);
+        }
+    }
+
+    #[test]
+    fn test_validate_document_file() {
+        assert_eq!(
+            validate_document_file(br#"[{"id": "001"}, {"id": 2}]"#, "id", DocumentFormat::Json)
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            validate_document_file(b"[]", "id", DocumentFormat::Json).unwrap(),
+            0
+        );
+        assert!(validate_document_file(br#"[{"id": "001"},"#, "id", DocumentFormat::Json).is_err());
+        assert!(validate_document_file(br#"{"id": "001"}"#, "id", DocumentFormat::Json).is_err());
+        assert!(validate_document_file(br#"[{"id": null}]"#, "id", DocumentFormat::Json).is_err());
+        assert!(validate_document_file(br#"["001"]"#, "id", DocumentFormat::Json).is_err());
+        assert_eq!(
+            validate_document_file(
+                b"{\"id\": \"001\"}\n{\"id\": \"002\"}\n",
+                "id",
+                DocumentFormat::Ndjson
+            )
+            .unwrap(),
+            2
+        );
+        assert!(validate_document_file(
+            b"{\"id\": \"001\"}\n{\"id\"",
+            "id",
+            DocumentFormat::Ndjson
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "integration")]
+    #[tokio::test]
+    async fn test_post_documents_in_container() {
+        use crate::testing::containers::SolrContainer;
+
+        let solr = SolrContainer::start("example").await;
+        let dir = prepare_documents("post_documents_in_container");
+
+        DocumentUploader::new()
+            .post_documents(solr.core("example"), &dir, &PostOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let status = solr.core("example").status().await.unwrap();
+        assert_eq!(status.index.num_docs, 2);
+    }
+}
@@ -0,0 +1,156 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+
+/// Table of romaji (Hepburn and Kunrei-shiki) to hiragana.
+#[rustfmt::skip]
+static ROMAJI_TABLE: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
+    HashMap::from([
+        ("a", "あ"), ("i", "い"), ("u", "う"), ("e", "え"), ("o", "お"),
+        ("ka", "か"), ("ki", "き"), ("ku", "く"), ("ke", "け"), ("ko", "こ"),
+        ("sa", "さ"), ("si", "し"), ("shi", "し"), ("su", "す"), ("se", "せ"), ("so", "そ"),
+        ("ta", "た"), ("ti", "ち"), ("chi", "ち"), ("tu", "つ"), ("tsu", "つ"), ("te", "て"), ("to", "と"),
+        ("na", "な"), ("ni", "に"), ("nu", "ぬ"), ("ne", "ね"), ("no", "の"),
+        ("ha", "は"), ("hi", "ひ"), ("hu", "ふ"), ("fu", "ふ"), ("he", "へ"), ("ho", "ほ"),
+        ("ma", "ま"), ("mi", "み"), ("mu", "む"), ("me", "め"), ("mo", "も"),
+        ("ya", "や"), ("yu", "ゆ"), ("yo", "よ"),
+        ("ra", "ら"), ("ri", "り"), ("ru", "る"), ("re", "れ"), ("ro", "ろ"),
+        ("wa", "わ"), ("wo", "を"),
+        ("ga", "が"), ("gi", "ぎ"), ("gu", "ぐ"), ("ge", "げ"), ("go", "ご"),
+        ("za", "ざ"), ("zi", "じ"), ("ji", "じ"), ("zu", "ず"), ("ze", "ぜ"), ("zo", "ぞ"),
+        ("da", "だ"), ("di", "ぢ"), ("du", "づ"), ("de", "で"), ("do", "ど"),
+        ("ba", "ば"), ("bi", "び"), ("bu", "ぶ"), ("be", "べ"), ("bo", "ぼ"),
+        ("pa", "ぱ"), ("pi", "ぴ"), ("pu", "ぷ"), ("pe", "ぺ"), ("po", "ぽ"),
+        ("kya", "きゃ"), ("kyu", "きゅ"), ("kyo", "きょ"),
+        ("sya", "しゃ"), ("syu", "しゅ"), ("syo", "しょ"),
+        ("sha", "しゃ"), ("shu", "しゅ"), ("she", "しぇ"), ("sho", "しょ"),
+        ("tya", "ちゃ"), ("tyu", "ちゅ"), ("tyo", "ちょ"),
+        ("cha", "ちゃ"), ("chu", "ちゅ"), ("che", "ちぇ"), ("cho", "ちょ"),
+        ("nya", "にゃ"), ("nyu", "にゅ"), ("nyo", "にょ"),
+        ("hya", "ひゃ"), ("hyu", "ひゅ"), ("hyo", "ひょ"),
+        ("mya", "みゃ"), ("myu", "みゅ"), ("myo", "みょ"),
+        ("rya", "りゃ"), ("ryu", "りゅ"), ("ryo", "りょ"),
+        ("gya", "ぎゃ"), ("gyu", "ぎゅ"), ("gyo", "ぎょ"),
+        ("zya", "じゃ"), ("zyu", "じゅ"), ("zyo", "じょ"),
+        ("ja", "じゃ"), ("ju", "じゅ"), ("je", "じぇ"), ("jo", "じょ"),
+        ("bya", "びゃ"), ("byu", "びゅ"), ("byo", "びょ"),
+        ("pya", "ぴゃ"), ("pyu", "ぴゅ"), ("pyo", "ぴょ"),
+        ("fa", "ふぁ"), ("fi", "ふぃ"), ("fe", "ふぇ"), ("fo", "ふぉ"),
+        ("nn", "ん"), ("n'", "ん"), ("-", "ー"),
+    ])
+});
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'i' | 'u' | 'e' | 'o')
+}
+
+/// Convert katakana in the text into hiragana. Other characters are kept as they are.
+pub fn katakana_to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Convert romaji in the text into hiragana.
+///
+/// Returns `None` if the text has an alphabet sequence which can't be read as romaji.
+pub fn romaji_to_hiragana(text: &str) -> Option<String> {
+    let chars = text.to_lowercase().chars().collect::<Vec<char>>();
+    let mut result = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        // 子音の連続は促音として扱う
+        if c.is_ascii_alphabetic() && !is_vowel(c) && c != 'n' && next == Some(c) {
+            result.push('っ');
+            i += 1;
+            continue;
+        }
+        // 母音・y・アポストロフィが続かない`n`は撥音として扱う
+        if c == 'n' && !matches!(next, Some(n) if is_vowel(n) || n == 'y' || n == 'n' || n == '\'')
+        {
+            result.push('ん');
+            i += 1;
+            continue;
+        }
+
+        let matched = (1..=3).rev().find_map(|length| {
+            let candidate = chars.get(i..i + length)?.iter().collect::<String>();
+            ROMAJI_TABLE
+                .get(candidate.as_str())
+                .map(|kana| (length, *kana))
+        });
+        match matched {
+            Some((length, kana)) => {
+                result.push_str(kana);
+                i += length;
+            }
+            None if c.is_ascii_alphabetic() => return None,
+            None => {
+                result.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    Some(result)
+}
+
+/// Generate the hiragana reading of the text written in kana or romaji.
+///
+/// Returns `None` if the text can't be read as a whole, e.g. it contains English words or kanji.
+pub fn to_reading(text: &str) -> Option<String> {
+    let text = katakana_to_hiragana(&text.nfkc().collect::<String>());
+    if text
+        .chars()
+        .any(|c| !c.is_ascii() && !matches!(c, '\u{3041}'..='\u{3096}' | 'ー'))
+    {
+        return None;
+    }
+    romaji_to_hiragana(&text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_romaji_to_hiragana() {
+        assert_eq!(romaji_to_hiragana("hamayan").as_deref(), Some("はまやん"));
+        assert_eq!(
+            romaji_to_hiragana("Kyoupuro").as_deref(),
+            Some("きょうぷろ")
+        );
+        assert_eq!(
+            romaji_to_hiragana("chokudai").as_deref(),
+            Some("ちょくだい")
+        );
+        assert_eq!(romaji_to_hiragana("kinnyu").as_deref(), Some("きんゆ"));
+        assert_eq!(romaji_to_hiragana("kitte").as_deref(), Some("きって"));
+        assert_eq!(romaji_to_hiragana("sin'ya").as_deref(), Some("しんや"));
+        assert_eq!(
+            romaji_to_hiragana("kyoupuro_123").as_deref(),
+            Some("きょうぷろ_123")
+        );
+        assert_eq!(romaji_to_hiragana("abc"), None);
+        assert_eq!(romaji_to_hiragana("tourist"), None);
+    }
+
+    #[test]
+    fn test_to_reading() {
+        assert_eq!(to_reading("ハマヤン").as_deref(), Some("はまやん"));
+        assert_eq!(to_reading("ｈａｍａｙａｎ").as_deref(), Some("はまやん"));
+        assert_eq!(
+            to_reading("きょぷろ_2023").as_deref(),
+            Some("きょぷろ_2023")
+        );
+        assert_eq!(to_reading("競プロ"), None);
+        assert_eq!(to_reading("tourist"), None);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+use utoipa::ToSchema;
+
+/// Language of a search keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum Language {
+    #[serde(rename = "ja")]
+    Japanese,
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "mixed")]
+    Mixed,
+}
+
+fn is_japanese(c: char) -> bool {
+    matches!(c,
+        '\u{3005}'                  // 々
+        | '\u{3040}'..='\u{309F}'   // Hiragana
+        | '\u{30A0}'..='\u{30FF}'   // Katakana
+        | '\u{31F0}'..='\u{31FF}'   // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}'   // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+    )
+}
+
+/// Detect the language of the text by the characters it contains.
+///
+/// Kana and kanji indicate Japanese, and ASCII letters (including full-width ones) indicate English.
+/// Returns `None` when the text has neither of them, e.g. it consists of only digits or symbols.
+pub fn detect_language(text: &str) -> Option<Language> {
+    let (mut japanese, mut english) = (false, false);
+    for c in text.nfkc() {
+        japanese |= is_japanese(c);
+        english |= c.is_ascii_alphabetic();
+    }
+
+    match (japanese, english) {
+        (true, false) => Some(Language::Japanese),
+        (false, true) => Some(Language::English),
+        (true, true) => Some(Language::Mixed),
+        (false, false) => None,
+    }
+}
+
+/// Build the `qf` parameter which boosts the fields for the detected language.
+///
+/// `ja_fields` are boosted for Japanese keywords and `en_fields` for English ones. The other fields
+/// and keywords of mixed or unknown language are not boosted.
+pub fn weighted_qf(
+    language: Option<Language>,
+    ja_fields: &[&str],
+    en_fields: &[&str],
+    other_fields: &[&str],
+    boost: f64,
+) -> String {
+    let weight = |fields: &[&str], target: Language| {
+        fields
+            .iter()
+            .map(|field| {
+                if language == Some(target) {
+                    format!("{}^{}", field, boost)
+                } else {
+                    field.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut fields = weight(ja_fields, Language::Japanese);
+    fields.extend(weight(en_fields, Language::English));
+    fields.extend(other_fields.iter().map(|field| field.to_string()));
+    fields.join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("動的計画法"), Some(Language::Japanese));
+        assert_eq!(
+            detect_language("ナップサック 問題"),
+            Some(Language::Japanese)
+        );
+        assert_eq!(detect_language("knapsack problem"), Some(Language::English));
+        assert_eq!(detect_language("ＤＰ"), Some(Language::English));
+        assert_eq!(detect_language("DP 高速化"), Some(Language::Mixed));
+        assert_eq!(detect_language("300 + 1"), None);
+        assert_eq!(detect_language(""), None);
+    }
+
+    #[test]
+    fn test_weighted_qf() {
+        let qf = |language| weighted_qf(language, &["text_ja"], &["text_en"], &["text_1gram"], 2.0);
+        assert_eq!(qf(Some(Language::Japanese)), "text_ja^2 text_en text_1gram");
+        assert_eq!(qf(Some(Language::English)), "text_ja text_en^2 text_1gram");
+        assert_eq!(qf(Some(Language::Mixed)), "text_ja text_en text_1gram");
+        assert_eq!(qf(None), "text_ja text_en text_1gram");
+    }
+}
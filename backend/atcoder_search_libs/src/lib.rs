@@ -1,18 +1,32 @@
 pub mod api;
+pub mod color;
+pub mod country;
 pub mod indexing;
+pub mod kana;
+pub mod language;
+pub mod query_expansion;
+pub mod schema;
 pub mod solr;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
-pub use api::{FieldList, ToQueryParameter};
-pub use atcoder_search_derive::{ExpandField, FieldList};
+pub use api::{ApiError, ErrorCode, FieldList, ToQueryParameter};
+pub use atcoder_search_derive::{ExpandField, FieldList, SolrSchema};
 pub use indexing::{
-    DocumentUploader, ExpandField, GenerateDocument, PostDocument, ReadRows, ToDocument,
+    DocumentFileSummary, DocumentFormat, DocumentUploader, ExpandField, ExpandMode,
+    GenerateDocument, IndexingProgress, PostDocument, PostOptions, ProgressReporter, ReadRows,
+    ToDocument, ValidationSummary,
 };
+pub use schema::SolrSchema;
 
 #[cfg(test)]
 mod test {
-    use crate::{api::FieldList, indexing::ExpandField};
+    use crate::{
+        api::FieldList,
+        indexing::{ExpandField, ExpandMode},
+    };
     use atcoder_search_derive::{ExpandField, FieldList};
-    use chrono::{DateTime, Local, TimeZone};
+    use chrono::{DateTime, Local, NaiveDateTime};
 
     #[derive(ExpandField)]
     struct MyStruct {
@@ -29,8 +43,9 @@ mod test {
             id: 1,
             title: String::from("my title"),
             sentence: vec![String::from("foo"), String::from("bar")],
-            published_at: Local
-                .datetime_from_str("2023/05/21 12:31:28", "%Y/%m/%d %H:%M:%S")
+            published_at: NaiveDateTime::parse_from_str("2023/05/21 12:31:28", "%Y/%m/%d %H:%M:%S")
+                .unwrap()
+                .and_local_timezone(Local)
                 .unwrap(),
         };
 
@@ -42,6 +57,94 @@ mod test {
         assert_eq!(expected, serde_json::to_string(&data).unwrap())
     }
 
+    #[derive(ExpandField)]
+    struct OptionalStruct {
+        #[serde(rename = "id")]
+        user_id: String,
+        #[suffix(text_ja)]
+        affiliation: Option<String>,
+        rating: Option<i32>,
+        updated_at: Option<DateTime<Local>>,
+    }
+
+    #[test]
+    fn test_expand_optional_fields() {
+        let obj = OptionalStruct {
+            user_id: String::from("fjnkt98"),
+            affiliation: None,
+            rating: Some(1200),
+            updated_at: Some(
+                NaiveDateTime::parse_from_str("2023/05/21 12:31:28", "%Y/%m/%d %H:%M:%S")
+                    .unwrap()
+                    .and_local_timezone(Local)
+                    .unwrap(),
+            ),
+        };
+
+        let expected =
+            String::from(r#"{"id":"fjnkt98","rating":1200,"updated_at":"2023-05-21T03:31:28Z"}"#);
+        assert_eq!(expected, serde_json::to_string(&obj.expand()).unwrap());
+
+        let obj = OptionalStruct {
+            user_id: String::from("fjnkt98"),
+            affiliation: Some(String::from("University")),
+            rating: None,
+            updated_at: None,
+        };
+
+        let expected = String::from(
+            r#"{"affiliation":"University","affiliation__text_ja":"University","id":"fjnkt98"}"#,
+        );
+        assert_eq!(expected, serde_json::to_string(&obj.expand()).unwrap());
+    }
+
+    fn to_upper(values: &[String]) -> Vec<String> {
+        values.iter().map(|value| value.to_uppercase()).collect()
+    }
+
+    #[derive(ExpandField)]
+    struct TransformedStruct {
+        #[suffix(text_ja, text_reading = "to_upper")]
+        sentence: Vec<String>,
+        #[suffix(text_reading = "to_upper")]
+        note: Option<Vec<String>>,
+    }
+
+    #[test]
+    fn test_expand_transformed_fields() {
+        let obj = TransformedStruct {
+            sentence: vec![String::from("foo"), String::from("bar")],
+            note: None,
+        };
+
+        let expected = String::from(
+            r#"{"sentence":["foo","bar"],"sentence__text_ja":["foo","bar"],"sentence__text_reading":["FOO","BAR"]}"#,
+        );
+        assert_eq!(expected, serde_json::to_string(&obj.expand()).unwrap());
+    }
+
+    #[test]
+    fn test_expand_copy_field_mode() {
+        let obj = TransformedStruct {
+            sentence: vec![String::from("foo")],
+            note: Some(vec![String::from("bar")]),
+        };
+
+        // 元のフィールドと同じ値のサフィックス付きフィールドは出力せず、変換したものだけを出力する
+        let expected = String::from(
+            r#"{"note":["bar"],"note__text_reading":["BAR"],"sentence":["foo"],"sentence__text_reading":["FOO"]}"#,
+        );
+        assert_eq!(
+            expected,
+            serde_json::to_string(&obj.expand_with(ExpandMode::CopyField)).unwrap()
+        );
+        assert_eq!(
+            "copyfield".parse::<ExpandMode>().unwrap(),
+            ExpandMode::CopyField
+        );
+        assert!("copy".parse::<ExpandMode>().is_err());
+    }
+
     #[allow(dead_code)]
     #[derive(FieldList)]
     struct ResponseDocument {
@@ -56,4 +159,29 @@ mod test {
         let expected = "id,title,sentence";
         assert_eq!(field_list, expected);
     }
+
+    #[allow(dead_code)]
+    #[derive(FieldList)]
+    struct ContestDocument {
+        #[field_list(rename = "contest_id")]
+        id: String,
+        #[field_list(skip)]
+        score: f64,
+    }
+
+    #[allow(dead_code)]
+    #[derive(FieldList)]
+    struct ProblemDocument {
+        problem_id: String,
+        #[field_list(flatten)]
+        contest: ContestDocument,
+        #[field_list(rename = "title")]
+        problem_title: String,
+    }
+
+    #[test]
+    fn test_field_list_attributes() {
+        assert_eq!(ContestDocument::field_list(), "contest_id");
+        assert_eq!(ProblemDocument::field_list(), "problem_id,contest_id,title");
+    }
 }
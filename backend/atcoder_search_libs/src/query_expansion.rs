@@ -0,0 +1,164 @@
+use crate::solr::query::parse_keyword;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use unicode_normalization::UnicodeNormalization;
+use utoipa::ToSchema;
+
+/// Groups of synonyms of competitive programming jargon in Japanese and English.
+///
+/// Every word in a group is expanded into the others.
+pub const COMPETITIVE_PROGRAMMING_SYNONYMS: &[&[&str]] = &[
+    &["DP", "動的計画法", "dynamic programming"],
+    &["BFS", "幅優先探索", "breadth first search"],
+    &["DFS", "深さ優先探索", "depth first search"],
+    &["二分探索", "binary search", "にぶたん"],
+    &["UnionFind", "Union-Find", "素集合データ構造", "DSU"],
+    &["セグ木", "セグメント木", "segment tree"],
+    &["BIT", "フェニック木", "Fenwick tree"],
+    &["ダイクストラ法", "Dijkstra"],
+    &["最小全域木", "MST", "minimum spanning tree"],
+    &["最短経路", "shortest path"],
+    &["累積和", "prefix sum", "cumulative sum"],
+    &["いもす法", "imos"],
+    &["尺取り法", "two pointers"],
+    &["貪欲法", "greedy"],
+    &["全探索", "brute force"],
+    &["木DP", "tree DP"],
+    &["桁DP", "digit DP"],
+    &["bitDP", "bit DP", "bit全探索"],
+    &["トポロジカルソート", "topological sort"],
+    &["最大流", "max flow", "maxflow"],
+    &["素数", "prime"],
+    &["約数", "divisor"],
+    &["最大公約数", "GCD"],
+    &["最小公倍数", "LCM"],
+    &["幾何", "geometry"],
+    &["文字列", "string"],
+];
+
+/// An expansion applied to a term of the keyword.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct QueryExpansion {
+    /// The term in the keyword
+    pub term: String,
+    /// The synonyms added to the query
+    pub synonyms: Vec<String>,
+}
+
+/// Keyword converted into an edismax query with the synonyms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandedKeyword {
+    pub query: String,
+    pub expansions: Vec<QueryExpansion>,
+}
+
+/// Dictionary to look up the synonyms of a word, ignoring the case and the width of characters.
+pub struct SynonymDictionary {
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+fn normalize(word: &str) -> String {
+    word.nfkc().collect::<String>().to_lowercase()
+}
+
+impl SynonymDictionary {
+    pub fn new(groups: &[&[&str]]) -> Self {
+        let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+        for group in groups {
+            for word in group.iter() {
+                let entry = synonyms.entry(normalize(word)).or_default();
+                // 表記揺れだけの違いは同義語として扱わない
+                for synonym in group
+                    .iter()
+                    .filter(|synonym| normalize(synonym) != normalize(word))
+                {
+                    if !entry.iter().any(|existing| existing == synonym) {
+                        entry.push(synonym.to_string());
+                    }
+                }
+            }
+        }
+        Self { synonyms }
+    }
+
+    pub fn get(&self, word: &str) -> Option<&Vec<String>> {
+        self.synonyms.get(&normalize(word))
+    }
+
+    /// Convert the keyword into an escaped edismax query, adding the synonyms of each term with `OR`.
+    ///
+    /// Field-restricted terms are not expanded. See [`parse_keyword`] for the syntax of the keyword.
+    pub fn expand(&self, keyword: &str, fields: &[&str]) -> ExpandedKeyword {
+        let mut expansions = Vec::new();
+        let query = parse_keyword(keyword, fields)
+            .into_iter()
+            .map(|term| {
+                let synonyms = match term.field {
+                    Some(_) => None,
+                    None => self.get(&term.value),
+                };
+                match synonyms {
+                    Some(synonyms) => {
+                        expansions.push(QueryExpansion {
+                            term: term.value.clone(),
+                            synonyms: synonyms.clone(),
+                        });
+                        term.with_alternatives(synonyms)
+                    }
+                    None => term.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        ExpandedKeyword { query, expansions }
+    }
+}
+
+/// Dictionary of [`COMPETITIVE_PROGRAMMING_SYNONYMS`].
+pub static SYNONYMS: Lazy<SynonymDictionary> =
+    Lazy::new(|| SynonymDictionary::new(COMPETITIVE_PROGRAMMING_SYNONYMS));
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_synonyms() {
+        let dictionary = SynonymDictionary::new(&[&["DP", "動的計画法"], &["DP", "dp"]]);
+        assert_eq!(
+            dictionary.get("ｄｐ"),
+            Some(&vec![String::from("動的計画法")])
+        );
+        assert_eq!(
+            dictionary.get("動的計画法"),
+            Some(&vec![String::from("DP")])
+        );
+        assert_eq!(dictionary.get("greedy"), None);
+    }
+
+    #[test]
+    fn test_expand() {
+        let expanded = SYNONYMS.expand(r#"dp -BFS "binary search" category:DP"#, &["category"]);
+        assert_eq!(
+            expanded.query,
+            r#"(dp OR 動的計画法 OR "dynamic programming") -(BFS OR 幅優先探索 OR "breadth first search") ("binary search" OR 二分探索 OR にぶたん) category:DP"#
+        );
+        assert_eq!(
+            expanded
+                .expansions
+                .iter()
+                .map(|expansion| expansion.term.as_str())
+                .collect::<Vec<_>>(),
+            vec!["dp", "BFS", "binary search"]
+        );
+    }
+
+    #[test]
+    fn test_expand_without_synonyms() {
+        let expanded = SYNONYMS.expand("abc300 A", &[]);
+        assert_eq!(expanded.query, "abc300 A");
+        assert!(expanded.expansions.is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{
+    indexing::ExpandMode,
+    solr::{
+        core::{SolrCore, SolrCoreError},
+        model::{SolrCopyField, SolrSchemaField, SolrSchemaInfo},
+    },
+};
+use serde_json::{Map, Value};
+
+/// ドキュメントの構造体からSolrのスキーマ定義を生成するトレイト
+///
+/// `#[derive(SolrSchema)]`で実装する。導出したコードは`SolrSchemaField`を参照するので、併せてインポートしておくこと。
+pub trait SolrSchema {
+    /// 構造体のフィールドに対応するフィールド定義
+    fn fields() -> Vec<SolrSchemaField>;
+
+    /// `#[suffix(...)]`で指定されたサフィックスの一覧
+    fn suffixes() -> Vec<&'static str>;
+
+    /// 変換関数を指定していないサフィックス付きフィールドの、元のフィールド名とサフィックスの組
+    fn copied_suffixes() -> Vec<(&'static str, &'static str)>;
+
+    /// サフィックス付きフィールドを受け付ける動的フィールドの定義
+    fn dynamic_fields() -> Vec<SolrSchemaField> {
+        Self::suffixes()
+            .into_iter()
+            .map(|suffix| SolrSchemaField {
+                name: format!("*__{}", suffix),
+                field_type: suffix_field_type(suffix),
+                indexed: Some(true),
+                stored: Some(false),
+                required: None,
+                multi_valued: Some(true),
+                doc_values: None,
+            })
+            .collect()
+    }
+
+    /// 同じサフィックスを持つフィールドをまとめて検索するためのフィールドの定義
+    fn aggregated_fields() -> Vec<SolrSchemaField> {
+        Self::suffixes()
+            .into_iter()
+            .map(|suffix| SolrSchemaField {
+                name: String::from(suffix),
+                field_type: suffix_field_type(suffix),
+                indexed: Some(true),
+                stored: Some(true),
+                required: None,
+                multi_valued: Some(true),
+                doc_values: None,
+            })
+            .collect()
+    }
+
+    fn copy_fields() -> Vec<SolrCopyField> {
+        Self::suffixes()
+            .into_iter()
+            .map(|suffix| SolrCopyField {
+                source: format!("*__{}", suffix),
+                dest: String::from(suffix),
+            })
+            .collect()
+    }
+
+    /// `mode`で出力するドキュメントに必要なcopyFieldの定義
+    ///
+    /// copyFieldモードでは元のフィールドからサフィックス付きフィールドへ複製させる。
+    /// copyFieldの複製先からはさらに複製されないので、まとめて検索するフィールドへも直接複製させる。
+    fn copy_fields_with(mode: ExpandMode) -> Vec<SolrCopyField> {
+        let mut copy_fields = Self::copy_fields();
+        if mode == ExpandMode::CopyField {
+            for (name, suffix) in Self::copied_suffixes() {
+                copy_fields.push(SolrCopyField {
+                    source: String::from(name),
+                    dest: format!("{}__{}", name, suffix),
+                });
+                copy_fields.push(SolrCopyField {
+                    source: String::from(name),
+                    dest: String::from(suffix),
+                });
+            }
+        }
+        copy_fields
+    }
+
+    /// スキーマ全体を追加するSchema APIのリクエストボディ
+    fn schema() -> Value {
+        Self::schema_with(ExpandMode::Inline)
+    }
+
+    /// `mode`で出力するドキュメントのスキーマ全体を追加するSchema APIのリクエストボディ
+    fn schema_with(mode: ExpandMode) -> Value {
+        let mut fields = Self::fields();
+        fields.extend(Self::aggregated_fields());
+
+        let mut commands = Map::new();
+        for (command, definitions) in [
+            ("add-field", serde_json::json!(fields)),
+            (
+                "add-dynamic-field",
+                serde_json::json!(Self::dynamic_fields()),
+            ),
+            (
+                "add-copy-field",
+                serde_json::json!(Self::copy_fields_with(mode)),
+            ),
+        ] {
+            if definitions
+                .as_array()
+                .map(|d| !d.is_empty())
+                .unwrap_or(false)
+            {
+                commands.insert(String::from(command), definitions);
+            }
+        }
+        Value::Object(commands)
+    }
+}
+
+/// サフィックスに対応するSolrのフィールド型の名前を返す関数
+///
+/// `text_ja`のようなスネークケースのサフィックスを`TextJa`のようなパスカルケースに変換する。
+/// 変換規則に当てはまらないものは個別に対応付ける。
+pub fn suffix_field_type(suffix: &str) -> String {
+    match suffix {
+        "text_1gram" => String::from("Text1Gram"),
+        _ => suffix
+            .split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                    None => String::new(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// 現在のスキーマとの差分を取り、スキーマ定義を反映するSchema APIのリクエストボディを作る関数
+///
+/// 存在しないフィールドは追加し、定義が異なるフィールドは置き換える。同じ定義のフィールドには何もしない。
+/// copyFieldは`mode`で必要なもののうち、存在しないものを追加する。
+pub fn schema_commands<S: SolrSchema>(current: &SolrSchemaInfo, mode: ExpandMode) -> Value {
+    let mut fields = S::fields();
+    fields.extend(S::aggregated_fields());
+
+    let mut commands = Map::new();
+    for (add, replace, desired, existing) in [
+        ("add-field", "replace-field", fields, &current.fields),
+        (
+            "add-dynamic-field",
+            "replace-dynamic-field",
+            S::dynamic_fields(),
+            &current.dynamic_fields,
+        ),
+    ] {
+        let mut added = Vec::new();
+        let mut replaced = Vec::new();
+        for field in desired {
+            match existing.iter().find(|existing| existing.name == field.name) {
+                Some(existing) if existing == &field => {}
+                Some(_) => replaced.push(field),
+                None => added.push(field),
+            }
+        }
+        if !added.is_empty() {
+            commands.insert(String::from(add), serde_json::json!(added));
+        }
+        if !replaced.is_empty() {
+            commands.insert(String::from(replace), serde_json::json!(replaced));
+        }
+    }
+
+    let copy_fields = S::copy_fields_with(mode)
+        .into_iter()
+        .filter(|copy_field| !current.copy_fields.contains(copy_field))
+        .collect::<Vec<_>>();
+    if !copy_fields.is_empty() {
+        commands.insert(
+            String::from("add-copy-field"),
+            serde_json::json!(copy_fields),
+        );
+    }
+
+    Value::Object(commands)
+}
+
+/// スキーマ定義をSolrのコアに反映する関数
+///
+/// 反映したSchema APIのリクエストボディを返す。差分が無ければリクエストを送らない。
+pub async fn apply_schema<S, C>(core: &C, mode: ExpandMode) -> Result<Value, SolrCoreError>
+where
+    S: SolrSchema,
+    C: SolrCore + Sync,
+{
+    let current = core.schema().await?;
+    let commands = schema_commands::<S>(&current, mode);
+
+    if commands.as_object().map(Map::is_empty).unwrap_or(true) {
+        tracing::info!("schema of {} is up to date", current.name);
+        return Ok(commands);
+    }
+
+    core.update_schema(serde_json::to_vec(&commands)?).await?;
+    tracing::info!("schema of {} has been updated", current.name);
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{MockRequest, MockSolrCore};
+    use atcoder_search_derive::SolrSchema;
+    use chrono::{DateTime, Local};
+    use serde_json::json;
+
+    #[allow(dead_code)]
+    #[derive(SolrSchema)]
+    struct Document {
+        id: String,
+        #[suffix(text_ja, text_reading = "to_reading")]
+        title: String,
+        #[solr(field_type = "TextEn", stored = false)]
+        #[suffix(text_en)]
+        sentence: Vec<String>,
+        #[serde(rename = "score")]
+        rating: Option<i32>,
+        #[solr(doc_values = false)]
+        published_at: DateTime<Local>,
+    }
+
+    #[test]
+    fn test_schema() {
+        let expected = json!({
+            "add-field": [
+                {"name": "id", "type": "String", "indexed": true, "stored": true, "required": true, "multiValued": false},
+                {"name": "title", "type": "String", "indexed": true, "stored": true, "required": true, "multiValued": false},
+                {"name": "sentence", "type": "TextEn", "indexed": true, "stored": false, "required": true, "multiValued": true},
+                {"name": "score", "type": "i32", "indexed": true, "stored": true, "required": false, "multiValued": false},
+                {"name": "published_at", "type": "DateTime", "indexed": true, "stored": true, "required": true, "multiValued": false, "docValues": false},
+                {"name": "text_ja", "type": "TextJa", "indexed": true, "stored": true, "multiValued": true},
+                {"name": "text_reading", "type": "TextReading", "indexed": true, "stored": true, "multiValued": true},
+                {"name": "text_en", "type": "TextEn", "indexed": true, "stored": true, "multiValued": true},
+            ],
+            "add-dynamic-field": [
+                {"name": "*__text_ja", "type": "TextJa", "indexed": true, "stored": false, "multiValued": true},
+                {"name": "*__text_reading", "type": "TextReading", "indexed": true, "stored": false, "multiValued": true},
+                {"name": "*__text_en", "type": "TextEn", "indexed": true, "stored": false, "multiValued": true},
+            ],
+            "add-copy-field": [
+                {"source": "*__text_ja", "dest": "text_ja"},
+                {"source": "*__text_reading", "dest": "text_reading"},
+                {"source": "*__text_en", "dest": "text_en"},
+            ],
+        });
+        assert_eq!(Document::schema(), expected);
+    }
+
+    #[test]
+    fn test_schema_with_copy_field() {
+        assert_eq!(
+            Document::copied_suffixes(),
+            vec![("title", "text_ja"), ("sentence", "text_en")]
+        );
+
+        let schema = Document::schema_with(ExpandMode::CopyField);
+        assert_eq!(
+            schema["add-copy-field"],
+            json!([
+                {"source": "*__text_ja", "dest": "text_ja"},
+                {"source": "*__text_reading", "dest": "text_reading"},
+                {"source": "*__text_en", "dest": "text_en"},
+                {"source": "title", "dest": "title__text_ja"},
+                {"source": "title", "dest": "text_ja"},
+                {"source": "sentence", "dest": "sentence__text_en"},
+                {"source": "sentence", "dest": "text_en"},
+            ])
+        );
+        assert_eq!(schema["add-field"], Document::schema()["add-field"]);
+    }
+
+    #[test]
+    fn test_suffix_field_type() {
+        assert_eq!(suffix_field_type("text_ja"), "TextJa");
+        assert_eq!(suffix_field_type("text_reading"), "TextReading");
+        assert_eq!(suffix_field_type("text_1gram"), "Text1Gram");
+    }
+
+    #[tokio::test]
+    async fn test_apply_schema() {
+        let core = MockSolrCore::new("example");
+        let mut fields = Document::fields();
+        fields[0].stored = Some(false);
+        fields.remove(1);
+        fields.extend(Document::aggregated_fields());
+        core.set_schema(SolrSchemaInfo {
+            name: String::from("example"),
+            unique_key: Some(String::from("id")),
+            fields,
+            dynamic_fields: Document::dynamic_fields(),
+            copy_fields: Document::copy_fields(),
+        });
+
+        let commands = apply_schema::<Document, _>(&core, ExpandMode::Inline)
+            .await
+            .unwrap();
+        assert_eq!(
+            commands,
+            json!({
+                "add-field": [
+                    {"name": "title", "type": "String", "indexed": true, "stored": true, "required": true, "multiValued": false},
+                ],
+                "replace-field": [
+                    {"name": "id", "type": "String", "indexed": true, "stored": true, "required": true, "multiValued": false},
+                ],
+            })
+        );
+        assert_eq!(
+            core.requests(),
+            vec![
+                MockRequest::Schema,
+                MockRequest::UpdateSchema(Some(serde_json::to_vec(&commands).unwrap())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_schema_up_to_date() {
+        let core = MockSolrCore::new("example");
+        let mut fields = Document::fields();
+        fields.extend(Document::aggregated_fields());
+        core.set_schema(SolrSchemaInfo {
+            name: String::from("example"),
+            unique_key: Some(String::from("id")),
+            fields,
+            dynamic_fields: Document::dynamic_fields(),
+            copy_fields: Document::copy_fields(),
+        });
+
+        let commands = apply_schema::<Document, _>(&core, ExpandMode::Inline)
+            .await
+            .unwrap();
+        assert_eq!(commands, json!({}));
+        assert_eq!(core.requests(), vec![MockRequest::Schema]);
+    }
+}
@@ -0,0 +1,211 @@
+#[cfg(feature = "opensearch")]
+use crate::solr::opensearch::OpenSearchCore;
+use crate::solr::{
+    core::{
+        CommitParams, SolrCore, SolrCoreConfig, SolrCoreError, StandaloneSolrCore, UpdateParams,
+    },
+    model::*,
+};
+use async_trait::async_trait;
+use reqwest::Body;
+use serde::de::DeserializeOwned;
+use std::{fmt, str::FromStr};
+
+type Result<T> = std::result::Result<T, SolrCoreError>;
+
+/// 検索バックエンドの種類
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackendKind {
+    #[default]
+    Solr,
+    OpenSearch,
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BackendKind::Solr => write!(f, "solr"),
+            BackendKind::OpenSearch => write!(f, "opensearch"),
+        }
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = SolrCoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "solr" => Ok(BackendKind::Solr),
+            "opensearch" => Ok(BackendKind::OpenSearch),
+            _ => Err(SolrCoreError::UnexpectedError(format!(
+                "unknown search backend {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// 実行時に選択された検索バックエンドのクライアント
+///
+/// [`SolrCore`]を実装し、SolrのパラメータでOpenSearchも検索できるようにする。
+/// OpenSearchは`opensearch`フィーチャーを有効にした場合だけ使える。
+// クライアントは起動時に作成してArcで共有するので、サイズの差は問題にならない
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum SearchBackend {
+    Solr(StandaloneSolrCore),
+    #[cfg(feature = "opensearch")]
+    OpenSearch(OpenSearchCore),
+}
+
+impl SearchBackend {
+    /// `url`で動いている`kind`のバックエンドの、`name`のコア(インデックス)のクライアントを作成する
+    ///
+    /// `unique_key`はOpenSearchでドキュメントのIDに使うフィールド。
+    pub fn new(kind: BackendKind, name: &str, url: &str, unique_key: &str) -> Result<Self> {
+        Self::with_config(kind, name, url, unique_key, &SolrCoreConfig::default())
+    }
+
+    /// 認証やTLSの設定`config`を指定してクライアントを作成する
+    pub fn with_config(
+        kind: BackendKind,
+        name: &str,
+        url: &str,
+        unique_key: &str,
+        config: &SolrCoreConfig,
+    ) -> Result<Self> {
+        match kind {
+            BackendKind::Solr => Ok(SearchBackend::Solr(StandaloneSolrCore::with_config(
+                name, url, config,
+            )?)),
+            #[cfg(feature = "opensearch")]
+            BackendKind::OpenSearch => Ok(SearchBackend::OpenSearch(OpenSearchCore::with_config(
+                name, url, unique_key, config,
+            )?)),
+            #[cfg(not(feature = "opensearch"))]
+            BackendKind::OpenSearch => {
+                let _ = (unique_key, config);
+                Err(SolrCoreError::UnexpectedError(String::from(
+                    "OpenSearch backend requires the `opensearch` feature",
+                )))
+            }
+        }
+    }
+
+    pub fn kind(&self) -> BackendKind {
+        match self {
+            SearchBackend::Solr(_) => BackendKind::Solr,
+            #[cfg(feature = "opensearch")]
+            SearchBackend::OpenSearch(_) => BackendKind::OpenSearch,
+        }
+    }
+}
+
+// 選択されたバックエンドのクライアントに処理を委ねるマクロ
+macro_rules! dispatch {
+    ($backend:expr, $core:ident => $call:expr) => {
+        match $backend {
+            SearchBackend::Solr($core) => $call,
+            #[cfg(feature = "opensearch")]
+            SearchBackend::OpenSearch($core) => $call,
+        }
+    };
+}
+
+#[async_trait]
+impl SolrCore for SearchBackend {
+    async fn ping(&self) -> Result<SolrPingResponse> {
+        dispatch!(self, core => core.ping().await)
+    }
+
+    async fn status(&self) -> Result<SolrCoreStatus> {
+        dispatch!(self, core => core.status().await)
+    }
+
+    async fn reload(&self) -> Result<SolrSimpleResponse> {
+        dispatch!(self, core => core.reload().await)
+    }
+
+    async fn swap(&self, other: &str) -> Result<SolrSimpleResponse> {
+        dispatch!(self, core => core.swap(other).await)
+    }
+
+    async fn select<D: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        params: &[(impl ToString + Sync, impl ToString + Sync)],
+    ) -> Result<SolrSelectResponse<D, F>> {
+        dispatch!(self, core => core.select(params).await)
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        dispatch!(self, core => core.post(body, params).await)
+    }
+
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        dispatch!(self, core => core.post_docs(body, params).await)
+    }
+
+    async fn commit(&self, params: &CommitParams) -> Result<()> {
+        dispatch!(self, core => core.commit(params).await)
+    }
+
+    async fn optimize(&self, params: &CommitParams) -> Result<()> {
+        dispatch!(self, core => core.optimize(params).await)
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        dispatch!(self, core => core.rollback().await)
+    }
+
+    async fn truncate(&self) -> Result<()> {
+        dispatch!(self, core => core.truncate().await)
+    }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        dispatch!(self, core => core.schema().await)
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse> {
+        dispatch!(self, core => core.update_schema(body).await)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backend_kind() {
+        assert_eq!("solr".parse::<BackendKind>().unwrap(), BackendKind::Solr);
+        assert_eq!(
+            "OpenSearch".parse::<BackendKind>().unwrap(),
+            BackendKind::OpenSearch
+        );
+        assert!("elasticsearch".parse::<BackendKind>().is_err());
+        assert_eq!(BackendKind::OpenSearch.to_string(), "opensearch");
+    }
+
+    #[test]
+    fn test_new_search_backend() {
+        let backend =
+            SearchBackend::new(BackendKind::Solr, "problems", "http://localhost:8983", "id")
+                .unwrap();
+        assert_eq!(backend.kind(), BackendKind::Solr);
+        #[cfg(not(feature = "opensearch"))]
+        assert!(SearchBackend::new(
+            BackendKind::OpenSearch,
+            "problems",
+            "http://localhost:9200",
+            "id"
+        )
+        .is_err());
+    }
+}
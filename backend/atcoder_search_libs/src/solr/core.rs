@@ -1,9 +1,14 @@
-use crate::solr::model::*;
+use crate::solr::{
+    model::*,
+    query::{exceeds_url_length, json_request_body},
+};
 use async_trait::async_trait;
-use hyper::header::CONTENT_TYPE;
-use reqwest::{self, Body, Client, Url};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::{self, Body, Certificate, Client, Url};
 use serde::de::DeserializeOwned;
 use serde_json;
+use std::{fmt, path::PathBuf};
 use thiserror::Error;
 
 type Result<T> = std::result::Result<T, SolrCoreError>;
@@ -18,52 +23,190 @@ pub enum SolrCoreError {
     InvalidUrlError(#[from] url::ParseError),
     #[error("core not found")]
     CoreNotFoundError(String),
+    #[error("failed to read the CA certificate")]
+    CertificateError(#[from] std::io::Error),
     #[error("{0}")]
     UnexpectedError(String),
 }
 
+/// コアへ接続するときの認証とTLSの設定
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SolrCoreConfig {
+    /// BasicAuthのユーザ名
+    pub username: Option<String>,
+    /// BasicAuthのパスワード
+    pub password: Option<String>,
+    /// 接続先の証明書を検証するPEM形式のCA証明書のパス
+    pub ca_cert: Option<PathBuf>,
+    /// `true`の場合、接続先の証明書を検証しない
+    pub insecure: bool,
+}
+
+// パスワードをログに出さないようにする
+impl fmt::Debug for SolrCoreConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SolrCoreConfig")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "********"))
+            .field("ca_cert", &self.ca_cert)
+            .field("insecure", &self.insecure)
+            .finish()
+    }
+}
+
+impl SolrCoreConfig {
+    /// 設定を反映したHTTPクライアントを作成するメソッド
+    ///
+    /// ユーザ名が設定されている場合は、すべてのリクエストに`Authorization`ヘッダを付ける。
+    pub fn client(&self) -> Result<Client> {
+        let mut builder = Client::builder();
+        if let Some(header) = self.authorization() {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, header);
+            builder = builder.default_headers(headers);
+        }
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path)?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+        if self.insecure {
+            tracing::warn!("TLS certificate verification of the Solr connection is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder.build()?)
+    }
+
+    fn authorization(&self) -> Option<HeaderValue> {
+        let username = self.username.as_ref()?;
+        let credentials = format!(
+            "{}:{}",
+            username,
+            self.password.as_deref().unwrap_or_default()
+        );
+        let mut header =
+            HeaderValue::from_str(&format!("Basic {}", STANDARD.encode(credentials))).ok()?;
+        // デバッグ出力にヘッダの値を出さないようにする
+        header.set_sensitive(true);
+        Some(header)
+    }
+}
+
+/// ドキュメントを追加するリクエストに付与するパラメータ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateParams {
+    /// 指定したミリ秒以内に自動でコミットさせる
+    pub commit_within: Option<u64>,
+    /// `false`の場合、ユニークキーが重複するドキュメントを上書きしない
+    pub overwrite: Option<bool>,
+}
+
+impl UpdateParams {
+    pub fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(commit_within) = self.commit_within {
+            query.push(("commitWithin", commit_within.to_string()));
+        }
+        if let Some(overwrite) = self.overwrite {
+            query.push(("overwrite", overwrite.to_string()));
+        }
+        query
+    }
+}
+
+/// コミット・最適化のコマンドに付与するパラメータ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitParams {
+    /// `false`の場合、新しいサーチャーが開かれるのを待たずにレスポンスを返させる
+    pub wait_searcher: Option<bool>,
+    /// ディスクへの書き込みを待たずに、変更を検索結果へ反映するだけのソフトコミットにする
+    pub soft_commit: bool,
+}
+
+impl CommitParams {
+    fn command(&self, name: &str) -> Vec<u8> {
+        let mut params = serde_json::Map::new();
+        if let Some(wait_searcher) = self.wait_searcher {
+            params.insert(
+                String::from("waitSearcher"),
+                serde_json::Value::Bool(wait_searcher),
+            );
+        }
+        if self.soft_commit {
+            params.insert(String::from("softCommit"), serde_json::Value::Bool(true));
+        }
+        serde_json::json!({ name: params }).to_string().into_bytes()
+    }
+}
+
 #[async_trait]
 pub trait SolrCore {
     async fn ping(&self) -> Result<SolrPingResponse>;
     async fn status(&self) -> Result<SolrCoreStatus>;
     async fn reload(&self) -> Result<SolrSimpleResponse>;
+    /// このコアと`other`のコアのインデックスを入れ替える
+    async fn swap(&self, other: &str) -> Result<SolrSimpleResponse>;
     async fn select<D: DeserializeOwned, F: DeserializeOwned>(
         &self,
         params: &[(impl ToString + Sync, impl ToString + Sync)],
     ) -> Result<SolrSelectResponse<D, F>>;
-    async fn post<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse>;
-    async fn commit(&self) -> Result<()>;
-    async fn optimize(&self) -> Result<()>;
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse>;
+    /// 改行区切りのJSONドキュメントを`/update/json/docs`へ送る
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse>;
+    async fn commit(&self, params: &CommitParams) -> Result<()>;
+    async fn optimize(&self, params: &CommitParams) -> Result<()>;
     async fn rollback(&self) -> Result<()>;
     async fn truncate(&self) -> Result<()>;
+    async fn schema(&self) -> Result<SolrSchemaInfo>;
+    async fn update_schema<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse>;
 }
 
+#[derive(Clone)]
 pub struct StandaloneSolrCore {
     name: String,
     admin_url: Url,
     ping_url: Url,
     post_url: Url,
+    post_docs_url: Url,
     select_url: Url,
+    schema_url: Url,
     client: Client,
 }
 
 impl StandaloneSolrCore {
     pub fn new(name: &str, solr_url: &str) -> Result<Self> {
+        Self::with_config(name, solr_url, &SolrCoreConfig::default())
+    }
+
+    /// 認証やTLSの設定`config`を指定してクライアントを作成する
+    pub fn with_config(name: &str, solr_url: &str, config: &SolrCoreConfig) -> Result<Self> {
         let mut solr_url = Url::parse(solr_url)?;
         solr_url.set_path("");
         let base_url = solr_url;
         let admin_url = base_url.join("solr/admin/cores")?;
         let ping_url = base_url.join(&format!("solr/{}/admin/ping", name))?;
         let post_url = base_url.join(&format!("solr/{}/update", name))?;
+        let post_docs_url = base_url.join(&format!("solr/{}/update/json/docs", name))?;
         let select_url = base_url.join(&format!("solr/{}/select", name))?;
+        let schema_url = base_url.join(&format!("solr/{}/schema", name))?;
 
-        let client = Client::new();
+        let client = config.client()?;
         Ok(StandaloneSolrCore {
             name: String::from(name),
             admin_url,
             ping_url,
             post_url,
+            post_docs_url,
             select_url,
+            schema_url,
             client,
         })
     }
@@ -80,14 +223,10 @@ impl SolrCore for StandaloneSolrCore {
             }
             Err(e) => {
                 let body: SolrSimpleResponse = res.json().await?;
-                let msg = body
-                    .error
-                    .and_then(|error| Some(error.msg))
-                    .unwrap_or(String::default());
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
                 Err(SolrCoreError::UnexpectedError(format!(
                     "unexpected error [{}] cause [{}]",
-                    e.to_string(),
-                    msg
+                    e, msg
                 )))
             }
         }
@@ -114,14 +253,10 @@ impl SolrCore for StandaloneSolrCore {
             }
             Err(e) => {
                 let body: SolrSimpleResponse = res.json().await?;
-                let msg = body
-                    .error
-                    .and_then(|error| Some(error.msg))
-                    .unwrap_or(String::default());
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
                 Err(SolrCoreError::UnexpectedError(format!(
                     "unexpected error [{}] cause [{}]",
-                    e.to_string(),
-                    msg
+                    e, msg
                 )))
             }
         }
@@ -141,14 +276,33 @@ impl SolrCore for StandaloneSolrCore {
             }
             Err(e) => {
                 let body: SolrSimpleResponse = res.json().await?;
-                let msg = body
-                    .error
-                    .and_then(|error| Some(error.msg))
-                    .unwrap_or(String::default());
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
                 Err(SolrCoreError::UnexpectedError(format!(
                     "unexpected error [{}] cause [{}]",
-                    e.to_string(),
-                    msg
+                    e, msg
+                )))
+            }
+        }
+    }
+
+    async fn swap(&self, other: &str) -> Result<SolrSimpleResponse> {
+        let res = self
+            .client
+            .get(self.admin_url.clone())
+            .query(&[("action", "SWAP"), ("core", &self.name), ("other", other)])
+            .send()
+            .await?;
+        match res.error_for_status_ref() {
+            Ok(_) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                Ok(body)
+            }
+            Err(e) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
+                Err(SolrCoreError::UnexpectedError(format!(
+                    "unexpected error [{}] cause [{}]",
+                    e, msg
                 )))
             }
         }
@@ -162,36 +316,70 @@ impl SolrCore for StandaloneSolrCore {
             .iter()
             .map(|(key, value)| (key.to_string(), value.to_string()))
             .collect();
+        // IDの集合による絞り込みなどでURLが長くなりすぎる場合は、JSONのリクエストボディで送る
+        let request = if exceeds_url_length(&params) {
+            self.client
+                .post(self.select_url.clone())
+                .json(&json_request_body(&params))
+        } else {
+            self.client.get(self.select_url.clone()).query(&params)
+        };
+        let res = request.send().await?;
+        match res.error_for_status_ref() {
+            Ok(_) => {
+                let body: SolrSelectResponse<D, F> = res.json().await?;
+                Ok(body)
+            }
+            Err(e) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
+                Err(SolrCoreError::UnexpectedError(format!(
+                    "unexpected error [{}] cause [{}]",
+                    e, msg
+                )))
+            }
+        }
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
         let res = self
             .client
-            .get(self.select_url.clone())
-            .query(&params)
+            .post(self.post_url.clone())
+            .query(&params.query())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
             .send()
             .await?;
+
         match res.error_for_status_ref() {
             Ok(_) => {
-                let body: SolrSelectResponse<D, F> = res.json().await?;
+                let body: SolrSimpleResponse = res.json().await?;
                 Ok(body)
             }
             Err(e) => {
                 let body: SolrSimpleResponse = res.json().await?;
-                let msg = body
-                    .error
-                    .and_then(|error| Some(error.msg))
-                    .unwrap_or(String::default());
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
                 Err(SolrCoreError::UnexpectedError(format!(
                     "unexpected error [{}] cause [{}]",
-                    e.to_string(),
-                    msg
+                    e, msg
                 )))
             }
         }
     }
 
-    async fn post<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse> {
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
         let res = self
             .client
-            .post(self.post_url.clone())
+            .post(self.post_docs_url.clone())
+            .query(&params.query())
             .header(CONTENT_TYPE, "application/json")
             .body(body)
             .send()
@@ -204,47 +392,156 @@ impl SolrCore for StandaloneSolrCore {
             }
             Err(e) => {
                 let body: SolrSimpleResponse = res.json().await?;
-                let msg = body
-                    .error
-                    .and_then(|error| Some(error.msg))
-                    .unwrap_or(String::default());
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
                 Err(SolrCoreError::UnexpectedError(format!(
                     "unexpected error [{}] cause [{}]",
-                    e.to_string(),
-                    msg
+                    e, msg
                 )))
             }
         }
     }
 
-    async fn commit(&self) -> Result<()> {
-        self.post(br#"{"commit": {}}"#.to_vec()).await?;
+    async fn commit(&self, params: &CommitParams) -> Result<()> {
+        self.post(params.command("commit"), &UpdateParams::default())
+            .await?;
         Ok(())
     }
 
-    async fn optimize(&self) -> Result<()> {
-        self.post(br#"{"optimize": {}}"#.to_vec()).await?;
+    async fn optimize(&self, params: &CommitParams) -> Result<()> {
+        self.post(params.command("optimize"), &UpdateParams::default())
+            .await?;
         Ok(())
     }
 
     async fn rollback(&self) -> Result<()> {
-        self.post(br#"{"rollback": {}}"#.to_vec()).await?;
+        self.post(br#"{"rollback": {}}"#.to_vec(), &UpdateParams::default())
+            .await?;
         Ok(())
     }
 
     async fn truncate(&self) -> Result<()> {
-        self.post(br#"{"delete":{"query": "*:*"}}"#.to_vec())
-            .await?;
+        self.post(
+            br#"{"delete":{"query": "*:*"}}"#.to_vec(),
+            &UpdateParams::default(),
+        )
+        .await?;
         Ok(())
     }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        let res = self.client.get(self.schema_url.clone()).send().await?;
+        match res.error_for_status_ref() {
+            Ok(_) => {
+                let body: SolrSchemaResponse = res.json().await?;
+                Ok(body.schema)
+            }
+            Err(e) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
+                Err(SolrCoreError::UnexpectedError(format!(
+                    "unexpected error [{}] cause [{}]",
+                    e, msg
+                )))
+            }
+        }
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse> {
+        let res = self
+            .client
+            .post(self.schema_url.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+
+        match res.error_for_status_ref() {
+            Ok(_) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                Ok(body)
+            }
+            Err(e) => {
+                let body: SolrSimpleResponse = res.json().await?;
+                let msg = body.error.map(|error| error.msg).unwrap_or_default();
+                Err(SolrCoreError::UnexpectedError(format!(
+                    "unexpected error [{}] cause [{}]",
+                    e, msg
+                )))
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use chrono::{DateTime, Utc};
-    use serde::{Deserialize, Serialize};
-    use serde_json::{self, Value};
+
+    #[test]
+    fn test_update_params() {
+        assert!(UpdateParams::default().query().is_empty());
+        assert_eq!(
+            UpdateParams {
+                commit_within: Some(5000),
+                overwrite: Some(false),
+            }
+            .query(),
+            vec![
+                ("commitWithin", String::from("5000")),
+                ("overwrite", String::from("false"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_commit_params() {
+        assert_eq!(
+            CommitParams::default().command("commit"),
+            br#"{"commit":{}}"#.to_vec()
+        );
+        assert_eq!(
+            CommitParams {
+                wait_searcher: Some(false),
+                ..Default::default()
+            }
+            .command("optimize"),
+            br#"{"optimize":{"waitSearcher":false}}"#.to_vec()
+        );
+        assert_eq!(
+            CommitParams {
+                soft_commit: true,
+                ..Default::default()
+            }
+            .command("commit"),
+            br#"{"commit":{"softCommit":true}}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_core_config() {
+        assert!(SolrCoreConfig::default().authorization().is_none());
+
+        let config = SolrCoreConfig {
+            username: Some(String::from("solr")),
+            password: Some(String::from("SolrRocks")),
+            ..Default::default()
+        };
+        let header = config.authorization().unwrap();
+        assert_eq!(header, "Basic c29scjpTb2xyUm9ja3M=");
+        assert!(header.is_sensitive());
+        assert!(!format!("{:?}", config).contains("SolrRocks"));
+        assert!(
+            StandaloneSolrCore::with_config("example", "https://localhost:8983", &config).is_ok()
+        );
+
+        let config = SolrCoreConfig {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            StandaloneSolrCore::with_config("example", "https://localhost:8983", &config),
+            Err(SolrCoreError::CertificateError(_))
+        ));
+    }
 
     #[test]
     fn create_new_core() {
@@ -266,222 +563,117 @@ mod test {
             core.select_url,
             Url::parse("http://localhost:8983/solr/example/select").unwrap()
         );
+        assert_eq!(
+            core.schema_url,
+            Url::parse("http://localhost:8983/solr/example/schema").unwrap()
+        );
     }
 
-    /// Normal system test to get core status.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
+    /// Status, reload, ping and select of a core on Solr started in a Docker container.
+    #[cfg(feature = "integration")]
     #[tokio::test]
-    #[ignore]
-    async fn test_get_status() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
-        let status = core.status().await.unwrap();
+    async fn test_core_in_container() {
+        use crate::testing::containers::SolrContainer;
+        use chrono::{DateTime, Utc};
+        use serde_json::Value;
 
-        assert_eq!(status.name, String::from("example"));
-    }
+        let solr = SolrContainer::start("example").await;
+        let core = solr.core("example");
 
-    /// Normal system test of reload of the core.
-    ///
-    /// The reload is considered successful if the time elapsed between the start of the reload
-    /// and the start of the reloaded core is less than or equal to 1 second.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
-    #[tokio::test]
-    #[ignore]
-    async fn test_reload() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
+        assert_eq!(core.status().await.unwrap().name, "example");
+        assert_eq!(core.ping().await.unwrap().status, "OK");
 
+        // The reloaded core starts within 1 second after the reload is requested.
         let before = Utc::now();
-
         core.reload().await.unwrap();
-
-        let status = core.status().await.unwrap();
-        let after = status.start_time.replace("Z", "+00:00");
+        let after = core
+            .status()
+            .await
+            .unwrap()
+            .start_time
+            .replace('Z', "+00:00");
         let after = DateTime::parse_from_rfc3339(&after)
             .unwrap()
             .with_timezone(&Utc);
-
         assert!(before < after);
+        assert!((after - before).num_milliseconds() < 1000);
 
-        let duration = (after - before).num_milliseconds();
-        assert!(duration.abs() < 1000);
-    }
-
-    #[derive(Serialize, Deserialize)]
-    struct Document {
-        id: String,
-    }
-
-    /// Normal system test of the function to ping api.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
-    #[tokio::test]
-    #[ignore]
-    async fn test_ping() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
-        let response = core.ping().await.unwrap();
-
-        assert_eq!(response.status, String::from("OK"));
-    }
-
-    /// Normal system test of the function to search documents.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
-    #[tokio::test]
-    #[ignore]
-    async fn test_select_in_normal() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
-
-        let params = vec![("q".to_string(), "*:*".to_string())];
-        let response = core.select::<Document, ()>(&params).await.unwrap();
-
+        let response = core.select::<Value, ()>(&[("q", "*:*")]).await.unwrap();
         assert_eq!(response.header.status, 0);
-    }
 
-    /// Anomaly system test of the function to search documents.
-    ///
-    /// If nonexistent field was specified, select() method will return error.
-    #[tokio::test]
-    #[ignore]
-    async fn test_select_in_non_normal() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
-
-        let params = vec![("q".to_string(), "text_hoge:*".to_string())];
-        let response = core.select::<Document, ()>(&params).await;
-
-        assert!(response.is_err());
+        // A query on a nonexistent field is an error.
+        assert!(core
+            .select::<Value, ()>(&[("q", "text_hoge:*")])
+            .await
+            .is_err());
     }
 
-    /// Normal system test of the function to analyze the word.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
-    // #[tokio::test]
-    // #[ignore]
-    // async fn test_analyze() {
-    //     let core = StandaloneSolrCore::new("example", "http://localhost:8983");
-
-    //     let word = "solr-client";
-    //     let expected = vec![String::from("solr"), String::from("client")];
-
-    //     let actual = core.analyze(word, "text_en", "index").await.unwrap();
-
-    //     assert_eq!(expected, actual);
-    // }
-
-    /// Test scenario to test the behavior of a series of process: post documents to core, reload core, search for document, delete documents.
-    ///
-    /// Run this test with the Docker container started with the following command.
-    ///
-    /// ```ignore
-    /// docker run --rm -d -p 8983:8983 solr:9.1.0 solr-precreate example
-    /// ```
+    /// Post, search and delete documents on Solr started in a Docker container.
+    #[cfg(feature = "integration")]
     #[tokio::test]
-    #[ignore]
-    async fn test_scenario() {
-        let core = StandaloneSolrCore::new("example", "http://localhost:8983").unwrap();
+    async fn test_scenario_in_container() {
+        use crate::testing::containers::SolrContainer;
+        use serde_json::Value;
 
-        // Define schema for test with Schema API
-        let client = reqwest::Client::new();
-        client
-            .post(format!("http://localhost:8983/solr/example/schema"))
-            .body(
-                serde_json::json!(
-                    {
-                        "add-field": [
-                            {
-                                "name": "name",
-                                "type": "string",
-                                "indexed": true,
-                                "stored": true,
-                                "multiValued": false
-                            },
-                            {
-                                "name": "gender",
-                                "type": "string",
-                                "indexed": true,
-                                "stored": true,
-                                "multiValued": false
-                            }
-                        ]
-                    }
-                )
-                .to_string(),
-            )
-            .send()
-            .await
-            .unwrap();
+        let solr = SolrContainer::start("example").await;
+        let core = solr.core("example");
+
+        assert_eq!(core.ping().await.unwrap().status, "OK");
+        assert_eq!(core.status().await.unwrap().name, "example");
 
-        // Documents for test
         let documents = serde_json::json!(
             [
-                {
-                    "id": "001",
-                    "name": "alice",
-                    "gender": "female"
-                },
-                {
-                    "id": "002",
-                    "name": "bob",
-                    "gender": "male"
-                },
-                {
-                    "id": "003",
-                    "name": "charles",
-                    "gender": "male"
-                }
+                {"id": "001", "name": "alice"},
+                {"id": "002", "name": "bob"},
+                {"id": "003", "name": "charles"}
             ]
         )
-        .to_string()
-        .as_bytes()
-        .to_vec();
-
-        // Reload core (Only for operation check)
-        core.reload().await.unwrap();
-
-        // Post the documents to core.
-        core.post(documents).await.unwrap();
-        core.commit().await.unwrap();
-        let status = core.status().await.unwrap();
-
-        // Verify that 3 documents are registered.
-        assert_eq!(status.index.num_docs, 3);
+        .to_string();
+        core.post(documents, &UpdateParams::default())
+            .await
+            .unwrap();
+        core.commit(&CommitParams::default()).await.unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 3);
 
-        // Test to search document
         let result = core
-            .select::<Value, ()>(&[("q", "name:alice"), ("fl", "id,name,gender")])
+            .select::<Value, ()>(&[("q", "id:002"), ("fl", "id")])
             .await
             .unwrap();
-        assert_eq!(result.response.num_found, 1);
-        assert_eq!(
-            result.response.docs,
-            vec![serde_json::json!({"id": "001", "name": "alice", "gender": "female"})]
-        );
+        assert_eq!(result.response.docs, vec![serde_json::json!({"id": "002"})]);
 
-        // Delete all documents.
         core.truncate().await.unwrap();
-        core.commit().await.unwrap();
-        let status = core.status().await.unwrap();
-        // Verify that no documents in index.
-        assert_eq!(status.index.num_docs, 0);
+        core.commit(&CommitParams::default()).await.unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 0);
+    }
+
+    /// A select request too long for the URL is sent as a JSON request body.
+    #[cfg(feature = "integration")]
+    #[tokio::test]
+    async fn test_select_long_query_in_container() {
+        use crate::{solr::query::terms_filter_query, testing::containers::SolrContainer};
+        use serde_json::Value;
+
+        let solr = SolrContainer::start("example").await;
+        let core = solr.core("example");
+
+        let documents =
+            serde_json::json!([{"id": "001"}, {"id": "002"}, {"id": "003"}]).to_string();
+        core.post(documents, &UpdateParams::default())
+            .await
+            .unwrap();
+        core.commit(&CommitParams::default()).await.unwrap();
+
+        let ids: Vec<String> = (4..2000).map(|i| format!("{:04}", i)).collect();
+        let fq = terms_filter_query("id", &ids, true).unwrap();
+        let params = [("q", "*:*"), ("fq", fq.as_str()), ("sort", "id asc")];
+        assert!(exceeds_url_length(
+            &params
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        ));
+
+        let result = core.select::<Value, ()>(&params).await.unwrap();
+        assert_eq!(result.response.num_found, 3);
     }
 }
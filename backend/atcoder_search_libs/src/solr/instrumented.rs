@@ -0,0 +1,201 @@
+use crate::solr::{
+    core::{CommitParams, SolrCore, SolrCoreError, UpdateParams},
+    model::*,
+};
+use async_trait::async_trait;
+use reqwest::Body;
+use serde::de::DeserializeOwned;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+type Result<T> = std::result::Result<T, SolrCoreError>;
+
+/// 遅い検索リクエストを出力するtracingのターゲット
+pub const SLOW_QUERY_TARGET: &str = "slowquery";
+
+/// 検索リクエストの件数を数えるカウンタ
+///
+/// 複数のコアで共有して、サーバー全体の件数を集計する。
+#[derive(Debug, Default)]
+pub struct SelectMetrics {
+    selects: AtomicU64,
+    slow_selects: AtomicU64,
+}
+
+impl SelectMetrics {
+    /// 検索リクエストの件数
+    pub fn selects(&self) -> u64 {
+        self.selects.load(Ordering::Relaxed)
+    }
+
+    /// しきい値より時間のかかった検索リクエストの件数
+    pub fn slow_selects(&self) -> u64 {
+        self.slow_selects.load(Ordering::Relaxed)
+    }
+}
+
+/// `select`にかかった時間を計測し、しきい値を超えたリクエストをログに出力するコア
+///
+/// `select`以外のメソッドはそのまま内側のコアに委ねる。
+#[derive(Clone)]
+pub struct InstrumentedCore<C> {
+    core: C,
+    name: String,
+    slow_threshold: Duration,
+    metrics: Arc<SelectMetrics>,
+}
+
+impl<C> InstrumentedCore<C> {
+    /// ログに出力するコアの名前`name`と、遅いとみなすしきい値`slow_threshold`を指定して`core`を包む
+    pub fn new(core: C, name: &str, slow_threshold: Duration) -> Self {
+        Self {
+            core,
+            name: String::from(name),
+            slow_threshold,
+            metrics: Arc::new(SelectMetrics::default()),
+        }
+    }
+
+    /// 件数を数えるカウンタを他のコアと共有するメソッド
+    pub fn with_metrics(self, metrics: Arc<SelectMetrics>) -> Self {
+        Self { metrics, ..self }
+    }
+
+    pub fn metrics(&self) -> &Arc<SelectMetrics> {
+        &self.metrics
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.core
+    }
+}
+
+#[async_trait]
+impl<C> SolrCore for InstrumentedCore<C>
+where
+    C: SolrCore + Send + Sync,
+{
+    async fn ping(&self) -> Result<SolrPingResponse> {
+        self.core.ping().await
+    }
+
+    async fn status(&self) -> Result<SolrCoreStatus> {
+        self.core.status().await
+    }
+
+    async fn reload(&self) -> Result<SolrSimpleResponse> {
+        self.core.reload().await
+    }
+
+    async fn swap(&self, other: &str) -> Result<SolrSimpleResponse> {
+        self.core.swap(other).await
+    }
+
+    async fn select<D: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        params: &[(impl ToString + Sync, impl ToString + Sync)],
+    ) -> Result<SolrSelectResponse<D, F>> {
+        let start = Instant::now();
+        let response = self.core.select(params).await;
+        let elapsed = start.elapsed();
+
+        self.metrics.selects.fetch_add(1, Ordering::Relaxed);
+        if elapsed >= self.slow_threshold {
+            self.metrics.slow_selects.fetch_add(1, Ordering::Relaxed);
+            // どのファセットや絞り込みの組み合わせが遅いかを追えるように、パラメータをそのまま出力する
+            let params = params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key.to_string(), value.to_string()))
+                .collect::<Vec<String>>()
+                .join("&");
+            tracing::warn!(
+                target: SLOW_QUERY_TARGET,
+                core = %self.name,
+                qtime = response.as_ref().ok().map(|response| response.header.qtime),
+                elapsed_ms = elapsed.as_millis() as u64,
+                threshold_ms = self.slow_threshold.as_millis() as u64,
+                params = %params,
+                "slow select request",
+            );
+        }
+
+        response
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        self.core.post(body, params).await
+    }
+
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        self.core.post_docs(body, params).await
+    }
+
+    async fn commit(&self, params: &CommitParams) -> Result<()> {
+        self.core.commit(params).await
+    }
+
+    async fn optimize(&self, params: &CommitParams) -> Result<()> {
+        self.core.optimize(params).await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.core.rollback().await
+    }
+
+    async fn truncate(&self) -> Result<()> {
+        self.core.truncate().await
+    }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        self.core.schema().await
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse> {
+        self.core.update_schema(body).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::MockSolrCore;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_count_slow_selects() {
+        let metrics = Arc::new(SelectMetrics::default());
+        let fast = InstrumentedCore::new(
+            MockSolrCore::new("problems"),
+            "problems",
+            Duration::from_secs(60),
+        )
+        .with_metrics(metrics.clone());
+        let slow = InstrumentedCore::new(MockSolrCore::new("users"), "users", Duration::ZERO)
+            .with_metrics(metrics.clone());
+
+        let _: SolrSelectResponse<Value, Value> = fast.select(&[("q", "*:*")]).await.unwrap();
+        let _: SolrSelectResponse<Value, Value> = slow.select(&[("q", "*:*")]).await.unwrap();
+        assert_eq!(metrics.selects(), 2);
+        assert_eq!(metrics.slow_selects(), 1);
+
+        // 失敗したリクエストも数える
+        slow.inner().set_available(false);
+        let result: Result<SolrSelectResponse<Value, Value>> = slow.select(&[("q", "*:*")]).await;
+        assert!(result.is_err());
+        assert_eq!(metrics.selects(), 3);
+        assert_eq!(metrics.slow_selects(), 2);
+    }
+}
@@ -0,0 +1,1267 @@
+//! In-memory search engine implementing [`SolrCore`], for development without a Solr instance.
+//!
+//! Enabled for this crate's own tests, and for other crates through the `memory` feature.
+//!
+//! It understands the subset of the Solr query syntax and JSON Facet API that the search handlers
+//! use: keyword terms, phrases, `field:value`, ranges, `(a OR b)` groups and negation in `q` and
+//! `fq`, tagged filters excluded by facets, `sort`, `start`, `rows`, `fl`, and terms/range/query
+//! facets with `min`, `max`, `avg` and `percentile` aggregations. Full-text terms are looked up
+//! in an inverted index of character unigrams and bigrams over all the string values of a
+//! document, so Japanese text is searchable without a tokenizer. There is no analysis or relevance
+//! model beyond counting the occurrences of the terms.
+use crate::{
+    indexing::{document_files, read_document_file},
+    solr::{
+        core::{CommitParams, SolrCore, SolrCoreError, UpdateParams},
+        model::*,
+    },
+    DocumentFormat,
+};
+use async_trait::async_trait;
+use reqwest::Body;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Number, Value};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
+    path::Path,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+type Result<T> = std::result::Result<T, SolrCoreError>;
+type Document = Map<String, Value>;
+
+/// In-memory implementation of [`SolrCore`].
+///
+/// Added documents become searchable on `commit()`, as with Solr. Clones share the same index.
+#[derive(Clone)]
+pub struct MemoryCore {
+    name: String,
+    unique_key: String,
+    state: Arc<RwLock<MemoryState>>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    documents: BTreeMap<String, Document>,
+    pending: Vec<Document>,
+    index: InvertedIndex,
+}
+
+impl MemoryCore {
+    pub fn new(name: &str, unique_key: &str) -> Self {
+        Self {
+            name: String::from(name),
+            unique_key: String::from(unique_key),
+            state: Arc::new(RwLock::new(MemoryState::default())),
+        }
+    }
+
+    /// Create a core holding the documents of the files generated into `save_dir`.
+    pub async fn load(name: &str, unique_key: &str, save_dir: &Path) -> Result<Self> {
+        let core = Self::new(name, unique_key);
+        let files = document_files(save_dir)
+            .await
+            .map_err(|e| SolrCoreError::UnexpectedError(e.to_string()))?;
+        for file in files {
+            let content = read_document_file(&file)
+                .await
+                .map_err(|e| SolrCoreError::UnexpectedError(e.to_string()))?;
+            let documents = match DocumentFormat::from_path(&file).unwrap_or_default() {
+                DocumentFormat::Json => parse_json_documents(&content)?,
+                DocumentFormat::Ndjson => parse_ndjson_documents(&content)?,
+            };
+            core.add(documents)?;
+        }
+        core.commit(&CommitParams::default()).await?;
+
+        tracing::info!(
+            "{} documents loaded into the memory core {}",
+            core.state.read().unwrap().documents.len(),
+            name
+        );
+        Ok(core)
+    }
+
+    fn add(&self, documents: Vec<Document>) -> Result<()> {
+        if let Some(document) = documents
+            .iter()
+            .find(|document| unique_value(document, &self.unique_key).is_none())
+        {
+            return Err(SolrCoreError::UnexpectedError(format!(
+                "document is missing the unique key {}: {}",
+                self.unique_key,
+                Value::Object(document.clone())
+            )));
+        }
+        self.state.write().unwrap().pending.extend(documents);
+        Ok(())
+    }
+
+    fn header(qtime: u32) -> SolrResponseHeader {
+        SolrResponseHeader {
+            zk_connected: None,
+            status: 0,
+            qtime,
+            params: None,
+            partial_results: None,
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> SolrCoreError {
+        SolrCoreError::UnexpectedError(format!(
+            "{} is not supported by the memory core {}",
+            operation, self.name
+        ))
+    }
+}
+
+fn unique_value(document: &Document, unique_key: &str) -> Option<String> {
+    match document.get(unique_key)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn parse_json_documents(content: &[u8]) -> Result<Vec<Document>> {
+    match serde_json::from_slice(content)? {
+        Value::Array(documents) => documents
+            .into_iter()
+            .map(|document| match document {
+                Value::Object(document) => Ok(document),
+                other => Err(SolrCoreError::UnexpectedError(format!(
+                    "document must be an object: {}",
+                    other
+                ))),
+            })
+            .collect(),
+        Value::Object(document) => Ok(vec![document]),
+        other => Err(SolrCoreError::UnexpectedError(format!(
+            "unsupported update body: {}",
+            other
+        ))),
+    }
+}
+
+fn parse_ndjson_documents(content: &[u8]) -> Result<Vec<Document>> {
+    content
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|line| Ok(serde_json::from_slice(line)?))
+        .collect()
+}
+
+/// Inverted index from character unigrams and bigrams to the documents containing them.
+#[derive(Default)]
+struct InvertedIndex {
+    /// Unique keys of the documents, in the order of the document numbers.
+    ids: Vec<String>,
+    /// Lowercased concatenation of all the string values of each document.
+    texts: Vec<String>,
+    postings: HashMap<String, Vec<usize>>,
+}
+
+impl InvertedIndex {
+    fn build(documents: &BTreeMap<String, Document>) -> Self {
+        let mut index = InvertedIndex::default();
+        for (i, (id, document)) in documents.iter().enumerate() {
+            let mut text = String::new();
+            for value in document.values() {
+                collect_text(value, &mut text);
+            }
+            let text = text.to_lowercase();
+
+            for gram in index_grams(&text) {
+                index.postings.entry(gram).or_default().push(i);
+            }
+            index.ids.push(id.clone());
+            index.texts.push(text);
+        }
+        index
+    }
+
+    /// Document numbers which may contain `term`. The caller must still check the text.
+    fn candidates(&self, term: &str) -> Vec<usize> {
+        let mut postings = query_grams(term)
+            .into_iter()
+            .map(|gram| self.postings.get(&gram));
+        let mut result: Vec<usize> = match postings.next() {
+            Some(Some(first)) => first.clone(),
+            Some(None) => return Vec::new(),
+            None => return (0..self.ids.len()).collect(),
+        };
+        for posting in postings {
+            match posting {
+                Some(posting) => result.retain(|i| posting.binary_search(i).is_ok()),
+                None => return Vec::new(),
+            }
+        }
+        result
+    }
+}
+
+fn collect_text(value: &Value, text: &mut String) {
+    match value {
+        Value::String(value) => {
+            text.push_str(value);
+            text.push('\n');
+        }
+        Value::Array(values) => values.iter().for_each(|value| collect_text(value, text)),
+        _ => {}
+    }
+}
+
+/// Character unigrams and bigrams of `text` ignoring whitespaces, which are indexed.
+fn index_grams(text: &str) -> HashSet<String> {
+    let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    chars
+        .iter()
+        .map(|c| c.to_string())
+        .chain(chars.windows(2).map(|pair| pair.iter().collect()))
+        .collect()
+}
+
+/// Grams looked up for a term: its bigrams, or its unigram if it is a single character.
+fn query_grams(term: &str) -> Vec<String> {
+    let chars: Vec<char> = term.chars().filter(|c| !c.is_whitespace()).collect();
+    if chars.len() < 2 {
+        chars.iter().map(|c| c.to_string()).collect()
+    } else {
+        chars.windows(2).map(|pair| pair.iter().collect()).collect()
+    }
+}
+
+/// Parsed query of `q` and `fq`.
+#[derive(Debug, Clone, PartialEq)]
+enum Query {
+    All,
+    /// A term searched in all the string values, or matched with the values of `field`.
+    /// A phrase matched with a field must be equal to one of its values.
+    Term {
+        field: Option<String>,
+        value: String,
+        phrase: bool,
+    },
+    Range {
+        field: String,
+        lower: Bound<String>,
+        upper: Bound<String>,
+    },
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+/// Filter query with the tag given by the `{!tag=...}` local parameter.
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    tags: Vec<String>,
+    query: Query,
+}
+
+impl Filter {
+    fn parse(fq: &str) -> Self {
+        let fq = fq.trim();
+        let (tags, query) = match fq.strip_prefix("{!").and_then(|fq| fq.split_once('}')) {
+            Some((params, query)) => {
+                let tags = params
+                    .split_whitespace()
+                    .filter_map(|param| param.strip_prefix("tag="))
+                    .flat_map(|tags| tags.split(','))
+                    .map(String::from)
+                    .collect();
+                (tags, query)
+            }
+            None => (Vec::new(), fq),
+        };
+
+        Self {
+            tags,
+            query: Query::parse(query, true),
+        }
+    }
+}
+
+impl Query {
+    /// Parse a query. Clauses are combined with AND if `and` is true, otherwise with OR,
+    /// unless they are joined with an explicit `OR`.
+    fn parse(query: &str, and: bool) -> Self {
+        let chars: Vec<char> = query.chars().collect();
+        let mut pos = 0;
+        Self::parse_clauses(&chars, &mut pos, and, None)
+    }
+
+    fn parse_clauses(chars: &[char], pos: &mut usize, and: bool, field: Option<&str>) -> Self {
+        let mut clauses = Vec::new();
+        let mut or = false;
+        let mut negate_next = false;
+        loop {
+            while *pos < chars.len() && chars[*pos].is_whitespace() {
+                *pos += 1;
+            }
+            if *pos >= chars.len() {
+                break;
+            }
+            if chars[*pos] == ')' {
+                *pos += 1;
+                break;
+            }
+
+            let word = read_word(chars, *pos);
+            match word.as_str() {
+                "OR" | "||" => {
+                    *pos += word.chars().count();
+                    or = true;
+                    continue;
+                }
+                "AND" | "&&" => {
+                    *pos += word.chars().count();
+                    continue;
+                }
+                "NOT" => {
+                    *pos += 3;
+                    negate_next = true;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let clause = Self::parse_clause(chars, pos, and, field);
+            if negate_next {
+                clauses.push(Query::Not(Box::new(clause)));
+                negate_next = false;
+            } else {
+                clauses.push(clause);
+            }
+        }
+
+        match (clauses.len(), or || !and) {
+            (1, _) => clauses.pop().unwrap(),
+            (_, true) => Query::Or(clauses),
+            (_, false) => Query::And(clauses),
+        }
+    }
+
+    fn parse_clause(chars: &[char], pos: &mut usize, and: bool, field: Option<&str>) -> Self {
+        let negated = chars[*pos] == '-';
+        if negated || chars[*pos] == '+' {
+            *pos += 1;
+        }
+
+        let rest: String = chars[*pos..].iter().take(3).collect();
+        let clause = if rest == "*:*" {
+            *pos += 3;
+            Query::All
+        } else {
+            // A name followed by `:` restricts the field of the value.
+            let start = *pos;
+            while *pos < chars.len() && (chars[*pos].is_ascii_alphanumeric() || chars[*pos] == '_')
+            {
+                *pos += 1;
+            }
+            let field = if *pos > start && *pos < chars.len() && chars[*pos] == ':' {
+                let name: String = chars[start..*pos].iter().collect();
+                *pos += 1;
+                Some(name)
+            } else {
+                *pos = start;
+                field.map(String::from)
+            };
+            Self::parse_value(chars, pos, and, field)
+        };
+
+        if negated {
+            Query::Not(Box::new(clause))
+        } else {
+            clause
+        }
+    }
+
+    fn parse_value(chars: &[char], pos: &mut usize, and: bool, field: Option<String>) -> Self {
+        match chars.get(*pos) {
+            Some('(') => {
+                *pos += 1;
+                Self::parse_clauses(chars, pos, and, field.as_deref())
+            }
+            Some('"') => {
+                *pos += 1;
+                let value = read_until(chars, pos, |c| c == '"');
+                *pos += 1;
+                Query::Term {
+                    field,
+                    value,
+                    phrase: true,
+                }
+            }
+            Some(open @ ('[' | '{')) if field.is_some() => {
+                let inclusive_lower = *open == '[';
+                *pos += 1;
+                let range = read_until(chars, pos, |c| c == ']' || c == '}');
+                let inclusive_upper = chars.get(*pos) == Some(&']');
+                *pos += 1;
+                let (lower, upper) = range.split_once(" TO ").unwrap_or((&range, "*"));
+                let bound = |value: &str, inclusive: bool| match value.trim() {
+                    "*" => Bound::Unbounded,
+                    value if inclusive => Bound::Included(value.to_string()),
+                    value => Bound::Excluded(value.to_string()),
+                };
+                Query::Range {
+                    field: field.unwrap(),
+                    lower: bound(lower, inclusive_lower),
+                    upper: bound(upper, inclusive_upper),
+                }
+            }
+            _ => {
+                let value = read_until(chars, pos, |c| c.is_whitespace() || c == ')');
+                if value == "*" {
+                    return match field {
+                        Some(field) => Query::Range {
+                            field,
+                            lower: Bound::Unbounded,
+                            upper: Bound::Unbounded,
+                        },
+                        None => Query::All,
+                    };
+                }
+                Query::Term {
+                    field,
+                    value,
+                    phrase: false,
+                }
+            }
+        }
+    }
+
+    /// Full-text terms which a matching document must contain, used to narrow down the candidates.
+    fn required_terms(&self) -> Vec<&str> {
+        match self {
+            Query::Term {
+                field: None, value, ..
+            } => vec![value.as_str()],
+            Query::And(clauses) => clauses
+                .iter()
+                .flat_map(|clause| clause.required_terms())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Full-text terms which contribute to the score.
+    fn scoring_terms(&self) -> Vec<&str> {
+        match self {
+            Query::Term {
+                field: None, value, ..
+            } => vec![value.as_str()],
+            Query::And(clauses) | Query::Or(clauses) => clauses
+                .iter()
+                .flat_map(|clause| clause.scoring_terms())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn matches(&self, document: &Document, text: &str) -> bool {
+        match self {
+            Query::All => true,
+            Query::Term {
+                field: None, value, ..
+            } => text.contains(&value.to_lowercase()),
+            Query::Term {
+                field: Some(field),
+                value,
+                phrase,
+            } => {
+                let value = value.to_lowercase();
+                field_values(document, field).any(|v| {
+                    let v = v.to_lowercase();
+                    if *phrase {
+                        v == value
+                    } else {
+                        v.contains(&value)
+                    }
+                })
+            }
+            Query::Range {
+                field,
+                lower,
+                upper,
+            } => field_values(document, field).any(|v| {
+                let above = match lower {
+                    Bound::Included(lower) => compare_values(&v, lower).is_ge(),
+                    Bound::Excluded(lower) => compare_values(&v, lower).is_gt(),
+                    Bound::Unbounded => true,
+                };
+                let below = match upper {
+                    Bound::Included(upper) => compare_values(&v, upper).is_le(),
+                    Bound::Excluded(upper) => compare_values(&v, upper).is_lt(),
+                    Bound::Unbounded => true,
+                };
+                above && below
+            }),
+            Query::Not(query) => !query.matches(document, text),
+            Query::And(clauses) => clauses.iter().all(|clause| clause.matches(document, text)),
+            Query::Or(clauses) => clauses.iter().any(|clause| clause.matches(document, text)),
+        }
+    }
+}
+
+fn read_word(chars: &[char], pos: usize) -> String {
+    chars[pos..]
+        .iter()
+        .take_while(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// Read characters until `end`. A character escaped by a backslash is taken literally.
+fn read_until(chars: &[char], pos: &mut usize, end: impl Fn(char) -> bool) -> String {
+    let mut value = String::new();
+    while *pos < chars.len() && !end(chars[*pos]) {
+        if chars[*pos] == '\\' && *pos + 1 < chars.len() {
+            *pos += 1;
+        }
+        value.push(chars[*pos]);
+        *pos += 1;
+    }
+    value
+}
+
+/// Values of `field` in `document` as strings. An array yields each of its elements.
+fn field_values<'a>(document: &'a Document, field: &str) -> impl Iterator<Item = String> + 'a {
+    let values = match document.get(field) {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    };
+    values.into_iter().filter_map(|value| match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    })
+}
+
+fn numeric_values(document: &Document, field: &str) -> Vec<f64> {
+    field_values(document, field)
+        .filter_map(|value| value.parse().ok())
+        .collect()
+}
+
+/// Compare as numbers if both are numeric, otherwise as strings.
+fn compare_values(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+fn compare_json(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .unwrap_or_default()
+            .total_cmp(&b.as_f64().unwrap_or_default()),
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        // Documents without the value come last in both orders.
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+/// A matched document with its score.
+struct Hit<'a> {
+    document: &'a Document,
+    score: f64,
+}
+
+impl MemoryState {
+    /// Document numbers matching `q` and the filters not tagged with `excludes`.
+    fn search(&self, q: &Query, filters: &[Filter], excludes: &[String]) -> Vec<usize> {
+        let mut candidates: Option<Vec<usize>> = None;
+        for term in q.required_terms() {
+            let found = self.index.candidates(&term.to_lowercase());
+            candidates = Some(match candidates {
+                Some(current) => current
+                    .into_iter()
+                    .filter(|i| found.binary_search(i).is_ok())
+                    .collect(),
+                None => found,
+            });
+        }
+
+        candidates
+            .unwrap_or_else(|| (0..self.index.ids.len()).collect())
+            .into_iter()
+            .filter(|i| {
+                let document = &self.documents[&self.index.ids[*i]];
+                let text = &self.index.texts[*i];
+                q.matches(document, text)
+                    && filters
+                        .iter()
+                        .filter(|filter| !filter.tags.iter().any(|tag| excludes.contains(tag)))
+                        .all(|filter| filter.query.matches(document, text))
+            })
+            .collect()
+    }
+
+    fn document(&self, i: usize) -> &Document {
+        &self.documents[&self.index.ids[i]]
+    }
+
+    fn facet(&self, spec: &Value, q: &Query, filters: &[Filter], matched: &[usize]) -> Value {
+        let excludes: Vec<String> = spec
+            .pointer("/domain/excludeTags")
+            .and_then(Value::as_array)
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tag.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let domain = if excludes.is_empty() {
+            matched.to_vec()
+        } else {
+            self.search(q, filters, &excludes)
+        };
+        let field = spec
+            .get("field")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        match spec.get("type").and_then(Value::as_str) {
+            Some("terms") => self.terms_facet(spec, field, &domain),
+            Some("range") => self.range_facet(spec, field, &domain),
+            Some("query") => self.query_facet(spec, &domain),
+            _ => json!({ "count": domain.len() }),
+        }
+    }
+
+    fn terms_facet(&self, spec: &Value, field: &str, domain: &[usize]) -> Value {
+        let mincount = spec.get("mincount").and_then(Value::as_u64).unwrap_or(1);
+        let limit = spec.get("limit").and_then(Value::as_i64).unwrap_or(10);
+
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        if mincount == 0 {
+            for document in self.documents.values() {
+                for value in field_values(document, field) {
+                    counts.entry(value).or_default();
+                }
+            }
+        }
+        for i in domain {
+            for value in field_values(self.document(*i), field) {
+                *counts.entry(value).or_default() += 1;
+            }
+        }
+
+        let mut buckets: Vec<(String, u64)> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= mincount)
+            .collect();
+        buckets.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        if limit >= 0 {
+            buckets.truncate(limit as usize);
+        }
+
+        json!({
+            "buckets": buckets
+                .into_iter()
+                .map(|(val, count)| json!({"val": val, "count": count}))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn range_facet(&self, spec: &Value, field: &str, domain: &[usize]) -> Value {
+        let number = |key: &str| spec.get(key).and_then(Value::as_f64).unwrap_or_default();
+        let (start, end, gap) = (number("start"), number("end"), number("gap"));
+        let values: Vec<f64> = domain
+            .iter()
+            .flat_map(|i| numeric_values(self.document(*i), field))
+            .collect();
+        let count = |from: f64, to: f64| values.iter().filter(|v| from <= **v && **v < to).count();
+        // Integral bounds are returned as integers, as Solr does for an integer field.
+        let to_json = |v: f64| {
+            if v.fract() == 0.0 {
+                Value::Number(Number::from(v as i64))
+            } else {
+                json!(v)
+            }
+        };
+
+        let mut buckets = Vec::new();
+        if gap > 0.0 {
+            let mut lower = start;
+            while lower < end {
+                let upper = (lower + gap).min(end);
+                buckets.push(json!({"val": to_json(lower), "count": count(lower, upper)}));
+                lower += gap;
+            }
+        }
+        let mut facet = json!({ "buckets": buckets });
+        if spec.get("other").and_then(Value::as_str) == Some("all") {
+            facet["before"] = json!({"count": count(f64::NEG_INFINITY, start)});
+            facet["after"] = json!({"count": count(end, f64::INFINITY)});
+            facet["between"] = json!({"count": count(start, end)});
+        }
+        facet
+    }
+
+    fn query_facet(&self, spec: &Value, domain: &[usize]) -> Value {
+        let domain: Vec<usize> = match spec.get("q").and_then(Value::as_str) {
+            Some(q) => {
+                let q = Query::parse(q, true);
+                domain
+                    .iter()
+                    .copied()
+                    .filter(|i| q.matches(self.document(*i), &self.index.texts[*i]))
+                    .collect()
+            }
+            None => domain.to_vec(),
+        };
+
+        let mut facet = json!({ "count": domain.len() });
+        for (name, aggregation) in spec
+            .get("facet")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(aggregation) = aggregation.as_str() {
+                facet[name] = self.aggregate(aggregation, &domain);
+            }
+        }
+        facet
+    }
+
+    /// Compute `min(field)`, `max(field)`, `avg(field)` or `percentile(field,p1,p2,...)`.
+    fn aggregate(&self, aggregation: &str, domain: &[usize]) -> Value {
+        let Some((function, args)) = aggregation
+            .strip_suffix(')')
+            .and_then(|aggregation| aggregation.split_once('('))
+        else {
+            return Value::Null;
+        };
+        let mut args = args.split(',').map(str::trim);
+        let field = args.next().unwrap_or_default();
+        let mut values: Vec<f64> = domain
+            .iter()
+            .flat_map(|i| numeric_values(self.document(*i), field))
+            .collect();
+        if values.is_empty() {
+            return Value::Null;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        match function {
+            "min" => json!(values[0]),
+            "max" => json!(values[values.len() - 1]),
+            "avg" => json!(values.iter().sum::<f64>() / values.len() as f64),
+            "percentile" => {
+                let percentiles: Vec<f64> = args
+                    .filter_map(|p| p.parse::<f64>().ok())
+                    .map(|p| {
+                        let rank = ((p / 100.0) * values.len() as f64).ceil() as usize;
+                        values[rank.clamp(1, values.len()) - 1]
+                    })
+                    .collect();
+                json!(percentiles)
+            }
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Sort expression like `difficulty desc,problem_id asc`.
+fn sort_hits(hits: &mut [Hit], sort: &str) {
+    let keys: Vec<(&str, bool)> = sort
+        .split(',')
+        .filter_map(|key| {
+            let mut key = key.split_whitespace();
+            let field = key.next()?;
+            let descending = key.next().map(|order| order == "desc").unwrap_or(false);
+            Some((field, descending))
+        })
+        .collect();
+
+    hits.sort_by(|a, b| {
+        keys.iter()
+            .map(|(field, descending)| {
+                let ordering = if *field == "score" {
+                    a.score.total_cmp(&b.score)
+                } else {
+                    compare_json(a.document.get(*field), b.document.get(*field))
+                };
+                let missing = a.document.get(*field).is_none() || b.document.get(*field).is_none();
+                if *descending && (*field == "score" || !missing) {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn select_fields(document: &Document, fields: &[&str], score: f64) -> Value {
+    let mut selected = if fields.is_empty() || fields.contains(&"*") {
+        document.clone()
+    } else {
+        fields
+            .iter()
+            .filter_map(|field| Some((field.to_string(), document.get(*field)?.clone())))
+            .collect()
+    };
+    if fields.contains(&"score") {
+        selected.insert(String::from("score"), json!(score));
+    }
+    Value::Object(selected)
+}
+
+#[async_trait]
+impl SolrCore for MemoryCore {
+    async fn ping(&self) -> Result<SolrPingResponse> {
+        Ok(SolrPingResponse {
+            header: Self::header(0),
+            status: String::from("OK"),
+        })
+    }
+
+    async fn status(&self) -> Result<SolrCoreStatus> {
+        let num_docs = self.state.read().unwrap().documents.len() as u64;
+        Ok(SolrCoreStatus {
+            name: self.name.clone(),
+            instance_dir: String::new(),
+            data_dir: String::new(),
+            config: String::new(),
+            schema: String::new(),
+            start_time: String::new(),
+            uptime: 0,
+            index: SolrIndexInfo {
+                num_docs,
+                max_doc: num_docs,
+                deleted_docs: 0,
+                version: 1,
+                segment_count: 1,
+                current: true,
+                has_deletions: false,
+                directory: String::new(),
+                segments_file: String::new(),
+                segments_file_size_in_bytes: 0,
+                user_data: json!({}),
+                size_in_bytes: 0,
+                size: String::from("0 bytes"),
+            },
+        })
+    }
+
+    async fn reload(&self) -> Result<SolrSimpleResponse> {
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn swap(&self, _other: &str) -> Result<SolrSimpleResponse> {
+        Err(self.unsupported("swap"))
+    }
+
+    async fn select<D: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        params: &[(impl ToString + Sync, impl ToString + Sync)],
+    ) -> Result<SolrSelectResponse<D, F>> {
+        let started_at = Instant::now();
+        let params: Vec<(String, String)> = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let and = param("q.op") == Some("AND");
+        let q = match param("q").map(str::trim).filter(|q| !q.is_empty()) {
+            Some(q) => Query::parse(q, and),
+            None => Query::parse(param("q.alt").unwrap_or("*:*"), and),
+        };
+        let filters: Vec<Filter> = params
+            .iter()
+            .filter(|(key, _)| key == "fq")
+            .map(|(_, fq)| Filter::parse(fq))
+            .collect();
+        let start: usize = param("start").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let rows: usize = param("rows").and_then(|v| v.parse().ok()).unwrap_or(10);
+        let fields: Vec<&str> = param("fl")
+            .map(|fl| {
+                fl.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let state = self.state.read().unwrap();
+        let matched = state.search(&q, &filters, &[]);
+
+        let terms: Vec<String> = q
+            .scoring_terms()
+            .into_iter()
+            .map(|term| term.to_lowercase())
+            .collect();
+        let mut hits: Vec<Hit> = matched
+            .iter()
+            .map(|i| Hit {
+                document: state.document(*i),
+                score: terms
+                    .iter()
+                    .map(|term| state.index.texts[*i].matches(term.as_str()).count() as f64)
+                    .sum::<f64>()
+                    .max(1.0),
+            })
+            .collect();
+        sort_hits(&mut hits, param("sort").unwrap_or("score desc"));
+
+        let docs: Vec<Value> = hits
+            .iter()
+            .skip(start)
+            .take(rows)
+            .map(|hit| select_fields(hit.document, &fields, hit.score))
+            .collect();
+
+        let mut response = json!({
+            "response": {
+                "numFound": matched.len(),
+                "start": start,
+                "numFoundExact": true,
+                "docs": docs,
+            }
+        });
+        if let Some(facet) = param("json.facet").filter(|facet| !facet.is_empty()) {
+            let specs: Map<String, Value> = serde_json::from_str(facet)?;
+            let mut facets = json!({ "count": matched.len() });
+            for (name, spec) in specs.iter() {
+                facets[name] = state.facet(spec, &q, &filters, &matched);
+            }
+            response["facets"] = facets;
+        }
+        response["responseHeader"] =
+            serde_json::to_value(Self::header(started_at.elapsed().as_millis() as u32))?;
+
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        _params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        let content = body
+            .as_bytes()
+            .ok_or_else(|| self.unsupported("posting a stream body"))?;
+        self.add(parse_json_documents(content)?)?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        _params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        let content = body
+            .as_bytes()
+            .ok_or_else(|| self.unsupported("posting a stream body"))?;
+        self.add(parse_ndjson_documents(content)?)?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn commit(&self, _params: &CommitParams) -> Result<()> {
+        let mut state = self.state.write().unwrap();
+        let pending = std::mem::take(&mut state.pending);
+        for document in pending {
+            if let Some(id) = unique_value(&document, &self.unique_key) {
+                state.documents.insert(id, document);
+            }
+        }
+        state.index = InvertedIndex::build(&state.documents);
+        Ok(())
+    }
+
+    async fn optimize(&self, params: &CommitParams) -> Result<()> {
+        self.commit(params).await
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.state.write().unwrap().pending.clear();
+        Ok(())
+    }
+
+    async fn truncate(&self) -> Result<()> {
+        *self.state.write().unwrap() = MemoryState::default();
+        Ok(())
+    }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        Ok(SolrSchemaInfo {
+            name: self.name.clone(),
+            unique_key: Some(self.unique_key.clone()),
+            ..Default::default()
+        })
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, _body: T) -> Result<SolrSimpleResponse> {
+        Err(self.unsupported("updating the schema"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    async fn core() -> MemoryCore {
+        let core = MemoryCore::new("problems", "problem_id");
+        let documents = json!([
+            {"problem_id": "abc001_a", "problem_title": "積雪深差", "category": "ABC", "difficulty": 100, "text_ja": ["積雪の深さの差を求めてください"]},
+            {"problem_id": "abc002_d", "problem_title": "派閥", "category": "ABC", "difficulty": 1300, "text_ja": ["bit全探索で派閥の最大人数を求めてください"]},
+            {"problem_id": "arc001_c", "problem_title": "パズルのお手伝い", "category": "ARC", "difficulty": 1500, "text_en": ["eight queens puzzle with dfs"]},
+            {"problem_id": "agc001_a", "problem_title": "BBQ Easy", "category": "AGC", "difficulty": 400, "text_en": ["greedy sort skewers"]},
+            {"problem_id": "abc003_d", "problem_title": "AtCoder社の冬", "category": "ABC", "text_ja": ["包除原理で数え上げる dp"]}
+        ]);
+        core.post(documents.to_string(), &UpdateParams::default())
+            .await
+            .unwrap();
+        core.commit(&CommitParams::default()).await.unwrap();
+        core
+    }
+
+    async fn ids(core: &MemoryCore, params: &[(&str, &str)]) -> Vec<String> {
+        let response = core.select::<Value, Value>(params).await.unwrap();
+        response
+            .response
+            .docs
+            .iter()
+            .map(|doc| doc["problem_id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(Query::parse("*:*", true), Query::All);
+        assert_eq!(
+            Query::parse(r#"dp -category:"ABC" difficulty:[800 TO *}"#, true),
+            Query::And(vec![
+                Query::Term {
+                    field: None,
+                    value: String::from("dp"),
+                    phrase: false
+                },
+                Query::Not(Box::new(Query::Term {
+                    field: Some(String::from("category")),
+                    value: String::from("ABC"),
+                    phrase: true
+                })),
+                Query::Range {
+                    field: String::from("difficulty"),
+                    lower: Bound::Included(String::from("800")),
+                    upper: Bound::Unbounded
+                }
+            ])
+        );
+        assert_eq!(
+            Filter::parse(r#"{!tag=category}category:("ABC" OR "ARC")"#),
+            Filter {
+                tags: vec![String::from("category")],
+                query: Query::Or(vec![
+                    Query::Term {
+                        field: Some(String::from("category")),
+                        value: String::from("ABC"),
+                        phrase: true
+                    },
+                    Query::Term {
+                        field: Some(String::from("category")),
+                        value: String::from("ARC"),
+                        phrase: true
+                    }
+                ])
+            }
+        );
+        assert_eq!(
+            Query::parse(r"abc300\:a", true),
+            Query::Term {
+                field: None,
+                value: String::from("abc300:a"),
+                phrase: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select() {
+        let core = core().await;
+
+        assert_eq!(ids(&core, &[("q", "派閥")]).await, vec!["abc002_d"]);
+        assert_eq!(ids(&core, &[("q", "差")]).await, vec!["abc001_a"]);
+        assert_eq!(ids(&core, &[("q", "BBQ")]).await, vec!["agc001_a"]);
+        assert_eq!(
+            ids(&core, &[("q", "dp OR greedy"), ("sort", "problem_id asc")]).await,
+            vec!["abc003_d", "agc001_a"]
+        );
+        assert_eq!(
+            ids(
+                &core,
+                &[
+                    ("q", ""),
+                    ("q.alt", "*:*"),
+                    ("fq", r#"{!tag=category}category:("ABC" OR "ARC")"#),
+                    ("fq", "{!tag=difficulty}difficulty:[1000 TO *}"),
+                    ("sort", "difficulty desc,problem_id asc"),
+                ]
+            )
+            .await,
+            vec!["arc001_c", "abc002_d"]
+        );
+        assert_eq!(
+            ids(
+                &core,
+                &[
+                    ("q", "*:*"),
+                    ("fq", r#"-category:("ABC")"#),
+                    ("sort", "problem_id asc")
+                ]
+            )
+            .await,
+            vec!["agc001_a", "arc001_c"]
+        );
+
+        let response = core
+            .select::<Value, Value>(&[
+                ("q", "*:*"),
+                ("sort", "difficulty asc,problem_id asc"),
+                ("start", "1"),
+                ("rows", "2"),
+                ("fl", "problem_id,difficulty"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(response.response.num_found, 5);
+        assert_eq!(
+            response.response.docs,
+            vec![
+                json!({"problem_id": "agc001_a", "difficulty": 400}),
+                json!({"problem_id": "abc002_d", "difficulty": 1300})
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_select_with_facets() {
+        let core = core().await;
+        let facet = json!({
+            "category": {"type": "terms", "field": "category", "limit": -1, "mincount": 0, "domain": {"excludeTags": ["category"]}},
+            "difficulty": {"type": "range", "field": "difficulty", "start": 0, "end": 1200, "gap": 400, "other": "all"},
+            "difficulty_stats": {"type": "query", "q": "*:*", "facet": {"min": "min(difficulty)", "max": "max(difficulty)", "percentiles": "percentile(difficulty,50)"}}
+        });
+        let response = core
+            .select::<Value, Value>(&[
+                ("q", "*:*"),
+                ("fq", "{!tag=category}category:(\"ABC\")"),
+                ("json.facet", &facet.to_string()),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.facets.unwrap(),
+            json!({
+                "count": 3,
+                "category": {"buckets": [
+                    {"val": "ABC", "count": 3},
+                    {"val": "AGC", "count": 1},
+                    {"val": "ARC", "count": 1}
+                ]},
+                "difficulty": {
+                    "buckets": [
+                        {"val": 0, "count": 1},
+                        {"val": 400, "count": 0},
+                        {"val": 800, "count": 0}
+                    ],
+                    "before": {"count": 0},
+                    "after": {"count": 1},
+                    "between": {"count": 1}
+                },
+                "difficulty_stats": {"count": 3, "min": 100.0, "max": 1300.0, "percentiles": [100.0]}
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_rollback() {
+        let core = MemoryCore::new("users", "user_name");
+        core.post_docs(
+            "{\"user_name\": \"tourist\"}\n{\"user_name\": \"fjnkt98\"}\n",
+            &UpdateParams::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 0);
+
+        core.commit(&CommitParams::default()).await.unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 2);
+
+        core.post("[{\"user_name\": \"rng_58\"}]", &UpdateParams::default())
+            .await
+            .unwrap();
+        core.rollback().await.unwrap();
+        core.commit(&CommitParams::default()).await.unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 2);
+
+        assert!(core
+            .post("[{\"name\": \"rng_58\"}]", &UpdateParams::default())
+            .await
+            .is_err());
+
+        core.truncate().await.unwrap();
+        assert_eq!(core.status().await.unwrap().index.num_docs, 0);
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let save_dir = std::env::temp_dir().join("atcoder_search_test_memory_core_load");
+        tokio::fs::create_dir_all(&save_dir).await.unwrap();
+        tokio::fs::write(
+            save_dir.join("doc-1.json"),
+            r#"[{"problem_id": "abc001_a"}, {"problem_id": "abc001_b"}]"#,
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            save_dir.join("doc-2.ndjson"),
+            "{\"problem_id\": \"abc001_c\"}\n",
+        )
+        .await
+        .unwrap();
+
+        let core = MemoryCore::load("problems", "problem_id", &save_dir)
+            .await
+            .unwrap();
+        tokio::fs::remove_dir_all(&save_dir).await.unwrap();
+
+        assert_eq!(core.status().await.unwrap().index.num_docs, 3);
+        assert_eq!(
+            ids(&core, &[("q", "*:*"), ("sort", "problem_id desc")]).await,
+            vec!["abc001_c", "abc001_b", "abc001_a"]
+        );
+    }
+}
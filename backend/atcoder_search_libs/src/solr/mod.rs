@@ -1,3 +1,9 @@
+pub mod backend;
 pub mod core;
+pub mod instrumented;
+#[cfg(any(test, feature = "memory"))]
+pub mod memory;
 pub mod model;
+#[cfg(any(test, feature = "opensearch"))]
+pub mod opensearch;
 pub mod query;
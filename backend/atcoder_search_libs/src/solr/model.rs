@@ -12,6 +12,13 @@ pub struct SolrResponseHeader {
     #[serde(alias = "QTime")]
     pub qtime: u32,
     pub params: Option<BTreeMap<String, Value>>,
+    /// `true` if the search was stopped by `timeAllowed` and the results are partial.
+    #[serde(
+        alias = "partialResults",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub partial_results: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -119,6 +126,28 @@ pub struct SolrSelectResponse<D, F> {
     pub response: SolrSelectBody<D>,
     pub facets: Option<F>,
     pub error: Option<SolrErrorInfo>,
+    #[serde(alias = "nextCursorMark")]
+    pub next_cursor_mark: Option<String>,
+    pub debug: Option<SolrDebugInfo>,
+}
+
+/// Model of the `debug` field in the response JSON of a request with `debug=all`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolrDebugInfo {
+    /// Score explanations keyed by the unique key of the documents. Available with `debug.explain.structured=true`.
+    pub explain: Option<BTreeMap<String, SolrExplanation>>,
+    pub parsedquery: Option<Value>,
+}
+
+/// Model of a structured explanation of how the score of a document is computed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolrExplanation {
+    #[serde(rename = "match")]
+    pub is_match: bool,
+    pub value: f64,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<SolrExplanation>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -133,31 +162,94 @@ pub struct SolrSelectBody<D> {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Bucket<T> {
-    val: T,
-    count: u32,
+    pub val: T,
+    pub count: u32,
 }
 
+/// Model of a terms facet.
+///
+/// Values of boolean or numeric fields are converted into strings, such as `"true"` or `"42"`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolrTermFacetCount {
-    buckets: Vec<Bucket<String>>,
+    #[serde(deserialize_with = "deserialize_term_buckets")]
+    pub buckets: Vec<Bucket<String>>,
+}
+
+fn deserialize_term_buckets<'de, D>(deserializer: D) -> Result<Vec<Bucket<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let buckets = Vec::<Bucket<Value>>::deserialize(deserializer)?;
+    Ok(buckets
+        .into_iter()
+        .map(|bucket| Bucket {
+            val: match bucket.val {
+                Value::String(val) => val,
+                val => val.to_string(),
+            },
+            count: bucket.count,
+        })
+        .collect())
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolrRangeFacetCount<T> {
-    buckets: Vec<Bucket<T>>,
-    before: Option<SolrRangeFacetCountInfo>,
-    after: Option<SolrRangeFacetCountInfo>,
-    between: Option<SolrRangeFacetCountInfo>,
+    pub buckets: Vec<Bucket<T>>,
+    pub before: Option<SolrRangeFacetCountInfo>,
+    pub after: Option<SolrRangeFacetCountInfo>,
+    pub between: Option<SolrRangeFacetCountInfo>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolrRangeFacetCountInfo {
-    count: u32,
+    pub count: u32,
+}
+
+/// Model of a bucket of a facet that has sub-facets.
+///
+/// The sub-facets are returned as sibling fields of `val` and `count`, so they are flattened into `facets`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NestedBucket<T, F> {
+    pub val: T,
+    pub count: u32,
+    #[serde(flatten)]
+    pub facets: F,
+}
+
+/// Model of a range facet whose buckets have the sub-facets `F`.
+///
+/// Sub-facets of `before`, `after` and `between` are ignored.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolrNestedRangeFacetCount<T, F> {
+    pub buckets: Vec<NestedBucket<T, F>>,
+    pub before: Option<SolrRangeFacetCountInfo>,
+    pub after: Option<SolrRangeFacetCountInfo>,
+    pub between: Option<SolrRangeFacetCountInfo>,
+}
+
+/// Model of a query facet that has the sub-facets `F`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolrNestedQueryFacetCount<F> {
+    pub count: u32,
+    #[serde(flatten)]
+    pub facets: F,
+}
+
+/// Model of the aggregations of a numeric field computed by a query facet.
+///
+/// `percentiles` is in the same order as the percentiles requested in `percentile(field, ...)`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolrStatsFacetCount {
+    pub count: u32,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub avg: Option<f64>,
+    pub percentiles: Option<Vec<f64>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolrQueryFacetCount {
-    buckets: Vec<Bucket<String>>,
+    pub buckets: Vec<Bucket<String>>,
 }
 
 /// Model of the `analysis` field in the response JSON of a request to `/solr/<CORE_NAME>/analysis/field`.
@@ -182,6 +274,59 @@ pub struct SolrAnalysisResponse {
     pub error: Option<SolrErrorInfo>,
 }
 
+/// Model of a field or dynamic field definition in the Solr Schema API.
+///
+/// Properties left as `None` are inherited from the field type.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolrSchemaField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(
+        rename = "multiValued",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub multi_valued: Option<bool>,
+    #[serde(rename = "docValues", default, skip_serializing_if = "Option::is_none")]
+    pub doc_values: Option<bool>,
+}
+
+/// Model of a copy field rule in the Solr Schema API.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolrCopyField {
+    pub source: String,
+    pub dest: String,
+}
+
+/// Model of the `schema` field in the response JSON of a request to `/solr/<CORE_NAME>/schema`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SolrSchemaInfo {
+    pub name: String,
+    #[serde(alias = "uniqueKey")]
+    pub unique_key: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<SolrSchemaField>,
+    #[serde(alias = "dynamicFields", default)]
+    pub dynamic_fields: Vec<SolrSchemaField>,
+    #[serde(alias = "copyFields", default)]
+    pub copy_fields: Vec<SolrCopyField>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SolrSchemaResponse {
+    #[serde(alias = "responseHeader")]
+    pub header: SolrResponseHeader,
+    pub schema: SolrSchemaInfo,
+    pub error: Option<SolrErrorInfo>,
+}
+
 pub struct FromSolrDateTime;
 
 impl SerializeAs<DateTime<FixedOffset>> for FromSolrDateTime {
@@ -648,4 +793,78 @@ mod test {
         let select: SolrSelectResponse<Document, ()> = serde_json::from_str(raw).unwrap();
         assert_eq!(select.response.num_found, 0);
     }
+
+    #[test]
+    fn test_deserialize_debug_explain() {
+        let raw = r#"
+        {
+            "responseHeader": {"status": 0, "QTime": 3},
+            "response": {"numFound": 1, "start": 0, "numFoundExact": true, "docs": []},
+            "debug": {
+                "parsedquery": "+text_ja:dp",
+                "explain": {
+                    "abc300_a": {
+                        "match": true,
+                        "value": 1.5,
+                        "description": "sum of:",
+                        "details": [
+                            {"match": true, "value": 1.5, "description": "weight(text_ja:dp in 0)"}
+                        ]
+                    }
+                }
+            }
+        }
+        "#;
+        let select: SolrSelectResponse<Document, ()> = serde_json::from_str(raw).unwrap();
+        let explain = select.debug.unwrap().explain.unwrap();
+        assert_eq!(explain["abc300_a"].value, 1.5);
+        assert_eq!(
+            explain["abc300_a"].details[0].description,
+            "weight(text_ja:dp in 0)"
+        );
+        assert!(explain["abc300_a"].details[0].details.is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_nested_facets() {
+        #[derive(Deserialize)]
+        struct Category {
+            category: Option<SolrTermFacetCount>,
+        }
+
+        let raw = r#"
+        {
+            "buckets": [
+                {"val": 0, "count": 3, "category": {"buckets": [{"val": "ABC", "count": 3}]}},
+                {"val": 400, "count": 0}
+            ],
+            "before": {"count": 1, "category": {"buckets": [{"val": "ARC", "count": 1}]}},
+            "after": {"count": 0}
+        }
+        "#;
+        let facet: SolrNestedRangeFacetCount<i32, Category> = serde_json::from_str(raw).unwrap();
+        assert_eq!(facet.buckets[0].val, 0);
+        assert_eq!(facet.buckets[0].count, 3);
+        assert_eq!(
+            facet.buckets[0].facets.category.as_ref().unwrap().buckets[0].val,
+            "ABC"
+        );
+        assert!(facet.buckets[1].facets.category.is_none());
+        assert_eq!(facet.before.unwrap().count, 1);
+        assert!(facet.between.is_none());
+
+        let raw = r#"{"count": 2, "category": {"buckets": [{"val": "PAST", "count": 2}]}}"#;
+        let facet: SolrNestedQueryFacetCount<Category> = serde_json::from_str(raw).unwrap();
+        assert_eq!(facet.count, 2);
+        assert_eq!(facet.facets.category.unwrap().buckets[0].count, 2);
+    }
+
+    #[test]
+    fn test_deserialize_term_facet_of_non_string_field() {
+        let raw = r#"{"buckets": [{"val": true, "count": 3}, {"val": 42, "count": 1}]}"#;
+        let facet: SolrTermFacetCount = serde_json::from_str(raw).unwrap();
+        assert_eq!(facet.buckets[0].val, "true");
+        assert_eq!(facet.buckets[0].count, 3);
+        assert_eq!(facet.buckets[1].val, "42");
+    }
 }
@@ -0,0 +1,1149 @@
+//! OpenSearch client implementing [`SolrCore`], for deployments which don't run Solr.
+//!
+//! Enabled for this crate's own tests, and for other crates through the `opensearch` feature.
+//!
+//! The select parameters built for Solr are translated into a request to the `_search` API: `q`
+//! (or `q.alt`) becomes a `query_string` query over the `qf` fields with the operator of `q.op`,
+//! each `fq` becomes a `query_string` filter, and `sort`, `start`, `rows`, `fl` and `timeAllowed`
+//! are mapped to their counterparts. Filters tagged with `{!tag=...}` are applied as a post filter
+//! so that the terms, range and query facets of `json.facet` can exclude them with `excludeTags`,
+//! as with Solr. A `{!collapse field=...}` filter becomes a field collapsing. The response is translated back into the shape of a Solr select response. Boost
+//! parameters such as `pf`, `bq` and `boost` have no counterpart and are ignored.
+//!
+//! Documents are indexed with the bulk API using the value of the unique key as `_id`, and a
+//! commit refreshes the index. OpenSearch has no transaction, so a rollback can't discard the
+//! documents already posted.
+use crate::solr::{
+    core::{CommitParams, SolrCore, SolrCoreConfig, SolrCoreError, UpdateParams},
+    model::*,
+};
+use async_trait::async_trait;
+use hyper::header::CONTENT_TYPE;
+use reqwest::{Body, Client, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+type Result<T> = std::result::Result<T, SolrCoreError>;
+type Document = Map<String, Value>;
+
+/// Upper bound of the buckets returned by a terms facet with `limit: -1`.
+const UNLIMITED_TERMS: u64 = 10000;
+
+/// Client of an OpenSearch index implementing [`SolrCore`].
+#[derive(Clone)]
+pub struct OpenSearchCore {
+    name: String,
+    unique_key: String,
+    base_url: Url,
+    client: Client,
+}
+
+impl OpenSearchCore {
+    /// Create a client of the index `name` on the cluster at `url`. Documents are identified by the
+    /// value of the field `unique_key`.
+    pub fn new(name: &str, url: &str, unique_key: &str) -> Result<Self> {
+        Self::with_config(name, url, unique_key, &SolrCoreConfig::default())
+    }
+
+    /// Create a client with the credentials and TLS settings of `config`.
+    pub fn with_config(
+        name: &str,
+        url: &str,
+        unique_key: &str,
+        config: &SolrCoreConfig,
+    ) -> Result<Self> {
+        let mut base_url = Url::parse(url)?;
+        base_url.set_path("");
+        Ok(Self {
+            name: String::from(name),
+            unique_key: String::from(unique_key),
+            base_url,
+            client: config.client()?,
+        })
+    }
+
+    fn url(&self, endpoint: &str) -> Result<Url> {
+        Ok(self.base_url.join(&format!("{}/{}", self.name, endpoint))?)
+    }
+
+    /// Send the request and return the JSON body, or the reason of the error returned by OpenSearch.
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let res = request.send().await?;
+        match res.error_for_status_ref() {
+            Ok(_) => Ok(res.json().await?),
+            Err(e) => {
+                let body: Value = res.json().await.unwrap_or_default();
+                let reason = body["error"]["reason"]
+                    .as_str()
+                    .map(String::from)
+                    .unwrap_or_else(|| body["error"].to_string());
+                Err(SolrCoreError::UnexpectedError(format!(
+                    "unexpected error [{}] cause [{}]",
+                    e, reason
+                )))
+            }
+        }
+    }
+
+    /// Index or delete documents with the bulk API.
+    async fn bulk(&self, actions: Vec<Value>) -> Result<()> {
+        if actions.is_empty() {
+            return Ok(());
+        }
+        let mut body = String::new();
+        for action in actions {
+            body.push_str(&action.to_string());
+            body.push('\n');
+        }
+        let response = self
+            .send(
+                self.client
+                    .post(self.url("_bulk")?)
+                    .header(CONTENT_TYPE, "application/x-ndjson")
+                    .body(body),
+            )
+            .await?;
+        bulk_error(&response).map_or(Ok(()), |reason| {
+            Err(SolrCoreError::UnexpectedError(format!(
+                "failed to index documents into {} cause [{}]",
+                self.name, reason
+            )))
+        })
+    }
+
+    fn index_actions(&self, documents: Vec<Document>, params: &UpdateParams) -> Result<Vec<Value>> {
+        // `create` doesn't overwrite an existing document, as with `overwrite=false`.
+        let action = if params.overwrite == Some(false) {
+            "create"
+        } else {
+            "index"
+        };
+        let mut actions = Vec::with_capacity(documents.len() * 2);
+        for document in documents {
+            let id = unique_value(&document, &self.unique_key).ok_or_else(|| {
+                SolrCoreError::UnexpectedError(format!(
+                    "document without the unique key {}",
+                    self.unique_key
+                ))
+            })?;
+            actions.push(json!({ action: { "_id": id } }));
+            actions.push(Value::Object(document));
+        }
+        Ok(actions)
+    }
+
+    fn header(qtime: u64) -> SolrResponseHeader {
+        SolrResponseHeader {
+            zk_connected: None,
+            status: 0,
+            qtime: qtime as u32,
+            params: None,
+            partial_results: None,
+        }
+    }
+
+    fn unsupported(&self, operation: &str) -> SolrCoreError {
+        SolrCoreError::UnexpectedError(format!(
+            "{} is not supported by the OpenSearch index {}",
+            operation, self.name
+        ))
+    }
+}
+
+fn unique_value(document: &Document, unique_key: &str) -> Option<String> {
+    match document.get(unique_key)? {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Return the reason of the first failed item of a bulk response. Conflicts of `create` are not failures.
+fn bulk_error(response: &Value) -> Option<String> {
+    if !response["errors"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    response["items"]
+        .as_array()?
+        .iter()
+        .filter_map(|item| item.as_object()?.values().next())
+        .find(|result| result["status"].as_u64() != Some(409) && !result["error"].is_null())
+        .map(|result| {
+            result["error"]["reason"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or_else(|| result["error"].to_string())
+        })
+}
+
+/// Split the local parameters `{!tag=a,b}` off a filter query, returning the tags and the query.
+fn split_local_params(fq: &str) -> (Vec<String>, &str) {
+    let Some(rest) = fq.strip_prefix("{!") else {
+        return (Vec::new(), fq);
+    };
+    let Some(end) = rest.find('}') else {
+        return (Vec::new(), fq);
+    };
+    let tags = rest[..end]
+        .split_whitespace()
+        .filter_map(|param| param.strip_prefix("tag="))
+        .flat_map(|tags| tags.split(','))
+        .map(String::from)
+        .collect();
+    (tags, &rest[end + 1..])
+}
+
+/// The field of a collapsing filter `{!collapse field=...}`, or `None` for other filters.
+fn collapse_field(fq: &str) -> Option<&str> {
+    let rest = fq.strip_prefix("{!")?;
+    let (local_params, query) = rest.split_once('}')?;
+    let mut local_params = local_params.split_whitespace();
+    if local_params.next()? != "collapse" || !query.trim().is_empty() {
+        return None;
+    }
+    local_params.find_map(|param| param.strip_prefix("field="))
+}
+
+fn query_string(query: &str) -> Value {
+    json!({ "query_string": { "query": query } })
+}
+
+/// A filter query with its tags.
+struct TaggedFilter {
+    tags: Vec<String>,
+    query: Value,
+}
+
+/// Facets of `json.facet` translated into aggregations, with what is needed to translate the results back.
+enum FacetKind {
+    Terms,
+    /// Keys and lower bounds of the buckets, and whether the `before`/`after`/`between` counts are requested.
+    Range {
+        buckets: Vec<(String, Value)>,
+        other: bool,
+    },
+    /// Names of the aggregations and whether each one is a percentile aggregation.
+    Query {
+        aggregations: Vec<(String, bool)>,
+    },
+}
+
+/// Translate a facet of `json.facet` into an aggregation.
+fn facet_aggregation(name: &str, spec: &Value) -> Result<(Value, FacetKind)> {
+    let field = spec["field"].as_str().unwrap_or_default();
+    match spec["type"].as_str().unwrap_or("terms") {
+        "terms" => {
+            let size = match spec["limit"].as_i64() {
+                Some(limit) if limit >= 0 => limit as u64,
+                Some(_) => UNLIMITED_TERMS,
+                None => 10,
+            };
+            let aggregation = json!({
+                "terms": {
+                    "field": field,
+                    "size": size,
+                    "min_doc_count": spec["mincount"].as_u64().unwrap_or(1),
+                }
+            });
+            Ok((aggregation, FacetKind::Terms))
+        }
+        "range" => {
+            let (Some(start), Some(end), Some(gap)) = (
+                spec["start"].as_f64(),
+                spec["end"].as_f64(),
+                spec["gap"].as_f64(),
+            ) else {
+                return Err(SolrCoreError::UnexpectedError(format!(
+                    "range facet {} requires start, end and gap",
+                    name
+                )));
+            };
+            if gap <= 0.0 {
+                return Err(SolrCoreError::UnexpectedError(format!(
+                    "gap of range facet {} must be positive",
+                    name
+                )));
+            }
+            let integral = [&spec["start"], &spec["gap"]]
+                .iter()
+                .all(|value| value.is_i64() || value.is_u64());
+            let to_json = |value: f64| {
+                if integral {
+                    json!(value as i64)
+                } else {
+                    json!(value)
+                }
+            };
+
+            let mut ranges = Vec::new();
+            let mut buckets = Vec::new();
+            let mut lower = start;
+            while lower < end {
+                let key = format!("bucket_{}", buckets.len());
+                ranges.push(json!({"key": key, "from": lower, "to": lower + gap}));
+                buckets.push((key, to_json(lower)));
+                lower += gap;
+            }
+            let other = spec["other"].as_str() == Some("all");
+            if other {
+                ranges.push(json!({"key": "before", "to": start}));
+                ranges.push(json!({"key": "after", "from": end}));
+                ranges.push(json!({"key": "between", "from": start, "to": end}));
+            }
+            let aggregation = json!({ "range": { "field": field, "ranges": ranges } });
+            Ok((aggregation, FacetKind::Range { buckets, other }))
+        }
+        "query" => {
+            let mut aggregations = Map::new();
+            let mut names = Vec::new();
+            for (name, function) in spec["facet"].as_object().into_iter().flatten() {
+                let function = function.as_str().unwrap_or_default();
+                let Some((function, args)) = function
+                    .strip_suffix(')')
+                    .and_then(|function| function.split_once('('))
+                else {
+                    continue;
+                };
+                let mut args = args.split(',').map(str::trim);
+                let field = args.next().unwrap_or_default();
+                let aggregation = match function {
+                    "min" | "max" | "avg" | "sum" => json!({ function: { "field": field } }),
+                    "percentile" => {
+                        let percents: Vec<f64> = args.filter_map(|p| p.parse().ok()).collect();
+                        json!({
+                            "percentiles": { "field": field, "percents": percents, "keyed": false }
+                        })
+                    }
+                    _ => continue,
+                };
+                names.push((name.clone(), function == "percentile"));
+                aggregations.insert(name.clone(), aggregation);
+            }
+            let aggregation = json!({
+                "filter": query_string(spec["q"].as_str().unwrap_or("*:*")),
+                "aggs": aggregations,
+            });
+            Ok((
+                aggregation,
+                FacetKind::Query {
+                    aggregations: names,
+                },
+            ))
+        }
+        other => Err(SolrCoreError::UnexpectedError(format!(
+            "unsupported facet type {} of {}",
+            other, name
+        ))),
+    }
+}
+
+/// Translate the result of an aggregation built by [`facet_aggregation`] into a Solr facet.
+fn facet_result(kind: &FacetKind, result: &Value) -> Value {
+    match kind {
+        FacetKind::Terms => {
+            let buckets: Vec<Value> = result["buckets"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|bucket| json!({"val": bucket["key"], "count": bucket["doc_count"]}))
+                .collect();
+            json!({ "buckets": buckets })
+        }
+        FacetKind::Range { buckets, other } => {
+            let count = |key: &str| {
+                result["buckets"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .find(|bucket| bucket["key"].as_str() == Some(key))
+                    .and_then(|bucket| bucket["doc_count"].as_u64())
+                    .unwrap_or(0)
+            };
+            let mut facet = json!({
+                "buckets": buckets
+                    .iter()
+                    .map(|(key, val)| json!({"val": val, "count": count(key)}))
+                    .collect::<Vec<_>>()
+            });
+            if *other {
+                for key in ["before", "after", "between"] {
+                    facet[key] = json!({ "count": count(key) });
+                }
+            }
+            facet
+        }
+        FacetKind::Query { aggregations } => {
+            let mut facet = json!({ "count": result["doc_count"] });
+            if result["doc_count"].as_u64().unwrap_or(0) == 0 {
+                return facet;
+            }
+            for (name, percentile) in aggregations {
+                facet[name] = if *percentile {
+                    let values: Vec<Value> = result[name]["values"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .map(|value| value["value"].clone())
+                        .collect();
+                    json!(values)
+                } else {
+                    result[name]["value"].clone()
+                };
+            }
+            facet
+        }
+    }
+}
+
+/// Request body of the `_search` API translated from Solr select parameters, with the facets to translate back.
+struct SearchRequest {
+    body: Value,
+    start: u64,
+    score: bool,
+    facets: Vec<(String, FacetKind)>,
+}
+
+impl SearchRequest {
+    fn new(params: &[(String, String)]) -> Result<Self> {
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let q = param("q")
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .or(param("q.alt"))
+            .unwrap_or("*:*");
+        let mut query = json!({
+            "query": q,
+            "default_operator": param("q.op").unwrap_or("OR"),
+        });
+        let fields: Vec<&str> = param("qf")
+            .map(|qf| qf.split_whitespace().collect())
+            .unwrap_or_default();
+        if !fields.is_empty() {
+            query["fields"] = json!(fields);
+        } else if let Some(df) = param("df") {
+            query["default_field"] = json!(df);
+        }
+
+        // Untagged filters are never excluded from facets, so they go into the query itself.
+        let mut filters = Vec::new();
+        let mut tagged: Vec<TaggedFilter> = Vec::new();
+        let mut collapse = None;
+        for (_, fq) in params.iter().filter(|(key, _)| key == "fq") {
+            if let Some(field) = collapse_field(fq) {
+                collapse = Some(field);
+                continue;
+            }
+            let (tags, fq) = split_local_params(fq);
+            if tags.is_empty() {
+                filters.push(query_string(fq));
+            } else {
+                tagged.push(TaggedFilter {
+                    tags,
+                    query: query_string(fq),
+                });
+            }
+        }
+
+        let start: u64 = param("start").and_then(|v| v.parse().ok()).unwrap_or(0);
+        let rows: u64 = param("rows").and_then(|v| v.parse().ok()).unwrap_or(10);
+        let mut body = json!({
+            "query": {
+                "bool": {
+                    "must": [{ "query_string": query }],
+                    "filter": filters,
+                }
+            },
+            "from": start,
+            "size": rows,
+            "track_total_hits": true,
+        });
+        if !tagged.is_empty() {
+            body["post_filter"] = json!({
+                "bool": {
+                    "filter": tagged.iter().map(|filter| &filter.query).collect::<Vec<_>>()
+                }
+            });
+        }
+        if let Some(field) = collapse {
+            body["collapse"] = json!({ "field": field });
+        }
+
+        if let Some(sort) = param("sort") {
+            let sort: Vec<Value> = sort
+                .split(',')
+                .filter_map(|clause| {
+                    let mut clause = clause.split_whitespace();
+                    let field = match clause.next()? {
+                        "score" => "_score",
+                        field => field,
+                    };
+                    let order = clause.next().unwrap_or("asc");
+                    Some(json!({ field: order }))
+                })
+                .collect();
+            body["sort"] = json!(sort);
+        }
+
+        let fl: Vec<&str> = param("fl")
+            .map(|fl| {
+                fl.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|field| !field.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let score = fl.contains(&"score");
+        let source: Vec<&str> = fl
+            .iter()
+            .copied()
+            .filter(|field| *field != "score")
+            .collect();
+        if !source.is_empty() && !source.contains(&"*") {
+            body["_source"] = json!(source);
+        }
+        if score {
+            body["track_scores"] = json!(true);
+        }
+
+        if let Some(time_allowed) = param("timeAllowed") {
+            body["timeout"] = json!(format!("{}ms", time_allowed));
+        }
+
+        let mut facets = Vec::new();
+        if let Some(facet) = param("json.facet").filter(|facet| !facet.is_empty()) {
+            let specs: Map<String, Value> = serde_json::from_str(facet)?;
+            let mut aggregations = Map::new();
+            for (name, spec) in specs.iter() {
+                let (aggregation, kind) = facet_aggregation(name, spec)?;
+                let excludes: Vec<&str> = spec["domain"]["excludeTags"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .collect();
+                // Facets count the documents matching the tagged filters they don't exclude.
+                let domain: Vec<&Value> = tagged
+                    .iter()
+                    .filter(|filter| {
+                        !filter
+                            .tags
+                            .iter()
+                            .any(|tag| excludes.contains(&tag.as_str()))
+                    })
+                    .map(|filter| &filter.query)
+                    .collect();
+                aggregations.insert(
+                    name.clone(),
+                    json!({
+                        "filter": { "bool": { "filter": domain } },
+                        "aggs": { "facet": aggregation },
+                    }),
+                );
+                facets.push((name.clone(), kind));
+            }
+            body["aggs"] = Value::Object(aggregations);
+        }
+
+        Ok(Self {
+            body,
+            start,
+            score,
+            facets,
+        })
+    }
+
+    /// Translate the response of the `_search` API into the shape of a Solr select response.
+    fn response(&self, result: &Value) -> Result<Value> {
+        let docs: Vec<Value> = result["hits"]["hits"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|hit| {
+                let mut document = hit["_source"].clone();
+                if self.score {
+                    document["score"] = hit["_score"].clone();
+                }
+                document
+            })
+            .collect();
+        let total = &result["hits"]["total"];
+
+        let mut header = OpenSearchCore::header(result["took"].as_u64().unwrap_or(0));
+        if result["timed_out"].as_bool() == Some(true) {
+            header.partial_results = Some(true);
+        }
+        let mut response = json!({
+            "responseHeader": serde_json::to_value(header)?,
+            "response": {
+                "numFound": total["value"],
+                "start": self.start,
+                "numFoundExact": total["relation"].as_str() != Some("gte"),
+                "docs": docs,
+            }
+        });
+        if !self.facets.is_empty() {
+            let mut facets = json!({ "count": total["value"] });
+            for (name, kind) in self.facets.iter() {
+                facets[name] = facet_result(kind, &result["aggregations"][name]["facet"]);
+            }
+            response["facets"] = facets;
+        }
+        Ok(response)
+    }
+}
+
+/// Parse the body of a Solr update request: an array of documents, a single document, or a delete command.
+enum UpdateCommand {
+    Add(Vec<Document>),
+    DeleteById(Vec<String>),
+    DeleteByQuery(String),
+}
+
+impl UpdateCommand {
+    fn parse(content: &[u8]) -> Result<Self> {
+        let invalid = |value: &Value| {
+            SolrCoreError::UnexpectedError(format!("unsupported update body: {}", value))
+        };
+        match serde_json::from_slice(content)? {
+            Value::Array(documents) => documents
+                .into_iter()
+                .map(|document| match document {
+                    Value::Object(document) => Ok(document),
+                    other => Err(invalid(&other)),
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Self::Add),
+            Value::Object(mut document) => match document.remove("delete") {
+                Some(Value::String(id)) => Ok(Self::DeleteById(vec![id])),
+                Some(Value::Array(ids)) => ids
+                    .iter()
+                    .map(|id| id.as_str().map(String::from).ok_or_else(|| invalid(id)))
+                    .collect::<Result<Vec<_>>>()
+                    .map(Self::DeleteById),
+                Some(Value::Object(delete)) => match delete.get("query") {
+                    Some(Value::String(query)) => Ok(Self::DeleteByQuery(query.clone())),
+                    _ => Err(invalid(&Value::Object(delete))),
+                },
+                Some(other) => Err(invalid(&other)),
+                None => Ok(Self::Add(vec![document])),
+            },
+            other => Err(invalid(&other)),
+        }
+    }
+}
+
+fn parse_ndjson_documents(content: &[u8]) -> Result<Vec<Document>> {
+    content
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+        .map(|line| Ok(serde_json::from_slice(line)?))
+        .collect()
+}
+
+#[async_trait]
+impl SolrCore for OpenSearchCore {
+    async fn ping(&self) -> Result<SolrPingResponse> {
+        let response = self.send(self.client.get(self.url("_count")?)).await?;
+        Ok(SolrPingResponse {
+            header: Self::header(0),
+            status: if response["count"].is_u64() {
+                String::from("OK")
+            } else {
+                response.to_string()
+            },
+        })
+    }
+
+    async fn status(&self) -> Result<SolrCoreStatus> {
+        let response = self
+            .send(self.client.get(self.url("_stats/docs,store")?))
+            .await?;
+        let primaries = &response["_all"]["primaries"];
+        let num_docs = primaries["docs"]["count"].as_u64().unwrap_or(0);
+        let deleted_docs = primaries["docs"]["deleted"].as_u64().unwrap_or(0);
+        let size_in_bytes = primaries["store"]["size_in_bytes"].as_u64().unwrap_or(0);
+        Ok(SolrCoreStatus {
+            name: self.name.clone(),
+            instance_dir: String::new(),
+            data_dir: String::new(),
+            config: String::new(),
+            schema: String::new(),
+            start_time: String::new(),
+            uptime: 0,
+            index: SolrIndexInfo {
+                num_docs,
+                max_doc: num_docs + deleted_docs,
+                deleted_docs,
+                version: 0,
+                segment_count: 0,
+                current: true,
+                has_deletions: deleted_docs > 0,
+                directory: String::new(),
+                segments_file: String::new(),
+                segments_file_size_in_bytes: 0,
+                user_data: json!({}),
+                size_in_bytes,
+                size: format!("{} bytes", size_in_bytes),
+            },
+        })
+    }
+
+    async fn reload(&self) -> Result<SolrSimpleResponse> {
+        self.send(self.client.post(self.url("_refresh")?)).await?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn swap(&self, _other: &str) -> Result<SolrSimpleResponse> {
+        Err(self.unsupported("swap"))
+    }
+
+    async fn select<D: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        params: &[(impl ToString + Sync, impl ToString + Sync)],
+    ) -> Result<SolrSelectResponse<D, F>> {
+        let params: Vec<(String, String)> = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let request = SearchRequest::new(&params)?;
+        let result = self
+            .send(self.client.post(self.url("_search")?).json(&request.body))
+            .await?;
+        Ok(serde_json::from_value(request.response(&result)?)?)
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        let content = body
+            .as_bytes()
+            .ok_or_else(|| self.unsupported("posting a stream body"))?;
+        match UpdateCommand::parse(content)? {
+            UpdateCommand::Add(documents) => {
+                self.bulk(self.index_actions(documents, params)?).await?
+            }
+            UpdateCommand::DeleteById(ids) => {
+                self.bulk(
+                    ids.iter()
+                        .map(|id| json!({"delete": {"_id": id}}))
+                        .collect(),
+                )
+                .await?
+            }
+            UpdateCommand::DeleteByQuery(query) => {
+                self.send(
+                    self.client
+                        .post(self.url("_delete_by_query")?)
+                        .json(&json!({ "query": query_string(&query) })),
+                )
+                .await?;
+            }
+        }
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        let content = body
+            .as_bytes()
+            .ok_or_else(|| self.unsupported("posting a stream body"))?;
+        self.bulk(self.index_actions(parse_ndjson_documents(content)?, params)?)
+            .await?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(0),
+            error: None,
+        })
+    }
+
+    async fn commit(&self, _params: &CommitParams) -> Result<()> {
+        self.send(self.client.post(self.url("_refresh")?)).await?;
+        Ok(())
+    }
+
+    async fn optimize(&self, params: &CommitParams) -> Result<()> {
+        self.commit(params).await?;
+        self.send(
+            self.client
+                .post(self.url("_forcemerge")?)
+                .query(&[("max_num_segments", "1")]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        // Succeed with a warning so that the error which caused the rollback is the one reported.
+        tracing::warn!(
+            "OpenSearch index {} can't roll back; the documents already posted remain",
+            self.name
+        );
+        Ok(())
+    }
+
+    async fn truncate(&self) -> Result<()> {
+        self.send(
+            self.client
+                .post(self.url("_delete_by_query")?)
+                .query(&[("refresh", "true")])
+                .json(&json!({"query": {"match_all": {}}})),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        self.send(self.client.get(self.url("_mapping")?)).await?;
+        Ok(SolrSchemaInfo {
+            name: self.name.clone(),
+            unique_key: Some(self.unique_key.clone()),
+            ..Default::default()
+        })
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, _body: T) -> Result<SolrSimpleResponse> {
+        Err(self.unsupported("updating the schema"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    fn params(params: &[(&str, &str)]) -> Vec<(String, String)> {
+        params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_split_local_params() {
+        assert_eq!(
+            split_local_params(r#"{!tag=category}category:("ABC")"#),
+            (vec![String::from("category")], r#"category:("ABC")"#)
+        );
+        assert_eq!(
+            split_local_params("{!tag=a,b}x:1"),
+            (vec![String::from("a"), String::from("b")], "x:1")
+        );
+        assert_eq!(split_local_params("x:1"), (vec![], "x:1"));
+    }
+
+    #[test]
+    fn test_collapse_field() {
+        assert_eq!(
+            collapse_field("{!collapse field=canonical_problem_id}"),
+            Some("canonical_problem_id")
+        );
+        assert_eq!(collapse_field("{!tag=collapse}x:1"), None);
+        assert_eq!(collapse_field("x:1"), None);
+
+        let request = SearchRequest::new(&params(&[
+            ("q.alt", "*:*"),
+            ("fq", "contest_id:abc001"),
+            ("fq", "{!collapse field=canonical_problem_id}"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            request.body["collapse"],
+            json!({"field": "canonical_problem_id"})
+        );
+        assert_eq!(
+            request.body["query"]["bool"]["filter"],
+            json!([{"query_string": {"query": "contest_id:abc001"}}])
+        );
+    }
+
+    #[test]
+    fn test_search_request() {
+        let request = SearchRequest::new(&params(&[
+            ("q", "dp"),
+            ("q.alt", "*:*"),
+            ("q.op", "AND"),
+            ("qf", "text_ja^2 text_en"),
+            ("fq", "contest_id:abc001"),
+            ("fq", r#"{!tag=category}category:("ABC")"#),
+            ("sort", "score desc,problem_id asc"),
+            ("start", "20"),
+            ("rows", "10"),
+            ("fl", "problem_id,score"),
+            ("timeAllowed", "500"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            request.body,
+            json!({
+                "query": {
+                    "bool": {
+                        "must": [{"query_string": {
+                            "query": "dp",
+                            "default_operator": "AND",
+                            "fields": ["text_ja^2", "text_en"],
+                        }}],
+                        "filter": [{"query_string": {"query": "contest_id:abc001"}}],
+                    }
+                },
+                "post_filter": {
+                    "bool": {"filter": [{"query_string": {"query": r#"category:("ABC")"#}}]}
+                },
+                "from": 20,
+                "size": 10,
+                "track_total_hits": true,
+                "sort": [{"_score": "desc"}, {"problem_id": "asc"}],
+                "_source": ["problem_id"],
+                "track_scores": true,
+                "timeout": "500ms",
+            })
+        );
+        assert!(request.score);
+
+        let request = SearchRequest::new(&params(&[("q", " "), ("q.alt", "*:*")])).unwrap();
+        assert_eq!(
+            request.body["query"]["bool"]["must"][0]["query_string"]["query"],
+            json!("*:*")
+        );
+        assert!(request.body.get("post_filter").is_none());
+    }
+
+    #[test]
+    fn test_search_request_with_problem_filters() {
+        // The filters generated by the problem search API are passed through as query strings.
+        let request = SearchRequest::new(&params(&[
+            ("q", r#"(dp OR 動的計画法 OR "dynamic programming") -tle"#),
+            ("q.op", "AND"),
+            ("fq", r#"{!tag=category}-category:("ARC" OR "AGC")"#),
+            ("fq", "{!tag=difficulty}difficulty:[800 TO 1200}"),
+            ("fq", "statement_length:[300 TO *}"),
+            ("fq", "last_updated_at:[NOW/DAY-7DAYS TO *}"),
+            ("fq", "{!tag=is_experimental}-is_experimental:true"),
+            ("fq", "has_english:true"),
+            ("fq", "{!collapse field=canonical_problem_id}"),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            request.body["query"]["bool"],
+            json!({
+                "must": [{"query_string": {
+                    "query": r#"(dp OR 動的計画法 OR "dynamic programming") -tle"#,
+                    "default_operator": "AND",
+                }}],
+                "filter": [
+                    {"query_string": {"query": "statement_length:[300 TO *}"}},
+                    {"query_string": {"query": "last_updated_at:[NOW/DAY-7DAYS TO *}"}},
+                    {"query_string": {"query": "has_english:true"}},
+                ],
+            })
+        );
+        assert_eq!(
+            request.body["post_filter"],
+            json!({"bool": {"filter": [
+                {"query_string": {"query": r#"-category:("ARC" OR "AGC")"#}},
+                {"query_string": {"query": "difficulty:[800 TO 1200}"}},
+                {"query_string": {"query": "-is_experimental:true"}},
+            ]}})
+        );
+        assert_eq!(
+            request.body["collapse"],
+            json!({"field": "canonical_problem_id"})
+        );
+    }
+
+    #[test]
+    fn test_search_request_with_facets() {
+        let facet = json!({
+            "category": {
+                "type": "terms",
+                "field": "category",
+                "limit": -1,
+                "mincount": 0,
+                "domain": {"excludeTags": ["category"]}
+            },
+            "difficulty": {
+                "type": "range",
+                "field": "difficulty",
+                "start": 0,
+                "end": 800,
+                "gap": 400,
+                "other": "all",
+                "domain": {"excludeTags": ["difficulty"]}
+            }
+        });
+        let request = SearchRequest::new(&params(&[
+            ("q", "*:*"),
+            ("fq", r#"{!tag=category}category:("ABC")"#),
+            ("fq", "{!tag=difficulty}difficulty:[400 TO *]"),
+            ("json.facet", &facet.to_string()),
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            request.body["aggs"]["category"],
+            json!({
+                "filter": {"bool": {"filter": [{"query_string": {"query": "difficulty:[400 TO *]"}}]}},
+                "aggs": {"facet": {"terms": {"field": "category", "size": 10000, "min_doc_count": 0}}},
+            })
+        );
+        assert_eq!(
+            request.body["aggs"]["difficulty"]["aggs"]["facet"]["range"]["ranges"],
+            json!([
+                {"key": "bucket_0", "from": 0.0, "to": 400.0},
+                {"key": "bucket_1", "from": 400.0, "to": 800.0},
+                {"key": "before", "to": 0.0},
+                {"key": "after", "from": 800.0},
+                {"key": "between", "from": 0.0, "to": 800.0},
+            ])
+        );
+
+        let result = json!({
+            "took": 3,
+            "timed_out": false,
+            "hits": {
+                "total": {"value": 2, "relation": "eq"},
+                "hits": [
+                    {"_id": "abc001_a", "_score": 1.0, "_source": {"problem_id": "abc001_a"}},
+                    {"_id": "abc002_a", "_score": 1.0, "_source": {"problem_id": "abc002_a"}},
+                ]
+            },
+            "aggregations": {
+                "category": {"doc_count": 5, "facet": {"buckets": [
+                    {"key": "ABC", "doc_count": 2},
+                    {"key": "ARC", "doc_count": 3},
+                ]}},
+                "difficulty": {"doc_count": 2, "facet": {"buckets": [
+                    {"key": "bucket_0", "doc_count": 0},
+                    {"key": "bucket_1", "doc_count": 2},
+                    {"key": "before", "doc_count": 0},
+                    {"key": "after", "doc_count": 0},
+                    {"key": "between", "doc_count": 2},
+                ]}},
+            }
+        });
+
+        #[derive(Deserialize)]
+        struct Facets {
+            count: u32,
+            category: SolrTermFacetCount,
+            difficulty: SolrRangeFacetCount<i64>,
+        }
+        let response: SolrSelectResponse<Value, Facets> =
+            serde_json::from_value(request.response(&result).unwrap()).unwrap();
+        assert_eq!(response.header.qtime, 3);
+        assert_eq!(response.response.num_found, 2);
+        assert_eq!(response.response.docs[1], json!({"problem_id": "abc002_a"}));
+        let facets = response.facets.unwrap();
+        assert_eq!(facets.count, 2);
+        assert_eq!(facets.category.buckets[1].val, "ARC");
+        assert_eq!(facets.category.buckets[1].count, 3);
+        assert_eq!(facets.difficulty.buckets[1].val, 400);
+        assert_eq!(facets.difficulty.buckets[1].count, 2);
+        assert_eq!(facets.difficulty.between.unwrap().count, 2);
+    }
+
+    #[test]
+    fn test_query_facet() {
+        let (aggregation, kind) = facet_aggregation(
+            "difficulty_stats",
+            &json!({
+                "type": "query",
+                "q": "*:*",
+                "facet": {
+                    "min": "min(difficulty)",
+                    "percentiles": "percentile(difficulty,25,50)",
+                }
+            }),
+        )
+        .unwrap();
+        assert_eq!(
+            aggregation,
+            json!({
+                "filter": {"query_string": {"query": "*:*"}},
+                "aggs": {
+                    "min": {"min": {"field": "difficulty"}},
+                    "percentiles": {"percentiles": {"field": "difficulty", "percents": [25.0, 50.0], "keyed": false}},
+                }
+            })
+        );
+
+        let facet = facet_result(
+            &kind,
+            &json!({
+                "doc_count": 4,
+                "min": {"value": 100.0},
+                "percentiles": {"values": [{"key": 25.0, "value": 200.0}, {"key": 50.0, "value": 400.0}]},
+            }),
+        );
+        assert_eq!(
+            facet,
+            json!({"count": 4, "min": 100.0, "percentiles": [200.0, 400.0]})
+        );
+    }
+
+    #[test]
+    fn test_update_command() {
+        let core = OpenSearchCore::new("problems", "http://localhost:9200", "problem_id").unwrap();
+        let UpdateCommand::Add(documents) =
+            UpdateCommand::parse(br#"[{"problem_id": "abc001_a"}]"#).unwrap()
+        else {
+            panic!("documents expected");
+        };
+        assert_eq!(
+            core.index_actions(
+                documents,
+                &UpdateParams {
+                    overwrite: Some(false),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            vec![
+                json!({"create": {"_id": "abc001_a"}}),
+                json!({"problem_id": "abc001_a"}),
+            ]
+        );
+        assert!(core
+            .index_actions(
+                vec![json!({"id": "1"}).as_object().unwrap().clone()],
+                &UpdateParams::default()
+            )
+            .is_err());
+
+        assert!(matches!(
+            UpdateCommand::parse(br#"{"delete": ["abc001_c", "arc001_a"]}"#).unwrap(),
+            UpdateCommand::DeleteById(ids) if ids == vec!["abc001_c", "arc001_a"]
+        ));
+        assert!(matches!(
+            UpdateCommand::parse(br#"{"delete": {"query": "*:*"}}"#).unwrap(),
+            UpdateCommand::DeleteByQuery(query) if query == "*:*"
+        ));
+
+        assert_eq!(
+            bulk_error(&json!({"errors": true, "items": [
+                {"create": {"status": 409, "error": {"reason": "conflict"}}},
+                {"index": {"status": 400, "error": {"reason": "mapper_parsing_exception"}}},
+            ]})),
+            Some(String::from("mapper_parsing_exception"))
+        );
+        assert_eq!(bulk_error(&json!({"errors": false, "items": []})), None);
+    }
+}
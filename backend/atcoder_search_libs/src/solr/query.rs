@@ -1,11 +1,14 @@
 use core::fmt;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde_json::{Map, Value};
+use std::mem;
+use thiserror::Error;
 use unicode_normalization::UnicodeNormalization;
 
 /// Regex object for sanitizing the [Solr special characters](https://solr.apache.org/guide/solr/latest/query-guide/standard-query-parser.html#escaping-special-characters).
 pub static SOLR_SPECIAL_CHARACTERS: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"(\+|\-|&&|\|\||!|\(|\)|\{|\}|\[|\]|\^|"|\~|\*|\?|:|/|AND|OR)"#).unwrap()
+    Regex::new(r#"(\\|\+|\-|&&|\|\||!|\(|\)|\{|\}|\[|\]|\^|"|\~|\*|\?|:|/|AND|OR)"#).unwrap()
 });
 
 pub fn sanitize(s: &str) -> String {
@@ -14,6 +17,128 @@ pub fn sanitize(s: &str) -> String {
         .to_string()
 }
 
+/// A term of the keyword parsed by [`parse_keyword`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordTerm {
+    pub negated: bool,
+    pub field: Option<String>,
+    pub value: String,
+    pub phrase: bool,
+}
+
+impl fmt::Display for KeywordTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negated {
+            write!(f, "-")?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, "{}:", field)?;
+        }
+        if self.phrase {
+            write!(f, "\"{}\"", sanitize(&self.value))
+        } else {
+            write!(f, "{}", sanitize(&self.value))
+        }
+    }
+}
+
+impl KeywordTerm {
+    /// Format the term with the alternative values joined by `OR`, e.g. `(dp OR "dynamic programming")`.
+    ///
+    /// An alternative containing whitespaces is quoted as a phrase. A negated term excludes all the values.
+    pub fn with_alternatives(&self, alternatives: &[String]) -> String {
+        if alternatives.is_empty() {
+            return self.to_string();
+        }
+
+        let values = std::iter::once(KeywordTerm {
+            negated: false,
+            ..self.clone()
+        })
+        .chain(alternatives.iter().map(|alternative| KeywordTerm {
+            negated: false,
+            field: self.field.clone(),
+            value: alternative.clone(),
+            phrase: alternative.contains(char::is_whitespace),
+        }))
+        .map(|term| term.to_string())
+        .collect::<Vec<_>>();
+
+        format!(
+            "{}({})",
+            if self.negated { "-" } else { "" },
+            values.join(" OR ")
+        )
+    }
+}
+
+/// Parse the keyword given by a user into terms.
+///
+/// The keyword is split by whitespaces except inside double quotes. A term may be prefixed by `-` to
+/// negate it, and by `<field>:` to restrict the field to search if the field is in `fields`. A quoted
+/// term is treated as a phrase. An unterminated quote extends to the end of the keyword.
+pub fn parse_keyword(keyword: &str, fields: &[&str]) -> Vec<KeywordTerm> {
+    let keyword = keyword.nfkc().collect::<String>();
+    let mut terms = Vec::new();
+    let mut chars = keyword.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let negated = chars.next_if_eq(&'-').is_some();
+
+        // フィールド名の候補を読む。許可されたフィールドでなければ値の一部として扱う。
+        let mut prefix = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            prefix.push(c);
+        }
+        let field = if fields.contains(&prefix.as_str()) && chars.next_if_eq(&':').is_some() {
+            Some(mem::take(&mut prefix))
+        } else {
+            None
+        };
+
+        let phrase = prefix.is_empty() && chars.next_if_eq(&'"').is_some();
+        let mut value = prefix;
+        if phrase {
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                value.push(c);
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+
+        if value.is_empty() {
+            continue;
+        }
+        terms.push(KeywordTerm {
+            negated,
+            field,
+            value,
+            phrase,
+        });
+    }
+
+    terms
+}
+
+/// Convert the keyword given by a user into a safely escaped query string for edismax.
+pub fn keyword_query(keyword: &str, fields: &[&str]) -> String {
+    parse_keyword(keyword, fields)
+        .iter()
+        .map(|term| term.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[derive(Clone, PartialEq, Eq)]
 pub enum Operator {
     AND,
@@ -29,198 +154,316 @@ impl fmt::Display for Operator {
     }
 }
 
-pub struct EDisMaxQueryBuilder {
+/// Error returned by [`EDisMaxQueryBuilder::try_build`] when the parameters are invalid.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum QueryBuilderError {
+    #[error("invalid value `{value}` for parameter `{key}`: {reason}")]
+    InvalidValue {
+        key: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+    #[error("parameter `{key}` requires parameter `{requires}`")]
+    MissingDependency {
+        key: &'static str,
+        requires: &'static str,
+    },
+}
+
+/// Parameters which can be given multiple times in a request. The others hold a single value.
+const MULTI_VALUED_PARAMETERS: [&str; 4] = ["fq", "bq", "bf", "boost"];
+
+/// Pairs of a parameter and the parameter it depends on.
+const PARAMETER_DEPENDENCIES: [(&str, &str); 3] = [("ps", "pf"), ("ps2", "pf2"), ("ps3", "pf3")];
+
+/// Ordered map of request parameters.
+///
+/// Setting a single-valued parameter again overrides the previous value, and the same value of a
+/// multi-valued parameter is kept only once. The insertion order of the keys is preserved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParameterMap {
     params: Vec<(&'static str, String)>,
 }
 
-impl EDisMaxQueryBuilder {
+impl ParameterMap {
     pub fn new() -> Self {
-        Self {
-            params: vec![("defType", String::from("edismax"))],
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &'static str, value: String) {
+        if MULTI_VALUED_PARAMETERS.contains(&key) {
+            if !self.params.iter().any(|(k, v)| *k == key && *v == value) {
+                self.params.push((key, value));
+            }
+        } else {
+            match self.params.iter_mut().find(|(k, _)| *k == key) {
+                Some(param) => param.1 = value,
+                None => self.params.push((key, value)),
+            }
         }
     }
-    pub fn build(self) -> Vec<(String, String)> {
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.params.iter().any(|(k, _)| *k == key)
+    }
+
+    pub fn into_vec(self) -> Vec<(String, String)> {
         self.params
             .into_iter()
             .map(|(key, value)| (key.to_string(), value))
             .collect()
     }
-    pub fn sort(mut self, sort: impl ToString + Sync + Send) -> Self {
-        let sort = sort.to_string();
-        if !sort.is_empty() {
-            self.params.push(("sort", sort));
+}
+
+/// Maximum length of the query string sent in the URL of a select request.
+///
+/// Longer parameters, e.g. a filter by thousands of IDs, are sent to Solr as a JSON request body
+/// instead, since servers and proxies reject too long URLs.
+pub const MAX_QUERY_STRING_LENGTH: usize = 4096;
+
+/// Returns whether `params` are too long to be sent in the URL of a GET request.
+pub fn exceeds_url_length(params: &[(String, String)]) -> bool {
+    let length = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish()
+        .len();
+    length > MAX_QUERY_STRING_LENGTH
+}
+
+/// Convert request parameters into the body of the [JSON Request API](https://solr.apache.org/guide/solr/latest/query-guide/json-request-api.html),
+/// `{"params": {...}}`.
+///
+/// Parameters given more than once, such as `fq`, become arrays.
+pub fn json_request_body(params: &[(String, String)]) -> Value {
+    let mut map = Map::new();
+    for (key, value) in params {
+        let value = Value::String(value.clone());
+        match map.get_mut(key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(previous) => *previous = Value::Array(vec![previous.take(), value]),
+            None => {
+                map.insert(key.clone(), value);
+            }
         }
-        self
     }
-    pub fn start(mut self, start: u32) -> Self {
-        self.params.push(("start", start.to_string()));
-        self
+
+    let mut body = Map::new();
+    body.insert(String::from("params"), Value::Object(map));
+    Value::Object(body)
+}
+
+/// Build a filter query of the terms query parser matching the documents whose `field` is one of `values`.
+///
+/// If `exclude` is `true`, the filter matches the documents whose `field` is none of `values` instead.
+/// Values must not contain commas, which separate the terms. Returns `None` if `values` is empty.
+pub fn terms_filter_query(
+    field: &str,
+    values: &[impl ToString + Sync + Send],
+    exclude: bool,
+) -> Option<String> {
+    if values.is_empty() {
+        return None;
+    }
+    let terms = values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",");
+
+    if exclude {
+        // 否定するにはクエリの一部にする必要があるので、値は`v`パラメータに渡す
+        let terms = terms.replace('\\', "\\\\").replace('\'', "\\'");
+        Some(format!("-{{!terms f={} v='{}'}}", field, terms))
+    } else {
+        Some(format!("{{!terms f={}}}{}", field, terms))
     }
-    pub fn rows(mut self, rows: u32) -> Self {
-        self.params.push(("rows", rows.to_string()));
-        self
+}
+
+pub struct EDisMaxQueryBuilder {
+    params: ParameterMap,
+    errors: Vec<QueryBuilderError>,
+}
+
+impl Default for EDisMaxQueryBuilder {
+    fn default() -> Self {
+        Self::new()
     }
-    pub fn fq(mut self, fq: &[impl ToString + Sync + Send]) -> Self {
-        for fq in fq.iter() {
-            let fq = fq.to_string();
-            if !fq.is_empty() {
-                self.params.push(("fq", fq));
-            }
+}
+
+impl EDisMaxQueryBuilder {
+    pub fn new() -> Self {
+        let mut params = ParameterMap::new();
+        params.insert("defType", String::from("edismax"));
+        Self {
+            params,
+            errors: Vec::new(),
         }
-        self
     }
-    pub fn fl(mut self, fl: impl ToString + Sync + Send) -> Self {
-        let fl = fl.to_string();
-        if !fl.is_empty() {
-            self.params.push(("fl", fl));
+    /// Build the request parameters without validation.
+    ///
+    /// Invalid values are passed to Solr as they are. Use [`Self::try_build`] to reject them.
+    pub fn build(self) -> Vec<(String, String)> {
+        self.params.into_vec()
+    }
+    /// Build the request parameters, returning the first error if some parameter is invalid.
+    pub fn try_build(self) -> Result<Vec<(String, String)>, QueryBuilderError> {
+        if let Some(error) = self.errors.into_iter().next() {
+            return Err(error);
         }
-        self
+        for (key, requires) in PARAMETER_DEPENDENCIES {
+            if self.params.contains_key(key) && !self.params.contains_key(requires) {
+                return Err(QueryBuilderError::MissingDependency { key, requires });
+            }
+        }
+        Ok(self.params.into_vec())
     }
-    pub fn debug(mut self) -> Self {
-        self.params.push(("debug", "all".to_string()));
-        self.params
-            .push(("debug.explain.structured", "true".to_string()));
+    fn push(mut self, key: &'static str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        if !value.is_empty() {
+            self.params.insert(key, value);
+        }
         self
     }
-    pub fn wt(mut self, wt: impl ToString + Sync + Send) -> Self {
-        let wt = wt.to_string();
-        if !wt.is_empty() {
-            self.params.push(("wt", wt));
+    fn push_all(mut self, key: &'static str, values: &[impl ToString + Sync + Send]) -> Self {
+        for value in values.iter() {
+            self = self.push(key, value.to_string());
         }
         self
     }
-    pub fn facet(mut self, facet: impl ToString + Sync + Send) -> Self {
-        let facet = facet.to_string();
-        if !facet.is_empty() {
-            self.params.push(("json.facet", facet.to_string()));
+    pub fn sort(self, sort: impl ToString + Sync + Send) -> Self {
+        self.push("sort", sort)
+    }
+    pub fn start(self, start: u32) -> Self {
+        self.push("start", start)
+    }
+    pub fn rows(self, rows: u32) -> Self {
+        self.push("rows", rows)
+    }
+    pub fn fq(self, fq: &[impl ToString + Sync + Send]) -> Self {
+        self.push_all("fq", fq)
+    }
+    /// Filter the documents by the set of `values` of `field` with the terms query parser.
+    ///
+    /// See [`terms_filter_query`]. Nothing is added if `values` is empty.
+    pub fn terms_fq(
+        self,
+        field: &str,
+        values: &[impl ToString + Sync + Send],
+        exclude: bool,
+    ) -> Self {
+        match terms_filter_query(field, values, exclude) {
+            Some(fq) => self.push("fq", fq),
+            None => self,
         }
-        self
     }
-    pub fn op(mut self, op: Operator) -> Self {
-        self.params.push(("q.op", op.to_string()));
-        self
+    pub fn fl(self, fl: impl ToString + Sync + Send) -> Self {
+        self.push("fl", fl)
     }
-    pub fn df(mut self, df: impl ToString + Sync + Send) -> Self {
-        let df = df.to_string();
-        if !df.is_empty() {
-            self.params.push(("df", df));
-        }
-        self
+    pub fn debug(self) -> Self {
+        self.push("debug", "all")
+            .push("debug.explain.structured", "true")
     }
-    pub fn q(mut self, q: impl ToString + Sync + Send) -> Self {
-        let q = q.to_string();
-        if !q.is_empty() {
-            self.params.push(("q", q));
-        }
-        self
+    pub fn wt(self, wt: impl ToString + Sync + Send) -> Self {
+        self.push("wt", wt)
     }
-    pub fn qf(mut self, qf: impl ToString + Sync + Send) -> Self {
-        let qf = qf.to_string();
-        if !qf.is_empty() {
-            self.params.push(("qf", qf));
-        }
-        self
+    pub fn facet(self, facet: impl ToString + Sync + Send) -> Self {
+        self.push("json.facet", facet)
     }
-    pub fn qs(mut self, qs: u32) -> Self {
-        self.params.push(("qs", qs.to_string()));
-        self
+    pub fn op(self, op: Operator) -> Self {
+        self.push("q.op", op)
     }
-    pub fn pf(mut self, pf: impl ToString + Sync + Send) -> Self {
-        let pf = pf.to_string();
-        if !pf.is_empty() {
-            self.params.push(("pf", pf));
-        }
-        self
+    pub fn df(self, df: impl ToString + Sync + Send) -> Self {
+        self.push("df", df)
     }
-    pub fn ps(mut self, ps: u32) -> Self {
-        self.params.push(("ps", ps.to_string()));
-        self
+    pub fn q(self, q: impl ToString + Sync + Send) -> Self {
+        self.push("q", q)
     }
-    pub fn mm(mut self, mm: impl ToString + Sync + Send) -> Self {
-        let mm = mm.to_string();
-        if !mm.is_empty() {
-            self.params.push(("mm", mm));
-        }
-        self
+    pub fn qf(self, qf: impl ToString + Sync + Send) -> Self {
+        self.push("qf", qf)
     }
-    pub fn q_alt(mut self, q: impl ToString + Sync + Send) -> Self {
-        let q = q.to_string();
-        if !q.is_empty() {
-            self.params.push(("q.alt", q));
-        }
-        self
+    pub fn qs(self, qs: u32) -> Self {
+        self.push("qs", qs)
     }
-    pub fn tie(mut self, tie: f64) -> Self {
-        self.params.push(("tie", tie.to_string()));
-        self
+    pub fn pf(self, pf: impl ToString + Sync + Send) -> Self {
+        self.push("pf", pf)
     }
-    pub fn bq(mut self, bq: &[impl ToString + Sync + Send]) -> Self {
-        for bq in bq.iter() {
-            let bq = bq.to_string();
-            if !bq.is_empty() {
-                self.params.push(("bq", bq));
-            }
-        }
-        self
+    pub fn ps(self, ps: u32) -> Self {
+        self.push("ps", ps)
     }
-    pub fn bf(mut self, bf: &[impl ToString + Sync + Send]) -> Self {
-        for bf in bf.iter() {
-            let bf = bf.to_string();
-            if !bf.is_empty() {
-                self.params.push(("bf", bf));
-            }
-        }
-        self
+    pub fn mm(self, mm: impl ToString + Sync + Send) -> Self {
+        self.push("mm", mm)
     }
-    pub fn sow(mut self, sow: bool) -> Self {
-        self.params.push(("sow", sow.to_string()));
-        self
+    pub fn q_alt(self, q: impl ToString + Sync + Send) -> Self {
+        self.push("q.alt", q)
     }
-    pub fn boost(mut self, boost: &[impl ToString + Sync + Send]) -> Self {
-        for boost in boost.iter() {
-            let boost = boost.to_string();
-            if !boost.is_empty() {
-                self.params.push(("boost", boost.to_string()));
-            }
+    pub fn tie(mut self, tie: f64) -> Self {
+        if !(0.0..=1.0).contains(&tie) {
+            self.errors.push(QueryBuilderError::InvalidValue {
+                key: "tie",
+                value: tie.to_string(),
+                reason: "must be between 0.0 and 1.0",
+            });
         }
-        self
+        self.push("tie", tie)
     }
-    pub fn lowercase_operators(mut self, flag: bool) -> Self {
-        self.params.push(("lowercaseOperators", flag.to_string()));
-        self
+    pub fn bq(self, bq: &[impl ToString + Sync + Send]) -> Self {
+        self.push_all("bq", bq)
     }
-    pub fn pf2(mut self, pf: impl ToString + Sync + Send) -> Self {
-        let pf = pf.to_string();
-        if !pf.is_empty() {
-            self.params.push(("pf2", pf.to_string()));
-        }
-        self
+    pub fn bf(self, bf: &[impl ToString + Sync + Send]) -> Self {
+        self.push_all("bf", bf)
     }
-    pub fn ps2(mut self, ps: u32) -> Self {
-        self.params.push(("ps2", ps.to_string()));
-        self
+    pub fn sow(self, sow: bool) -> Self {
+        self.push("sow", sow)
     }
-    pub fn pf3(mut self, pf: impl ToString + Sync + Send) -> Self {
-        let pf = pf.to_string();
-        if !pf.is_empty() {
-            self.params.push(("pf3", pf.to_string()));
-        }
-        self
+    pub fn boost(self, boost: &[impl ToString + Sync + Send]) -> Self {
+        self.push_all("boost", boost)
     }
-    pub fn ps3(mut self, ps: u32) -> Self {
-        self.params.push(("ps3", ps.to_string()));
-        self
+    pub fn lowercase_operators(self, flag: bool) -> Self {
+        self.push("lowercaseOperators", flag)
     }
-    pub fn stopwords(mut self, flag: bool) -> Self {
-        self.params.push(("stopwords", flag.to_string()));
-        self
+    pub fn pf2(self, pf: impl ToString + Sync + Send) -> Self {
+        self.push("pf2", pf)
+    }
+    pub fn ps2(self, ps: u32) -> Self {
+        self.push("ps2", ps)
+    }
+    pub fn pf3(self, pf: impl ToString + Sync + Send) -> Self {
+        self.push("pf3", pf)
+    }
+    pub fn ps3(self, ps: u32) -> Self {
+        self.push("ps3", ps)
+    }
+    pub fn stopwords(self, flag: bool) -> Self {
+        self.push("stopwords", flag)
     }
-    pub fn uf(mut self, uf: impl ToString + Sync + Send) -> Self {
-        let uf = uf.to_string();
-        if !uf.is_empty() {
-            self.params.push(("uf3", uf.to_string()));
+    pub fn uf(self, uf: impl ToString + Sync + Send) -> Self {
+        self.push("uf", uf)
+    }
+    /// Abort the search after `milliseconds` and return the partial results collected so far.
+    pub fn time_allowed(mut self, milliseconds: u32) -> Self {
+        if milliseconds == 0 {
+            self.errors.push(QueryBuilderError::InvalidValue {
+                key: "timeAllowed",
+                value: milliseconds.to_string(),
+                reason: "must be greater than 0",
+            });
         }
-        self
+        self.push("timeAllowed", milliseconds)
     }
 }
 
@@ -246,7 +489,7 @@ mod test {
             .fq(&["name:alice"])
             .fq(&["{!collapse field=grade}"])
             .fl("id,name,grade");
-        let expected = vec![
+        let expected = [
             ("defType", "edismax"),
             ("start", "10"),
             ("rows", "20"),
@@ -259,4 +502,204 @@ mod test {
         .collect_vec();
         assert_eq!(builder.build(), expected);
     }
+
+    #[test]
+    fn test_override_single_valued_params() {
+        let builder = EDisMaxQueryBuilder::new()
+            .rows(20)
+            .q("foo")
+            .rows(30)
+            .uf("title");
+        let expected = [
+            ("defType", "edismax"),
+            ("rows", "30"),
+            ("q", "foo"),
+            ("uf", "title"),
+        ]
+        .iter()
+        .map(|param| (param.0.to_string(), param.1.to_string()))
+        .collect_vec();
+        assert_eq!(builder.build(), expected);
+    }
+
+    #[test]
+    fn test_dedupe_multi_valued_params() {
+        let builder = EDisMaxQueryBuilder::new()
+            .fq(&["name:alice", "grade:1"])
+            .fq(&["name:alice"]);
+        let expected = [
+            ("defType", "edismax"),
+            ("fq", "name:alice"),
+            ("fq", "grade:1"),
+        ]
+        .iter()
+        .map(|param| (param.0.to_string(), param.1.to_string()))
+        .collect_vec();
+        assert_eq!(builder.build(), expected);
+    }
+
+    #[test]
+    fn test_terms_fq() {
+        let builder = EDisMaxQueryBuilder::new()
+            .terms_fq("problem_id", &["abc001_a", "abc001_b"], false)
+            .terms_fq("problem_id", &["abc300_a", "it's"], true)
+            .terms_fq("problem_id", &[] as &[&str], true);
+        let expected = [
+            ("defType", "edismax"),
+            ("fq", "{!terms f=problem_id}abc001_a,abc001_b"),
+            ("fq", r"-{!terms f=problem_id v='abc300_a,it\'s'}"),
+        ]
+        .iter()
+        .map(|param| (param.0.to_string(), param.1.to_string()))
+        .collect_vec();
+        assert_eq!(builder.build(), expected);
+    }
+
+    #[test]
+    fn test_long_terms_fq() {
+        let ids = (0..1000)
+            .map(|i| format!("abc{:03}_a", i))
+            .collect::<Vec<String>>();
+        let params = EDisMaxQueryBuilder::new()
+            .q("dp")
+            .fq(&["category:ABC"])
+            .terms_fq("problem_id", &ids, true)
+            .build();
+        assert!(exceeds_url_length(&params));
+
+        let body = json_request_body(&params);
+        assert_eq!(body["params"]["q"], "dp");
+        assert_eq!(body["params"]["fq"][0], "category:ABC");
+        let terms = body["params"]["fq"][1].as_str().unwrap();
+        assert!(terms.starts_with("-{!terms f=problem_id v='abc000_a,abc001_a,"));
+        assert_eq!(terms.matches(',').count(), 999);
+
+        let params = EDisMaxQueryBuilder::new()
+            .terms_fq("problem_id", &ids[..10], true)
+            .build();
+        assert!(!exceeds_url_length(&params));
+    }
+
+    #[test]
+    fn test_json_request_body() {
+        let params = [("q", "*:*"), ("fq", "a:1"), ("fq", "b:2"), ("fq", "c:3")]
+            .iter()
+            .map(|param| (param.0.to_string(), param.1.to_string()))
+            .collect_vec();
+        assert_eq!(
+            json_request_body(&params),
+            serde_json::json!({"params": {"q": "*:*", "fq": ["a:1", "b:2", "c:3"]}})
+        );
+    }
+
+    #[test]
+    fn test_try_build() {
+        assert!(EDisMaxQueryBuilder::new()
+            .tie(0.1)
+            .pf("title")
+            .ps(2)
+            .try_build()
+            .is_ok());
+        assert_eq!(
+            EDisMaxQueryBuilder::new().tie(1.5).try_build(),
+            Err(QueryBuilderError::InvalidValue {
+                key: "tie",
+                value: String::from("1.5"),
+                reason: "must be between 0.0 and 1.0",
+            })
+        );
+        assert_eq!(
+            EDisMaxQueryBuilder::new().time_allowed(0).try_build(),
+            Err(QueryBuilderError::InvalidValue {
+                key: "timeAllowed",
+                value: String::from("0"),
+                reason: "must be greater than 0",
+            })
+        );
+        assert_eq!(
+            EDisMaxQueryBuilder::new().time_allowed(20).try_build(),
+            Ok(vec![
+                (String::from("defType"), String::from("edismax")),
+                (String::from("timeAllowed"), String::from("20")),
+            ])
+        );
+        assert_eq!(
+            EDisMaxQueryBuilder::new().ps2(2).try_build(),
+            Err(QueryBuilderError::MissingDependency {
+                key: "ps2",
+                requires: "pf2",
+            })
+        );
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize(r"a\b:c"), r"a\\b\:c");
+        assert_eq!(sanitize("ＡＢＣ AND ｄ"), r"ABC \AND d");
+    }
+
+    #[test]
+    fn test_parse_keyword() {
+        let terms = parse_keyword(
+            r#"-"dynamic programming" category:ABC -foo:bar"#,
+            &["category"],
+        );
+        assert_eq!(
+            terms,
+            vec![
+                KeywordTerm {
+                    negated: true,
+                    field: None,
+                    value: String::from("dynamic programming"),
+                    phrase: true,
+                },
+                KeywordTerm {
+                    negated: false,
+                    field: Some(String::from("category")),
+                    value: String::from("ABC"),
+                    phrase: false,
+                },
+                KeywordTerm {
+                    negated: true,
+                    field: None,
+                    value: String::from("foo:bar"),
+                    phrase: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keyword_query() {
+        let fields = ["category", "contest_id"];
+        assert_eq!(keyword_query("", &fields), "");
+        assert_eq!(keyword_query("  dp  graph ", &fields), "dp graph");
+        assert_eq!(
+            keyword_query(r#"category:"ABC-Like" -abc300"#, &fields),
+            r#"category:"ABC\-Like" -abc300"#
+        );
+        assert_eq!(
+            keyword_query(r#"problem_id:abc300_a {!join} "unterminated"#, &fields),
+            r#"problem_id\:abc300_a \{\!join\} "unterminated""#
+        );
+        assert_eq!(keyword_query("- -- \"\"", &fields), r"-\-");
+    }
+
+    #[test]
+    fn test_with_alternatives() {
+        let term = KeywordTerm {
+            negated: true,
+            field: None,
+            value: String::from("DP"),
+            phrase: false,
+        };
+        assert_eq!(term.with_alternatives(&[]), "-DP");
+        assert_eq!(
+            term.with_alternatives(&[
+                String::from("動的計画法"),
+                String::from("dynamic programming")
+            ]),
+            r#"-(DP OR 動的計画法 OR "dynamic programming")"#
+        );
+    }
 }
@@ -0,0 +1,389 @@
+//! Test doubles for the Solr client.
+//!
+//! Enabled for this crate's own tests, and for other crates through the `testing` feature.
+//! Docker containers for integration tests are in [`containers`] with the `integration` feature.
+use crate::solr::{
+    core::{CommitParams, SolrCore, SolrCoreError, UpdateParams},
+    model::*,
+};
+use async_trait::async_trait;
+use reqwest::Body;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "integration")]
+pub mod containers;
+
+type Result<T> = std::result::Result<T, SolrCoreError>;
+
+/// A request received by [`MockSolrCore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockRequest {
+    Ping,
+    Status,
+    Reload,
+    /// Swapped with the core of the name
+    Swap(String),
+    Select(Vec<(String, String)>),
+    /// The posted body. `None` when the body was a stream (e.g. a file) whose bytes are not accessible.
+    Post(Option<Vec<u8>>),
+    /// The body posted to `/update/json/docs`. `None` when the body was a stream.
+    PostDocs(Option<Vec<u8>>),
+    Commit,
+    SoftCommit,
+    Optimize,
+    Rollback,
+    Truncate,
+    Schema,
+    /// The posted Schema API commands. `None` when the body was a stream.
+    UpdateSchema(Option<Vec<u8>>),
+}
+
+struct MockState {
+    available: bool,
+    num_docs: u64,
+    select_responses: VecDeque<Value>,
+    post_failures: usize,
+    schema: SolrSchemaInfo,
+    requests: Vec<MockRequest>,
+    update_params: Vec<UpdateParams>,
+}
+
+/// In-memory implementation of [`SolrCore`] with programmable responses and request capture.
+///
+/// Clones share the same state, so a clone can be handed to code that takes ownership of the core
+/// and the original can be used to inspect the captured requests afterwards.
+#[derive(Clone)]
+pub struct MockSolrCore {
+    name: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockSolrCore {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            state: Arc::new(Mutex::new(MockState {
+                available: true,
+                num_docs: 0,
+                select_responses: VecDeque::new(),
+                post_failures: 0,
+                schema: SolrSchemaInfo {
+                    name: String::from(name),
+                    ..Default::default()
+                },
+                requests: Vec::new(),
+                update_params: Vec::new(),
+            })),
+        }
+    }
+
+    /// Make every request fail as if the core were down.
+    pub fn set_available(&self, available: bool) {
+        self.state.lock().unwrap().available = available;
+    }
+
+    /// Set the number of documents reported by `status()`.
+    pub fn set_num_docs(&self, num_docs: u64) {
+        self.state.lock().unwrap().num_docs = num_docs;
+    }
+
+    /// Queue a raw Solr select response body. Queued responses are returned in order; once the
+    /// queue is empty, `select()` returns an empty result.
+    pub fn push_select_response(&self, response: Value) {
+        self.state
+            .lock()
+            .unwrap()
+            .select_responses
+            .push_back(response);
+    }
+
+    /// Make the next `n` calls of `post()` fail.
+    pub fn fail_next_posts(&self, n: usize) {
+        self.state.lock().unwrap().post_failures = n;
+    }
+
+    /// Set the schema returned by `schema()`.
+    pub fn set_schema(&self, schema: SolrSchemaInfo) {
+        self.state.lock().unwrap().schema = schema;
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Parameters of the `post()` and `post_docs()` requests received so far, in order.
+    pub fn update_params(&self) -> Vec<UpdateParams> {
+        self.state.lock().unwrap().update_params.clone()
+    }
+
+    /// Parameters of the select requests received so far.
+    pub fn selects(&self) -> Vec<Vec<(String, String)>> {
+        self.requests()
+            .into_iter()
+            .filter_map(|request| match request {
+                MockRequest::Select(params) => Some(params),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, request: MockRequest) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.requests.push(request);
+        if state.available {
+            Ok(())
+        } else {
+            Err(SolrCoreError::UnexpectedError(format!(
+                "core {} is not available",
+                self.name
+            )))
+        }
+    }
+
+    fn header() -> SolrResponseHeader {
+        SolrResponseHeader {
+            zk_connected: None,
+            status: 0,
+            qtime: 0,
+            params: None,
+            partial_results: None,
+        }
+    }
+}
+
+#[async_trait]
+impl SolrCore for MockSolrCore {
+    async fn ping(&self) -> Result<SolrPingResponse> {
+        self.record(MockRequest::Ping)?;
+        Ok(SolrPingResponse {
+            header: Self::header(),
+            status: String::from("OK"),
+        })
+    }
+
+    async fn status(&self) -> Result<SolrCoreStatus> {
+        self.record(MockRequest::Status)?;
+        let num_docs = self.state.lock().unwrap().num_docs;
+        Ok(SolrCoreStatus {
+            name: self.name.clone(),
+            instance_dir: format!("/var/solr/data/{}", self.name),
+            data_dir: format!("/var/solr/data/{}/data/", self.name),
+            config: String::from("solrconfig.xml"),
+            schema: String::from("schema.xml"),
+            start_time: String::from("2023-01-01T00:00:00.000Z"),
+            uptime: 0,
+            index: SolrIndexInfo {
+                num_docs,
+                max_doc: num_docs,
+                deleted_docs: 0,
+                version: 1,
+                segment_count: 1,
+                current: true,
+                has_deletions: false,
+                directory: String::new(),
+                segments_file: String::from("segments_1"),
+                segments_file_size_in_bytes: 0,
+                user_data: json!({}),
+                size_in_bytes: 0,
+                size: String::from("0 bytes"),
+            },
+        })
+    }
+
+    async fn reload(&self) -> Result<SolrSimpleResponse> {
+        self.record(MockRequest::Reload)?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(),
+            error: None,
+        })
+    }
+
+    async fn swap(&self, other: &str) -> Result<SolrSimpleResponse> {
+        self.record(MockRequest::Swap(String::from(other)))?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(),
+            error: None,
+        })
+    }
+
+    async fn select<D: DeserializeOwned, F: DeserializeOwned>(
+        &self,
+        params: &[(impl ToString + Sync, impl ToString + Sync)],
+    ) -> Result<SolrSelectResponse<D, F>> {
+        let params = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self.record(MockRequest::Select(params))?;
+
+        let response = self
+            .state
+            .lock()
+            .unwrap()
+            .select_responses
+            .pop_front()
+            .unwrap_or_else(|| {
+                json!({
+                    "responseHeader": {"status": 0, "QTime": 0},
+                    "response": {"numFound": 0, "start": 0, "numFoundExact": true, "docs": []}
+                })
+            });
+        Ok(serde_json::from_value(response)?)
+    }
+
+    async fn post<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        self.record(MockRequest::Post(
+            body.as_bytes().map(|bytes| bytes.to_vec()),
+        ))?;
+
+        let mut state = self.state.lock().unwrap();
+        state.update_params.push(params.clone());
+        if state.post_failures > 0 {
+            state.post_failures -= 1;
+            return Err(SolrCoreError::UnexpectedError(String::from(
+                "post failed by mock",
+            )));
+        }
+
+        Ok(SolrSimpleResponse {
+            header: Self::header(),
+            error: None,
+        })
+    }
+
+    async fn post_docs<T: Into<Body> + Send>(
+        &self,
+        body: T,
+        params: &UpdateParams,
+    ) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        self.record(MockRequest::PostDocs(
+            body.as_bytes().map(|bytes| bytes.to_vec()),
+        ))?;
+
+        let mut state = self.state.lock().unwrap();
+        state.update_params.push(params.clone());
+        if state.post_failures > 0 {
+            state.post_failures -= 1;
+            return Err(SolrCoreError::UnexpectedError(String::from(
+                "post failed by mock",
+            )));
+        }
+
+        Ok(SolrSimpleResponse {
+            header: Self::header(),
+            error: None,
+        })
+    }
+
+    async fn commit(&self, params: &CommitParams) -> Result<()> {
+        if params.soft_commit {
+            self.record(MockRequest::SoftCommit)
+        } else {
+            self.record(MockRequest::Commit)
+        }
+    }
+
+    async fn optimize(&self, _params: &CommitParams) -> Result<()> {
+        self.record(MockRequest::Optimize)
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        self.record(MockRequest::Rollback)
+    }
+
+    async fn truncate(&self) -> Result<()> {
+        self.record(MockRequest::Truncate)
+    }
+
+    async fn schema(&self) -> Result<SolrSchemaInfo> {
+        self.record(MockRequest::Schema)?;
+        Ok(self.state.lock().unwrap().schema.clone())
+    }
+
+    async fn update_schema<T: Into<Body> + Send>(&self, body: T) -> Result<SolrSimpleResponse> {
+        let body: Body = body.into();
+        self.record(MockRequest::UpdateSchema(
+            body.as_bytes().map(|bytes| bytes.to_vec()),
+        ))?;
+        Ok(SolrSimpleResponse {
+            header: Self::header(),
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_select_returns_queued_response() {
+        let core = MockSolrCore::new("example");
+        core.push_select_response(json!({
+            "responseHeader": {"status": 0, "QTime": 1},
+            "response": {"numFound": 1, "start": 0, "numFoundExact": true, "docs": [{"id": "001"}]}
+        }));
+
+        let first = core.select::<Value, ()>(&[("q", "id:001")]).await.unwrap();
+        assert_eq!(first.response.docs, vec![json!({"id": "001"})]);
+
+        let second = core.select::<Value, ()>(&[("q", "*:*")]).await.unwrap();
+        assert_eq!(second.response.num_found, 0);
+
+        assert_eq!(
+            core.selects(),
+            vec![
+                vec![(String::from("q"), String::from("id:001"))],
+                vec![(String::from("q"), String::from("*:*"))],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_core() {
+        let core = MockSolrCore::new("example");
+        core.set_available(false);
+
+        assert!(core.ping().await.is_err());
+        assert!(core.status().await.is_err());
+        assert_eq!(
+            core.requests(),
+            vec![MockRequest::Ping, MockRequest::Status]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_state() {
+        let core = MockSolrCore::new("example");
+        core.set_num_docs(3);
+        let cloned = core.clone();
+
+        cloned
+            .post(br#"[{"id": "001"}]"#.to_vec(), &UpdateParams::default())
+            .await
+            .unwrap();
+        cloned.commit(&CommitParams::default()).await.unwrap();
+
+        assert_eq!(core.status().await.unwrap().index.num_docs, 3);
+        assert_eq!(
+            core.requests(),
+            vec![
+                MockRequest::Post(Some(br#"[{"id": "001"}]"#.to_vec())),
+                MockRequest::Commit,
+                MockRequest::Status,
+            ]
+        );
+    }
+}